@@ -0,0 +1,19 @@
+#import bevy_sprite::mesh2d_types
+#import bevy_sprite::mesh2d_view_bindings
+
+struct AmbientTintMaterial {
+    color: vec4<f32>,
+};
+
+@group(1) @binding(0)
+var<uniform> material: AmbientTintMaterial;
+
+struct FragmentInput {
+    #import bevy_sprite::mesh2d_vertex_output
+};
+
+// The pipeline multiplies whatever is already on screen by this colour.
+@fragment
+fn fragment(in: FragmentInput) -> @location(0) vec4<f32> {
+    return material.color;
+}
@@ -0,0 +1,62 @@
+//! Criterion benchmarks of the math hot paths.
+//!
+//! `cargo bench --no-default-features --features glm` from `gamelibs/`. Criterion keeps the previous run's results
+//! under `target/criterion` and reports the change against them, so bench the previous commit first to compare.
+//!
+//! There's no `catmull_rom` to bench, nor a `fast_normalize`; normalizing by `q_rsqrt` is written out inline to
+//! stand in for the latter. Noise is one-dimensional, so `value_noise` stands in for a 2D version, and
+//! `shake_offset` samples it along both axes.
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use gamelibs::prelude::*;
+use nalgebra_glm::{DVec3, normalize};
+
+/// Inputs in `(0, 1]`, where `q_rsqrt` is at its most accurate.
+const INPUT: f64 = 0.37;
+
+fn inverse_sqrt(c: &mut Criterion) {
+    c.bench_function("q_rsqrt", |b| b.iter(|| q_rsqrt(black_box(INPUT))));
+    c.bench_function("f64::sqrt().recip()", |b| b.iter(|| black_box(INPUT).sqrt().recip()));
+
+    let vector = DVec3::new(INPUT, 1.0 - INPUT, 0.5);
+    c.bench_function("normalize by q_rsqrt", |b| {
+        b.iter(|| {
+            let v = black_box(vector);
+            v * q_rsqrt(v.dot(&v))
+        })
+    });
+    c.bench_function("nalgebra_glm::normalize", |b| b.iter(|| normalize(&black_box(vector))));
+}
+
+fn curves(c: &mut Criterion) {
+    let curve = CurveType::from_style(CurveStyle::SmoothDamped);
+    c.bench_function("weighted_step", |b| {
+        b.iter(|| weighted_step(&curve, 1.0 / 60.0, black_box(INPUT), 0.0, 0.0, 0.0))
+    });
+    c.bench_function("calc_weighted_next", |b| {
+        b.iter(|| {
+            let x = black_box(INPUT);
+            calc_weighted_next(WeightedNextBundle {
+                base_func: |t| (x + t).sin(),
+                time: 1.0 / 60.0,
+                max_step: DEFAULT_MAX_STEP,
+                curve,
+                last_pos: DVec3::zeros(),
+                last_vel: DVec3::zeros(),
+                last_acc: DVec3::zeros(),
+            })
+        })
+    });
+    let mut follower = CurveFollower::new(CurveStyle::SmoothDamped, 0.0);
+    c.bench_function("CurveFollower::step", |b| b.iter(|| follower.step(black_box(INPUT), 1.0 / 60.0)));
+}
+
+fn noise(c: &mut Criterion) {
+    c.bench_function("value_noise", |b| b.iter(|| value_noise(black_box(INPUT * 100.0), black_box(7))));
+    c.bench_function("shake_offset", |b| b.iter(|| shake_offset(black_box(0.5), black_box(INPUT), black_box(7))));
+}
+
+criterion_group!(benches, inverse_sqrt, curves, noise);
+criterion_main!(benches);
@@ -0,0 +1,200 @@
+use nalgebra_glm::*;
+
+/// How many push-outs `resolve_overlap` tries before giving up, enough for a box wedged into a corner.
+const MAX_RESOLVE_ITERATIONS: usize = 4;
+/// How far a box can already be inside a collider, from rounding after the last sweep stopped it against it, and
+/// still be treated as touching it rather than overlapping.
+const CONTACT_EPSILON: f64 = 1e-9;
+
+/// A double-precision axis-aligned bounding box.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb2 {
+    pub min: DVec2,
+    pub max: DVec2,
+}
+
+impl Aabb2 {
+    pub fn new(min: DVec2, max: DVec2) -> Self {
+        Aabb2 { min, max }
+    }
+
+    pub fn from_center(center: DVec2, half_extents: DVec2) -> Self {
+        Aabb2 {
+            min: center - half_extents,
+            max: center + half_extents,
+        }
+    }
+
+    pub fn center(&self) -> DVec2 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn half_extents(&self) -> DVec2 {
+        (self.max - self.min) * 0.5
+    }
+
+    /// Whether the boxes overlap; boxes that only touch along an edge don't.
+    pub fn overlaps(&self, other: &Aabb2) -> bool {
+        self.min.x < other.max.x && self.max.x > other.min.x && self.min.y < other.max.y && self.max.y > other.min.y
+    }
+
+    /// The smallest translation that moves `self` out of `other`, along whichever axis is shallower.
+    pub fn penetration(&self, other: &Aabb2) -> Option<DVec2> {
+        if !self.overlaps(other) {
+            return None;
+        }
+        let depth_x = self.max.x.min(other.max.x) - self.min.x.max(other.min.x);
+        let depth_y = self.max.y.min(other.max.y) - self.min.y.max(other.min.y);
+        let away = self.center() - other.center();
+
+        if depth_x < depth_y {
+            Some(DVec2::new(depth_x.copysign(away.x), 0.0))
+        } else {
+            Some(DVec2::new(0.0, depth_y.copysign(away.y)))
+        }
+    }
+}
+
+/// Pushes a box of `half_extents` centred on `pos` out of every overlapping static collider and returns its new centre.
+///
+/// Each overlap is resolved along its minimum-translation axis. One push can shove the box into a neighbour, so this
+/// repeats until nothing overlaps (or the iteration cap is hit, leaving the best position found).
+pub fn resolve_overlap(pos: DVec2, half_extents: DVec2, colliders: &[Aabb2]) -> DVec2 {
+    let mut pos = pos;
+
+    for _ in 0..MAX_RESOLVE_ITERATIONS {
+        let mut moved = false;
+        for collider in colliders {
+            if let Some(push) = Aabb2::from_center(pos, half_extents).penetration(collider) {
+                pos += push;
+                moved = true;
+            }
+        }
+        if !moved {
+            break;
+        }
+    }
+    pos
+}
+
+/*
+ * Sweeps `moving` along `velocity` (the whole displacement for the step) and returns the fraction of it travelled
+ * before the first hit, with the normal of the face hit, or `(1.0, zero)` when nothing is in the way.
+ *
+ * Each static is grown by `moving`'s half extents, so the sweep becomes a ray from its centre, and the ray's entry and
+ * exit times through the slabs on each axis are compared. Boxes already overlapping are left to `resolve_overlap`,
+ * as are ones the box is moving away from or only sliding along.
+ */
+pub fn sweep_aabb(moving: Aabb2, velocity: DVec2, statics: &[Aabb2]) -> (f64, DVec2) {
+    let center = moving.center();
+    let half_extents = moving.half_extents();
+    let mut first = (1.0, DVec2::zeros());
+
+    for collider in statics {
+        let min = collider.min - half_extents;
+        let max = collider.max + half_extents;
+        let mut entry = [f64::NEG_INFINITY; 2];
+        let mut exit = [f64::INFINITY; 2];
+
+        let mut missed = false;
+        for axis in 0..2 {
+            if velocity[axis] == 0.0 {
+                missed |= center[axis] <= min[axis] || center[axis] >= max[axis];
+            } else {
+                let near = (min[axis] - center[axis]) / velocity[axis];
+                let far = (max[axis] - center[axis]) / velocity[axis];
+                entry[axis] = near.min(far);
+                exit[axis] = near.max(far);
+            }
+        }
+        if missed {
+            continue;
+        }
+
+        // Ties go to the vertical axis, so a box landing exactly on a corner stands on it.
+        let axis = if entry[0] > entry[1] { 0 } else { 1 };
+        let toi = entry[axis];
+        let leave = exit[0].min(exit[1]);
+        let depth = -toi * velocity[axis].abs();
+        if toi >= leave || depth > CONTACT_EPSILON || toi >= first.0 {
+            continue;
+        }
+
+        let mut normal = DVec2::zeros();
+        normal[axis] = -velocity[axis].signum();
+        first = (toi.max(0.0), normal);
+    }
+    first
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: DVec2, b: DVec2) {
+        assert!((a - b).norm() < 1e-9, "{a:?} != {b:?}");
+    }
+
+    const HALF: f64 = 0.5;
+
+    fn floor() -> Aabb2 {
+        Aabb2::new(DVec2::new(0.0, 0.0), DVec2::new(10.0, 1.0))
+    }
+
+    fn wall() -> Aabb2 {
+        Aabb2::new(DVec2::new(0.0, 0.0), DVec2::new(1.0, 10.0))
+    }
+
+    #[test]
+    fn pushes_out_along_the_shallow_axis() {
+        let resolved = resolve_overlap(DVec2::new(5.0, 1.2), DVec2::repeat(HALF), &[floor()]);
+        assert_close(resolved, DVec2::new(5.0, 1.5));
+    }
+
+    #[test]
+    fn leaves_clear_boxes_alone() {
+        let pos = DVec2::new(5.0, 3.0);
+        assert_close(resolve_overlap(pos, DVec2::repeat(HALF), &[floor(), wall()]), pos);
+    }
+
+    #[test]
+    fn corner_needs_two_pushes() {
+        let pos = DVec2::new(1.3, 1.2);
+        let resolved = resolve_overlap(pos, DVec2::repeat(HALF), &[floor(), wall()]);
+        assert_close(resolved, DVec2::new(1.5, 1.5));
+        let resolved_box = Aabb2::from_center(resolved, DVec2::repeat(HALF));
+        assert!(resolved_box.penetration(&floor()).is_none() && resolved_box.penetration(&wall()).is_none());
+    }
+
+    fn sweep(pos: DVec2, velocity: DVec2) -> (f64, DVec2) {
+        sweep_aabb(Aabb2::from_center(pos, DVec2::repeat(HALF)), velocity, &[floor(), wall()])
+    }
+
+    #[test]
+    fn sweep_stops_head_on_at_the_face() {
+        let (toi, normal) = sweep(DVec2::new(-3.0, 5.0), DVec2::new(4.0, 0.0));
+        assert!((toi - 0.625).abs() < 1e-9, "hit at {toi}");
+        assert_eq!(normal, DVec2::new(-1.0, 0.0));
+    }
+
+    #[test]
+    fn glancing_sweep_hits_the_face_it_crosses_first() {
+        // Coming down at an angle onto the floor, well clear of the wall.
+        let (toi, normal) = sweep(DVec2::new(3.0, 3.0), DVec2::new(2.0, -4.0));
+        assert!((toi - 0.375).abs() < 1e-9, "hit at {toi}");
+        assert_eq!(normal, DVec2::new(0.0, 1.0));
+        // Dropping just enough to clip the top of the wall's side.
+        let (toi, normal) = sweep(DVec2::new(-2.0, 10.8), DVec2::new(2.0, -1.0));
+        assert!((toi - 0.75).abs() < 1e-9, "hit at {toi}");
+        assert_eq!(normal, DVec2::new(-1.0, 0.0));
+    }
+
+    #[test]
+    fn sweep_without_a_collision_goes_the_whole_way() {
+        let clear = (1.0, DVec2::zeros());
+        // Short of the floor, passing over the wall, and sliding along the floor's top.
+        assert_eq!(sweep(DVec2::new(5.0, 5.0), DVec2::new(0.0, -2.0)), clear);
+        assert_eq!(sweep(DVec2::new(-3.0, 11.0), DVec2::new(6.0, 0.0)), clear);
+        assert_eq!(sweep(DVec2::new(3.0, 1.5), DVec2::new(4.0, 0.0)), clear);
+    }
+}
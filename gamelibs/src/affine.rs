@@ -0,0 +1,108 @@
+use nalgebra_glm::*;
+
+/// A double-precision 2D affine transform: a linear part `mat` followed by a `translation`.
+///
+/// This is the offline/procedural counterpart to Bevy's `f32` transforms, for placing things
+/// (rotated collider banks, mirrored level sections) without accumulating single-precision error.
+///
+/// * `Affine2::from_rotation(a)` rotates counter-clockwise by `a` radians.
+///
+/// * `Affine2::from_scale(s)` scales per axis; a negative component mirrors across that axis.
+///
+/// * `a.then(&b)` is the transform that applies `a` first and `b` second.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Affine2 {
+    pub mat: DMat2,
+    pub translation: DVec2,
+}
+
+impl Affine2 {
+    pub fn identity() -> Self {
+        Affine2 {
+            mat: DMat2::identity(),
+            translation: DVec2::zeros(),
+        }
+    }
+
+    pub fn from_rotation(angle: f64) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Affine2 {
+            mat: DMat2::new(
+                cos, -sin,
+                sin, cos,
+            ),
+            translation: DVec2::zeros(),
+        }
+    }
+
+    pub fn from_scale(scale: DVec2) -> Self {
+        Affine2 {
+            mat: DMat2::new(
+                scale.x, 0.0,
+                0.0, scale.y,
+            ),
+            translation: DVec2::zeros(),
+        }
+    }
+
+    pub fn from_translation(translation: DVec2) -> Self {
+        Affine2 {
+            mat: DMat2::identity(),
+            translation,
+        }
+    }
+
+    /// Composes two transforms so that `self` is applied first and `next` second.
+    pub fn then(&self, next: &Affine2) -> Self {
+        Affine2 {
+            mat: next.mat * self.mat,
+            translation: next.mat * self.translation + next.translation,
+        }
+    }
+
+    /// Transforms a position, applying both the linear part and the translation.
+    pub fn transform_point(&self, point: DVec2) -> DVec2 {
+        self.mat * point + self.translation
+    }
+
+    /// Transforms a direction or offset, which is unaffected by the translation.
+    pub fn transform_vector(&self, vector: DVec2) -> DVec2 {
+        self.mat * vector
+    }
+}
+
+impl Default for Affine2 {
+    fn default() -> Self {
+        Affine2::identity()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::FRAC_PI_2;
+
+    fn assert_close(a: DVec2, b: DVec2) {
+        assert!((a - b).norm() < 1e-12, "{a:?} != {b:?}");
+    }
+
+    #[test]
+    fn rotation_then_translation() {
+        let transform = Affine2::from_rotation(FRAC_PI_2).then(&Affine2::from_translation(DVec2::new(10.0, 0.0)));
+        assert_close(transform.transform_point(DVec2::new(1.0, 0.0)), DVec2::new(10.0, 1.0));
+        assert_close(transform.transform_vector(DVec2::new(1.0, 0.0)), DVec2::new(0.0, 1.0));
+    }
+
+    #[test]
+    fn translation_then_rotation() {
+        let transform = Affine2::from_translation(DVec2::new(10.0, 0.0)).then(&Affine2::from_rotation(FRAC_PI_2));
+        assert_close(transform.transform_point(DVec2::new(1.0, 0.0)), DVec2::new(0.0, 11.0));
+    }
+
+    #[test]
+    fn mirrored_scale() {
+        let transform = Affine2::from_scale(DVec2::new(-2.0, 1.0));
+        assert_close(transform.transform_point(DVec2::new(3.0, 4.0)), DVec2::new(-6.0, 4.0));
+        assert_eq!(Affine2::default().then(&transform), transform);
+    }
+}
@@ -0,0 +1,122 @@
+use crate::easing::{inverse_lerp, lerp};
+
+/// A colour as `[r, g, b, a]`, each channel nominally in `[0, 1]`.
+pub type Rgba = [f64; 4];
+
+/// A gradient through colour stops at increasing thresholds, e.g. red at `0.0`, yellow at `0.5`, green at `1.0` for a
+/// health bar.
+///
+/// Values between two stops blend them linearly; values before the first or after the last stop take that stop's
+/// colour.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColorRamp {
+    stops: Vec<(f64, Rgba)>,
+}
+
+impl ColorRamp {
+    /// Builds a ramp from `(threshold, colour)` stops in any order. Panics if there are no stops.
+    pub fn new(mut stops: Vec<(f64, Rgba)>) -> Self {
+        assert!(!stops.is_empty(), "a ColorRamp needs at least one stop");
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        ColorRamp { stops }
+    }
+
+    pub fn sample(&self, value: f64) -> Rgba {
+        let (first, last) = (self.stops[0], self.stops[self.stops.len() - 1]);
+        if value <= first.0 {
+            return first.1;
+        }
+        if value >= last.0 {
+            return last.1;
+        }
+
+        let upper = self.stops.iter().position(|stop| stop.0 > value).unwrap_or(self.stops.len() - 1);
+        let (from, to) = (self.stops[upper - 1], self.stops[upper]);
+        let t = inverse_lerp(from.0, to.0, value);
+        [0, 1, 2, 3].map(|channel| lerp(from.1[channel], to.1[channel], t))
+    }
+}
+
+/// The fractional part of the golden ratio as a 64-bit fixed-point fraction.
+const GOLDEN_RATIO_FRACTION: u64 = 0x9e37_79b9_7f4a_7c15;
+const DEBUG_SATURATION: f64 = 0.65;
+const DEBUG_VALUE: f64 = 0.95;
+
+/// An opaque colour for telling debug-drawn objects apart by `id`, the same on every run and platform.
+///
+/// Hues step around the wheel by the golden ratio (Fibonacci hashing), so consecutive ids, like entity indices,
+/// land far apart and no two ids share a hue until the wheel is very crowded. Saturation and value are fixed.
+pub fn debug_color_for(id: u64) -> Rgba {
+    let hashed = id.wrapping_mul(GOLDEN_RATIO_FRACTION);
+    // The top 53 bits fill an f64 mantissa exactly.
+    let hue = (hashed >> 11) as f64 / (1u64 << 53) as f64;
+    let [r, g, b] = hsv_to_rgb(hue, DEBUG_SATURATION, DEBUG_VALUE);
+    [r, g, b, 1.0]
+}
+
+/// Converts a colour from hue (in turns, `[0, 1)`), saturation and value to RGB.
+fn hsv_to_rgb(hue: f64, saturation: f64, value: f64) -> [f64; 3] {
+    let sector = hue.rem_euclid(1.0) * 6.0;
+    let chroma = value * saturation;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let min = value - chroma;
+    [r + min, g + min, b + min]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Rgba = [1.0, 0.0, 0.0, 1.0];
+    const YELLOW: Rgba = [1.0, 1.0, 0.0, 1.0];
+    const GREEN: Rgba = [0.0, 1.0, 0.0, 1.0];
+
+    fn health_ramp() -> ColorRamp {
+        ColorRamp::new(vec![(1.0, GREEN), (0.0, RED), (0.5, YELLOW)])
+    }
+
+    #[test]
+    fn blends_between_stops() {
+        let ramp = health_ramp();
+        assert_eq!(ramp.sample(0.25), [1.0, 0.5, 0.0, 1.0]);
+        assert_eq!(ramp.sample(0.75), [0.5, 1.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn matches_stops_exactly() {
+        let ramp = health_ramp();
+        assert_eq!(ramp.sample(0.0), RED);
+        assert_eq!(ramp.sample(0.5), YELLOW);
+        assert_eq!(ramp.sample(1.0), GREEN);
+    }
+
+    #[test]
+    fn clamps_outside_the_stops() {
+        let ramp = health_ramp();
+        assert_eq!(ramp.sample(-3.0), RED);
+        assert_eq!(ramp.sample(1.5), GREEN);
+        assert_eq!(ColorRamp::new(vec![(0.3, YELLOW)]).sample(0.9), YELLOW);
+    }
+
+    #[test]
+    fn debug_colors_are_stable_and_distinct() {
+        assert_eq!(debug_color_for(42), debug_color_for(42));
+
+        let colors: Vec<Rgba> = (0..32).map(debug_color_for).collect();
+        for (i, a) in colors.iter().enumerate() {
+            assert_eq!(a[3], 1.0);
+            for b in &colors[i + 1..] {
+                let difference = a.iter().zip(b).map(|(a, b)| (a - b).abs()).fold(0.0, f64::max);
+                assert!(difference > 0.02, "{a:?} and {b:?} look the same");
+            }
+        }
+    }
+}
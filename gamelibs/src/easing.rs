@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+
+/// Easing curves mapping normalized time `t` in `[0, 1]` to progress, for tweens and fades.
+///
+/// * `Ease::Linear` is a 1:1 response.
+///
+/// * `Ease::QuadIn`/`QuadOut`/`QuadInOut` and `Ease::CubicIn`/`CubicOut`/`CubicInOut` start slow, end slow, or both.
+///
+/// * `Ease::SineInOut` is a gentle start and stop, useful for looping motion.
+///
+/// * `Ease::BackOut` overshoots the target slightly before settling, for a "pop".
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Ease {
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    SineInOut,
+    BackOut,
+}
+
+/// Returns the eased progress for normalized time `t`, which is clamped to `[0, 1]`.
+pub fn ease(style: Ease, t: f64) -> f64 {
+    let t = t.clamp(0.0, 1.0);
+    match style {
+        Ease::Linear => t,
+        Ease::QuadIn => t * t,
+        Ease::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+        Ease::QuadInOut => {
+            if t < 0.5 {
+                2.0 * t * t
+            } else {
+                1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+            }
+        }
+        Ease::CubicIn => t * t * t,
+        Ease::CubicOut => 1.0 - (1.0 - t).powi(3),
+        Ease::CubicInOut => {
+            if t < 0.5 {
+                4.0 * t * t * t
+            } else {
+                1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+            }
+        }
+        Ease::SineInOut => -((std::f64::consts::PI * t).cos() - 1.0) / 2.0,
+        Ease::BackOut => {
+            const C1: f64 = 1.70158;
+            const C3: f64 = C1 + 1.0;
+            1.0 + C3 * (t - 1.0).powi(3) + C1 * (t - 1.0).powi(2)
+        }
+    }
+}
+
+/// Linearly interpolates from `a` to `b`; `t` is not clamped.
+pub fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a + (b - a) * t
+}
+
+/// The `t` at which `lerp(a, b, t) == value`, or `0` when `a == b`.
+pub fn inverse_lerp(a: f64, b: f64, value: f64) -> f64 {
+    if a == b {
+        return 0.0;
+    }
+    (value - a) / (b - a)
+}
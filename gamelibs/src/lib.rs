@@ -1,2 +1,57 @@
+#[cfg(feature = "glm")]
+pub mod aabb;
+#[cfg(feature = "glm")]
+pub mod affine;
+pub mod color;
+pub mod easing;
 pub mod map;
 pub mod math;
+pub mod noise;
+pub mod rng;
+#[cfg(feature = "glm")]
+pub mod sim;
+#[cfg(feature = "glm")]
+pub mod spatial;
+pub mod state_machine;
+
+/// `use gamelibs::prelude::*;` to import the commonly used math and gameplay helpers.
+pub mod prelude {
+    #[cfg(feature = "glm")]
+    pub use crate::aabb::{resolve_overlap, sweep_aabb, Aabb2};
+    pub use crate::color::{debug_color_for, ColorRamp, Rgba};
+    pub use crate::map::{cell_rects, greedy_rects, row_strips, CellRect};
+    pub use crate::math::{
+        approach_1d, clamp_timestep, ease, inverse_lerp, lerp, q_rsqrt, weighted_step, CurveFollower, CurveStyle,
+        CurveType, Ease, Spring, DEFAULT_MAX_STEP,
+    };
+    #[cfg(feature = "glm")]
+    pub use crate::math::{approach_2d, calc_weighted_next, Affine2, WeightedNextBundle};
+    #[cfg(feature = "glm")]
+    pub use crate::noise::shake_offset;
+    pub use crate::noise::value_noise;
+    pub use crate::rng::Rng;
+    #[cfg(feature = "glm")]
+    pub use crate::sim::{step_body, step_box, BodyStep};
+    #[cfg(feature = "glm")]
+    pub use crate::spatial::SpatialHash;
+    pub use crate::state_machine::StateMachine;
+}
+
+/// The scalar math is usable with every feature off: `cargo test --no-default-features`.
+#[cfg(all(test, not(any(feature = "bevy", feature = "glm"))))]
+mod tests {
+    use crate::prelude::*;
+
+    #[test]
+    fn scalar_math_builds_without_features() {
+        assert!((q_rsqrt(0.25) - 2.0).abs() < 1e-9);
+        assert_eq!(ease(Ease::QuadIn, 1.0), 1.0);
+        assert_eq!(lerp(2.0, 4.0, 0.5), 3.0);
+        assert_eq!(value_noise(1.5, 3), value_noise(1.5, 3));
+        assert_eq!(Rng::new(3).next_u64(), Rng::new(3).next_u64());
+
+        let mut follower = CurveFollower::new(CurveStyle::Custom { f: 2.0, z: 1.0, r: 0.0 }, 0.0);
+        let value = (0..600).map(|_| follower.step(1.0, 1.0 / 60.0)).last().unwrap();
+        assert!((value - 1.0).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,115 @@
+/// A rectangle of grid cells, with `x`/`y` the lowest corner cell and `width`/`height` in cells.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CellRect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+/// One rectangle per set cell of a row-major `width` x `height` grid, with no merging at all.
+pub fn cell_rects(cells: &[bool], width: usize, height: usize) -> Vec<CellRect> {
+    (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .filter(|&(x, y)| cells[y * width + x])
+        .map(|(x, y)| CellRect { x, y, width: 1, height: 1 })
+        .collect()
+}
+
+/// Merges each run of set cells along a row of a row-major `width` x `height` grid into one rectangle.
+///
+/// Cheaper than `greedy_rects` and never merges across rows, so every rectangle is a single cell tall.
+pub fn row_strips(cells: &[bool], width: usize, height: usize) -> Vec<CellRect> {
+    let mut rects = Vec::new();
+    for y in 0..height {
+        let mut x = 0;
+        while x < width {
+            if !cells[y * width + x] {
+                x += 1;
+                continue;
+            }
+            let start = x;
+            while x < width && cells[y * width + x] {
+                x += 1;
+            }
+            rects.push(CellRect { x: start, y, width: x - start, height: 1 });
+        }
+    }
+    rects
+}
+
+/// Merges the set cells of a row-major `width` x `height` grid into rectangles with a greedy sweep.
+///
+/// Each unclaimed cell starts a rectangle that grows along its row as far as it can, then grows across rows
+/// for as long as the whole span is set. The result covers exactly the set cells with no overlaps, and is far
+/// fewer shapes than one per cell for typical level geometry, though not always the minimum.
+pub fn greedy_rects(cells: &[bool], width: usize, height: usize) -> Vec<CellRect> {
+    let mut claimed = vec![false; cells.len()];
+    let mut rects = Vec::new();
+    let free = |claimed: &[bool], x: usize, y: usize| cells[y * width + x] && !claimed[y * width + x];
+
+    for y in 0..height {
+        for x in 0..width {
+            if !free(&claimed, x, y) {
+                continue;
+            }
+
+            let mut rect_width = 1;
+            while x + rect_width < width && free(&claimed, x + rect_width, y) {
+                rect_width += 1;
+            }
+
+            let mut rect_height = 1;
+            while y + rect_height < height
+                && (x..x + rect_width).all(|column| free(&claimed, column, y + rect_height))
+            {
+                rect_height += 1;
+            }
+
+            for row in y..y + rect_height {
+                for column in x..x + rect_width {
+                    claimed[row * width + column] = true;
+                }
+            }
+            rects.push(CellRect { x, y, width: rect_width, height: rect_height });
+        }
+    }
+
+    rects
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// How many of `rects` cover each cell, row-major.
+    fn coverage(rects: &[CellRect], width: usize, height: usize) -> Vec<usize> {
+        let mut covered = vec![0; width * height];
+        for rect in rects {
+            for y in rect.y..rect.y + rect.height {
+                for x in rect.x..rect.x + rect.width {
+                    covered[y * width + x] += 1;
+                }
+            }
+        }
+        covered
+    }
+
+    #[test]
+    fn every_strategy_covers_the_same_cells() {
+        let rows = ["##..####", "##..#..#", "########", "......#."];
+        let (width, height) = (rows[0].len(), rows.len());
+        let cells: Vec<bool> = rows.iter().flat_map(|row| row.chars().map(|c| c == '#')).collect();
+        let expected: Vec<usize> = cells.iter().map(|&set| set as usize).collect();
+
+        let per_cell = cell_rects(&cells, width, height);
+        let strips = row_strips(&cells, width, height);
+        let greedy = greedy_rects(&cells, width, height);
+        for rects in [&per_cell, &strips, &greedy] {
+            // Exactly once each: no gaps and no overlaps.
+            assert_eq!(coverage(rects, width, height), expected);
+        }
+        assert_eq!(per_cell.len(), cells.iter().filter(|&&set| set).count());
+        assert!(greedy.len() <= strips.len() && strips.len() < per_cell.len());
+    }
+}
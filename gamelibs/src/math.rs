@@ -1,6 +1,19 @@
+#[cfg(feature = "glm")]
 use nalgebra_glm::*;
 use std::f64::consts::PI;
 
+#[cfg(feature = "glm")]
+pub use crate::aabb::{resolve_overlap, sweep_aabb, Aabb2};
+#[cfg(feature = "glm")]
+pub use crate::affine::Affine2;
+pub use crate::easing::{ease, inverse_lerp, lerp, Ease};
+pub use crate::noise::value_noise;
+pub use crate::rng::Rng;
+
+/// The longest timestep `calc_weighted_next` takes by default, in seconds. Longer ones, from a frame hitch, can make
+/// stiff curves overshoot wildly, so they're cut down to this.
+pub const DEFAULT_MAX_STEP: f64 = 0.1;
+
 /// Defines the curve type based on the information in [**this video**](https://www.youtube.com/watch?v=KPoeNZZ6H4s).
 /// 
 /// * `CurveType::Linear` is a 1:1 I/O response.
@@ -25,7 +38,8 @@ use std::f64::consts::PI;
 ///         * At a value of `1`, the initial response follows the input function.
 ///         * At values where `1 < r`, the initial response causes an overshooting of the intended ceiling of the input function.
 ///         * At values where `r < 0`, the initial response is negative, causing an anticipation of the intended intended movement of the input function.
-enum CurveStyle {
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CurveStyle {
     Linear,
     Bezier,
     SmoothDamped,
@@ -33,7 +47,9 @@ enum CurveStyle {
     Custom{f: f64, z: f64, r: f64},
 }
 
-struct CurveType {
+/// The precomputed constants of a `CurveStyle`, ready to feed to `calc_weighted_next`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CurveType {
     f: f64,
     z: f64,
     r: f64,
@@ -43,7 +59,7 @@ struct CurveType {
 }
 
 impl CurveType {
-    fn from_style(c: CurveStyle) -> Self {
+    pub fn from_style(c: CurveStyle) -> Self {
         let get_fzr = 
             match c {
                 CurveStyle::Linear => CurveType {
@@ -88,42 +104,76 @@ impl CurveType {
         let z = get_fzr.z;
         let r = get_fzr.r;
         let _w = 2.0 * PI * f;
-        let _z = 0.0;
+        let _z = z;
         let _d = _w * f64::sqrt(f64::abs(z * z - 1.0));
         
+        // `f`, `z` and `r` hold k1, k2 and k3 respectively, in the order `weighted_step` reads them.
         CurveType {
             f: z / (PI * f),
-            r: 1.0 / (_w * _w),
-            z: (r * z) / _w,
+            z: 1.0 / (_w * _w),
+            r: (r * z) / _w,
             _w, _z, _d
         }
     }
 }
 
-struct WeightedNextBundle <F: Fn(f64) -> f64> {
-    base_func: F,
-    time: f64,
-    curve: CurveType,
-    last_pos: DVec3,
-    last_vel: DVec3,
-    last_acc: DVec3,
+/// One step of input to `calc_weighted_next`: the function being followed, the timestep, and the previous state.
+#[cfg(feature = "glm")]
+pub struct WeightedNextBundle <F: Fn(f64) -> f64> {
+    pub base_func: F,
+    pub time: f64,
+    /// The longest timestep taken; see `clamp_timestep`. Usually `DEFAULT_MAX_STEP`.
+    pub max_step: f64,
+    pub curve: CurveType,
+    pub last_pos: DVec3,
+    pub last_vel: DVec3,
+    pub last_acc: DVec3,
+}
+
+/// The timestep to actually take for a requested `dt`: `None` when it's zero, negative or not a number, since
+/// there's nothing to advance, and at most `max_step`, logging a warning when it has to be cut down.
+pub fn clamp_timestep(dt: f64, max_step: f64) -> Option<f64> {
+    if dt.is_nan() || dt <= 0.0 {
+        return None;
+    }
+    if dt > max_step {
+        log::warn!("Clamped a curve timestep of {dt}s to {max_step}s");
+        return Some(max_step);
+    }
+    Some(dt)
 }
 
-fn calc_weighted_next<F: Fn(f64) -> f64>(w: WeightedNextBundle<F>) ->
+/// Advances a second-order system following `base_func` by one step, returning the new position and velocity. A
+/// timestep that `clamp_timestep` rejects returns the previous state unchanged.
+#[cfg(feature = "glm")]
+pub fn calc_weighted_next<F: Fn(f64) -> f64>(w: WeightedNextBundle<F>) ->
 (DVec3, DVec3) {
-    /* Var initialization and definition */
-    let k1: f64 = w.curve.f;
-    let k2: f64 = w.curve.z;
-    let k3: f64 = w.curve.r;
-    let _w: f64 = w.curve._w;
-    let _z: f64 = w.curve._z;
-    let _d: f64 = w.curve._d;
-    let t: f64 = w.time;
-    let x: f64 = (w.base_func)(t);
-    let xd: f64 = derivative(w.base_func, t);
+    let Some(t) = clamp_timestep(w.time, w.max_step) else {
+        return (w.last_pos, w.last_vel);
+    };
+    let x: f64 = (w.base_func)(w.time);
+    let xd: f64 = derivative(w.base_func, w.time);
     let mut y = w.last_pos;
     let mut yd = w.last_vel;
 
+    for i in 0..3 {
+        (y[i], yd[i]) = weighted_step(&w.curve, t, x, xd, y[i], yd[i]);
+    }
+
+    (y, yd)
+}
+
+/// One step of `calc_weighted_next` along a single axis: follows `x` (with derivative `xd`) from position `y` and
+/// velocity `yd` over `t` seconds, returning the new position and velocity.
+pub fn weighted_step(curve: &CurveType, t: f64, x: f64, xd: f64, y: f64, yd: f64) -> (f64, f64) {
+    /* Var initialization and definition */
+    let k1: f64 = curve.f;
+    let k2: f64 = curve.z;
+    let k3: f64 = curve.r;
+    let _w: f64 = curve._w;
+    let _z: f64 = curve._z;
+    let _d: f64 = curve._d;
+
     let k1_stable: f64;
     let k2_stable: f64;
 
@@ -136,12 +186,11 @@ fn calc_weighted_next<F: Fn(f64) -> f64>(w: WeightedNextBundle<F>) ->
         );
     } else { // Pole matching algorithm
         let t1: f64 = f64::exp(-_z * _w * t);
-        let temp: f64;
-        if _z <= 1.0 {
-            temp = f64::cos(t * _d);
+        let temp: f64 = if _z <= 1.0 {
+            f64::cos(t * _d)
         } else {
-            temp = f64::cosh(t * _d);
-        }
+            f64::cosh(t * _d)
+        };
         let alpha = 2.0 * t1 * temp;
         let beta = t1 * t1;
         let t2 = t / (1.0 + beta - alpha);
@@ -151,27 +200,157 @@ fn calc_weighted_next<F: Fn(f64) -> f64>(w: WeightedNextBundle<F>) ->
     }
 
     /* Update position */
-    y.x = y.x + t * yd.x;
-    y.y = y.y + t * yd.y;
-    y.z = y.z + t * yd.z;
-    yd.x = yd.x + t * (x + k3 * xd - y.x - k1_stable * yd.x) / k2_stable;
-    yd.y = yd.y + t * (x + k3 * xd - y.y - k1_stable * yd.y) / k2_stable;
-    yd.z = yd.z + t * (x + k3 * xd - y.z - k1_stable * yd.z) / k2_stable;
+    let y = y + t * yd;
+    let yd = yd + t * (x + k3 * xd - y - k1_stable * yd) / k2_stable;
+
+    (y, yd)
+}
+
+/// A single value smoothly following a moving target through `calc_weighted_next`, e.g. one axis of a camera.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CurveFollower {
+    pub curve: CurveType,
+    pub value: f64,
+    pub velocity: f64,
+}
+
+impl CurveFollower {
+    pub fn new(style: CurveStyle, value: f64) -> Self {
+        CurveFollower {
+            curve: CurveType::from_style(style),
+            value,
+            velocity: 0.0,
+        }
+    }
 
-    return (y, yd)
+    /// Advances `dt` seconds towards `target` and returns the new value. Like `calc_weighted_next`, a `dt` that
+    /// `clamp_timestep` rejects leaves it where it is, and one longer than `DEFAULT_MAX_STEP` is cut down to it.
+    pub fn step(&mut self, target: f64, dt: f64) -> f64 {
+        let Some(dt) = clamp_timestep(dt, DEFAULT_MAX_STEP) else { return self.value };
+        // A fixed target doesn't move, so its derivative is zero.
+        (self.value, self.velocity) = weighted_step(&self.curve, dt, target, 0.0, self.value, self.velocity);
+        self.value
+    }
+
+    /// Jumps straight to `value` and stops moving.
+    pub fn reset(&mut self, value: f64) {
+        self.value = value;
+        self.velocity = 0.0;
+    }
+}
+
+/// A damped spring pulling `value` towards a target, for bouncy motion that overshoots and settles (a wobbling
+/// pickup, a squashed sprite recovering). Unlike `CurveFollower` it's set up in physical terms:
+///
+/// * `stiffness` is how hard the spring pulls per unit of distance, in 1/s²; higher is snappier.
+///
+/// * `damping` is how much of the velocity it bleeds off, in 1/s. `2 * sqrt(stiffness)` is critically damped and
+///   settles fastest without overshooting; less wobbles, more creeps in slowly.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Spring {
+    pub stiffness: f64,
+    pub damping: f64,
+    pub value: f64,
+    pub velocity: f64,
+}
+
+impl Spring {
+    pub fn new(stiffness: f64, damping: f64, value: f64) -> Self {
+        Spring { stiffness, damping, value, velocity: 0.0 }
+    }
+
+    /// A spring of `stiffness` with just enough damping to settle without overshooting.
+    pub fn critically_damped(stiffness: f64, value: f64) -> Self {
+        Spring::new(stiffness, 2.0 * stiffness.max(0.0).sqrt(), value)
+    }
+
+    /*
+     * Advances `dt` seconds towards `target` and returns the new value.
+     *
+     * The step is implicit (backward Euler): the spring and damping forces are taken at the end of the step, not
+     * the start, so however stiff the spring or long the frame it loses energy rather than blowing up.
+     */
+    pub fn step(&mut self, target: f64, dt: f64) -> f64 {
+        if dt.is_nan() || dt <= 0.0 {
+            return self.value;
+        }
+        let pull = self.stiffness * (target - self.value);
+        self.velocity = (self.velocity + dt * pull) / (1.0 + dt * self.damping + dt * dt * self.stiffness);
+        self.value += self.velocity * dt;
+        self.value
+    }
+
+    /// Jumps straight to `value` and stops moving.
+    pub fn reset(&mut self, value: f64) {
+        self.value = value;
+        self.velocity = 0.0;
+    }
+}
+
+/// The velocity to head for when `distance` (never negative) from the target: as fast as `max_speed` allows, but no
+/// faster than can still be braked to a stop at `max_accel`, or than covers the distance in one `dt` step.
+fn approach_speed(distance: f64, max_speed: f64, max_accel: f64, dt: f64) -> f64 {
+    max_speed.max(0.0).min((2.0 * max_accel.max(0.0) * distance).sqrt()).min(distance / dt)
+}
+
+/*
+ * Moves `pos` with velocity `vel` towards `target` over `dt` seconds, never faster than `max_speed` nor changing
+ * speed by more than `max_accel` a second: it speeds up, cruises, then brakes to stop on the target, a trapezoidal
+ * velocity profile rather than the curves' easing. Returns the new position and velocity.
+ *
+ * It never goes past the target. Reaching it stops it dead there, even if it came in too fast to brake in time.
+ */
+pub fn approach_1d(pos: f64, vel: f64, target: f64, max_speed: f64, max_accel: f64, dt: f64) -> (f64, f64) {
+    let offset = target - pos;
+    if dt <= 0.0 || (offset == 0.0 && vel == 0.0) {
+        return (pos, vel);
+    }
+    let desired = offset.signum() * approach_speed(offset.abs(), max_speed, max_accel, dt);
+    let budget = max_accel.max(0.0) * dt;
+    let vel = vel + (desired - vel).clamp(-budget, budget);
+    let pos = pos + vel * dt;
+    if (target - pos) * offset <= 0.0 {
+        (target, 0.0)
+    } else {
+        (pos, vel)
+    }
+}
+
+/// `approach_1d` in two dimensions, heading straight for `target` with `max_speed` and `max_accel` as limits on the
+/// length of the velocity and its change. Stops dead on the target once it would pass it.
+#[cfg(feature = "glm")]
+pub fn approach_2d(pos: DVec2, vel: DVec2, target: DVec2, max_speed: f64, max_accel: f64, dt: f64) -> (DVec2, DVec2) {
+    let offset = target - pos;
+    if dt <= 0.0 || (offset == DVec2::zeros() && vel == DVec2::zeros()) {
+        return (pos, vel);
+    }
+    let distance = offset.norm();
+    let direction = if distance > 0.0 { offset / distance } else { DVec2::zeros() };
+    let desired = direction * approach_speed(distance, max_speed, max_accel, dt);
+    let budget = max_accel.max(0.0) * dt;
+    let change = desired - vel;
+    let change = if change.norm() > budget { change.normalize() * budget } else { change };
+    let vel = vel + change;
+    let pos = pos + vel * dt;
+    if (target - pos).dot(&offset) <= 0.0 {
+        (target, DVec2::zeros())
+    } else {
+        (pos, vel)
+    }
 }
 
 /// Returns the derivative of a given function f(x) using Newtonian approximation
+#[cfg(feature = "glm")]
 fn derivative<F: Fn(f64) -> f64>(
     f: F,   // the function to be derived
     x: f64, // the argument to be derived from
 ) -> f64 {
-    const DELTA: f64 = f64::MIN_POSITIVE;
+    const DELTA: f64 = 1e-6; // small enough to be accurate, large enough that x ± DELTA != x
     let x1: f64 = x - DELTA;
     let x2: f64 = x + DELTA;
     let y1: f64 = f(x1);
     let y2: f64 = f(x2);
-    return (y2 - y1) / (x2 - x1)
+    (y2 - y1) / (x2 - x1)
 }
 
 /*
@@ -185,5 +364,156 @@ pub fn q_rsqrt(f_in: f64) -> f64 {
     let f_out: f64 = f_out * (1.5 - 0.5 * f_in * f_out * f_out); // 1st iteration
     let f_out: f64 = f_out * (1.5 - 0.5 * f_in * f_out * f_out); // 2nd iteration, can be removed
     let f_out: f64 = f_out * (1.5 - 0.5 * f_in * f_out * f_out); // 3rd iteration, can be removed; provides full precision.
-    return f_out;
+    f_out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Follows a target that jumps from 0 to 1 for `seconds` at 60 steps a second, returning the value each step.
+    fn step_response(style: CurveStyle, seconds: f64) -> Vec<f64> {
+        let mut follower = CurveFollower::new(style, 0.0);
+        (0..(seconds * 60.0) as usize).map(|_| follower.step(1.0, 1.0 / 60.0)).collect()
+    }
+
+    #[test]
+    fn constants_follow_the_second_order_system() {
+        let (f, z, r) = (2.0, 0.5, 1.5);
+        let curve = CurveType::from_style(CurveStyle::Custom { f, z, r });
+        let w = 2.0 * PI * f;
+        // k1, k2 and k3, in the order `weighted_step` reads them.
+        assert!((curve.f - z / (PI * f)).abs() < 1e-12);
+        assert!((curve.z - 1.0 / (w * w)).abs() < 1e-12);
+        assert!((curve.r - r * z / w).abs() < 1e-12);
+        assert_eq!(curve._z, z);
+    }
+
+    #[test]
+    fn critically_damped_curve_settles_without_overshoot() {
+        let response = step_response(CurveStyle::SmoothDamped, 5.0);
+        assert!(response.iter().all(|value| value.is_finite() && *value <= 1.0 + 1e-9));
+        assert!((response.last().unwrap() - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn underdamped_curve_overshoots_then_settles() {
+        let response = step_response(CurveStyle::Custom { f: 1.0, z: 0.3, r: 0.0 }, 10.0);
+        assert!(response.iter().any(|value| *value > 1.05));
+        assert!((response.last().unwrap() - 1.0).abs() < 1e-3);
+    }
+
+    #[cfg(feature = "glm")]
+    #[test]
+    fn derivative_of_a_parabola() {
+        assert!((derivative(|x| x * x, 3.0) - 6.0).abs() < 1e-6);
+        assert!((derivative(|x| x * x, 1000.0) - 2000.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn spring_settles_on_target() {
+        let mut spring = Spring::new(200.0, 5.0, 0.0);
+        let mut overshot = false;
+        for _ in 0..600 {
+            overshot |= spring.step(1.0, 1.0 / 60.0) > 1.0;
+        }
+        assert!(overshot, "an underdamped spring should wobble past the target");
+        assert!((spring.value - 1.0).abs() < 1e-3 && spring.velocity.abs() < 1e-2);
+    }
+
+    #[test]
+    fn critically_damped_spring_never_overshoots() {
+        let mut spring = Spring::critically_damped(100.0, 0.0);
+        for _ in 0..600 {
+            assert!(spring.step(1.0, 1.0 / 60.0) <= 1.0);
+        }
+        assert!((spring.value - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn spring_survives_long_steps() {
+        let mut spring = Spring::new(10_000.0, 1.0, 0.0);
+        for _ in 0..100 {
+            spring.step(1.0, 0.5);
+        }
+        assert!(spring.value.is_finite() && (spring.value - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn timesteps_are_rejected_or_clamped() {
+        assert_eq!(clamp_timestep(0.0, DEFAULT_MAX_STEP), None);
+        assert_eq!(clamp_timestep(-0.016, DEFAULT_MAX_STEP), None);
+        assert_eq!(clamp_timestep(f64::NAN, DEFAULT_MAX_STEP), None);
+        assert_eq!(clamp_timestep(0.016, DEFAULT_MAX_STEP), Some(0.016));
+        assert_eq!(clamp_timestep(3.0, DEFAULT_MAX_STEP), Some(DEFAULT_MAX_STEP));
+    }
+
+    #[cfg(feature = "glm")]
+    #[test]
+    fn weighted_next_holds_on_bad_steps_and_clamps_long_ones() {
+        let next = |time: f64| {
+            calc_weighted_next(WeightedNextBundle {
+                base_func: |_| 1.0,
+                time,
+                max_step: DEFAULT_MAX_STEP,
+                curve: CurveType::from_style(CurveStyle::Mechanical { f: 8.0, z: 0.2 }),
+                last_pos: DVec3::zeros(),
+                last_vel: DVec3::new(1.0, 0.0, 0.0),
+                last_acc: DVec3::zeros(),
+            })
+        };
+        let last = (DVec3::zeros(), DVec3::new(1.0, 0.0, 0.0));
+        assert_eq!(next(0.0), last);
+        assert_eq!(next(-1.0), last);
+        assert_eq!(next(f64::NAN), last);
+        // A long hitch takes one ordinary step rather than flinging the stiff curve off.
+        assert_eq!(next(5.0), next(DEFAULT_MAX_STEP));
+        assert!(next(5.0).0.iter().all(|value| value.is_finite() && value.abs() < 2.0));
+    }
+
+    #[test]
+    fn approach_arrives_without_overshooting() {
+        let (mut pos, mut vel) = (0.0, 0.0);
+        let mut steps = 0;
+        while (pos, vel) != (10.0, 0.0) {
+            (pos, vel) = approach_1d(pos, vel, 10.0, 4.0, 8.0, 1.0 / 60.0);
+            assert!(pos <= 10.0 && vel <= 4.0 + 1e-9);
+            steps += 1;
+            assert!(steps < 600, "never arrived, at {pos} moving {vel}");
+        }
+        // Coming in far too fast to brake still stops dead on the target.
+        assert_eq!(approach_1d(9.0, 100.0, 10.0, 4.0, 8.0, 0.125), (10.0, 0.0));
+        assert_eq!(approach_1d(3.0, 0.0, 3.0, 4.0, 8.0, 0.125), (3.0, 0.0));
+    }
+
+    #[cfg(feature = "glm")]
+    #[test]
+    fn approach_2d_arrives_without_overshooting() {
+        let target = DVec2::new(6.0, -8.0);
+        let (mut pos, mut vel) = (DVec2::zeros(), DVec2::new(0.0, 3.0));
+        let mut steps = 0;
+        while pos != target || vel != DVec2::zeros() {
+            (pos, vel) = approach_2d(pos, vel, target, 4.0, 8.0, 1.0 / 60.0);
+            assert!((target - pos).dot(&target) >= 0.0 && vel.norm() <= 4.0 + 1e-9);
+            steps += 1;
+            assert!(steps < 600, "never arrived, at {pos} moving {vel}");
+        }
+    }
+
+    #[test]
+    fn follower_ignores_bad_steps_and_clamps_long_ones() {
+        let mut follower = CurveFollower::new(CurveStyle::Mechanical { f: 8.0, z: 0.2 }, 0.0);
+        follower.step(1.0, 1.0 / 60.0);
+        let before = follower;
+        for dt in [f64::NAN, 0.0, -1.0] {
+            assert_eq!(follower.step(1.0, dt), before.value);
+            assert_eq!(follower, before);
+        }
+
+        let mut clamped = before;
+        clamped.step(1.0, DEFAULT_MAX_STEP);
+        follower.step(1.0, 5.0);
+        assert_eq!(follower, clamped);
+        assert!(follower.value.is_finite() && follower.velocity.is_finite());
+    }
 }
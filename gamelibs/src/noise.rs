@@ -0,0 +1,73 @@
+#[cfg(feature = "glm")]
+use nalgebra_glm::*;
+
+use crate::rng::Rng;
+
+/// How many times a second `shake_offset` changes direction, roughly.
+pub const SHAKE_FREQUENCY: f64 = 15.0;
+/// Mixed into the seed for the vertical axis of a shake, so it doesn't move in step with the horizontal one.
+#[cfg(feature = "glm")]
+const SHAKE_Y_SALT: u64 = 0x5bd1e9955bd1e995;
+
+/// Smooth one-dimensional noise in `[-1, 1]`: a random value at every whole `x`, eased between. The same `x` and
+/// `seed` always give the same value.
+pub fn value_noise(x: f64, seed: u64) -> f64 {
+    let cell = x.floor();
+    let t = x - cell;
+    // Smoothstep, so the noise has no corners at the lattice points.
+    let t = t * t * (3.0 - 2.0 * t);
+    let a = lattice_value(cell as i64, seed);
+    let b = lattice_value(cell as i64 + 1, seed);
+    a + (b - a) * t
+}
+
+/// The random value at lattice point `i`, in `[-1, 1)`.
+fn lattice_value(i: i64, seed: u64) -> f64 {
+    Rng::new(seed ^ (i as u64).wrapping_mul(0x9e3779b97f4a7c15)).next_f64() * 2.0 - 1.0
+}
+
+/// An offset for shaking something by `trauma` (0 to 1) `time` seconds in, each axis at most 1 either way. It grows
+/// with the square of the trauma, so light hits barely move it and heavy ones throw it about, and wanders smoothly
+/// through value noise rather than jittering. Scale it by the largest offset wanted, in pixels or radians.
+#[cfg(feature = "glm")]
+pub fn shake_offset(trauma: f64, time: f64, seed: u64) -> DVec2 {
+    let magnitude = trauma.clamp(0.0, 1.0).powi(2);
+    if magnitude == 0.0 {
+        return DVec2::zeros();
+    }
+    let x = time * SHAKE_FREQUENCY;
+    DVec2::new(value_noise(x, seed), value_noise(x, seed ^ SHAKE_Y_SALT)) * magnitude
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn value_noise_is_smooth_and_bounded() {
+        let samples: Vec<f64> = (0..1000).map(|i| value_noise(i as f64 * 0.01, 7)).collect();
+        assert!(samples.iter().all(|value| (-1.0..=1.0).contains(value)));
+        assert!(samples.windows(2).all(|pair| (pair[1] - pair[0]).abs() < 0.05), "no jumps between close samples");
+        assert_eq!(value_noise(3.3, 7), value_noise(3.3, 7));
+    }
+
+    #[cfg(feature = "glm")]
+    #[test]
+    fn no_trauma_no_shake() {
+        for time in [0.0, 0.37, 12.5] {
+            assert_eq!(shake_offset(0.0, time, 7), DVec2::zeros());
+            assert_eq!(shake_offset(-1.0, time, 7), DVec2::zeros());
+        }
+    }
+
+    #[cfg(feature = "glm")]
+    #[test]
+    fn shake_grows_with_the_square_of_trauma() {
+        for time in [0.0, 0.37, 12.5] {
+            let full = shake_offset(1.0, time, 7);
+            assert!(full.x.abs() <= 1.0 && full.y.abs() <= 1.0);
+            assert!((shake_offset(0.5, time, 7) - full * 0.25).norm() < 1e-12);
+            assert_eq!(shake_offset(3.0, time, 7), full, "trauma is capped at 1");
+        }
+    }
+}
@@ -0,0 +1,117 @@
+/// A small, fast, seeded random number generator (SplitMix64).
+///
+/// The same seed always produces the same sequence on every platform, so anything generated from it (spawns,
+/// loot, decorations) is reproducible. It is not suitable for cryptography.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// A uniform float in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        // The top 53 bits fill an f64 mantissa exactly.
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// A uniform float in `[min, max)`.
+    pub fn range_f64(&mut self, min: f64, max: f64) -> f64 {
+        min + (max - min) * self.next_f64()
+    }
+
+    /// A uniform index in `[0, len)`; `len` must not be zero.
+    pub fn index(&mut self, len: usize) -> usize {
+        (self.next_f64() * len as f64) as usize
+    }
+
+    /// Shuffles `slice` in place with a Fisher–Yates shuffle.
+    pub fn shuffle<T>(&mut self, slice: &mut [T]) {
+        for i in (1..slice.len()).rev() {
+            let j = self.index(i + 1);
+            slice.swap(i, j);
+        }
+    }
+
+    /// Picks an index with probability proportional to its weight.
+    ///
+    /// Negative and NaN weights count as zero. Returns `None` when there is nothing to pick: `weights` is empty or
+    /// every weight is zero.
+    pub fn weighted_index(&mut self, weights: &[f64]) -> Option<usize> {
+        let weight = |w: f64| if w > 0.0 { w } else { 0.0 };
+        let total: f64 = weights.iter().copied().map(weight).sum();
+        if total <= 0.0 {
+            return None;
+        }
+
+        let mut target = self.next_f64() * total;
+        for (i, w) in weights.iter().copied().map(weight).enumerate() {
+            if target < w {
+                return Some(i);
+            }
+            target -= w;
+        }
+        // Rounding can leave `target` just past the end; fall back to the last index that could be picked.
+        weights.iter().rposition(|&w| w > 0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_seed_always_shuffles_the_same_way() {
+        let shuffled = |seed| {
+            let mut values: Vec<u32> = (0..32).collect();
+            Rng::new(seed).shuffle(&mut values);
+            values
+        };
+        assert_eq!(shuffled(7), shuffled(7));
+        assert_ne!(shuffled(7), shuffled(8));
+
+        let mut sorted = shuffled(7);
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..32).collect::<Vec<_>>());
+
+        let mut empty: [u32; 0] = [];
+        Rng::new(7).shuffle(&mut empty);
+    }
+
+    #[test]
+    fn weighted_picks_converge_to_the_weights() {
+        let weights = [1.0, 0.0, 3.0, 6.0];
+        let draws = 100_000;
+        let mut rng = Rng::new(42);
+        let mut counts = [0usize; 4];
+        for _ in 0..draws {
+            counts[rng.weighted_index(&weights).unwrap()] += 1;
+        }
+
+        let total: f64 = weights.iter().sum();
+        for (count, weight) in counts.iter().zip(weights) {
+            let share = *count as f64 / draws as f64;
+            assert!((share - weight / total).abs() < 0.01, "{counts:?}");
+        }
+    }
+
+    #[test]
+    fn nothing_to_pick_is_none() {
+        let mut rng = Rng::new(1);
+        assert_eq!(rng.weighted_index(&[]), None);
+        assert_eq!(rng.weighted_index(&[0.0, 0.0]), None);
+        assert_eq!(rng.weighted_index(&[-1.0, f64::NAN]), None);
+        assert_eq!(rng.weighted_index(&[0.0, 2.0]), Some(1));
+    }
+}
@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+
+use nalgebra_glm::*;
+
+use crate::aabb::Aabb2;
+
+/// A uniform grid of square cells bucketing boxes by where they are, so a region can be searched without testing
+/// every box. `T` identifies each box to the caller: an entity, or an index into their own list.
+///
+/// A box goes into every cell it covers. Cells should be about the size of the boxes in it: much smaller and big
+/// boxes fill many cells, much larger and each query sifts through boxes that are nowhere near.
+#[derive(Clone, Debug)]
+pub struct SpatialHash<T> {
+    cell_size: f64,
+    entries: Vec<(T, Aabb2)>,
+    /// Indices into `entries`, by cell.
+    cells: HashMap<(i64, i64), Vec<usize>>,
+}
+
+impl<T: Copy> SpatialHash<T> {
+    /// An empty grid of `cell_size`-wide cells.
+    pub fn new(cell_size: f64) -> Self {
+        assert!(cell_size > 0.0, "cell size must be positive");
+        SpatialHash {
+            cell_size,
+            entries: Vec::new(),
+            cells: HashMap::new(),
+        }
+    }
+
+    pub fn cell_size(&self) -> f64 {
+        self.cell_size
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn insert(&mut self, id: T, aabb: Aabb2) {
+        let index = self.entries.len();
+        self.entries.push((id, aabb));
+        for cell in self.cells_covering(&aabb) {
+            self.cells.entry(cell).or_default().push(index);
+        }
+    }
+
+    /// Removes every box, keeping the memory for the next frame's.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.cells.values_mut().for_each(Vec::clear);
+    }
+
+    /// The ids of every box overlapping `region`, each once, in the order they were inserted. Boxes that only touch
+    /// its edge don't count, as with `Aabb2::overlaps`.
+    pub fn query_region(&self, region: Aabb2) -> Vec<T> {
+        let mut found: Vec<usize> = self
+            .cells_covering(&region)
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .filter(|&index| self.entries[index].1.overlaps(&region))
+            .collect();
+        // A box spanning several cells turns up once for each of them.
+        found.sort_unstable();
+        found.dedup();
+        found.into_iter().map(|index| self.entries[index].0).collect()
+    }
+
+    fn cell_of(&self, point: DVec2) -> (i64, i64) {
+        ((point.x / self.cell_size).floor() as i64, (point.y / self.cell_size).floor() as i64)
+    }
+
+    fn cells_covering(&self, aabb: &Aabb2) -> impl Iterator<Item = (i64, i64)> {
+        let (min, max) = (self.cell_of(aabb.min), self.cell_of(aabb.max));
+        (min.0..=max.0).flat_map(move |x| (min.1..=max.1).map(move |y| (x, y)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aabb(min: (f64, f64), max: (f64, f64)) -> Aabb2 {
+        Aabb2::new(DVec2::new(min.0, min.1), DVec2::new(max.0, max.1))
+    }
+
+    fn grid() -> SpatialHash<u32> {
+        let mut grid = SpatialHash::new(10.0);
+        grid.insert(1, aabb((1.0, 1.0), (4.0, 4.0)));
+        // Spans four cells, including negative ones.
+        grid.insert(2, aabb((-5.0, -5.0), (15.0, 5.0)));
+        grid.insert(3, aabb((22.0, 22.0), (28.0, 28.0)));
+        // In a queried cell, but not the queried region.
+        grid.insert(4, aabb((8.0, 8.0), (9.0, 9.0)));
+        grid
+    }
+
+    #[test]
+    fn region_query_returns_exactly_the_overlapping_ids() {
+        let grid = grid();
+        assert_eq!(grid.query_region(aabb((0.0, 0.0), (6.0, 6.0))), [1, 2]);
+        assert_eq!(grid.query_region(aabb((-30.0, -30.0), (30.0, 30.0))), [1, 2, 3, 4]);
+        assert_eq!(grid.query_region(aabb((12.0, -3.0), (13.0, -2.0))), [2]);
+        assert_eq!(grid.query_region(aabb((24.0, 24.0), (26.0, 26.0))), [3]);
+        // Empty cells, and boxes that only share an edge.
+        assert!(grid.query_region(aabb((40.0, 40.0), (50.0, 50.0))).is_empty());
+        assert!(grid.query_region(aabb((15.0, 0.0), (20.0, 5.0))).is_empty());
+    }
+
+    #[test]
+    fn clearing_empties_the_grid() {
+        let mut grid = grid();
+        assert_eq!(grid.len(), 4);
+        grid.clear();
+        assert!(grid.is_empty());
+        assert!(grid.query_region(aabb((-30.0, -30.0), (30.0, 30.0))).is_empty());
+        grid.insert(5, aabb((1.0, 1.0), (2.0, 2.0)));
+        assert_eq!(grid.query_region(aabb((0.0, 0.0), (6.0, 6.0))), [5]);
+    }
+}
@@ -0,0 +1,121 @@
+/// A small finite state machine for entity behaviour (player, enemies, doors).
+///
+/// It only tracks *which* state is active and for how long; the caller decides when to transition.
+///
+/// * `transition_to(s)` switches state and resets `time_in_state`; transitioning to the current state is a no-op.
+///
+/// * `tick(dt)` advances `time_in_state` by `dt` seconds.
+///
+/// * `on_enter()`/`on_exit()` report a transition once: each returns it the first time it is called after the
+///   transition and nothing afterwards, so entry/exit logic runs exactly once.
+#[derive(Clone, Debug)]
+pub struct StateMachine<S: Copy + Eq> {
+    current: S,
+    previous: Option<S>,
+    time_in_state: f64,
+    entered: bool,
+    exited: Option<S>,
+}
+
+impl<S: Copy + Eq> StateMachine<S> {
+    pub fn new(initial: S) -> Self {
+        StateMachine {
+            current: initial,
+            previous: None,
+            time_in_state: 0.0,
+            entered: false,
+            exited: None,
+        }
+    }
+
+    pub fn current(&self) -> S {
+        self.current
+    }
+
+    /// The state that was active before the most recent transition.
+    pub fn previous(&self) -> Option<S> {
+        self.previous
+    }
+
+    /// Seconds spent in the current state.
+    pub fn time_in_state(&self) -> f64 {
+        self.time_in_state
+    }
+
+    pub fn is(&self, state: S) -> bool {
+        self.current == state
+    }
+
+    pub fn transition_to(&mut self, state: S) {
+        if state == self.current {
+            return;
+        }
+        self.previous = Some(self.current);
+        self.exited = Some(self.current);
+        self.current = state;
+        self.time_in_state = 0.0;
+        self.entered = true;
+    }
+
+    pub fn tick(&mut self, dt: f64) {
+        self.time_in_state += dt;
+    }
+
+    /// Returns the newly entered state once per transition.
+    pub fn on_enter(&mut self) -> Option<S> {
+        if !self.entered {
+            return None;
+        }
+        self.entered = false;
+        Some(self.current)
+    }
+
+    /// Returns the state that was left once per transition.
+    pub fn on_exit(&mut self) -> Option<S> {
+        self.exited.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    enum Door {
+        Closed,
+        Open,
+    }
+
+    #[test]
+    fn time_in_state_resets_on_transition() {
+        let mut machine = StateMachine::new(Door::Closed);
+        machine.tick(0.5);
+        machine.tick(0.25);
+        assert_eq!(machine.time_in_state(), 0.75);
+
+        machine.transition_to(Door::Open);
+        assert_eq!(machine.time_in_state(), 0.0);
+        assert_eq!(machine.previous(), Some(Door::Closed));
+        machine.tick(0.1);
+
+        // Staying put isn't a transition.
+        machine.transition_to(Door::Open);
+        assert_eq!(machine.time_in_state(), 0.1);
+    }
+
+    #[test]
+    fn enter_and_exit_fire_once() {
+        let mut machine = StateMachine::new(Door::Closed);
+        assert_eq!(machine.on_enter(), None);
+        assert_eq!(machine.on_exit(), None);
+
+        machine.transition_to(Door::Open);
+        assert_eq!(machine.on_enter(), Some(Door::Open));
+        assert_eq!(machine.on_enter(), None);
+        assert_eq!(machine.on_exit(), Some(Door::Closed));
+        assert_eq!(machine.on_exit(), None);
+
+        machine.transition_to(Door::Open);
+        assert_eq!(machine.on_enter(), None);
+    }
+}
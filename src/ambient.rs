@@ -0,0 +1,221 @@
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::render::mesh::MeshVertexBufferLayout;
+use bevy::render::render_resource::{
+    AsBindGroup, BlendComponent, BlendFactor, BlendOperation, BlendState, RenderPipelineDescriptor, ShaderRef,
+    SpecializedMeshPipelineError,
+};
+use bevy::sprite::{Material2d, Material2dKey, Material2dPlugin, MaterialMesh2dBundle};
+use bevy::transform::TransformSystem;
+use bevy_ecs_ldtk::prelude::*;
+use gamelibs::prelude::*;
+
+use crate::camera::GameCamera;
+use crate::fields::LdtkFields;
+use crate::level::find_level;
+use crate::paths::AMBIENT_TINT_SHADER;
+
+/// LDtk level field identifier for the colour the level is tinted by. Levels without one aren't tinted.
+const AMBIENT_TINT_FIELD: &str = "AmbientTint";
+/// How long the tint takes to change between levels, in seconds.
+const TINT_TRANSITION_TIME: f32 = 1.0;
+
+/// The colour the world is multiplied by, for darker or coloured moods like caves and night. White leaves it as is.
+///
+/// The HUD is drawn after the world, so it's never tinted.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct AmbientTint(pub Color);
+
+impl Default for AmbientTint {
+    fn default() -> Self {
+        AmbientTint(Color::WHITE)
+    }
+}
+
+/// An `AmbientTint` on its way from one colour to another.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct TintTransition {
+    pub from: Color,
+    pub to: Color,
+    pub elapsed: f32,
+}
+
+/// The tint `elapsed` seconds into changing from `from` to `to`.
+pub fn transition_tint(from: Color, to: Color, elapsed: f32) -> Color {
+    let t = ease(Ease::SineInOut, (elapsed / TINT_TRANSITION_TIME) as f64) as f32;
+    let from = Vec4::from(from.as_rgba_f32());
+    let to = Vec4::from(to.as_rgba_f32());
+    let [r, g, b, a] = from.lerp(to, t).to_array();
+    Color::rgba(r, g, b, a)
+}
+
+/// Multiplies everything behind it by `color`.
+#[derive(AsBindGroup, TypeUuid, Clone, Debug)]
+#[uuid = "7d6c2f1e-48a9-4b3e-9c51-0e2a8f4d6b17"]
+pub struct AmbientTintMaterial {
+    #[uniform(0)]
+    pub color: Color,
+}
+
+impl Material2d for AmbientTintMaterial {
+    fn fragment_shader() -> ShaderRef {
+        AMBIENT_TINT_SHADER.into()
+    }
+
+    fn specialize(
+        descriptor: &mut RenderPipelineDescriptor,
+        _layout: &MeshVertexBufferLayout,
+        _key: Material2dKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        let multiply = BlendComponent {
+            src_factor: BlendFactor::Dst,
+            dst_factor: BlendFactor::Zero,
+            operation: BlendOperation::Add,
+        };
+        let keep = BlendComponent {
+            src_factor: BlendFactor::Zero,
+            dst_factor: BlendFactor::One,
+            operation: BlendOperation::Add,
+        };
+        if let Some(fragment) = descriptor.fragment.as_mut() {
+            for target in fragment.targets.iter_mut().flatten() {
+                target.blend = Some(BlendState { color: multiply, alpha: keep });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Marker for the full-screen quad the tint is drawn on.
+#[derive(Component)]
+struct TintQuad;
+
+pub struct AmbientPlugin;
+
+impl Plugin for AmbientPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_plugin(Material2dPlugin::<AmbientTintMaterial>::default())
+            .init_resource::<AmbientTint>()
+            .add_startup_system(spawn_tint_quad)
+            .add_system(tint_from_level)
+            .add_system(transition_ambient_tint.after(tint_from_level))
+            .add_system(update_tint_quad.after(transition_ambient_tint))
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                fit_tint_to_camera.before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+fn spawn_tint_quad(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<AmbientTintMaterial>>,
+) {
+    commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: meshes.add(Mesh::from(shape::Quad::new(Vec2::ONE))).into(),
+            material: materials.add(AmbientTintMaterial { color: Color::WHITE }),
+            visibility: Visibility { is_visible: false },
+            ..default()
+        },
+        TintQuad,
+    ));
+}
+
+/// Starts changing the tint to the new level's, or back to white if it doesn't declare one.
+fn tint_from_level(
+    mut commands: Commands,
+    mut level_events: EventReader<LevelEvent>,
+    levels: Query<&Handle<LdtkLevel>>,
+    level_assets: Res<Assets<LdtkLevel>>,
+    tint: Res<AmbientTint>,
+) {
+    for event in level_events.iter() {
+        let LevelEvent::Spawned(iid) = event else { continue };
+
+        let Some(level) = find_level(iid, &levels, &level_assets) else { continue };
+        let to = level.fields().get_color(AMBIENT_TINT_FIELD).unwrap_or(Color::WHITE);
+        if to != tint.0 {
+            commands.insert_resource(TintTransition {
+                from: tint.0,
+                to,
+                elapsed: 0.0,
+            });
+        }
+    }
+}
+
+/// Runs on real time, so a tint fading in while the game is paused still finishes.
+fn transition_ambient_tint(
+    mut commands: Commands,
+    time: Res<Time>,
+    transition: Option<ResMut<TintTransition>>,
+    mut tint: ResMut<AmbientTint>,
+) {
+    let Some(mut transition) = transition else { return };
+
+    transition.elapsed += time.delta_seconds();
+    tint.0 = transition_tint(transition.from, transition.to, transition.elapsed);
+    if transition.elapsed >= TINT_TRANSITION_TIME {
+        commands.remove_resource::<TintTransition>();
+    }
+}
+
+fn update_tint_quad(
+    tint: Res<AmbientTint>,
+    mut quads: Query<(&Handle<AmbientTintMaterial>, &mut Visibility), With<TintQuad>>,
+    mut materials: ResMut<Assets<AmbientTintMaterial>>,
+) {
+    if !tint.is_changed() {
+        return;
+    }
+
+    for (handle, mut visibility) in quads.iter_mut() {
+        // Multiplying by white changes nothing, so the quad isn't drawn at all.
+        visibility.is_visible = tint.0 != Color::WHITE;
+        if let Some(material) = materials.get_mut(handle) {
+            material.color = tint.0;
+        }
+    }
+}
+
+/// Keeps the quad over the whole view, just in front of the camera so it covers every sprite.
+fn fit_tint_to_camera(
+    cameras: Query<(&Transform, &OrthographicProjection), (With<GameCamera>, Without<TintQuad>)>,
+    mut quads: Query<&mut Transform, With<TintQuad>>,
+) {
+    let Ok((camera_transform, projection)) = cameras.get_single() else { return };
+
+    let width = (projection.right - projection.left) * projection.scale;
+    let height = (projection.top - projection.bottom) * projection.scale;
+    let depth = camera_transform.translation.z - projection.near - 0.01;
+
+    for mut transform in quads.iter_mut() {
+        transform.translation = camera_transform.translation.truncate().extend(depth);
+        transform.scale = Vec3::new(width, height, 1.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgba(color: Color) -> Vec4 {
+        Vec4::from(color.as_rgba_f32())
+    }
+
+    #[test]
+    fn tint_eases_between_levels() {
+        let (from, to) = (Color::WHITE, Color::rgba(0.2, 0.3, 0.6, 1.0));
+        assert!(rgba(transition_tint(from, to, 0.0)).abs_diff_eq(rgba(from), 1e-6));
+        assert!(rgba(transition_tint(from, to, TINT_TRANSITION_TIME)).abs_diff_eq(rgba(to), 1e-6));
+        // Halfway through the time is halfway between the colours, with a slow start and finish either side.
+        let halfway = rgba(from).lerp(rgba(to), 0.5);
+        assert!(rgba(transition_tint(from, to, TINT_TRANSITION_TIME / 2.0)).abs_diff_eq(halfway, 1e-6));
+        let early = rgba(transition_tint(from, to, TINT_TRANSITION_TIME / 10.0));
+        assert!(early.abs_diff_eq(rgba(from), 0.05) && !early.abs_diff_eq(rgba(from), 1e-6));
+        assert!(rgba(transition_tint(from, to, TINT_TRANSITION_TIME * 2.0)).abs_diff_eq(rgba(to), 1e-6));
+    }
+}
@@ -0,0 +1,231 @@
+use bevy::asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::HashMap;
+use serde::Deserialize;
+
+use crate::paths::PLAYER_ANIMATIONS;
+use crate::physics::GameplayDelta;
+
+/// The animation definitions loaded at startup.
+const ANIMATION_FILES: &[&str] = &[PLAYER_ANIMATIONS];
+
+/// A named sequence of texture atlas frames.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct AnimationClip {
+    /// Atlas indices, in playback order.
+    pub frames: Vec<usize>,
+    pub fps: f32,
+    #[serde(default)]
+    pub looping: bool,
+    /// Positions in `frames` where a foot comes down, for footstep sounds.
+    #[serde(default)]
+    pub strides: Vec<usize>,
+}
+
+/// One `.anim.json` file: the size of the atlas it was authored against and its clips by name.
+#[derive(Deserialize, TypeUuid, Clone, Debug)]
+#[uuid = "3b0e5f6a-91c2-4d8e-a7f4-6c2d9e1b58a3"]
+pub struct AnimationSet {
+    pub columns: usize,
+    pub rows: usize,
+    pub clips: HashMap<String, AnimationClip>,
+}
+
+/// Every loaded clip, keyed by name. Clips with the same name in a later file replace earlier ones.
+#[derive(Resource, Default)]
+pub struct AnimationLibrary {
+    pub clips: HashMap<String, AnimationClip>,
+    handles: Vec<Handle<AnimationSet>>,
+}
+
+impl AnimationLibrary {
+    pub fn get(&self, name: &str) -> Option<&AnimationClip> {
+        self.clips.get(name)
+    }
+}
+
+/// Plays a clip from the `AnimationLibrary` on this entity's `TextureAtlasSprite`.
+#[derive(Component, Clone, Debug)]
+pub struct SpriteAnimation {
+    pub clip: String,
+    /// Seconds since the clip started.
+    pub elapsed: f32,
+}
+
+impl SpriteAnimation {
+    pub fn new(clip: &str) -> Self {
+        SpriteAnimation {
+            clip: clip.to_string(),
+            elapsed: 0.0,
+        }
+    }
+
+    /// Switches to `clip`, restarting only if it isn't already playing.
+    pub fn play(&mut self, clip: &str) {
+        if self.clip != clip {
+            *self = SpriteAnimation::new(clip);
+        }
+    }
+}
+
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_asset::<AnimationSet>()
+            .add_asset_loader(AnimationSetLoader)
+            .init_resource::<AnimationLibrary>()
+            .add_startup_system(load_animations)
+            .add_system(fill_library)
+            .add_system(animate_sprites.after(fill_library));
+    }
+}
+
+/// Parses an animation set, dropping (and logging) any frame that doesn't exist in its atlas.
+pub fn parse_animation_set(bytes: &[u8]) -> Result<AnimationSet, serde_json::Error> {
+    let mut set: AnimationSet = serde_json::from_slice(bytes)?;
+    let frame_count = set.columns * set.rows;
+
+    for (name, clip) in set.clips.iter_mut() {
+        clip.frames.retain(|&frame| {
+            if frame >= frame_count {
+                error!("Animation clip \"{name}\" uses frame {frame}, but its atlas only has {frame_count} frames");
+            }
+            frame < frame_count
+        });
+    }
+    Ok(set)
+}
+
+/// The position in `clip.frames` `elapsed` seconds into `clip`, holding the last one once a non-looping clip ends.
+pub fn clip_position(clip: &AnimationClip, elapsed: f32) -> Option<usize> {
+    let last = clip.frames.len().checked_sub(1)?;
+    let step = (elapsed * clip.fps).max(0.0) as usize;
+    Some(if clip.looping { step % clip.frames.len() } else { step.min(last) })
+}
+
+/// The atlas index to show `elapsed` seconds into `clip`.
+pub fn clip_frame(clip: &AnimationClip, elapsed: f32) -> Option<usize> {
+    clip_position(clip, elapsed).map(|position| clip.frames[position])
+}
+
+struct AnimationSetLoader;
+
+impl AssetLoader for AnimationSetLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let set = parse_animation_set(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(set));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["anim.json"]
+    }
+}
+
+fn load_animations(asset_server: Res<AssetServer>, mut library: ResMut<AnimationLibrary>) {
+    library.handles = ANIMATION_FILES.iter().map(|path| asset_server.load(*path)).collect();
+}
+
+/// Copies clips into the library as their files load, and again whenever one is hot-reloaded.
+fn fill_library(
+    mut events: EventReader<AssetEvent<AnimationSet>>,
+    sets: Res<Assets<AnimationSet>>,
+    mut library: ResMut<AnimationLibrary>,
+) {
+    for event in events.iter() {
+        let (AssetEvent::Created { handle } | AssetEvent::Modified { handle }) = event else { continue };
+        let Some(set) = sets.get(handle) else { continue };
+        library.clips.extend(set.clips.iter().map(|(name, clip)| (name.clone(), clip.clone())));
+    }
+}
+
+/// Animations keep time without an atlas sprite to show them on too, for anything timed off them like footsteps.
+/// An animated entity without an atlas sprite of its own shows its frames on those of its descendants, such as
+/// the player's, which hangs under pivots that turn and squash it.
+pub fn animate_sprites(
+    delta: Res<GameplayDelta>,
+    library: Res<AnimationLibrary>,
+    mut sprites: Query<(Entity, &mut SpriteAnimation, Option<&mut TextureAtlasSprite>)>,
+    children: Query<&Children>,
+    mut descendant_sprites: Query<&mut TextureAtlasSprite, Without<SpriteAnimation>>,
+) {
+    for (entity, mut animation, sprite) in sprites.iter_mut() {
+        animation.elapsed += delta.0;
+
+        let Some(clip) = library.get(&animation.clip) else { continue };
+        let Some(frame) = clip_frame(clip, animation.elapsed) else { continue };
+        match sprite {
+            Some(mut sprite) => {
+                if sprite.index != frame {
+                    sprite.index = frame;
+                }
+            }
+            None => {
+                for child in children.iter_descendants(entity) {
+                    let Ok(mut sprite) = descendant_sprites.get_mut(child) else { continue };
+                    if sprite.index != frame {
+                        sprite.index = frame;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"{
+        "columns": 4,
+        "rows": 2,
+        "clips": {
+            "idle": { "frames": [0, 1], "fps": 2.0, "looping": true },
+            "run": { "frames": [2, 3, 4, 5], "fps": 10.0, "looping": true, "strides": [1, 3] },
+            "jump": { "frames": [6, 8, 7], "fps": 1.0 }
+        }
+    }"#;
+
+    #[test]
+    fn parses_a_sample_definition() {
+        let set = parse_animation_set(SAMPLE.as_bytes()).unwrap();
+        assert_eq!((set.columns, set.rows), (4, 2));
+        assert_eq!(set.clips.len(), 3);
+        assert_eq!(
+            set.clips["run"],
+            AnimationClip { frames: vec![2, 3, 4, 5], fps: 10.0, looping: true, strides: vec![1, 3] }
+        );
+        let idle = &set.clips["idle"];
+        assert!(idle.looping && idle.strides.is_empty());
+        assert!(!set.clips["jump"].looping);
+    }
+
+    #[test]
+    fn drops_frames_outside_the_atlas() {
+        let set = parse_animation_set(SAMPLE.as_bytes()).unwrap();
+        assert_eq!(set.clips["jump"].frames, vec![6, 7]);
+    }
+
+    #[test]
+    fn rejects_malformed_definitions() {
+        assert!(parse_animation_set(br#"{ "columns": 4, "clips": {} }"#).is_err());
+    }
+
+    #[test]
+    fn player_animations_parse() {
+        let bytes = std::fs::read(std::path::Path::new("assets").join(PLAYER_ANIMATIONS)).unwrap();
+        let set = parse_animation_set(&bytes).unwrap();
+        for clip in ["idle", "run", "jump", "fall"] {
+            assert!(set.clips.contains_key(clip), "missing the {clip} clip");
+        }
+    }
+}
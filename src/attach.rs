@@ -0,0 +1,100 @@
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+use gamelibs::math::{CurveFollower, CurveStyle};
+
+use crate::physics::GameplayDelta;
+
+/// Keeps an effect (a shield bubble, an aura) at `offset` from another entity without being its child, so it can
+/// lag behind through `smoothing` instead of moving rigidly. It is despawned along with `parent`, which can't be an
+/// attachment itself.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct AttachedTo {
+    pub parent: Entity,
+    pub offset: Vec2,
+    /// How the attachment catches up with its spot; `None` sticks to it exactly.
+    pub smoothing: Option<CurveStyle>,
+}
+
+/// The smoothed position of an `AttachedTo` with smoothing, added on its first frame.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct AttachFollow {
+    x: CurveFollower,
+    y: CurveFollower,
+}
+
+impl AttachFollow {
+    pub fn new(style: CurveStyle, position: Vec2) -> Self {
+        AttachFollow {
+            x: CurveFollower::new(style, position.x as f64),
+            y: CurveFollower::new(style, position.y as f64),
+        }
+    }
+
+    /// Advances `dt` seconds towards `target` and returns the new position.
+    pub fn step(&mut self, target: Vec2, dt: f32) -> Vec2 {
+        Vec2::new(
+            self.x.step(target.x as f64, dt as f64) as f32,
+            self.y.step(target.y as f64, dt as f64) as f32,
+        )
+    }
+}
+
+pub struct AttachPlugin;
+
+impl Plugin for AttachPlugin {
+    fn build(&self, app: &mut App) {
+        // Physics has moved the parents by PostUpdate, so attachments see where they ended up this frame.
+        app.add_system_to_stage(
+            CoreStage::PostUpdate,
+            follow_parents.before(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+fn follow_parents(
+    mut commands: Commands,
+    delta: Res<GameplayDelta>,
+    parents: Query<&Transform, Without<AttachedTo>>,
+    mut attachments: Query<(Entity, &AttachedTo, &mut Transform, Option<&mut AttachFollow>)>,
+) {
+    for (entity, attached, mut transform, follow) in attachments.iter_mut() {
+        let Ok(parent) = parents.get(attached.parent) else {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+        let target = parent.translation.truncate() + attached.offset;
+
+        let position = match (attached.smoothing, follow) {
+            (None, _) => target,
+            (Some(_), Some(mut follow)) => follow.step(target, delta.0),
+            (Some(style), None) => {
+                commands.entity(entity).insert(AttachFollow::new(style, target));
+                target
+            }
+        };
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn smoothed_offset_closes_in_over_several_frames() {
+        let target = Vec2::new(10.0, -4.0);
+        let mut follow = AttachFollow::new(CurveStyle::Custom { f: 2.0, z: 1.0, r: 0.0 }, Vec2::ZERO);
+
+        let mut distance = target.length();
+        for _ in 0..10 {
+            let next = follow.step(target, 1.0 / 60.0).distance(target);
+            assert!(next <= distance);
+            distance = next;
+        }
+        // Starting from rest it takes a frame to get going, and it still lags behind after a few; but it gets there.
+        assert!(distance > 1.0 && distance < target.length());
+        let position = (0..300).map(|_| follow.step(target, 1.0 / 60.0)).last().unwrap();
+        assert!(position.abs_diff_eq(target, 1e-2));
+    }
+}
@@ -0,0 +1,438 @@
+use std::time::Duration;
+
+use bevy::audio::{play_queued_audio_system, AudioOutput, AudioSink, Source};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::HashMap;
+use gamelibs::easing::{ease, Ease};
+
+use crate::camera::GameCamera;
+use crate::settings::Settings;
+
+type SourceDecoder = <AudioSource as Decodable>::Decoder;
+
+/// Sound effects that can be played by name.
+#[derive(Resource, Default)]
+pub struct SfxLibrary(pub HashMap<String, Handle<AudioSource>>);
+
+/// How positioned sound effects fade with distance from the listener (the game camera).
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct SpatialAudio {
+    /// Beyond this many pixels a sound is silent and fully panned.
+    pub max_distance: f32,
+    pub falloff: Falloff,
+}
+
+impl Default for SpatialAudio {
+    fn default() -> Self {
+        SpatialAudio {
+            max_distance: 1200.0,
+            falloff: Falloff::Linear,
+        }
+    }
+}
+
+/// * `Falloff::Linear` fades from full volume at the listener to silence at `max_distance`.
+///
+/// * `Falloff::Inverse{reference}` is full volume within `reference` pixels and `reference / distance` beyond it.
+#[derive(Clone, Copy, Debug)]
+pub enum Falloff {
+    Linear,
+    Inverse { reference: f32 },
+}
+
+/// The mixer channels sounds play through, each with its own volume under `AudioBus::master`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Channel {
+    Music,
+    Sfx,
+    Dialog,
+}
+
+/// Turns the music down while dialog plays so the lines can be heard over it, and back up once it's over.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ducking {
+    /// Scales the music channel while fully ducked.
+    pub depth: f32,
+    /// Seconds to duck all the way once dialog starts.
+    pub attack: f32,
+    /// Seconds to come back up once dialog ends.
+    pub release: f32,
+    /// The shape of both ramps.
+    pub ease: Ease,
+    /// How far through the ramp down the music is, from 0 (full volume) to 1 (ducked).
+    progress: f32,
+}
+
+impl Default for Ducking {
+    fn default() -> Self {
+        Ducking {
+            depth: 0.3,
+            attack: 0.3,
+            release: 0.8,
+            ease: Ease::QuadInOut,
+            progress: 0.0,
+        }
+    }
+}
+
+impl Ducking {
+    /// Ramps `dt` seconds towards fully ducked while `dialog` is playing, or back towards full volume.
+    pub fn step(&mut self, dialog: bool, dt: f32) {
+        let (target, duration) = if dialog { (1.0, self.attack) } else { (0.0, self.release) };
+        if duration <= 0.0 {
+            self.progress = target;
+            return;
+        }
+        let max_change = dt / duration;
+        self.progress += (target - self.progress).clamp(-max_change, max_change);
+    }
+
+    /// What the music channel is scaled by right now, from 1 down to `depth`.
+    pub fn gain(&self) -> f32 {
+        let amount = ease(self.ease, self.progress as f64) as f32;
+        1.0 + (self.depth - 1.0) * amount
+    }
+}
+
+/// The volume of every channel, set from the `Settings`, and the music and dialog currently playing.
+#[derive(Resource, Default)]
+pub struct AudioBus {
+    pub master: f32,
+    pub music: f32,
+    pub sfx: f32,
+    pub dialog: f32,
+    pub ducking: Ducking,
+    /// Keeps the music ducked while set, e.g. while a conversation is on screen between lines.
+    pub hold_ducking: bool,
+    /// Seconds left of the dialog lines playing, when their length is known.
+    dialog_remaining: f32,
+    music_sink: Option<Handle<AudioSink>>,
+}
+
+impl AudioBus {
+    pub fn volume(&self, channel: Channel) -> f32 {
+        match channel {
+            Channel::Music => self.music,
+            Channel::Sfx => self.sfx,
+            Channel::Dialog => self.dialog,
+        }
+    }
+
+    /// The volume a sound on `channel` plays at: the channel's volume under the master volume, ducked for music.
+    pub fn gain(&self, channel: Channel) -> f32 {
+        let ducking = if channel == Channel::Music { self.ducking.gain() } else { 1.0 };
+        (self.master * self.volume(channel) * ducking).clamp(0.0, 1.0)
+    }
+
+    pub fn dialog_playing(&self) -> bool {
+        self.hold_ducking || self.dialog_remaining > 0.0
+    }
+}
+
+/// A sound effect with a stereo balance baked in; `pan` runs from `-1.0` (left) to `1.0` (right).
+#[derive(Clone, TypeUuid)]
+#[uuid = "5786ccc0-dec7-4955-bd9d-2af026f79a2c"]
+pub struct PannedSfx {
+    source: AudioSource,
+    pan: f32,
+}
+
+pub struct GameAudioPlugin;
+
+impl Plugin for GameAudioPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<SfxLibrary>()
+            .init_resource::<SpatialAudio>()
+            .init_resource::<AudioBus>()
+            .add_asset::<PannedSfx>()
+            .init_non_send_resource::<AudioOutput<PannedSfx>>()
+            .init_resource::<Audio<PannedSfx>>()
+            .add_system_to_stage(CoreStage::PostUpdate, play_queued_audio_system::<PannedSfx>)
+            .add_system_to_stage(CoreStage::PreUpdate, set_bus_volumes)
+            .add_system(duck_music);
+    }
+}
+
+/// Plays sounds from the `SfxLibrary` through the `AudioBus`: effects, optionally positioned in the world, music
+/// and dialog.
+#[derive(SystemParam)]
+pub struct Sfx<'w, 's> {
+    library: Res<'w, SfxLibrary>,
+    bus: ResMut<'w, AudioBus>,
+    sinks: Res<'w, Assets<AudioSink>>,
+    spatial: Res<'w, SpatialAudio>,
+    audio: Res<'w, Audio>,
+    panned_audio: Res<'w, Audio<PannedSfx>>,
+    sources: Res<'w, Assets<AudioSource>>,
+    panned_sources: ResMut<'w, Assets<PannedSfx>>,
+    listeners: Query<'w, 's, &'static GlobalTransform, With<GameCamera>>,
+}
+
+impl<'w, 's> Sfx<'w, 's> {
+    pub fn play_sfx(&self, name: &str) {
+        match self.library.0.get(name) {
+            Some(handle) => {
+                let playback = PlaybackSettings::ONCE.with_volume(self.bus.gain(Channel::Sfx));
+                self.audio.play_with_settings(handle.clone(), playback);
+            }
+            None => warn!("No sound effect named {name}"),
+        }
+    }
+
+    /// Plays a sound effect panned and attenuated by its offset from the camera.
+    pub fn play_sfx_at(&mut self, name: &str, world_pos: Vec2) {
+        let Some(handle) = self.library.0.get(name) else {
+            warn!("No sound effect named {name}");
+            return;
+        };
+        // Not loaded yet; a late sound is worse than a dropped one.
+        let Some(source) = self.sources.get(handle) else { return };
+        let Ok(listener) = self.listeners.get_single() else {
+            self.play_sfx(name);
+            return;
+        };
+
+        let offset = world_pos - listener.translation().truncate();
+        let (pan, volume) = spatial_mix(offset, self.spatial.max_distance, self.spatial.falloff);
+        let volume = volume * self.bus.gain(Channel::Sfx);
+        if volume <= 0.0 {
+            return;
+        }
+
+        let panned = self.panned_sources.add(PannedSfx { source: source.clone(), pan });
+        self.panned_audio.play_with_settings(panned, PlaybackSettings::ONCE.with_volume(volume));
+    }
+
+    /// Loops `name` as the music, replacing whatever was playing.
+    pub fn play_music(&mut self, name: &str) {
+        let Some(handle) = self.library.0.get(name).cloned() else {
+            warn!("No music named {name}");
+            return;
+        };
+        self.stop_music();
+        let playback = PlaybackSettings::LOOP.with_volume(self.bus.gain(Channel::Music));
+        let sink = self.audio.play_with_settings(handle, playback);
+        self.bus.music_sink = Some(self.sinks.get_handle(sink));
+    }
+
+    pub fn stop_music(&mut self) {
+        if let Some(sink) = self.bus.music_sink.take().and_then(|sink| self.sinks.get(&sink)) {
+            sink.stop();
+        }
+    }
+
+    /// Plays a line of dialog, ducking the music for as long as it lasts. Lines whose length can't be read
+    /// from the file only duck while `AudioBus::hold_ducking` is set.
+    pub fn play_dialog(&mut self, name: &str) {
+        let Some(handle) = self.library.0.get(name) else {
+            warn!("No dialog named {name}");
+            return;
+        };
+        let playback = PlaybackSettings::ONCE.with_volume(self.bus.gain(Channel::Dialog));
+        self.audio.play_with_settings(handle.clone(), playback);
+        let length = self.sources.get(handle).and_then(|source| source.decoder().total_duration());
+        if let Some(length) = length {
+            self.bus.dialog_remaining = self.bus.dialog_remaining.max(length.as_secs_f32());
+        }
+    }
+}
+
+fn set_bus_volumes(settings: Res<Settings>, mut bus: ResMut<AudioBus>) {
+    if !settings.is_changed() {
+        return;
+    }
+    bus.master = settings.master_volume;
+    bus.music = settings.music_volume;
+    bus.sfx = settings.sfx_volume;
+    bus.dialog = settings.dialog_volume;
+}
+
+/// Ramps the ducking on real time, so it carries on while the game is paused, and turns the music to match.
+fn duck_music(time: Res<Time>, mut bus: ResMut<AudioBus>, sinks: Res<Assets<AudioSink>>) {
+    let dt = time.delta_seconds();
+    bus.dialog_remaining = (bus.dialog_remaining - dt).max(0.0);
+    let dialog = bus.dialog_playing();
+    bus.ducking.step(dialog, dt);
+
+    let gain = bus.gain(Channel::Music);
+    let Some(sink) = bus.music_sink.as_ref().and_then(|sink| sinks.get(sink)) else { return };
+    if sink.volume() != gain {
+        sink.set_volume(gain);
+    }
+}
+
+/// Returns the `(pan, volume)` for a sound at `offset` from the listener.
+///
+/// Pan follows the horizontal offset and is clamped to `[-1, 1]`; volume follows the full distance.
+pub fn spatial_mix(offset: Vec2, max_distance: f32, falloff: Falloff) -> (f32, f32) {
+    let pan = (offset.x / max_distance).clamp(-1.0, 1.0);
+
+    let distance = offset.length();
+    let volume = if distance >= max_distance {
+        0.0
+    } else {
+        match falloff {
+            Falloff::Linear => 1.0 - distance / max_distance,
+            Falloff::Inverse { reference } => reference / distance.max(reference),
+        }
+    };
+
+    (pan, volume)
+}
+
+impl Decodable for PannedSfx {
+    type Decoder = Balanced<SourceDecoder>;
+    type DecoderItem = i16;
+
+    fn decoder(&self) -> Self::Decoder {
+        Balanced::new(self.source.decoder(), self.pan)
+    }
+}
+
+/*
+ * Applies a linear stereo balance to a decoded source: the far channel is turned down while the near one
+ * stays at full volume, so a centred sound is unchanged. Mono sources are upmixed to stereo so they can be panned.
+ */
+pub struct Balanced<D> {
+    inner: D,
+    left: f32,
+    right: f32,
+    upmix: bool,
+    channel: u16,
+    pending: Option<i16>,
+}
+
+impl<D: Source<Item = i16>> Balanced<D> {
+    fn new(inner: D, pan: f32) -> Self {
+        let upmix = inner.channels() == 1;
+        Balanced {
+            inner,
+            left: (1.0 - pan).min(1.0),
+            right: (1.0 + pan).min(1.0),
+            upmix,
+            channel: 0,
+            pending: None,
+        }
+    }
+}
+
+fn scale(sample: i16, gain: f32) -> i16 {
+    (sample as f32 * gain) as i16
+}
+
+impl<D: Source<Item = i16>> Iterator for Balanced<D> {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<i16> {
+        if self.upmix {
+            if let Some(sample) = self.pending.take() {
+                return Some(scale(sample, self.right));
+            }
+            let sample = self.inner.next()?;
+            self.pending = Some(sample);
+            return Some(scale(sample, self.left));
+        }
+
+        let sample = self.inner.next()?;
+        let channels = self.inner.channels();
+        let channel = self.channel;
+        self.channel = (channel + 1) % channels.max(1);
+        let gain = match (channels, channel) {
+            (2, 0) => self.left,
+            (2, 1) => self.right,
+            _ => 1.0,
+        };
+        Some(scale(sample, gain))
+    }
+}
+
+impl<D: Source<Item = i16>> Source for Balanced<D> {
+    fn current_frame_len(&self) -> Option<usize> {
+        let len = self.inner.current_frame_len()?;
+        Some(if self.upmix { len * 2 } else { len })
+    }
+
+    fn channels(&self) -> u16 {
+        if self.upmix { 2 } else { self.inner.channels() }
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+}
+
+/// Adds what `Sfx` needs without opening an audio device, for testing systems that play sounds. Nothing is heard.
+#[cfg(test)]
+pub fn init_silent_audio(app: &mut App) -> &mut App {
+    app
+        .add_plugin(CorePlugin::default())
+        .add_plugin(bevy::asset::AssetPlugin::default())
+        .add_asset::<AudioSource>()
+        .add_asset::<AudioSink>()
+        .add_asset::<PannedSfx>()
+        .init_resource::<Audio>()
+        .init_resource::<Audio<PannedSfx>>()
+        .init_resource::<SfxLibrary>()
+        .init_resource::<SpatialAudio>()
+        .init_resource::<AudioBus>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_mix(actual: (f32, f32), expected: (f32, f32)) {
+        assert!(
+            (actual.0 - expected.0).abs() < 1e-6 && (actual.1 - expected.1).abs() < 1e-6,
+            "{actual:?} != {expected:?}"
+        );
+    }
+
+    #[test]
+    fn linear_mix_at_several_offsets() {
+        assert_mix(spatial_mix(Vec2::ZERO, 100.0, Falloff::Linear), (0.0, 1.0));
+        assert_mix(spatial_mix(Vec2::new(50.0, 0.0), 100.0, Falloff::Linear), (0.5, 0.5));
+        assert_mix(spatial_mix(Vec2::new(-25.0, 0.0), 100.0, Falloff::Linear), (-0.25, 0.75));
+        // Straight above is centred, but still quieter for being further away.
+        assert_mix(spatial_mix(Vec2::new(0.0, 80.0), 100.0, Falloff::Linear), (0.0, 0.2));
+    }
+
+    #[test]
+    fn pan_clamps_and_volume_cuts_off_past_max_distance() {
+        assert_mix(spatial_mix(Vec2::new(250.0, 0.0), 100.0, Falloff::Linear), (1.0, 0.0));
+        assert_mix(spatial_mix(Vec2::new(-100.0, 0.0), 100.0, Falloff::Linear), (-1.0, 0.0));
+    }
+
+    #[test]
+    fn inverse_falloff_is_full_inside_the_reference() {
+        let falloff = Falloff::Inverse { reference: 10.0 };
+        assert_mix(spatial_mix(Vec2::new(5.0, 0.0), 100.0, falloff), (0.05, 1.0));
+        assert_mix(spatial_mix(Vec2::new(40.0, 0.0), 100.0, falloff), (0.4, 0.25));
+        assert_mix(spatial_mix(Vec2::new(0.0, 100.0), 100.0, falloff), (0.0, 0.0));
+    }
+
+    #[test]
+    fn ducking_ramps_down_and_restores() {
+        let mut ducking = Ducking { depth: 0.2, attack: 0.5, release: 1.0, ease: Ease::Linear, ..default() };
+        let mut gains = Vec::new();
+        for (dialog, dt) in [(true, 0.25), (true, 0.25), (true, 1.0), (false, 0.5), (false, 0.5), (false, 1.0)] {
+            ducking.step(dialog, dt);
+            gains.push(ducking.gain());
+        }
+        let expected = [0.6, 0.2, 0.2, 0.6, 1.0, 1.0];
+        assert!(gains.iter().zip(expected).all(|(gain, expected)| (gain - expected).abs() < 1e-6), "{gains:?}");
+
+        let mut bus = AudioBus { master: 0.5, music: 0.8, sfx: 1.0, ..default() };
+        bus.ducking = Ducking { depth: 0.5, attack: 0.0, ..default() };
+        bus.ducking.step(true, 0.0);
+        assert!((bus.gain(Channel::Music) - 0.2).abs() < 1e-6);
+        assert_eq!(bus.gain(Channel::Sfx), 0.5, "only the music ducks");
+    }
+}
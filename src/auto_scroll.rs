@@ -0,0 +1,137 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::camera::{camera_view, CameraFollow, GameCamera};
+use crate::fields::LdtkFields;
+use crate::level::{find_level, PlayerKilled, PlayerRespawned};
+use crate::physics::GameplayDelta;
+use crate::player::{Player, PlayerSpawn};
+
+/// Level fields giving the scroll velocity in pixels per second. A level with neither field doesn't auto-scroll.
+const SCROLL_X_FIELD: &str = "AutoScrollX";
+const SCROLL_Y_FIELD: &str = "AutoScrollY";
+
+/// Moves the camera on its own at `velocity` pixels per second, replacing `CameraFollow`.
+/// The player dies when they drop out of view.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct AutoScroll {
+    pub velocity: Vec2,
+}
+
+pub struct AutoScrollPlugin;
+
+impl Plugin for AutoScrollPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_system(auto_scroll_from_level)
+            .add_system(scroll_camera)
+            .add_system(kill_left_behind.after(scroll_camera))
+            .add_system(restart_scroll);
+    }
+}
+
+/// Whether a box of `half_extents` centred on `position` is entirely outside `view`, past any edge.
+pub fn is_outside_view(position: Vec2, half_extents: Vec2, view: Rect) -> bool {
+    position.x + half_extents.x < view.min.x
+        || position.x - half_extents.x > view.max.x
+        || position.y + half_extents.y < view.min.y
+        || position.y - half_extents.y > view.max.y
+}
+
+fn auto_scroll_from_level(
+    mut commands: Commands,
+    mut level_events: EventReader<LevelEvent>,
+    levels: Query<&Handle<LdtkLevel>>,
+    level_assets: Res<Assets<LdtkLevel>>,
+    cameras: Query<Entity, With<GameCamera>>,
+) {
+    for event in level_events.iter() {
+        let LevelEvent::Spawned(iid) = event else { continue };
+
+        let Some(level) = find_level(iid, &levels, &level_assets) else { continue };
+        let x = level.fields().get_float(SCROLL_X_FIELD);
+        let y = level.fields().get_float(SCROLL_Y_FIELD);
+
+        for camera in cameras.iter() {
+            if x.is_none() && y.is_none() {
+                commands.entity(camera).remove::<AutoScroll>();
+            } else {
+                let velocity = Vec2::new(x.unwrap_or(0.0), y.unwrap_or(0.0));
+                commands.entity(camera).insert(AutoScroll { velocity });
+            }
+        }
+    }
+}
+
+/// Scrolls the camera, keeping its follow smoothing in step so it resumes from here if scrolling stops.
+fn scroll_camera(delta: Res<GameplayDelta>, mut cameras: Query<(&mut Transform, &AutoScroll, Option<&mut CameraFollow>)>) {
+    for (mut transform, scroll, follow) in cameras.iter_mut() {
+        transform.translation += (scroll.velocity * delta.0).extend(0.0);
+        if let Some(mut follow) = follow {
+            follow.snap_to(transform.translation.truncate());
+        }
+    }
+}
+
+fn kill_left_behind(
+    cameras: Query<(&Transform, &OrthographicProjection), With<AutoScroll>>,
+    players: Query<(&Transform, &Collider), With<Player>>,
+    mut killed: EventWriter<PlayerKilled>,
+) {
+    let Ok((camera, projection)) = cameras.get_single() else { return };
+    let view = camera_view(camera, projection);
+
+    for (transform, collider) in players.iter() {
+        let half_extents = collider.raw.compute_local_aabb().half_extents();
+        if is_outside_view(transform.translation.truncate(), Vec2::new(half_extents.x, half_extents.y), view) {
+            killed.send(PlayerKilled);
+        }
+    }
+}
+
+/// Brings the camera back to the spawn point with the player, so they aren't immediately left behind again.
+fn restart_scroll(
+    mut respawns: EventReader<PlayerRespawned>,
+    spawn: Res<PlayerSpawn>,
+    mut cameras: Query<(&mut Transform, Option<&mut CameraFollow>), With<AutoScroll>>,
+) {
+    if respawns.iter().count() == 0 {
+        return;
+    }
+
+    for (mut transform, follow) in cameras.iter_mut() {
+        transform.translation.x = spawn.0.x;
+        transform.translation.y = spawn.0.y;
+        if let Some(mut follow) = follow {
+            follow.snap_to(spawn.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HALF: Vec2 = Vec2::new(15.0, 25.0);
+
+    fn view() -> Rect {
+        Rect::new(100.0, 0.0, 420.0, 180.0)
+    }
+
+    #[test]
+    fn left_behind_once_wholly_past_the_left_edge() {
+        assert!(!is_outside_view(Vec2::new(200.0, 90.0), HALF, view()));
+        // Half off the edge is still in play.
+        assert!(!is_outside_view(Vec2::new(100.0, 90.0), HALF, view()));
+        assert!(!is_outside_view(Vec2::new(85.0, 90.0), HALF, view()));
+        assert!(is_outside_view(Vec2::new(84.0, 90.0), HALF, view()));
+    }
+
+    #[test]
+    fn outside_past_any_edge() {
+        assert!(is_outside_view(Vec2::new(436.0, 90.0), HALF, view()));
+        assert!(is_outside_view(Vec2::new(200.0, -26.0), HALF, view()));
+        assert!(is_outside_view(Vec2::new(200.0, 206.0), HALF, view()));
+    }
+}
@@ -0,0 +1,86 @@
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::entity_registry::RegisterLevelEntity;
+use crate::flags::LevelRun;
+use crate::player::Player;
+use crate::state::GameplayEntity;
+
+/// The LDtk entity identifier that places a bean.
+pub const BEAN_ENTITY: &str = "Bean";
+
+/// Beans are collected when the player's centre comes within this many pixels.
+const BEAN_PICKUP_RADIUS: f32 = 12.0;
+const BEAN_SIZE: Vec2 = Vec2::new(6.0, 8.0);
+const BEAN_COLOR: Color = Color::rgb(0.45, 0.25, 0.1);
+
+/// A collectible bean, despawned once picked up.
+#[derive(Component)]
+pub struct Bean;
+
+/// Sent when the player picks up a bean.
+#[derive(Clone, Copy, Debug)]
+pub struct BeanCollected {
+    pub position: Vec2,
+}
+
+pub struct BeanPlugin;
+
+impl Plugin for BeanPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<BeanCollected>()
+            .register_level_entity(BEAN_ENTITY, spawn_bean)
+            .add_system(collect_beans);
+    }
+}
+
+/// Gives an LDtk bean its sprite. bevy_ecs_ldtk has already placed it, as a child of its level.
+fn spawn_bean(bean: &mut EntityCommands, _: &EntityInstance, _: Vec2) {
+    bean.insert(bean_components());
+}
+
+/// Drops a bean at `transform`, as a child of `parent` (e.g. the level) if given so it goes away with it.
+pub fn drop_bean(commands: &mut Commands, transform: Transform, parent: Option<Entity>) -> Entity {
+    let bean = commands.spawn((SpatialBundle::from_transform(transform), bean_components())).id();
+    match parent {
+        Some(parent) => commands.entity(parent).add_child(bean),
+        None => commands.entity(bean).insert(GameplayEntity),
+    };
+    bean
+}
+
+fn bean_components() -> impl Bundle {
+    (
+        Sprite {
+            color: BEAN_COLOR,
+            custom_size: Some(BEAN_SIZE),
+            ..default()
+        },
+        Handle::<Image>::default(),
+        Bean,
+    )
+}
+
+pub fn collect_beans(
+    mut commands: Commands,
+    players: Query<&GlobalTransform, With<Player>>,
+    beans: Query<(Entity, &GlobalTransform), With<Bean>>,
+    mut run: ResMut<LevelRun>,
+    mut collected: EventWriter<BeanCollected>,
+) {
+    let Ok(player) = players.get_single() else { return };
+    let player = player.translation().truncate();
+
+    for (entity, transform) in beans.iter() {
+        let position = transform.translation().truncate();
+        if position.distance(player) > BEAN_PICKUP_RADIUS {
+            continue;
+        }
+        commands.entity(entity).despawn_recursive();
+        run.beans += 1;
+        info!("Collected a bean at {position}");
+        collected.send(BeanCollected { position });
+    }
+}
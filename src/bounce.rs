@@ -0,0 +1,115 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::physics::PhysicsUnits;
+
+/// Limits on how bodies with a `BounceGuard` come back off what they hit, in meters per second.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct BounceLimits {
+    /// How much faster than its restitution allows a body may leave a surface, as a multiple of it. Solver error,
+    /// or a body pushed back out of the ground after sinking into it, can otherwise throw it off faster than it came.
+    pub max_gain: f32,
+    /// Below this speed away from a surface a bounce is dropped and the body comes to rest on it, rather than
+    /// jittering on tiny bounces forever.
+    pub rest_speed: f32,
+}
+
+impl Default for BounceLimits {
+    fn default() -> Self {
+        BounceLimits { max_gain: 1.0, rest_speed: 0.3 }
+    }
+}
+
+/// Keeps this body's bounces within the `BounceLimits`. Holds its velocity as it went into the last physics step.
+///
+/// Best paired with `Ccd::enabled()` on anything fast enough to pass into the ground within a step.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct BounceGuard {
+    approach: Vec2,
+}
+
+pub struct BouncePlugin;
+
+impl Plugin for BouncePlugin {
+    fn build(&self, app: &mut App) {
+        // After the last step's velocities are written back and gameplay has had its say, before they're sent on.
+        app
+            .init_resource::<BounceLimits>()
+            .add_system_to_stage(
+                PhysicsStages::SyncBackend,
+                limit_bounces.before(systems::apply_rigid_body_user_changes),
+            );
+    }
+}
+
+/// The speed a body should leave a surface at after coming into it at `incoming` and being thrown back off at
+/// `outgoing` by the solver, both along the contact normal and in meters per second. A rebound is capped at
+/// `incoming * restitution * max_gain`, and one too slow to clear `rest_speed` is dropped to 0.
+pub fn limit_bounce(incoming: f32, outgoing: f32, restitution: f32, limits: &BounceLimits) -> f32 {
+    let outgoing = outgoing.min(incoming.max(0.0) * restitution.max(0.0) * limits.max_gain.max(0.0));
+    if outgoing < limits.rest_speed {
+        return 0.0;
+    }
+    outgoing
+}
+
+/*
+ * A bounce shows as a body touching something that it was moving into before the step and is moving away from
+ * after it. Its speed away along the contact normal is then held to what its restitution allows for the speed it
+ * came in at, or taken away entirely if that's too little to be worth a bounce. Only the normal part of the
+ * velocity changes, so a ball still rolls on along the ground once it's done bouncing. The body's own coefficient
+ * is taken as the bound, which is the most Rapier's default combine rule can give it against anything less bouncy.
+ */
+fn limit_bounces(
+    rapier_context: Res<RapierContext>,
+    units: Res<PhysicsUnits>,
+    limits: Res<BounceLimits>,
+    mut bodies: Query<(Entity, &mut Velocity, &Restitution, &mut BounceGuard)>,
+) {
+    for (entity, mut velocity, restitution, mut guard) in bodies.iter_mut() {
+        let mut linvel = velocity.linvel;
+        for pair in rapier_context.contacts_with(entity) {
+            if !pair.has_any_active_contacts() {
+                continue;
+            }
+            let Some(manifold) = pair.manifolds().find(|manifold| manifold.num_points() > 0) else { continue };
+            // Pointing out of the surface, towards the body.
+            let normal = if pair.collider1() == entity { -manifold.normal() } else { manifold.normal() };
+            let incoming = -guard.approach.dot(normal);
+            let outgoing = linvel.dot(normal);
+            if incoming <= 0.0 || outgoing <= 0.0 {
+                continue;
+            }
+            let limited = limit_bounce(
+                units.px_to_m(incoming),
+                units.px_to_m(outgoing),
+                restitution.coefficient,
+                &limits,
+            );
+            linvel += normal * (units.m_to_px(limited) - outgoing);
+        }
+
+        if velocity.linvel != linvel {
+            velocity.linvel = linvel;
+        }
+        guard.approach = linvel;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebounds_are_capped_and_slow_ones_come_to_rest() {
+        let limits = BounceLimits::default();
+        // The solver throwing it back off faster than it came in is held to what its restitution allows.
+        assert_eq!(limit_bounce(4.0, 6.0, 0.5, &limits), 2.0);
+        assert_eq!(limit_bounce(4.0, 1.5, 0.5, &limits), 1.5);
+        assert_eq!(limit_bounce(4.0, 6.0, 0.5, &BounceLimits { max_gain: 1.5, ..limits }), 3.0);
+        // Too slow to be worth a bounce, including ones only the cap made too slow.
+        assert_eq!(limit_bounce(4.0, 0.25, 0.5, &limits), 0.0);
+        assert_eq!(limit_bounce(0.5, 2.0, 0.5, &limits), 0.0);
+        assert_eq!(limit_bounce(4.0, 2.0, 0.0, &limits), 0.0);
+    }
+}
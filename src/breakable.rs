@@ -0,0 +1,367 @@
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::bean::drop_bean;
+use crate::entity_registry::RegisterLevelEntity;
+use crate::fields::LdtkFields;
+use crate::level::PlayerRespawned;
+use crate::physics::{GameplayDelta, PhysicsUnits, GRAVITY};
+use crate::player::{move_player, Player};
+use crate::projectile::Projectile;
+use crate::state::GameplayEntity;
+
+/// The LDtk entity identifier of a breakable block, a solid block the size of the entity.
+pub const BREAKABLE_ENTITY: &str = "Breakable";
+/// Bool fields choosing what breaks the block; all default to true.
+const FROM_BELOW_FIELD: &str = "FromBelow";
+const DASH_FIELD: &str = "Dash";
+const PROJECTILE_FIELD: &str = "Projectile";
+/// Bool field: leave a bean where the block was. Defaults to false.
+const DROPS_BEAN_FIELD: &str = "DropsBean";
+/// Bool field: put the block back when the player respawns. Defaults to false.
+const RESTORES_FIELD: &str = "Restores";
+
+const BLOCK_COLOR: Color = Color::rgb(0.6, 0.4, 0.25);
+/// A contact normal at least this steep counts as hitting the block from that side.
+const HIT_NORMAL: f32 = 0.7;
+const DEBRIS_COUNT: usize = 6;
+const DEBRIS_SIZE: f32 = 3.0;
+/// How fast debris flies out, in m/s.
+const DEBRIS_SPEED: f32 = 3.0;
+const DEBRIS_LIFETIME: f32 = 0.6;
+
+/// The ways a block can be hit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockHit {
+    /// The player jumped into it from underneath.
+    FromBelow,
+    /// The player ran into its side faster than `BreakableConfig::dash_speed`.
+    Dash,
+    Projectile,
+}
+
+/// Whether a broken block comes back when the player respawns.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BreakPolicy {
+    /// Put back on every respawn, e.g. blocks that have to be broken to reach a checkpoint's next section.
+    RestoreOnRespawn,
+    /// Stays broken until the player leaves the level, even if the level is reloaded.
+    #[default]
+    StayBroken,
+}
+
+/// A solid block that breaks when hit in one of the ways it allows.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Breakable {
+    pub from_below: bool,
+    pub dash: bool,
+    pub projectile: bool,
+    pub drops_bean: bool,
+    pub policy: BreakPolicy,
+}
+
+impl Default for Breakable {
+    fn default() -> Self {
+        Breakable {
+            from_below: true,
+            dash: true,
+            projectile: true,
+            drops_bean: false,
+            policy: BreakPolicy::StayBroken,
+        }
+    }
+}
+
+impl Breakable {
+    pub fn breaks_on(&self, hit: BlockHit) -> bool {
+        match hit {
+            BlockHit::FromBelow => self.from_below,
+            BlockHit::Dash => self.dash,
+            BlockHit::Projectile => self.projectile,
+        }
+    }
+}
+
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct BreakableConfig {
+    /// The horizontal speed, in m/s, the player has to run into a block's side at to break it: faster than running.
+    pub dash_speed: f32,
+}
+
+impl Default for BreakableConfig {
+    fn default() -> Self {
+        BreakableConfig { dash_speed: 6.0 }
+    }
+}
+
+/// Sent when a block breaks.
+#[derive(Clone, Copy, Debug)]
+pub struct BlockBroken {
+    pub position: Vec2,
+    pub hit: BlockHit,
+}
+
+/// Broken blocks of the current level: the LDtk IIDs of those that stay broken, and what's needed to put back the
+/// ones that don't.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct BrokenBlocks {
+    pub persisted: HashSet<String>,
+    to_restore: Vec<(EntityInstance, Transform, Option<Entity>)>,
+}
+
+/// A flying chip of a broken block. Velocity is in pixels per second.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Debris {
+    pub velocity: Vec2,
+    pub remaining: f32,
+}
+
+pub struct BreakablePlugin;
+
+impl Plugin for BreakablePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<BreakableConfig>()
+            .init_resource::<BrokenBlocks>()
+            .add_event::<BlockBroken>()
+            .register_level_entity(BREAKABLE_ENTITY, spawn_breakable)
+            .add_system(hit_blocks.after(move_player))
+            .add_system(restore_blocks.after(hit_blocks))
+            .add_system(skip_persisted_breaks)
+            .add_system(clear_breaks_on_level_change)
+            .add_system(fly_debris);
+    }
+}
+
+/// How the player is hitting a block, given the contact normal pointing from the block to the player and the
+/// player's velocity in m/s. Only the underside and the sides can be hit; standing on top does nothing.
+pub fn player_hit(normal: Vec2, velocity: Vec2, dash_speed: f32) -> Option<BlockHit> {
+    if normal.y <= -HIT_NORMAL {
+        Some(BlockHit::FromBelow)
+    } else if normal.x.abs() >= HIT_NORMAL && velocity.x * normal.x < 0.0 && velocity.x.abs() >= dash_speed {
+        Some(BlockHit::Dash)
+    } else {
+        None
+    }
+}
+
+fn spawn_breakable(block: &mut EntityCommands, instance: &EntityInstance, _: Vec2) {
+    let fields = instance.fields();
+    let policy = if fields.get_bool(RESTORES_FIELD).unwrap_or(false) {
+        BreakPolicy::RestoreOnRespawn
+    } else {
+        BreakPolicy::StayBroken
+    };
+    let size = Vec2::new(instance.width as f32, instance.height as f32);
+
+    block.insert((
+        Breakable {
+            from_below: fields.get_bool(FROM_BELOW_FIELD).unwrap_or(true),
+            dash: fields.get_bool(DASH_FIELD).unwrap_or(true),
+            projectile: fields.get_bool(PROJECTILE_FIELD).unwrap_or(true),
+            drops_bean: fields.get_bool(DROPS_BEAN_FIELD).unwrap_or(false),
+            policy,
+        },
+        Collider::cuboid(size.x / 2.0, size.y / 2.0),
+        Sprite {
+            color: BLOCK_COLOR,
+            custom_size: Some(size),
+            ..default()
+        },
+        Handle::<Image>::default(),
+    ));
+}
+
+/*
+ * Player hits come from the contacts of the last physics step, read after the controller has set this frame's
+ * velocity: the solver will have stopped the player against the block, but the controller still says how hard
+ * they're pushing into it. Projectiles hit on their first collision, before they're stopped by it.
+ */
+#[allow(clippy::too_many_arguments)]
+fn hit_blocks(
+    mut commands: Commands,
+    config: Res<BreakableConfig>,
+    units: Res<PhysicsUnits>,
+    rapier_context: Res<RapierContext>,
+    mut collisions: EventReader<CollisionEvent>,
+    mut broken: ResMut<BrokenBlocks>,
+    mut events: EventWriter<BlockBroken>,
+    blocks: Query<(Entity, &Breakable, &Transform, &GlobalTransform, Option<&Parent>, Option<&EntityInstance>)>,
+    players: Query<&Velocity, With<Player>>,
+    projectiles: Query<(), With<Projectile>>,
+) {
+    let mut hits: Vec<(Entity, BlockHit)> = Vec::new();
+
+    for collision in collisions.iter() {
+        let CollisionEvent::Started(a, b, _) = *collision else { continue };
+        for (block, other) in [(a, b), (b, a)] {
+            if blocks.contains(block) && projectiles.contains(other) {
+                hits.push((block, BlockHit::Projectile));
+            }
+        }
+    }
+
+    for (block, _, _, _, _, _) in blocks.iter() {
+        for pair in rapier_context.contacts_with(block) {
+            if !pair.has_any_active_contacts() {
+                continue;
+            }
+            let Some(manifold) = pair.manifolds().find(|manifold| manifold.num_points() > 0) else { continue };
+            let (other, normal) = if pair.collider1() == block {
+                (pair.collider2(), manifold.normal())
+            } else {
+                (pair.collider1(), -manifold.normal())
+            };
+            let body = rapier_context.collider_parent(other).unwrap_or(other);
+            let Ok(velocity) = players.get(body) else { continue };
+            let velocity = Vec2::new(units.px_to_m(velocity.linvel.x), units.px_to_m(velocity.linvel.y));
+            if let Some(hit) = player_hit(normal, velocity, config.dash_speed) {
+                hits.push((block, hit));
+            }
+        }
+    }
+
+    let mut broken_now = HashSet::new();
+    for (block, hit) in hits {
+        let Ok((entity, breakable, transform, global, parent, instance)) = blocks.get(block) else { continue };
+        if !breakable.breaks_on(hit) || !broken_now.insert(entity) {
+            continue;
+        }
+
+        let position = global.translation().truncate();
+        commands.entity(entity).despawn_recursive();
+        spawn_debris(&mut commands, position, BLOCK_COLOR, &units);
+        if breakable.drops_bean {
+            drop_bean(&mut commands, *transform, parent.map(Parent::get));
+        }
+        if let Some(instance) = instance {
+            match breakable.policy {
+                BreakPolicy::StayBroken => {
+                    broken.persisted.insert(instance.iid.clone());
+                }
+                BreakPolicy::RestoreOnRespawn => {
+                    broken.to_restore.push((instance.clone(), *transform, parent.map(Parent::get)));
+                }
+            }
+        }
+        events.send(BlockBroken { position, hit });
+    }
+}
+
+/// Bursts `color` pieces out of `position`, e.g. a broken block or a defeated enemy.
+pub fn spawn_debris(commands: &mut Commands, position: Vec2, color: Color, units: &PhysicsUnits) {
+    for index in 0..DEBRIS_COUNT {
+        // Fanned out upwards, so the pieces arc away and fall.
+        let angle = std::f32::consts::PI * (index as f32 + 0.5) / DEBRIS_COUNT as f32;
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color,
+                    custom_size: Some(Vec2::splat(DEBRIS_SIZE)),
+                    ..default()
+                },
+                transform: Transform::from_translation(position.extend(2.0)),
+                ..default()
+            },
+            Debris {
+                velocity: Vec2::from_angle(angle) * units.m_to_px(DEBRIS_SPEED),
+                remaining: DEBRIS_LIFETIME,
+            },
+            GameplayEntity,
+        ));
+    }
+}
+
+fn fly_debris(
+    mut commands: Commands,
+    delta: Res<GameplayDelta>,
+    units: Res<PhysicsUnits>,
+    mut debris: Query<(Entity, &mut Debris, &mut Transform, &mut Sprite)>,
+) {
+    for (entity, mut piece, mut transform, mut sprite) in debris.iter_mut() {
+        piece.remaining -= delta.0;
+        if piece.remaining <= 0.0 {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        piece.velocity.y -= units.m_to_px(GRAVITY) * delta.0;
+        transform.translation += (piece.velocity * delta.0).extend(0.0);
+        sprite.color.set_a(piece.remaining / DEBRIS_LIFETIME);
+    }
+}
+
+/// Puts back the blocks that don't stay broken when the player respawns; they're set up again like freshly loaded
+/// LDtk entities.
+fn restore_blocks(
+    mut commands: Commands,
+    mut respawns: EventReader<PlayerRespawned>,
+    mut broken: ResMut<BrokenBlocks>,
+    entities: Query<()>,
+) {
+    if respawns.iter().count() == 0 {
+        return;
+    }
+
+    for (instance, transform, parent) in broken.to_restore.drain(..) {
+        if parent.is_some_and(|parent| !entities.contains(parent)) {
+            continue;
+        }
+        let block = commands.spawn((instance, SpatialBundle::from_transform(transform))).id();
+        match parent {
+            Some(parent) => commands.entity(parent).add_child(block),
+            None => commands.entity(block).insert(GameplayEntity),
+        };
+    }
+}
+
+/// Keeps persisted breaks broken when their level is respawned and its entities come back.
+fn skip_persisted_breaks(
+    mut commands: Commands,
+    broken: Res<BrokenBlocks>,
+    blocks: Query<(Entity, &EntityInstance), Added<Breakable>>,
+) {
+    for (entity, instance) in blocks.iter() {
+        if broken.persisted.contains(&instance.iid) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn clear_breaks_on_level_change(selection: Option<Res<LevelSelection>>, mut broken: ResMut<BrokenBlocks>) {
+    if selection.is_some_and(|selection| selection.is_changed()) {
+        broken.persisted.clear();
+        broken.to_restore.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DASH_SPEED: f32 = 6.0;
+
+    #[test]
+    fn hits_from_below_or_dashing_into_a_side() {
+        // Jumping into the underside breaks it whatever the speed.
+        assert_eq!(player_hit(Vec2::NEG_Y, Vec2::new(0.0, 3.0), DASH_SPEED), Some(BlockHit::FromBelow));
+        // Running into the left side, at dash speed or just walking.
+        assert_eq!(player_hit(Vec2::NEG_X, Vec2::new(7.0, 0.0), DASH_SPEED), Some(BlockHit::Dash));
+        assert_eq!(player_hit(Vec2::NEG_X, Vec2::new(4.0, 0.0), DASH_SPEED), None);
+        // Dashing away from a side it's touching.
+        assert_eq!(player_hit(Vec2::X, Vec2::new(7.0, 0.0), DASH_SPEED), None);
+        // Standing or landing on top.
+        assert_eq!(player_hit(Vec2::Y, Vec2::new(7.0, -5.0), DASH_SPEED), None);
+    }
+
+    #[test]
+    fn blocks_break_only_on_the_hits_they_allow() {
+        let shot_only = Breakable { from_below: false, dash: false, ..default() };
+        assert!(shot_only.breaks_on(BlockHit::Projectile));
+        assert!(!shot_only.breaks_on(BlockHit::FromBelow) && !shot_only.breaks_on(BlockHit::Dash));
+        let any = Breakable::default();
+        assert!([BlockHit::FromBelow, BlockHit::Dash, BlockHit::Projectile].iter().all(|&hit| any.breaks_on(hit)));
+    }
+}
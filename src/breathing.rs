@@ -0,0 +1,137 @@
+use std::f64::consts::TAU;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use gamelibs::prelude::*;
+use nalgebra_glm::DVec3;
+
+use crate::physics::{GameplayDelta, PhysicsUnits};
+use crate::player::{move_player, Player, PlayerState, PlayerStateMachine};
+use crate::settings::Settings;
+
+/// Seconds per breath, and how far the sprite stretches at the top of one.
+const BREATH_PERIOD: f64 = 2.4;
+const BREATH_SCALE: f64 = 0.025;
+
+/// Below this speed in m/s a grounded player counts as standing still.
+const IDLE_SPEED: f32 = 0.1;
+/// Seconds for the breathing to fade fully in after the player stops, and back out once they move.
+const BLEND_TIME: f32 = 0.5;
+
+/// Gently stretches and settles its entity's children while the player stands still.
+///
+/// It sits on its own entity between the player and their sprite, so the squash, flip and crouch effects on the
+/// sprite's transform compose with it rather than overwrite it.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Breathing {
+    /// 0 while moving, rising to 1 over `BLEND_TIME` of standing still.
+    pub blend: f32,
+    /// Seconds into the breathing cycle.
+    pub phase: f64,
+    curve: CurveType,
+    position: DVec3,
+    velocity: DVec3,
+}
+
+impl Default for Breathing {
+    fn default() -> Self {
+        Breathing {
+            blend: 0.0,
+            phase: 0.0,
+            curve: CurveType::from_style(CurveStyle::Mechanical { f: 1.5, z: 1.0 }),
+            position: DVec3::zeros(),
+            velocity: DVec3::zeros(),
+        }
+    }
+}
+
+impl Breathing {
+    /// Advances by `dt` and returns how far stretched the sprite is, between -1 and 1.
+    ///
+    /// The breath itself is a sine faded by the blend, but rather than being sampled directly it's followed through
+    /// the curve integrator, so switching it on and off mid-breath settles smoothly instead of snapping.
+    pub fn step(&mut self, idle: bool, dt: f32) -> f32 {
+        let direction = if idle { 1.0 } else { -1.0 };
+        self.blend = (self.blend + direction * dt / BLEND_TIME).clamp(0.0, 1.0);
+        if self.blend == 0.0 {
+            self.phase = 0.0;
+        }
+
+        let weight = breathing_weight(self.blend) as f64;
+        let phase = self.phase;
+        let (position, velocity) = calc_weighted_next(WeightedNextBundle {
+            // `calc_weighted_next` samples at the timestep, so shift the breath to start where this step does.
+            base_func: |t| weight * (TAU * (phase + t) / BREATH_PERIOD).sin(),
+            time: dt as f64,
+            max_step: DEFAULT_MAX_STEP,
+            curve: self.curve,
+            last_pos: self.position,
+            last_vel: self.velocity,
+            last_acc: DVec3::zeros(),
+        });
+        self.position = position;
+        self.velocity = velocity;
+        self.phase = (self.phase + dt as f64) % BREATH_PERIOD;
+
+        self.position.x as f32
+    }
+}
+
+/// How strongly the breathing shows `blend` of the way through fading in, easing at both ends.
+pub fn breathing_weight(blend: f32) -> f32 {
+    ease(Ease::SineInOut, blend.clamp(0.0, 1.0) as f64) as f32
+}
+
+pub struct BreathingPlugin;
+
+impl Plugin for BreathingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(breathe.after(move_player));
+    }
+}
+
+/// Stretches the sprite upwards from the player's feet, so they stay planted on the ground.
+fn breathe(
+    delta: Res<GameplayDelta>,
+    units: Res<PhysicsUnits>,
+    settings: Res<Settings>,
+    players: Query<(&Velocity, &PlayerStateMachine, &Collider), With<Player>>,
+    mut pivots: Query<(&mut Breathing, &mut Transform, &Parent)>,
+) {
+    for (mut breathing, mut transform, parent) in pivots.iter_mut() {
+        let Ok((velocity, state, collider)) = players.get(parent.get()) else { continue };
+        let idle = state.is(PlayerState::Grounded) && units.px_to_m(velocity.linvel.x.abs()) < IDLE_SPEED;
+
+        let stretch = breathing.step(idle, delta.0) * BREATH_SCALE as f32 * settings.juice.squash_stretch.max(0.0);
+        let feet = collider.raw.compute_local_aabb().mins.y;
+        transform.scale.y = 1.0 + stretch;
+        transform.translation.y = -feet * stretch;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn breathing_blends_in_while_idle_and_out_when_moving() {
+        let dt = 1.0 / 60.0;
+        let mut breathing = Breathing::default();
+        let mut weights = Vec::new();
+        for _ in 0..(BLEND_TIME / dt).round() as usize {
+            breathing.step(true, dt);
+            weights.push(breathing_weight(breathing.blend));
+        }
+        assert!(weights.windows(2).all(|pair| pair[1] >= pair[0]));
+        assert!(weights[0] < 0.01);
+        assert!((weights.last().unwrap() - 1.0).abs() < 1e-4);
+
+        // Half the blend time of moving fades it back out halfway; the ease makes that half the weight too.
+        for _ in 0..(BLEND_TIME / dt / 2.0).round() as usize {
+            breathing.step(false, dt);
+        }
+        assert!((breathing_weight(breathing.blend) - 0.5).abs() < 1e-3);
+        assert_eq!(breathing_weight(0.0), 0.0);
+        assert_eq!(breathing_weight(2.0), 1.0);
+    }
+}
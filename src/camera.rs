@@ -0,0 +1,468 @@
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+use bevy_rapier2d::prelude::Velocity;
+use gamelibs::math::{CurveFollower, CurveStyle};
+use gamelibs::noise::shake_offset;
+
+use crate::auto_scroll::AutoScroll;
+use crate::confiner::CameraConfiner;
+use crate::physics::GameplayDelta;
+use crate::player::{Player, PlayerEvent, PlayerState, PlayerStateMachine};
+use crate::settings::Settings;
+
+/// Empty space kept around a `CameraFocus` rectangle, in world pixels.
+const FOCUS_PADDING: f32 = 32.0;
+/// Trauma added to the camera shake when the player is hurt.
+const DAMAGE_TRAUMA: f32 = 0.4;
+
+/// Marker for the camera that renders the game world.
+#[derive(Component)]
+pub struct GameCamera;
+
+/// How the camera follows the player up and down.
+///
+/// * `VerticalFollow::Always` tracks their height like any other movement.
+///
+/// * `VerticalFollow::OnLanding { max_lag }` holds the height they last stood at while they're in the air, so the
+///   view doesn't bob with every jump, and moves to the new height when they land. It still follows as soon as they
+///   drop below that height, or climb more than `max_lag` pixels above it, so they never leave the view.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum VerticalFollow {
+    #[default]
+    Always,
+    OnLanding { max_lag: f32 },
+}
+
+/// Zooms the camera out as the player speeds up, for a sense of speed.
+///
+/// The zoom is a multiple of `CameraFollow::scale`: `min_zoom` at or below `min_speed`, `max_zoom` at or above
+/// `max_speed`, and in proportion between. Speeds are in pixels/s.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpeedZoom {
+    pub min_speed: f32,
+    pub max_speed: f32,
+    pub min_zoom: f32,
+    pub max_zoom: f32,
+}
+
+impl Default for SpeedZoom {
+    fn default() -> Self {
+        SpeedZoom {
+            min_speed: 200.0,
+            max_speed: 600.0,
+            min_zoom: 1.0,
+            max_zoom: 1.25,
+        }
+    }
+}
+
+impl SpeedZoom {
+    /// The zoom for moving at `speed`.
+    pub fn zoom(&self, speed: f32) -> f32 {
+        let range = self.max_speed - self.min_speed;
+        let t = if range > 0.0 {
+            ((speed - self.min_speed) / range).clamp(0.0, 1.0)
+        } else if speed >= self.max_speed {
+            1.0
+        } else {
+            0.0
+        };
+        self.min_zoom + (self.max_zoom - self.min_zoom) * t
+    }
+}
+
+/// The nearest orthographic scale at which every world pixel covers a whole number of screen pixels, or a whole
+/// number of world pixels share each screen pixel when zoomed out.
+pub fn pixel_perfect_scale(scale: f32) -> f32 {
+    if scale >= 1.0 {
+        scale.round()
+    } else if scale > 0.0 {
+        1.0 / (1.0 / scale).round()
+    } else {
+        scale
+    }
+}
+
+/// Keeps the player in view, smoothing position and zoom through the curve integrator. Each axis has its own
+/// integrator, so vertical movement can be stiffer or looser than horizontal.
+///
+/// Something else can take the camera for a while with `set_camera_target`, e.g. a boss on its entrance, and hand
+/// it back with `restore_camera_target`. Overrides stack, so a nested one gives the camera back to the one it
+/// interrupted, and the camera glides between targets rather than cutting.
+#[derive(Component, Clone, Debug)]
+pub struct CameraFollow {
+    /// The orthographic scale to settle at while following.
+    pub scale: f32,
+    pub vertical: VerticalFollow,
+    /// Zooms out with the player's speed while following them, if set.
+    pub speed_zoom: Option<SpeedZoom>,
+    /// Snaps the eased scale with `pixel_perfect_scale`, so pixel art stays crisp at the cost of zooming in steps.
+    pub pixel_perfect: bool,
+    targets: Vec<Entity>,
+    /// The height the player last stood at, for `VerticalFollow::OnLanding`.
+    ground_y: Option<f32>,
+    x: CurveFollower,
+    y: CurveFollower,
+    zoom: CurveFollower,
+}
+
+impl CameraFollow {
+    pub fn new(style: CurveStyle) -> Self {
+        CameraFollow::with_axes(style, style)
+    }
+
+    /// Smooths horizontal movement and zoom with `horizontal`, and vertical movement with `vertical`.
+    pub fn with_axes(horizontal: CurveStyle, vertical: CurveStyle) -> Self {
+        CameraFollow {
+            scale: 1.0,
+            vertical: VerticalFollow::Always,
+            speed_zoom: None,
+            pixel_perfect: false,
+            targets: Vec::new(),
+            ground_y: None,
+            x: CurveFollower::new(horizontal, 0.0),
+            y: CurveFollower::new(vertical, 0.0),
+            zoom: CurveFollower::new(horizontal, 1.0),
+        }
+    }
+
+    /// The height to aim for while following a player at `player_y`, given whether they're on the ground.
+    pub fn vertical_target(&mut self, player_y: f32, grounded: bool) -> f32 {
+        let VerticalFollow::OnLanding { max_lag } = self.vertical else { return player_y };
+        if grounded {
+            self.ground_y = Some(player_y);
+        }
+        match self.ground_y {
+            Some(ground_y) if player_y >= ground_y && player_y <= ground_y + max_lag => ground_y,
+            _ => player_y,
+        }
+    }
+
+    /// Follows `entity` instead of the current target until it's restored.
+    pub fn set_camera_target(&mut self, entity: Entity) {
+        self.targets.push(entity);
+    }
+
+    /// Stops following the latest `set_camera_target` and goes back to the target before it, returning the entity
+    /// that was dropped. With no overrides left the camera is back on the player.
+    pub fn restore_camera_target(&mut self) -> Option<Entity> {
+        self.targets.pop()
+    }
+
+    /// The entity being followed in place of the player, if any.
+    pub fn camera_target(&self) -> Option<Entity> {
+        self.targets.last().copied()
+    }
+
+    /// Jumps the smoothed position straight to `position`, e.g. after something else has moved the camera.
+    pub fn snap_to(&mut self, position: Vec2) {
+        self.x.reset(position.x as f64);
+        self.y.reset(position.y as f64);
+    }
+}
+
+impl Default for CameraFollow {
+    fn default() -> Self {
+        CameraFollow::new(CurveStyle::SmoothDamped)
+    }
+}
+
+/// Frames `target_rect` for `duration` seconds, overriding `CameraFollow`; removed again once the time runs out.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct CameraFocus {
+    pub target_rect: Rect,
+    /// Seconds left, counted down while the focus is active.
+    pub duration: f32,
+}
+
+/// Shakes the camera while it has trauma, through `shake_offset`. Add some with `add_trauma` when something hits
+/// hard; it wears off by itself.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct CameraShake {
+    /// How shaken the camera is, from 0 to 1.
+    pub trauma: f32,
+    /// Trauma lost per second.
+    pub decay: f32,
+    /// How far (in world pixels) full trauma throws the camera along each axis.
+    pub max_offset: f32,
+    pub seed: u64,
+    /// Seconds of gameplay time the shake has run, driving the noise.
+    time: f32,
+    /// The offset on the camera this frame, taken back off before the next.
+    applied: Vec2,
+}
+
+impl CameraShake {
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).clamp(0.0, 1.0);
+    }
+
+    /// The offset for the current trauma and time.
+    pub fn offset(&self) -> Vec2 {
+        let offset = shake_offset(self.trauma as f64, self.time as f64, self.seed) * self.max_offset as f64;
+        Vec2::new(offset.x as f32, offset.y as f32)
+    }
+}
+
+impl Default for CameraShake {
+    fn default() -> Self {
+        CameraShake {
+            trauma: 0.0,
+            decay: 1.5,
+            max_offset: 12.0,
+            seed: 0,
+            time: 0.0,
+            applied: Vec2::ZERO,
+        }
+    }
+}
+
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_system_to_stage(CoreStage::PreUpdate, unshake_camera)
+            .add_system(follow_camera)
+            .add_system(shake_on_damage)
+            // Once everything has moved the camera for the frame, but before propagation so the shake is drawn.
+            .add_system_to_stage(CoreStage::PostUpdate, shake_camera.before(TransformSystem::TransformPropagate));
+    }
+}
+
+/// Converts the cursor position on `window` into a world-space point as seen by the given camera.
+///
+/// Returns `None` when the cursor is outside the window or the camera has no viewport yet.
+pub fn cursor_to_world(window: &Window, camera: &Camera, camera_transform: &GlobalTransform) -> Option<Vec2> {
+    let cursor = window.cursor_position()?;
+    camera
+        .viewport_to_world(camera_transform, cursor)
+        .map(|ray| ray.origin.truncate())
+}
+
+/// The world-space rectangle an orthographic camera currently sees.
+pub fn camera_view(transform: &Transform, projection: &OrthographicProjection) -> Rect {
+    let center = transform.translation.truncate();
+    Rect::new(
+        center.x + projection.left * projection.scale,
+        center.y + projection.bottom * projection.scale,
+        center.x + projection.right * projection.scale,
+        center.y + projection.top * projection.scale,
+    )
+}
+
+/// The orthographic scale at which a `rect_size` area plus `padding` on every side just fits a view that is
+/// `view_size` world units across at scale 1, keeping the view's aspect ratio.
+pub fn fit_scale(rect_size: Vec2, view_size: Vec2, padding: f32) -> f32 {
+    let padded = rect_size + Vec2::splat(padding * 2.0);
+    (padded / view_size).max_element()
+}
+
+/*
+ * The projection's unscaled extents are derived from the camera's viewport rather than the window, so a
+ * letterboxed viewport is fitted with its own aspect ratio. A `CameraConfiner` keeps the followed view inside
+ * the target's room; a focus frames its own rectangle wherever that is. Targets that have been despawned are
+ * dropped as though restored. The speed zoom eases through the same integrator as any other change of scale, and
+ * pixel-perfect snapping is applied after it, so the integrator keeps moving smoothly underneath the steps.
+ */
+fn follow_camera(
+    mut commands: Commands,
+    delta: Res<GameplayDelta>,
+    players: Query<(&Transform, &PlayerStateMachine, Option<&Velocity>), (With<Player>, Without<CameraFollow>)>,
+    targets: Query<&GlobalTransform, Without<CameraFollow>>,
+    mut cameras: Query<
+        (
+            Entity,
+            &mut Transform,
+            &mut OrthographicProjection,
+            &mut CameraFollow,
+            Option<&mut CameraFocus>,
+            Option<&mut CameraConfiner>,
+        ),
+        Without<AutoScroll>,
+    >,
+) {
+    let dt = delta.0 as f64;
+
+    for (entity, mut transform, mut projection, mut follow, focus, confiner) in cameras.iter_mut() {
+        let view_size = Vec2::new(projection.right - projection.left, projection.top - projection.bottom);
+        let (target, scale) = match focus {
+            Some(mut focus) => {
+                focus.duration -= delta.0;
+                if focus.duration <= 0.0 {
+                    commands.entity(entity).remove::<CameraFocus>();
+                }
+                let scale = fit_scale(focus.target_rect.size(), view_size, FOCUS_PADDING);
+                (focus.target_rect.center(), scale)
+            }
+            None => {
+                while follow.camera_target().is_some_and(|target| !targets.contains(target)) {
+                    follow.restore_camera_target();
+                }
+                let mut scale = follow.scale;
+                let mut target = match follow.camera_target().and_then(|target| targets.get(target).ok()) {
+                    Some(target) => target.translation().truncate(),
+                    None => {
+                        let Ok((player, state, velocity)) = players.get_single() else { continue };
+                        if let (Some(speed_zoom), Some(velocity)) = (follow.speed_zoom, velocity) {
+                            scale *= speed_zoom.zoom(velocity.linvel.length());
+                        }
+                        let y = follow.vertical_target(player.translation.y, state.is(PlayerState::Grounded));
+                        Vec2::new(player.translation.x, y)
+                    }
+                };
+                if let Some(mut confiner) = confiner {
+                    let half_view = view_size * scale / 2.0;
+                    target = confiner.confine(target, transform.translation.truncate(), half_view, delta.0);
+                }
+                (target, scale)
+            }
+        };
+
+        transform.translation.x = follow.x.step(target.x as f64, dt) as f32;
+        transform.translation.y = follow.y.step(target.y as f64, dt) as f32;
+        let mut zoom = follow.zoom.step(scale as f64, dt) as f32;
+        if follow.pixel_perfect {
+            zoom = pixel_perfect_scale(zoom);
+        }
+        if projection.scale != zoom {
+            projection.scale = zoom;
+        }
+    }
+}
+
+/// Takes last frame's shake back off, so everything during the frame sees, and moves, the steady camera.
+fn unshake_camera(mut cameras: Query<(&mut Transform, &mut CameraShake)>) {
+    for (mut transform, mut shake) in cameras.iter_mut() {
+        if shake.applied != Vec2::ZERO {
+            transform.translation -= shake.applied.extend(0.0);
+            shake.applied = Vec2::ZERO;
+        }
+    }
+}
+
+/// Shakes by the player's `JuiceSettings::screen_shake`; with it at 0 the trauma still wears off, unseen.
+pub fn shake_camera(
+    delta: Res<GameplayDelta>,
+    settings: Res<Settings>,
+    mut cameras: Query<(&mut Transform, &mut CameraShake)>,
+) {
+    for (mut transform, mut shake) in cameras.iter_mut() {
+        if shake.trauma <= 0.0 {
+            continue;
+        }
+        shake.time += delta.0;
+        shake.applied = shake.offset() * settings.juice.screen_shake.max(0.0);
+        transform.translation += shake.applied.extend(0.0);
+        shake.trauma = (shake.trauma - shake.decay * delta.0).max(0.0);
+    }
+}
+
+fn shake_on_damage(mut events: EventReader<PlayerEvent>, mut cameras: Query<&mut CameraShake>) {
+    for event in events.iter() {
+        if let PlayerEvent::Damaged { .. } = event {
+            for mut shake in cameras.iter_mut() {
+                shake.add_trauma(DAMAGE_TRAUMA);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fit_scale_fits_the_tighter_axis() {
+        let view = Vec2::new(320.0, 180.0);
+        assert_eq!(fit_scale(Vec2::new(320.0, 180.0), view, 0.0), 1.0);
+        // Wider than the view: the width decides, and the height has room to spare.
+        assert_eq!(fit_scale(Vec2::new(640.0, 90.0), view, 0.0), 2.0);
+        // Taller than the view's aspect: the height decides.
+        assert_eq!(fit_scale(Vec2::new(100.0, 360.0), view, 0.0), 2.0);
+        assert_eq!(fit_scale(Vec2::new(140.0, 70.0), view, 10.0), 0.5);
+    }
+
+    #[test]
+    fn fitted_rect_lies_inside_the_view() {
+        let view = Vec2::new(400.0, 225.0);
+        let rect = Vec2::new(700.0, 300.0);
+        let scale = fit_scale(rect, view, 16.0);
+        let seen = view * scale;
+        assert!(seen.x >= rect.x + 32.0 - 1e-3 && seen.y >= rect.y + 32.0 - 1e-3);
+        assert!((seen.x - (rect.x + 32.0)).abs() < 1e-3 || (seen.y - (rect.y + 32.0)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn camera_targets_restore_in_reverse_order() {
+        let [boss, door] = [1, 2].map(Entity::from_raw);
+        let mut follow = CameraFollow::default();
+        assert_eq!(follow.camera_target(), None);
+
+        follow.set_camera_target(boss);
+        follow.set_camera_target(door);
+        assert_eq!(follow.camera_target(), Some(door));
+
+        assert_eq!(follow.restore_camera_target(), Some(door));
+        assert_eq!(follow.camera_target(), Some(boss));
+        assert_eq!(follow.restore_camera_target(), Some(boss));
+        // Back on the player, and restoring again does nothing.
+        assert_eq!(follow.camera_target(), None);
+        assert_eq!(follow.restore_camera_target(), None);
+    }
+
+    #[test]
+    fn vertical_follow_waits_for_landing() {
+        let mut follow = CameraFollow { vertical: VerticalFollow::OnLanding { max_lag: 50.0 }, ..default() };
+        assert_eq!(follow.vertical_target(100.0, true), 100.0);
+        // Jumping holds the ground height, until the player rises out of the lag or drops below it.
+        assert_eq!(follow.vertical_target(140.0, false), 100.0);
+        assert_eq!(follow.vertical_target(170.0, false), 170.0);
+        assert_eq!(follow.vertical_target(80.0, false), 80.0);
+        // Landing on a ledge moves up to it.
+        assert_eq!(follow.vertical_target(130.0, true), 130.0);
+        assert_eq!(follow.vertical_target(160.0, false), 130.0);
+
+        let mut always = CameraFollow::default();
+        assert_eq!(always.vertical_target(140.0, false), 140.0);
+    }
+
+    #[test]
+    fn speed_zoom_runs_between_its_limits() {
+        let zoom = SpeedZoom { min_speed: 200.0, max_speed: 600.0, min_zoom: 1.0, max_zoom: 1.5 };
+        assert_eq!(zoom.zoom(0.0), 1.0);
+        assert_eq!(zoom.zoom(200.0), 1.0);
+        assert_eq!(zoom.zoom(400.0), 1.25);
+        assert_eq!(zoom.zoom(600.0), 1.5);
+        assert_eq!(zoom.zoom(5000.0), 1.5);
+
+        // With no range between the speeds it steps straight from one zoom to the other.
+        let step = SpeedZoom { min_speed: 300.0, max_speed: 300.0, ..zoom };
+        assert_eq!(step.zoom(299.0), 1.0);
+        assert_eq!(step.zoom(300.0), 1.5);
+    }
+
+    #[test]
+    fn pixel_perfect_scales_are_whole_ratios() {
+        assert_eq!(pixel_perfect_scale(1.25), 1.0);
+        assert_eq!(pixel_perfect_scale(2.75), 3.0);
+        assert_eq!(pixel_perfect_scale(0.45), 0.5);
+        assert_eq!(pixel_perfect_scale(0.0), 0.0);
+    }
+
+    #[test]
+    fn zero_screen_shake_leaves_the_camera_still() {
+        let mut settings = Settings::default();
+        settings.juice.screen_shake = 0.0;
+        let mut app = App::new();
+        app.insert_resource(settings).insert_resource(GameplayDelta(0.125)).add_system(shake_camera);
+        let camera = app.world.spawn((Transform::default(), CameraShake { trauma: 1.0, ..default() })).id();
+        app.update();
+
+        let shake = app.world.get::<CameraShake>(camera).unwrap();
+        assert_eq!(shake.applied, Vec2::ZERO);
+        // The trauma still wears off, unseen.
+        assert!(shake.trauma < 1.0);
+        assert_eq!(app.world.get::<Transform>(camera).unwrap().translation, Vec3::ZERO);
+    }
+}
@@ -0,0 +1,124 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::input::InputState;
+use crate::physics::{GameplayDelta, PhysicsUnits, GRAVITY};
+use crate::player::{jump_velocity, Player, PlayerEvent, PlayerSprite, PlayerState, PlayerStateMachine};
+use crate::settings::Settings;
+use crate::state::GameState;
+
+/// How much the player's sprite squashes at full charge, as a fraction of its height.
+const FULL_CHARGE_SQUASH: f32 = 0.3;
+
+/// Tuning for the charged super jump.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct ChargeJumpConfig {
+    /// How much charge builds per second while held, where 1 is full.
+    pub charge_rate: f32,
+    /// Peak height of a fully charged jump, in meters.
+    pub max_height: f32,
+}
+
+impl Default for ChargeJumpConfig {
+    fn default() -> Self {
+        ChargeJumpConfig {
+            charge_rate: 1.0,
+            max_height: 4.0,
+        }
+    }
+}
+
+/// The charge built up towards a super jump, in `[0, 1]`.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct ChargeJump {
+    pub charge: f32,
+}
+
+/// The launch speed (in m/s) of a jump released at `charge`, peaking at that fraction of `max_height` meters.
+pub fn charge_jump_velocity(charge: f32, max_height: f32, gravity: f32) -> f32 {
+    jump_velocity(max_height * charge.clamp(0.0, 1.0), gravity)
+}
+
+pub struct ChargeJumpPlugin;
+
+impl Plugin for ChargeJumpPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<ChargeJumpConfig>()
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_system(charge_jumps)
+                    .with_system(squash_while_charging.after(charge_jumps)),
+            );
+    }
+}
+
+/*
+ * Charge only builds while standing still on the ground. Walking or leaving the ground throws the charge away,
+ * and letting go of the button jumps with whatever has built up.
+ */
+fn charge_jumps(
+    delta: Res<GameplayDelta>,
+    input: Res<InputState>,
+    config: Res<ChargeJumpConfig>,
+    units: Res<PhysicsUnits>,
+    mut players: Query<(&mut ChargeJump, &mut Velocity, &PlayerStateMachine), With<Player>>,
+    mut events: EventWriter<PlayerEvent>,
+) {
+    for (mut charge_jump, mut velocity, state) in players.iter_mut() {
+        if !state.is(PlayerState::Grounded) || input.move_axis.x != 0.0 {
+            charge_jump.charge = 0.0;
+            continue;
+        }
+
+        if input.charge {
+            charge_jump.charge = (charge_jump.charge + config.charge_rate * delta.0).min(1.0);
+        } else if charge_jump.charge > 0.0 {
+            let launch = charge_jump_velocity(charge_jump.charge, config.max_height, GRAVITY);
+            velocity.linvel.y = units.m_to_px(launch);
+            charge_jump.charge = 0.0;
+            events.send(PlayerEvent::Jumped);
+        }
+    }
+}
+
+/// Squashes the player's sprite down as charge builds, keeping its feet on the ground.
+fn squash_while_charging(
+    settings: Res<Settings>,
+    players: Query<(Entity, &ChargeJump), (With<Player>, Changed<ChargeJump>)>,
+    children: Query<&Children>,
+    mut sprites: Query<(&mut Transform, &TextureAtlasSprite), With<PlayerSprite>>,
+) {
+    for (player, charge_jump) in players.iter() {
+        for child in children.iter_descendants(player) {
+            let Ok((mut transform, sprite)) = sprites.get_mut(child) else { continue };
+            let squash = FULL_CHARGE_SQUASH * charge_jump.charge * settings.juice.squash_stretch.max(0.0);
+            let height = sprite.custom_size.map_or(0.0, |size| size.y);
+            transform.scale = Vec3::new(1.0 + squash / 2.0, 1.0 - squash, 1.0);
+            transform.translation.y = -height * squash / 2.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// How high a jump launched at `velocity` m/s peaks.
+    fn peak(velocity: f32) -> f32 {
+        velocity * velocity / (2.0 * GRAVITY)
+    }
+
+    #[test]
+    fn charge_maps_to_a_share_of_the_max_height() {
+        let max_height = 4.0;
+        assert_eq!(charge_jump_velocity(0.0, max_height, GRAVITY), 0.0);
+        for charge in [0.25, 0.5, 1.0] {
+            let height = peak(charge_jump_velocity(charge, max_height, GRAVITY));
+            assert!((height - charge * max_height).abs() < 1e-4, "{charge} peaks at {height} m");
+        }
+        // Overcharging doesn't go past the max.
+        assert_eq!(charge_jump_velocity(3.0, max_height, GRAVITY), charge_jump_velocity(1.0, max_height, GRAVITY));
+        assert_eq!(charge_jump_velocity(-1.0, max_height, GRAVITY), 0.0);
+    }
+}
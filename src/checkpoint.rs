@@ -0,0 +1,247 @@
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use gamelibs::easing::{ease, Ease};
+
+use crate::audio::Sfx;
+use crate::entity_registry::RegisterLevelEntity;
+use crate::physics::GameplayDelta;
+use crate::player::{Player, PlayerSpawn};
+use crate::state::GameState;
+
+/// The LDtk entity identifier that places a checkpoint.
+pub const CHECKPOINT_ENTITY: &str = "Checkpoint";
+/// Played when a checkpoint is activated.
+const CHECKPOINT_SFX: &str = "checkpoint";
+
+/// A checkpoint activates when the player's centre comes within this many pixels.
+const CHECKPOINT_RADIUS: f32 = 16.0;
+const POLE_SIZE: Vec2 = Vec2::new(2.0, 32.0);
+const FLAG_SIZE: Vec2 = Vec2::new(12.0, 8.0);
+const POLE_COLOR: Color = Color::rgb(0.35, 0.3, 0.25);
+const INACTIVE_FLAG_COLOR: Color = Color::rgb(0.55, 0.55, 0.55);
+const ACTIVE_FLAG_COLOR: Color = Color::rgb(0.2, 0.8, 0.3);
+/// Seconds for a flag to run all the way up or down its pole.
+const RAISE_TIME: f32 = 0.6;
+/// How far a lowered flag droops, in radians.
+const LOWERED_ANGLE: f32 = -0.6;
+
+/// A place the player comes back to after dying, once they've touched it. Only the most recently touched
+/// checkpoint is active.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct Checkpoint {
+    pub active: bool,
+}
+
+/// The flag on a checkpoint's pole, `raised` of the way up, which follows whether its checkpoint is active.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct CheckpointFlag {
+    pub raised: f32,
+}
+
+/// Sent when the player activates a checkpoint.
+#[derive(Clone, Copy, Debug)]
+pub struct CheckpointReached {
+    pub checkpoint: Entity,
+    pub position: Vec2,
+}
+
+/// The active checkpoint, and where the player respawned before any checkpoint was reached.
+#[derive(Resource, Clone, Copy, Debug, Default)]
+pub struct Checkpoints {
+    pub active: Option<Entity>,
+    level_spawn: Option<Vec2>,
+}
+
+pub struct CheckpointPlugin;
+
+impl Plugin for CheckpointPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<Checkpoints>()
+            .add_event::<CheckpointReached>()
+            .register_level_entity(CHECKPOINT_ENTITY, spawn_checkpoint)
+            .add_system(activate_checkpoints)
+            .add_system(raise_flags.after(activate_checkpoints))
+            .add_system(reset_checkpoints_on_level_change)
+            .add_system_set(SystemSet::on_exit(GameState::InGame).with_system(reset_checkpoints));
+    }
+}
+
+/// Where a flag `raised` of the way up sits relative to its checkpoint, and its angle. It pops a little past the
+/// top on the way up.
+pub fn flag_pose(raised: f32) -> (Vec2, f32) {
+    let t = ease(Ease::BackOut, raised.clamp(0.0, 1.0) as f64) as f32;
+    let bottom = -POLE_SIZE.y / 2.0 + FLAG_SIZE.y / 2.0;
+    let top = POLE_SIZE.y / 2.0 - FLAG_SIZE.y / 2.0;
+    let position = Vec2::new((POLE_SIZE.x + FLAG_SIZE.x) / 2.0, bottom + (top - bottom) * t);
+    (position, LOWERED_ANGLE * (1.0 - t))
+}
+
+/// Gives an LDtk checkpoint its pole and lowered flag. bevy_ecs_ldtk has already placed it, as a child of its level.
+fn spawn_checkpoint(checkpoint: &mut EntityCommands, _: &EntityInstance, _: Vec2) {
+    let (flag_position, flag_angle) = flag_pose(0.0);
+    checkpoint.insert(Checkpoint::default()).with_children(|checkpoint| {
+        checkpoint.spawn(SpriteBundle {
+            sprite: Sprite {
+                color: POLE_COLOR,
+                custom_size: Some(POLE_SIZE),
+                ..default()
+            },
+            ..default()
+        });
+        checkpoint.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: INACTIVE_FLAG_COLOR,
+                    custom_size: Some(FLAG_SIZE),
+                    ..default()
+                },
+                transform: Transform::from_translation(flag_position.extend(0.1))
+                    .with_rotation(Quat::from_rotation_z(flag_angle)),
+                ..default()
+            },
+            CheckpointFlag::default(),
+        ));
+    });
+}
+
+/// Touching an inactive checkpoint makes it the respawn point and deactivates the previous one. Touching the
+/// active one again does nothing.
+fn activate_checkpoints(
+    players: Query<&GlobalTransform, With<Player>>,
+    mut checkpoints: Query<(Entity, &GlobalTransform, &mut Checkpoint)>,
+    mut active: ResMut<Checkpoints>,
+    mut spawn: ResMut<PlayerSpawn>,
+    mut reached: EventWriter<CheckpointReached>,
+    mut sfx: Sfx,
+) {
+    let Ok(player) = players.get_single() else { return };
+    let player = player.translation().truncate();
+
+    let touched = checkpoints.iter().find(|(_, transform, checkpoint)| {
+        !checkpoint.active && transform.translation().truncate().distance(player) <= CHECKPOINT_RADIUS
+    });
+    let Some((touched, transform, _)) = touched else { return };
+    let position = transform.translation().truncate();
+
+    for (entity, _, mut checkpoint) in checkpoints.iter_mut() {
+        let activate = entity == touched;
+        if checkpoint.active != activate {
+            checkpoint.active = activate;
+        }
+    }
+    active.active = Some(touched);
+    active.level_spawn.get_or_insert(spawn.0);
+    spawn.0 = position;
+    info!("Reached a checkpoint at {position}");
+    reached.send(CheckpointReached { checkpoint: touched, position });
+    sfx.play_sfx_at(CHECKPOINT_SFX, position);
+}
+
+/// Runs on gameplay time, so a flag caught mid-raise by the pause menu waits for play to resume.
+fn raise_flags(
+    delta: Res<GameplayDelta>,
+    checkpoints: Query<(&Checkpoint, &Children)>,
+    mut flags: Query<(&mut CheckpointFlag, &mut Transform, &mut Sprite)>,
+) {
+    let step = delta.0 / RAISE_TIME;
+
+    for (checkpoint, children) in checkpoints.iter() {
+        let mut flags = flags.iter_many_mut(children);
+        while let Some((mut flag, mut transform, mut sprite)) = flags.fetch_next() {
+            let color = if checkpoint.active { ACTIVE_FLAG_COLOR } else { INACTIVE_FLAG_COLOR };
+            if sprite.color != color {
+                sprite.color = color;
+            }
+
+            let target = if checkpoint.active { 1.0 } else { 0.0 };
+            if flag.raised == target {
+                continue;
+            }
+            flag.raised += (target - flag.raised).clamp(-step, step);
+            let (position, angle) = flag_pose(flag.raised);
+            transform.translation = position.extend(transform.translation.z);
+            transform.rotation = Quat::from_rotation_z(angle);
+        }
+    }
+}
+
+/// Checkpoints belong to their level, so a new level starts back at the player's original spawn.
+fn reset_checkpoints_on_level_change(
+    selection: Option<Res<LevelSelection>>,
+    checkpoints: ResMut<Checkpoints>,
+    spawn: ResMut<PlayerSpawn>,
+) {
+    if selection.is_some_and(|selection| selection.is_changed()) {
+        reset_checkpoints(checkpoints, spawn);
+    }
+}
+
+fn reset_checkpoints(mut checkpoints: ResMut<Checkpoints>, mut spawn: ResMut<PlayerSpawn>) {
+    if let Some(level_spawn) = checkpoints.level_spawn.take() {
+        spawn.0 = level_spawn;
+    }
+    checkpoints.active = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::CommandQueue;
+
+    use super::*;
+    use crate::audio::init_silent_audio;
+
+    fn app() -> App {
+        let mut app = App::new();
+        init_silent_audio(&mut app)
+            .init_resource::<PlayerSpawn>()
+            .insert_resource(GameplayDelta(0.1))
+            .init_resource::<Checkpoints>()
+            .add_event::<CheckpointReached>()
+            .add_system(activate_checkpoints)
+            .add_system(raise_flags.after(activate_checkpoints));
+        app
+    }
+
+    fn spawn(app: &mut App, position: Vec2) -> Entity {
+        let entity = app.world.spawn(GlobalTransform::from_translation(position.extend(0.0))).id();
+        let mut queue = CommandQueue::default();
+        spawn_checkpoint(&mut Commands::new(&mut queue, &app.world).entity(entity), &default(), position);
+        queue.apply(&mut app.world);
+        entity
+    }
+
+    /// How far up `checkpoint`'s flag is, and its color.
+    fn flag(app: &App, checkpoint: Entity) -> (f32, Color) {
+        let children = app.world.get::<Children>(checkpoint).unwrap();
+        let flag = children.iter().find(|&&child| app.world.get::<CheckpointFlag>(child).is_some()).unwrap();
+        (app.world.get::<CheckpointFlag>(*flag).unwrap().raised, app.world.get::<Sprite>(*flag).unwrap().color)
+    }
+
+    fn move_player(app: &mut App, player: Entity, position: Vec2) {
+        let transform = GlobalTransform::from_translation(position.extend(0.0));
+        *app.world.get_mut::<GlobalTransform>(player).unwrap() = transform;
+        for _ in 0..10 {
+            app.update();
+        }
+    }
+
+    #[test]
+    fn only_the_latest_checkpoint_is_raised() {
+        let mut app = app();
+        let [first, second] = [Vec2::ZERO, Vec2::new(200.0, 0.0)].map(|position| spawn(&mut app, position));
+        let player = app.world.spawn((Player, GlobalTransform::from_translation(Vec3::new(-100.0, 0.0, 0.0)))).id();
+
+        move_player(&mut app, player, Vec2::new(4.0, 0.0));
+        assert_eq!(app.world.resource::<Checkpoints>().active, Some(first));
+        assert_eq!(flag(&app, first), (1.0, ACTIVE_FLAG_COLOR));
+        assert_eq!(flag(&app, second), (0.0, INACTIVE_FLAG_COLOR));
+
+        move_player(&mut app, player, Vec2::new(196.0, 0.0));
+        assert_eq!(app.world.resource::<Checkpoints>().active, Some(second));
+        assert_eq!(app.world.resource::<PlayerSpawn>().0, Vec2::new(200.0, 0.0));
+        assert_eq!(flag(&app, first), (0.0, INACTIVE_FLAG_COLOR));
+        assert_eq!(flag(&app, second), (1.0, ACTIVE_FLAG_COLOR));
+    }
+}
@@ -0,0 +1,152 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use gamelibs::prelude::*;
+
+use crate::camera::GameCamera;
+
+/// LDtk entity identifier for a rectangle the camera is confined to while the player is inside it.
+const CAMERA_ROOM_ENTITY: &str = "CameraRoom";
+/// How long the camera takes to move over to a new room, in seconds.
+const ROOM_TRANSITION_TIME: f32 = 0.6;
+
+/// Confines the following camera to one of `rooms` at a time: the one the player is in. The view never shows past the
+/// room's edges, unless the room is smaller than the view, in which case it is centred.
+#[derive(Component, Clone, Debug, Default)]
+pub struct CameraConfiner {
+    pub rooms: Vec<Rect>,
+    room: Option<usize>,
+    transition: Option<RoomTransition>,
+}
+
+/// Easing from where the camera was aiming when the player changed rooms to where the new room allows.
+#[derive(Clone, Copy, Debug)]
+struct RoomTransition {
+    from: Vec2,
+    elapsed: f32,
+}
+
+impl CameraConfiner {
+    pub fn new(rooms: Vec<Rect>) -> Self {
+        CameraConfiner {
+            rooms,
+            room: None,
+            transition: None,
+        }
+    }
+
+    /// Where the camera should aim to follow `target` this frame, given it is at `camera` and sees `half_view`
+    /// pixels either side of its centre. Advances any room transition by `dt`.
+    pub fn confine(&mut self, target: Vec2, camera: Vec2, half_view: Vec2, dt: f32) -> Vec2 {
+        let Some(room) = room_for(target, &self.rooms, self.room) else { return target };
+        if self.room.is_some_and(|current| current != room) {
+            self.transition = Some(RoomTransition { from: camera, elapsed: 0.0 });
+        }
+        self.room = Some(room);
+
+        let confined = clamp_to_room(target, self.rooms[room], half_view);
+        let Some(transition) = &mut self.transition else { return confined };
+        transition.elapsed += dt;
+        let t = ease(Ease::SineInOut, (transition.elapsed / ROOM_TRANSITION_TIME) as f64) as f32;
+        let eased = transition.from.lerp(confined, t);
+        if transition.elapsed >= ROOM_TRANSITION_TIME {
+            self.transition = None;
+        }
+        eased
+    }
+}
+
+/// The room `point` is in: `current` while it still contains the point, so overlapping rooms don't flicker, then the
+/// first that contains it, then the nearest. `None` only when there are no rooms.
+pub fn room_for(point: Vec2, rooms: &[Rect], current: Option<usize>) -> Option<usize> {
+    if let Some(current) = current.filter(|&current| rooms.get(current).is_some_and(|room| room.contains(point))) {
+        return Some(current);
+    }
+    if let Some(containing) = rooms.iter().position(|room| room.contains(point)) {
+        return Some(containing);
+    }
+    rooms
+        .iter()
+        .map(|room| (point.clamp(room.min, room.max) - point).length_squared())
+        .enumerate()
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(index, _)| index)
+}
+
+/// Moves a camera centre at `point` as little as possible to keep a view `half_view` either side of it inside
+/// `room`. Along an axis where the room is narrower than the view, the view is centred on the room instead.
+pub fn clamp_to_room(point: Vec2, room: Rect, half_view: Vec2) -> Vec2 {
+    let min = room.min + half_view;
+    let max = room.max - half_view;
+    let axis = |point: f32, min: f32, max: f32, center: f32| if min > max { center } else { point.clamp(min, max) };
+    let center = room.center();
+    Vec2::new(axis(point.x, min.x, max.x, center.x), axis(point.y, min.y, max.y, center.y))
+}
+
+/// `clamp_to_room` within the room nearest `point`, or `point` itself if there are no rooms.
+pub fn confine_to_nearest_room(point: Vec2, rooms: &[Rect], half_view: Vec2) -> Vec2 {
+    match room_for(point, rooms, None) {
+        Some(room) => clamp_to_room(point, rooms[room], half_view),
+        None => point,
+    }
+}
+
+pub struct ConfinerPlugin;
+
+impl Plugin for ConfinerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(confiner_from_level);
+    }
+}
+
+/*
+ * bevy_ecs_ldtk places an entity's transform at its centre, relative to its level, so the rooms are read once the
+ * level is transformed. A level without any rooms leaves the camera free.
+ */
+fn confiner_from_level(
+    mut commands: Commands,
+    mut level_events: EventReader<LevelEvent>,
+    instances: Query<(&EntityInstance, &GlobalTransform)>,
+    cameras: Query<Entity, With<GameCamera>>,
+) {
+    for event in level_events.iter() {
+        let LevelEvent::Transformed(_) = event else { continue };
+
+        let rooms: Vec<Rect> = instances
+            .iter()
+            .filter(|(instance, _)| instance.identifier == CAMERA_ROOM_ENTITY)
+            .map(|(instance, transform)| {
+                let size = Vec2::new(instance.width as f32, instance.height as f32);
+                Rect::from_center_size(transform.translation().truncate(), size)
+            })
+            .collect();
+
+        for camera in cameras.iter() {
+            if rooms.is_empty() {
+                commands.entity(camera).remove::<CameraConfiner>();
+            } else {
+                commands.entity(camera).insert(CameraConfiner::new(rooms.clone()));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn points_clamp_into_the_nearest_room() {
+        let rooms = [Rect::new(0.0, 0.0, 400.0, 200.0), Rect::new(600.0, 0.0, 700.0, 300.0)];
+        let half_view = Vec2::new(80.0, 50.0);
+
+        // Inside a room, kept far enough from its edges to see only that room.
+        assert_eq!(confine_to_nearest_room(Vec2::new(200.0, 100.0), &rooms, half_view), Vec2::new(200.0, 100.0));
+        assert_eq!(confine_to_nearest_room(Vec2::new(10.0, 190.0), &rooms, half_view), Vec2::new(80.0, 150.0));
+        // Between rooms, pulled into the nearer one.
+        assert_eq!(confine_to_nearest_room(Vec2::new(450.0, 100.0), &rooms, half_view), Vec2::new(320.0, 100.0));
+        // A room narrower than the view centres it along that axis.
+        assert_eq!(confine_to_nearest_room(Vec2::new(690.0, 20.0), &rooms, half_view), Vec2::new(650.0, 50.0));
+        // No rooms leave the point alone.
+        assert_eq!(confine_to_nearest_room(Vec2::new(-5.0, 5.0), &[], half_view), Vec2::new(-5.0, 5.0));
+    }
+}
@@ -0,0 +1,138 @@
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_rapier2d::plugin::systems;
+use bevy_rapier2d::prelude::*;
+
+use crate::entity_registry::RegisterLevelEntity;
+use crate::fields::LdtkFields;
+use crate::physics::PhysicsUnits;
+use crate::surface::SurfaceMaterial;
+
+/// The LDtk entity identifier of a conveyor belt, a solid block the size of the entity. Its `Speed` field is the
+/// belt's speed in m/s, positive to the right.
+pub const CONVEYOR_ENTITY: &str = "Conveyor";
+const SPEED_FIELD: &str = "Speed";
+
+/// A contact normal pointing at least this far upwards means the body is resting on top of the belt rather than
+/// pressed against its side or bottom.
+const SUPPORT_NORMAL_Y: f32 = 0.7;
+
+/// A solid surface that carries whatever stands on top of it along at `velocity` m/s.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct Conveyor {
+    pub velocity: f32,
+}
+
+/// The velocity, in pixels per second, a conveyor is lending a dynamic body for the current physics step.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq)]
+pub struct Conveyed(pub Vec2);
+
+pub struct ConveyorPlugin;
+
+impl Plugin for ConveyorPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .register_level_entity(CONVEYOR_ENTITY, spawn_conveyor)
+            .add_system(track_conveyed_bodies)
+            // Like platform riders: after gameplay has set velocities, but before Rapier reads them.
+            .add_system_to_stage(
+                PhysicsStages::SyncBackend,
+                convey_bodies.before(systems::apply_rigid_body_user_changes),
+            )
+            .add_system_to_stage(CoreStage::PostUpdate, release_conveyed);
+    }
+}
+
+/// The surface velocity a belt moving at `speed` gives a body touching it, where `normal` is the contact normal
+/// pointing from the belt to the body. Only bodies on top are carried; touching a side or the bottom does nothing.
+pub fn surface_velocity(speed: f32, normal: Vec2) -> f32 {
+    if normal.y >= SUPPORT_NORMAL_Y { speed } else { 0.0 }
+}
+
+fn spawn_conveyor(conveyor: &mut EntityCommands, instance: &EntityInstance, _: Vec2) {
+    let half_extents = Vec2::new(instance.width as f32, instance.height as f32) / 2.0;
+    let material = SurfaceMaterial::Normal;
+    conveyor.insert((
+        Conveyor { velocity: instance.fields().get_float(SPEED_FIELD).unwrap_or(0.0) },
+        Collider::cuboid(half_extents.x, half_extents.y),
+        material.components(),
+        material,
+    ));
+}
+
+fn track_conveyed_bodies(
+    mut commands: Commands,
+    bodies: Query<(Entity, &RigidBody), (With<Velocity>, Without<Conveyed>)>,
+) {
+    for (entity, body) in bodies.iter() {
+        if *body == RigidBody::Dynamic {
+            commands.entity(entity).insert(Conveyed::default());
+        }
+    }
+}
+
+/*
+ * The belt's velocity is lent for the physics step and taken back in `release_conveyed`, so the player's controller
+ * never sees it: standing still drifts along with the belt, and walking against it at full speed holds position.
+ * A body over two belts goes with whichever is found last.
+ */
+fn convey_bodies(
+    units: Res<PhysicsUnits>,
+    rapier_context: Res<RapierContext>,
+    conveyors: Query<(Entity, &Conveyor)>,
+    mut bodies: Query<(&mut Velocity, &mut Conveyed)>,
+) {
+    for (_, mut conveyed) in bodies.iter_mut() {
+        conveyed.0 = Vec2::ZERO;
+    }
+
+    for (conveyor_entity, conveyor) in conveyors.iter() {
+        for pair in rapier_context.contacts_with(conveyor_entity) {
+            if !pair.has_any_active_contacts() {
+                continue;
+            }
+            let Some(manifold) = pair.manifolds().find(|manifold| manifold.num_points() > 0) else { continue };
+            let (other, normal) = if pair.collider1() == conveyor_entity {
+                (pair.collider2(), manifold.normal())
+            } else {
+                (pair.collider1(), -manifold.normal())
+            };
+
+            let speed = surface_velocity(conveyor.velocity, normal);
+            let body = rapier_context.collider_parent(other).unwrap_or(other);
+            let Ok((_, mut conveyed)) = bodies.get_mut(body) else { continue };
+            if speed != 0.0 {
+                conveyed.0 = Vec2::new(units.m_to_px(speed), 0.0);
+            }
+        }
+    }
+
+    for (mut velocity, conveyed) in bodies.iter_mut() {
+        if conveyed.0 != Vec2::ZERO {
+            velocity.linvel += conveyed.0;
+        }
+    }
+}
+
+fn release_conveyed(mut bodies: Query<(&Conveyed, &mut Velocity)>) {
+    for (conveyed, mut velocity) in bodies.iter_mut() {
+        if conveyed.0 != Vec2::ZERO {
+            velocity.linvel -= conveyed.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn carries_only_bodies_on_top() {
+        assert_eq!(surface_velocity(2.5, Vec2::Y), 2.5);
+        assert_eq!(surface_velocity(-2.5, Vec2::new(0.6, 0.8)), -2.5, "a body on a sloped contact still rides");
+        assert_eq!(surface_velocity(2.5, Vec2::X), 0.0);
+        assert_eq!(surface_velocity(2.5, Vec2::NEG_Y), 0.0);
+        assert_eq!(surface_velocity(2.5, Vec2::new(0.8, 0.6)), 0.0);
+    }
+}
@@ -0,0 +1,179 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use gamelibs::prelude::*;
+
+use crate::fields::LdtkFields;
+use crate::input::{read_input, InputState};
+use crate::level::find_level;
+use crate::menu::UiAssets;
+use crate::state::{GameState, GameplayEntity};
+
+/// Level field that starts the level with a countdown when set.
+const COUNTDOWN_FIELD: &str = "Countdown";
+
+const COUNTDOWN_FONT_SIZE: f32 = 160.0;
+const COUNTDOWN_COLOR: Color = Color::rgb(1.0, 0.85, 0.3);
+/// The share of each number's time spent popping in to full size.
+const POP_FRACTION: f32 = 0.35;
+
+/// How a start countdown counts.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct CountdownConfig {
+    /// The first number shown, counting down to 1 before "GO!".
+    pub from: u32,
+    /// Seconds each number, and then "GO!", stays up.
+    pub step: f32,
+}
+
+impl Default for CountdownConfig {
+    fn default() -> Self {
+        CountdownConfig { from: 3, step: 0.8 }
+    }
+}
+
+/// The countdown's on-screen text, which outlives the `Countdown` state to show "GO!" as play starts.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct Countdown {
+    pub elapsed: f32,
+}
+
+pub struct CountdownPlugin;
+
+impl Plugin for CountdownPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<CountdownConfig>()
+            .add_system_set(SystemSet::on_enter(GameState::Countdown).with_system(spawn_countdown))
+            .add_system_to_stage(CoreStage::PreUpdate, lock_input.after(read_input))
+            .add_system(countdown_from_level)
+            .add_system(run_countdown);
+    }
+}
+
+/// The text shown `elapsed` seconds into the countdown, or `None` once it's over.
+pub fn countdown_label(elapsed: f32, config: &CountdownConfig) -> Option<String> {
+    let index = (elapsed.max(0.0) / config.step) as u32;
+    match index.cmp(&config.from) {
+        std::cmp::Ordering::Less => Some((config.from - index).to_string()),
+        std::cmp::Ordering::Equal => Some("GO!".to_string()),
+        std::cmp::Ordering::Greater => None,
+    }
+}
+
+/// Whether the player may move `elapsed` seconds into the countdown: from the moment "GO!" appears.
+pub fn countdown_unlocked(elapsed: f32, config: &CountdownConfig) -> bool {
+    elapsed >= config.from as f32 * config.step
+}
+
+/// The text's scale `elapsed` seconds into the countdown. Each label pops in from nothing, overshooting slightly.
+pub fn countdown_scale(elapsed: f32, config: &CountdownConfig) -> f32 {
+    let into_step = elapsed.max(0.0) % config.step;
+    ease(Ease::BackOut, (into_step / (config.step * POP_FRACTION)) as f64) as f32
+}
+
+fn countdown_from_level(
+    mut level_events: EventReader<LevelEvent>,
+    levels: Query<&Handle<LdtkLevel>>,
+    level_assets: Res<Assets<LdtkLevel>>,
+    mut state: ResMut<State<GameState>>,
+) {
+    for event in level_events.iter() {
+        let LevelEvent::Spawned(iid) = event else { continue };
+        let Some(level) = find_level(iid, &levels, &level_assets) else { continue };
+        if level.fields().get_bool(COUNTDOWN_FIELD) == Some(true) && *state.current() == GameState::InGame {
+            let _ = state.push(GameState::Countdown);
+        }
+    }
+}
+
+fn spawn_countdown(mut commands: Commands, ui: Res<UiAssets>) {
+    let style = TextStyle {
+        font: ui.font.clone(),
+        font_size: COUNTDOWN_FONT_SIZE,
+        color: COUNTDOWN_COLOR,
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    position_type: PositionType::Absolute,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            GameplayEntity,
+        ))
+        .with_children(|root| {
+            root.spawn((
+                TextBundle {
+                    transform: Transform::from_scale(Vec3::ZERO),
+                    ..TextBundle::from_section("", style)
+                },
+                Countdown::default(),
+            ));
+        });
+}
+
+/// Nothing the player presses gets through until "GO!".
+fn lock_input(state: Res<State<GameState>>, mut input: ResMut<InputState>) {
+    if *state.current() == GameState::Countdown {
+        *input = InputState::default();
+    }
+}
+
+/*
+ * The count runs on real time rather than gameplay time, and ends the `Countdown` state (resuming physics and the
+ * level timer) the moment "GO!" appears, which then stays up for one more step while play gets going.
+ */
+fn run_countdown(
+    mut commands: Commands,
+    time: Res<Time>,
+    config: Res<CountdownConfig>,
+    mut state: ResMut<State<GameState>>,
+    parents: Query<&Parent>,
+    mut countdowns: Query<(Entity, &mut Countdown, &mut Text, &mut Transform)>,
+) {
+    for (entity, mut countdown, mut text, mut transform) in countdowns.iter_mut() {
+        countdown.elapsed += time.delta_seconds();
+
+        let Some(label) = countdown_label(countdown.elapsed, &config) else {
+            let root = parents.get(entity).map_or(entity, |parent| parent.get());
+            commands.entity(root).despawn_recursive();
+            continue;
+        };
+        if text.sections[0].value != label {
+            text.sections[0].value = label;
+        }
+        transform.scale = Vec3::splat(countdown_scale(countdown.elapsed, &config));
+
+        if countdown_unlocked(countdown.elapsed, &config) && *state.current() == GameState::Countdown {
+            let _ = state.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_down_to_go() {
+        let config = CountdownConfig { from: 3, step: 0.5 };
+        let times = [0.0, 0.49, 0.5, 1.2, 1.5, 1.99, 2.0];
+        let labels: Vec<_> = times.iter().map(|&t| countdown_label(t, &config)).collect();
+        let expected = [Some("3"), Some("3"), Some("2"), Some("1"), Some("GO!"), Some("GO!"), None];
+        assert_eq!(labels, expected.map(|label| label.map(str::to_string)));
+    }
+
+    #[test]
+    fn input_unlocks_when_go_appears() {
+        let config = CountdownConfig { from: 3, step: 0.5 };
+        assert!(!countdown_unlocked(0.0, &config));
+        assert!(!countdown_unlocked(1.49, &config));
+        assert!(countdown_unlocked(1.5, &config));
+        assert!(countdown_unlocked(3.0, &config));
+    }
+}
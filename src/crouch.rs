@@ -0,0 +1,192 @@
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use bevy_rapier2d::prelude::*;
+
+use crate::input::InputState;
+use crate::physics::{GameplayDelta, PhysicsUnits};
+use crate::player::{move_player, update_player_state, MoveConfig, Player, PlayerSprite, PlayerState, PlayerStateMachine};
+use crate::state::GameState;
+
+/// How far down the stick or keys have to be held to crouch.
+const CROUCH_INPUT_THRESHOLD: f32 = 0.5;
+
+/// Tuning for crouching and sliding.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct CrouchConfig {
+    /// The player's height while crouched or sliding, in meters.
+    pub height: f32,
+    /// Scales the top running speed while crouched.
+    pub speed_scale: f32,
+    /// Crouching while moving at least this fast (in m/s) starts a slide instead.
+    pub slide_speed: f32,
+    /// How quickly a slide bleeds off speed, in m/s². Less than running deceleration, so the slide carries.
+    pub slide_deceleration: f32,
+}
+
+impl Default for CrouchConfig {
+    fn default() -> Self {
+        CrouchConfig {
+            height: 0.3,
+            speed_scale: 0.4,
+            slide_speed: 3.0,
+            slide_deceleration: 6.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CrouchState {
+    #[default]
+    Standing,
+    Crouching,
+    /// Crouched and coasting on the speed they crouched with, without steering.
+    Sliding,
+}
+
+/// Whether the player is crouched, and their standing collider's half extents in pixels while they are.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct Crouch {
+    pub state: CrouchState,
+    standing_half_extents: Vec2,
+}
+
+/// Returns whether a crouched player has room to stand back up, given how far (in pixels) a probe straight up from
+/// their centre hit a ceiling and how far above their centre the top of their standing collider reaches.
+pub fn can_stand_up(ceiling_hit: Option<f32>, standing_half_height: f32) -> bool {
+    match ceiling_hit {
+        Some(distance) => distance >= standing_half_height,
+        None => true,
+    }
+}
+
+/// Steps a slide's horizontal velocity (in m/s) towards a stop at `deceleration` m/s².
+pub fn slide_velocity(current: f32, deceleration: f32, dt: f32) -> f32 {
+    let max_change = deceleration * dt;
+    current - current.clamp(-max_change, max_change)
+}
+
+pub struct CrouchPlugin;
+
+impl Plugin for CrouchPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<CrouchConfig>()
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_system(crouch.after(update_player_state).before(move_player))
+                    .with_system(slide.after(crouch)),
+            );
+    }
+}
+
+/*
+ * Crouching swaps in a shorter collider, lowered so the player's feet stay on the ground. The body itself doesn't
+ * move: Rapier places a swapped collider where its body was before any teleport made in the same frame. Standing
+ * back up only happens once a probe straight up finds room for the full height; until then the player stays
+ * crouched even after letting go.
+ */
+fn crouch(
+    input: Res<InputState>,
+    config: Res<CrouchConfig>,
+    units: Res<PhysicsUnits>,
+    rapier_context: Res<RapierContext>,
+    mut players: Query<
+        (Entity, &mut Crouch, &mut Collider, &Transform, &Velocity, &PlayerStateMachine),
+        With<Player>,
+    >,
+    children: Query<&Children>,
+    mut sprites: Query<&mut TextureAtlasSprite, With<PlayerSprite>>,
+) {
+    let crouched_half_height = units.m_to_px(config.height) / 2.0;
+
+    for (entity, mut crouch, mut collider, transform, velocity, state) in players.iter_mut() {
+        let wants_crouch = state.is(PlayerState::Grounded) && input.move_axis.y <= -CROUCH_INPUT_THRESHOLD;
+
+        match (crouch.state, wants_crouch) {
+            (CrouchState::Standing, true) => {
+                let standing = collider.raw.compute_local_aabb().half_extents();
+                crouch.standing_half_extents = Vec2::new(standing.x, standing.y);
+                let sliding = units.px_to_m(velocity.linvel.x.abs()) >= config.slide_speed;
+                crouch.state = if sliding { CrouchState::Sliding } else { CrouchState::Crouching };
+            }
+            (CrouchState::Crouching | CrouchState::Sliding, false) => {
+                let standing_half_height = crouch.standing_half_extents.y;
+                let ceiling = rapier_context.cast_ray(
+                    transform.translation.truncate(),
+                    Vec2::Y,
+                    standing_half_height,
+                    true,
+                    QueryFilter::default().exclude_rigid_body(entity).exclude_sensors(),
+                );
+                if !can_stand_up(ceiling.map(|(_, distance)| distance), standing_half_height) {
+                    continue;
+                }
+                crouch.state = CrouchState::Standing;
+            }
+            _ => continue,
+        }
+
+        let standing = crouch.standing_half_extents;
+        let (size, anchor) = if crouch.state == CrouchState::Standing {
+            *collider = Collider::cuboid(standing.x, standing.y);
+            (standing * 2.0, Anchor::Center)
+        } else {
+            let drop = standing.y - crouched_half_height;
+            *collider = Collider::compound(vec![(
+                Vec2::new(0.0, -drop),
+                0.0,
+                Collider::cuboid(standing.x, crouched_half_height),
+            )]);
+            let height = crouched_half_height * 2.0;
+            (Vec2::new(standing.x * 2.0, height), Anchor::Custom(Vec2::new(0.0, drop / height)))
+        };
+        for child in children.iter_descendants(entity) {
+            if let Ok(mut sprite) = sprites.get_mut(child) {
+                sprite.custom_size = Some(size);
+                sprite.anchor = anchor.clone();
+            }
+        }
+    }
+}
+
+/// A slide coasts to a stop and settles into a crouch once it's down to crouch-walking speed.
+fn slide(
+    delta: Res<GameplayDelta>,
+    config: Res<CrouchConfig>,
+    move_config: Res<MoveConfig>,
+    units: Res<PhysicsUnits>,
+    mut players: Query<(&mut Crouch, &mut Velocity), With<Player>>,
+) {
+    for (mut crouch, mut velocity) in players.iter_mut() {
+        if crouch.state != CrouchState::Sliding {
+            continue;
+        }
+
+        let next = slide_velocity(units.px_to_m(velocity.linvel.x), config.slide_deceleration, delta.0);
+        velocity.linvel.x = units.m_to_px(next);
+        if next.abs() <= move_config.max_speed * config.speed_scale {
+            crouch.state = CrouchState::Crouching;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn standing_up_needs_headroom() {
+        let standing_half_height = 24.0;
+        assert!(can_stand_up(None, standing_half_height));
+        assert!(can_stand_up(Some(40.0), standing_half_height));
+        assert!(can_stand_up(Some(24.0), standing_half_height));
+        assert!(!can_stand_up(Some(20.0), standing_half_height));
+    }
+
+    #[test]
+    fn slides_come_to_a_stop() {
+        assert_eq!(slide_velocity(5.0, 10.0, 0.1), 4.0);
+        assert_eq!(slide_velocity(-5.0, 10.0, 0.1), -4.0);
+        assert_eq!(slide_velocity(0.5, 10.0, 0.1), 0.0);
+    }
+}
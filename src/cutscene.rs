@@ -0,0 +1,375 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use gamelibs::math::{CurveFollower, CurveStyle};
+use serde::Deserialize;
+
+use crate::audio::Sfx;
+use crate::camera::GameCamera;
+use crate::input::{ControlLock, MenuInput};
+use crate::menu::UiAssets;
+use crate::physics::GameplayDelta;
+use crate::player::Player;
+use crate::state::{GameState, GameplayEntity};
+
+/// Seconds the controls stay locked after a cutscene ends, so a button held through it doesn't fire straight away.
+const CONTROL_RELEASE_DELAY: f32 = 0.1;
+/// How close (in pixels) a moving entity has to get to its destination for the move to finish.
+const ARRIVE_DISTANCE: f32 = 0.5;
+/// Critically damped and quick to settle, so a move doesn't lag far behind its duration.
+const MOVE_CURVE: CurveStyle = CurveStyle::Mechanical { f: 4.0, z: 1.0 };
+const TEXT_FONT_SIZE: f32 = 28.0;
+const TEXT_COLOR: Color = Color::WHITE;
+/// Distance of the cutscene text from the bottom of the screen, in logical pixels.
+const TEXT_MARGIN: f32 = 48.0;
+
+/// What a cutscene action applies to.
+///
+/// * `CutsceneTarget::Named(name)` is the entity with that `Name`.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub enum CutsceneTarget {
+    Player,
+    Camera,
+    Named(String),
+}
+
+/// One step of a cutscene. The next starts once it has finished.
+///
+/// * `CutsceneAction::MoveTo` moves `target` to `to` (in world pixels) over `duration` seconds, smoothed through the
+///   curve integrator. It finishes once the time is up and the target has arrived.
+///
+/// * `CutsceneAction::Wait` does nothing for `duration` seconds.
+///
+/// * `CutsceneAction::PlaySound` plays a sound effect from the `SfxLibrary` and finishes straight away.
+///
+/// * `CutsceneAction::ShowText` shows `text` along the bottom of the screen for `duration` seconds.
+///
+/// * `CutsceneAction::Fade` fades the screen to black at opacity `to` over `duration` seconds. The screen stays
+///   faded after the cutscene until another fade brings it back to 0.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub enum CutsceneAction {
+    MoveTo { target: CutsceneTarget, to: Vec2, duration: f32 },
+    Wait { duration: f32 },
+    PlaySound { name: String },
+    ShowText { text: String, duration: f32 },
+    Fade { to: f32, duration: f32 },
+}
+
+impl CutsceneAction {
+    pub fn duration(&self) -> f32 {
+        match self {
+            CutsceneAction::PlaySound { .. } => 0.0,
+            CutsceneAction::MoveTo { duration, .. }
+            | CutsceneAction::Wait { duration }
+            | CutsceneAction::ShowText { duration, .. }
+            | CutsceneAction::Fade { duration, .. } => duration.max(0.0),
+        }
+    }
+}
+
+/// A cutscene's actions, played in order. Deserializes from a plain list, so cutscenes can be written as data.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(transparent)]
+pub struct Timeline(pub Vec<CutsceneAction>);
+
+/// The cutscene playing. Insert one to start it; it removes itself once it's over. The player can't move while it
+/// plays, and can skip it with the menu's confirm button.
+#[derive(Resource, Clone, Debug)]
+pub struct Cutscene {
+    pub timeline: Timeline,
+    /// The index of the action playing.
+    current: usize,
+    /// Seconds since the current action started, or `None` if it hasn't yet.
+    elapsed: Option<f32>,
+    /// Seconds since the cutscene started.
+    time: f32,
+    /// Where a move started, and the followers smoothing each axis.
+    movement: (Vec2, [CurveFollower; 2]),
+    /// The screen's opacity when a fade started.
+    fade_from: f32,
+}
+
+impl Cutscene {
+    pub fn new(timeline: Timeline) -> Self {
+        Cutscene {
+            timeline,
+            current: 0,
+            elapsed: None,
+            time: 0.0,
+            movement: (Vec2::ZERO, [CurveFollower::new(MOVE_CURVE, 0.0); 2]),
+            fade_from: 0.0,
+        }
+    }
+}
+
+/// Sent as each action of a cutscene finishes, with its index in the timeline, including those skipped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CutsceneActionFinished(pub usize);
+
+/// Sent when a cutscene ends, whether it played out or was skipped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CutsceneFinished {
+    pub skipped: bool,
+}
+
+/// The text shown by `CutsceneAction::ShowText`.
+#[derive(Component)]
+struct CutsceneText;
+
+/// The full-screen overlay faded by `CutsceneAction::Fade`.
+#[derive(Component)]
+struct CutsceneFade;
+
+pub struct CutscenePlugin;
+
+impl Plugin for CutscenePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<CutsceneActionFinished>()
+            .add_event::<CutsceneFinished>()
+            .add_system_set(SystemSet::on_update(GameState::InGame).with_system(play_cutscene));
+    }
+}
+
+fn is_target(target: &CutsceneTarget, name: Option<&Name>, player: bool, camera: bool) -> bool {
+    match target {
+        CutsceneTarget::Player => player,
+        CutsceneTarget::Camera => camera,
+        CutsceneTarget::Named(wanted) => name.is_some_and(|name| name.as_str() == wanted),
+    }
+}
+
+fn spawn_text(commands: &mut Commands, ui: &UiAssets, text: &str) {
+    let style = TextStyle {
+        font: ui.font.clone(),
+        font_size: TEXT_FONT_SIZE,
+        color: TEXT_COLOR,
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Auto),
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        bottom: Val::Px(TEXT_MARGIN),
+                        ..default()
+                    },
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            CutsceneText,
+            GameplayEntity,
+        ))
+        .with_children(|root| {
+            root.spawn(TextBundle::from_section(text, style));
+        });
+}
+
+fn spawn_fade(commands: &mut Commands, alpha: f32) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            background_color: Color::rgba(0.0, 0.0, 0.0, alpha).into(),
+            z_index: ZIndex::Global(i32::MAX),
+            ..default()
+        },
+        CutsceneFade,
+        GameplayEntity,
+    ));
+}
+
+/*
+ * Plays as much of the timeline as fits in the frame's gameplay time, so actions without a duration (a sound, or a
+ * zero-length wait) run back to back in one frame, and the cutscene holds still while the game is paused. Skipping
+ * runs every remaining action to its end at once, without playing its sounds or showing its text. A skip press on
+ * the frame the cutscene starts is ignored, as it's likely the press that started it, and so is one while gameplay
+ * time is stopped, as that's the pause menu's.
+ */
+#[allow(clippy::too_many_arguments)]
+fn play_cutscene(
+    mut commands: Commands,
+    delta: Res<GameplayDelta>,
+    menu_input: Res<MenuInput>,
+    cutscene: Option<ResMut<Cutscene>>,
+    ui: Option<Res<UiAssets>>,
+    mut lock: ResMut<ControlLock>,
+    sfx: Sfx,
+    mut targets: Query<(&mut Transform, Option<&mut Velocity>, Option<&Name>, Option<&Player>, Option<&GameCamera>)>,
+    texts: Query<Entity, With<CutsceneText>>,
+    mut fades: Query<&mut BackgroundColor, With<CutsceneFade>>,
+    mut action_finished: EventWriter<CutsceneActionFinished>,
+    mut finished: EventWriter<CutsceneFinished>,
+) {
+    let Some(mut cutscene) = cutscene else { return };
+    lock.lock(CONTROL_RELEASE_DELAY);
+    let skip = menu_input.confirm && cutscene.time > 0.0 && delta.0 > 0.0;
+    cutscene.time += delta.0;
+    let mut budget = delta.0;
+
+    while let Some(action) = cutscene.timeline.0.get(cutscene.current).cloned() {
+        let duration = action.duration();
+        let started = cutscene.elapsed.is_some();
+        let elapsed = cutscene.elapsed.unwrap_or(0.0);
+        let step = if skip { duration - elapsed } else { budget.min(duration - elapsed).max(0.0) };
+        let elapsed = elapsed + step;
+        budget -= step;
+        cutscene.elapsed = Some(elapsed);
+        let progress = if duration > 0.0 { elapsed / duration } else { 1.0 };
+
+        let mut done = elapsed >= duration;
+        match &action {
+            CutsceneAction::MoveTo { target, to, .. } => {
+                for (mut transform, velocity, name, player, camera) in targets.iter_mut() {
+                    if !is_target(target, name, player.is_some(), camera.is_some()) {
+                        continue;
+                    }
+                    let position = transform.translation.truncate();
+                    if !started {
+                        cutscene.movement.0 = position;
+                        cutscene.movement.1[0].reset(position.x as f64);
+                        cutscene.movement.1[1].reset(position.y as f64);
+                    }
+                    // The followers chase a point moving steadily from the start to `to`, which smooths both ends.
+                    // Once the time is up they keep settling on the rest of the frame's time.
+                    let dt = if done { step + budget } else { step };
+                    let (from, followers) = &mut cutscene.movement;
+                    let along = from.lerp(*to, progress.min(1.0));
+                    let mut next = Vec2::new(
+                        followers[0].step(along.x as f64, dt as f64) as f32,
+                        followers[1].step(along.y as f64, dt as f64) as f32,
+                    );
+                    if skip || (done && next.distance(*to) <= ARRIVE_DISTANCE) {
+                        next = *to;
+                    } else if done {
+                        budget = 0.0;
+                        done = false;
+                    }
+                    transform.translation.x = next.x;
+                    transform.translation.y = next.y;
+                    if let Some(mut velocity) = velocity {
+                        *velocity = Velocity::zero();
+                    }
+                }
+            }
+            CutsceneAction::Wait { .. } => {}
+            CutsceneAction::PlaySound { name } => {
+                if !skip {
+                    sfx.play_sfx(name);
+                }
+            }
+            CutsceneAction::ShowText { text, .. } => {
+                if !started && !done {
+                    if let Some(ui) = &ui {
+                        spawn_text(&mut commands, ui, text);
+                    }
+                }
+                if done {
+                    for entity in texts.iter() {
+                        commands.entity(entity).despawn_recursive();
+                    }
+                }
+            }
+            CutsceneAction::Fade { to, .. } => {
+                if !started {
+                    cutscene.fade_from = fades.iter().next().map_or(0.0, |color| color.0.a());
+                }
+                let alpha = cutscene.fade_from + (to - cutscene.fade_from) * progress.min(1.0);
+                if !started && fades.is_empty() {
+                    spawn_fade(&mut commands, alpha);
+                }
+                for mut color in fades.iter_mut() {
+                    if color.0.a() != alpha {
+                        color.0 = Color::rgba(0.0, 0.0, 0.0, alpha);
+                    }
+                }
+            }
+        }
+
+        if !done {
+            break;
+        }
+        action_finished.send(CutsceneActionFinished(cutscene.current));
+        cutscene.current += 1;
+        cutscene.elapsed = None;
+    }
+
+    if cutscene.current >= cutscene.timeline.0.len() {
+        commands.remove_resource::<Cutscene>();
+        finished.send(CutsceneFinished { skipped: skip });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::init_silent_audio;
+
+    #[test]
+    fn timeline_finishes_its_actions_in_order() {
+        let mut app = App::new();
+        init_silent_audio(&mut app)
+            .insert_resource(GameplayDelta(0.25))
+            .init_resource::<MenuInput>()
+            .init_resource::<ControlLock>()
+            .add_state(GameState::InGame)
+            .add_plugin(CutscenePlugin);
+        app.insert_resource(Cutscene::new(Timeline(vec![
+            CutsceneAction::Wait { duration: 0.5 },
+            CutsceneAction::PlaySound { name: "chime".to_string() },
+            CutsceneAction::Wait { duration: 0.25 },
+            CutsceneAction::Fade { to: 1.0, duration: 0.5 },
+        ])));
+
+        let mut actions = app.world.resource::<Events<CutsceneActionFinished>>().get_reader();
+        let mut endings = app.world.resource::<Events<CutsceneFinished>>().get_reader();
+        let mut frames = Vec::new();
+        for _ in 0..6 {
+            app.update();
+            let events = app.world.resource::<Events<CutsceneActionFinished>>();
+            frames.push(actions.iter(events).map(|finished| finished.0).collect::<Vec<_>>());
+            assert!(app.world.resource::<ControlLock>().is_locked());
+        }
+
+        // A sound takes no time, so it finishes in the same frame as the wait before it.
+        let expected: [&[usize]; 6] = [&[], &[0, 1], &[2], &[], &[3], &[]];
+        assert_eq!(frames, expected);
+        let ended: Vec<_> = endings.iter(app.world.resource::<Events<CutsceneFinished>>()).copied().collect();
+        assert_eq!(ended, [CutsceneFinished { skipped: false }]);
+        assert!(!app.world.contains_resource::<Cutscene>());
+    }
+
+    #[test]
+    fn confirming_while_paused_does_not_skip() {
+        let mut app = App::new();
+        init_silent_audio(&mut app)
+            .insert_resource(GameplayDelta(0.25))
+            .init_resource::<MenuInput>()
+            .init_resource::<ControlLock>()
+            .add_state(GameState::InGame)
+            .add_plugin(CutscenePlugin);
+        app.insert_resource(Cutscene::new(Timeline(vec![CutsceneAction::Wait { duration: 1.0 }])));
+        app.update();
+
+        let confirm = |app: &mut App| {
+            app.world.resource_mut::<MenuInput>().confirm = true;
+            app.update();
+            app.world.resource_mut::<MenuInput>().confirm = false;
+        };
+        app.insert_resource(GameplayDelta(0.0));
+        confirm(&mut app);
+        app.world.resource_mut::<State<GameState>>().push(GameState::Paused).unwrap();
+        app.update();
+        confirm(&mut app);
+        app.world.resource_mut::<State<GameState>>().pop().unwrap();
+        app.update();
+
+        let cutscene = app.world.resource::<Cutscene>();
+        assert_eq!((cutscene.current, cutscene.elapsed), (0, Some(0.25)));
+    }
+}
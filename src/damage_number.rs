@@ -0,0 +1,155 @@
+use std::time::Duration;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use gamelibs::prelude::*;
+
+use crate::menu::UiAssets;
+use crate::pool::{EntityPool, SpawnBudget};
+
+/// How long a damage number stays up, in seconds.
+const DAMAGE_NUMBER_LIFETIME: f32 = 0.8;
+/// How far a damage number floats up over its lifetime, in pixels.
+const DAMAGE_NUMBER_RISE: f32 = 32.0;
+const DAMAGE_NUMBER_FONT_SIZE: f32 = 20.0;
+/// Numbers starting within this many pixels of each other are nudged apart by `DAMAGE_NUMBER_STACK_OFFSET`.
+const DAMAGE_NUMBER_STACK_RADIUS: f32 = 12.0;
+const DAMAGE_NUMBER_STACK_OFFSET: Vec2 = Vec2::new(10.0, 12.0);
+/// Damage numbers draw over the level and its sprites.
+const DAMAGE_NUMBER_Z: f32 = 50.0;
+
+/// A floating number showing damage dealt, rising and fading from `origin` over its lifetime.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct DamageNumber {
+    pub origin: Vec2,
+    pub color: Color,
+    pub elapsed: f32,
+}
+
+/// How far above its origin a damage number is and how opaque, `elapsed` seconds after it appeared.
+pub fn damage_number_curve(elapsed: f32) -> (f32, f32) {
+    let t = (elapsed / DAMAGE_NUMBER_LIFETIME).clamp(0.0, 1.0) as f64;
+    let rise = DAMAGE_NUMBER_RISE * ease(Ease::CubicOut, t) as f32;
+    let alpha = 1.0 - ease(Ease::QuadIn, t) as f32;
+    (rise, alpha)
+}
+
+/// Damage numbers already spawned this frame, which the world doesn't show yet.
+#[derive(Default)]
+pub struct SpawnedThisFrame {
+    at: Duration,
+    origins: Vec<Vec2>,
+}
+
+/// Spawns damage numbers from a system.
+#[derive(SystemParam)]
+pub struct DamageNumbers<'w, 's> {
+    commands: Commands<'w, 's>,
+    pool: ResMut<'w, EntityPool<DamageNumber>>,
+    budget: Res<'w, SpawnBudget>,
+    ui: Option<Res<'w, UiAssets>>,
+    time: Res<'w, Time>,
+    active: Query<'w, 's, &'static DamageNumber>,
+    spawned: Local<'s, SpawnedThisFrame>,
+}
+
+impl<'w, 's> DamageNumbers<'w, 's> {
+    /// Shows `amount` floating up from `world_pos`, nudged aside if other numbers already start there.
+    pub fn spawn_damage_number(&mut self, world_pos: Vec2, amount: f32, color: Color) {
+        let Some(ui) = &self.ui else { return };
+
+        if self.spawned.at != self.time.elapsed() {
+            self.spawned.at = self.time.elapsed();
+            self.spawned.origins.clear();
+        }
+        let overlapping = self
+            .active
+            .iter()
+            .map(|number| number.origin)
+            .chain(self.spawned.origins.iter().copied())
+            .filter(|origin| origin.distance(world_pos) < DAMAGE_NUMBER_STACK_RADIUS)
+            .count();
+        let origin = world_pos + DAMAGE_NUMBER_STACK_OFFSET * overlapping as f32;
+        self.spawned.origins.push(origin);
+
+        let text = Text::from_section(
+            format!("{}", amount.round()),
+            TextStyle {
+                font: ui.font.clone(),
+                font_size: DAMAGE_NUMBER_FONT_SIZE,
+                color,
+            },
+        )
+        .with_alignment(TextAlignment::CENTER);
+
+        let entity = self.pool.take(&mut self.commands, &self.budget);
+        self.commands.entity(entity).insert((
+            Text2dBundle {
+                text,
+                transform: Transform::from_translation(origin.extend(DAMAGE_NUMBER_Z)),
+                ..default()
+            },
+            DamageNumber {
+                origin,
+                color,
+                elapsed: 0.0,
+            },
+        ));
+    }
+}
+
+pub struct DamageNumberPlugin;
+
+impl Plugin for DamageNumberPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<EntityPool<DamageNumber>>()
+            .init_resource::<SpawnBudget>()
+            .add_system(float_damage_numbers);
+    }
+}
+
+/// Numbers keep floating through pauses and menus; they're gone within a second anyway.
+fn float_damage_numbers(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut pool: ResMut<EntityPool<DamageNumber>>,
+    mut numbers: Query<(Entity, &mut DamageNumber, &mut Transform, &mut Text)>,
+) {
+    for (entity, mut number, mut transform, mut text) in numbers.iter_mut() {
+        number.elapsed += time.delta_seconds();
+        if number.elapsed >= DAMAGE_NUMBER_LIFETIME {
+            commands.entity(entity).remove::<DamageNumber>();
+            pool.release(&mut commands, entity);
+            continue;
+        }
+
+        let (rise, alpha) = damage_number_curve(number.elapsed);
+        transform.translation.y = number.origin.y + rise;
+        let mut color = number.color;
+        color.set_a(number.color.a() * alpha);
+        for section in text.sections.iter_mut() {
+            section.style.color = color;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_rise_and_fade_over_their_lifetime() {
+        assert_eq!(damage_number_curve(0.0), (0.0, 1.0));
+
+        let (rise, alpha) = damage_number_curve(DAMAGE_NUMBER_LIFETIME / 2.0);
+        // Rising quickly at first and fading slowly at first.
+        assert!(rise > DAMAGE_NUMBER_RISE / 2.0 && rise < DAMAGE_NUMBER_RISE);
+        assert!(alpha > 0.5 && alpha < 1.0);
+
+        let (rise, alpha) = damage_number_curve(DAMAGE_NUMBER_LIFETIME);
+        assert!((rise - DAMAGE_NUMBER_RISE).abs() < 1e-4);
+        assert!(alpha.abs() < 1e-6);
+        assert_eq!(damage_number_curve(DAMAGE_NUMBER_LIFETIME * 2.0), damage_number_curve(DAMAGE_NUMBER_LIFETIME));
+    }
+}
@@ -0,0 +1,135 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use bevy_rapier2d::render::DebugRenderContext;
+use gamelibs::color::debug_color_for;
+
+use crate::terrain::TerrainCollider;
+
+const SENSOR_COLOR: Color = Color::rgb(1.0, 0.8, 0.1);
+const TERRAIN_COLOR: Color = Color::rgb(0.3, 0.6, 1.0);
+
+/// Kinds of collider the debug overlay can show, as a bitmask.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct DebugCategories(u8);
+
+impl DebugCategories {
+    pub const NONE: Self = DebugCategories(0);
+    /// Colliders that bodies collide with, other than terrain.
+    pub const SOLID: Self = DebugCategories(1 << 0);
+    pub const SENSOR: Self = DebugCategories(1 << 1);
+    /// The colliders generated from the level's IntGrid.
+    pub const TERRAIN: Self = DebugCategories(1 << 2);
+    pub const ALL: Self = DebugCategories(0b111);
+
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn toggle(&mut self, other: Self) {
+        self.0 ^= other.0;
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+/// Which collider categories are drawn. Sensors and terrain each have a color, while every solid collider gets its
+/// own so neighbouring bodies can be told apart. Toggled with F1 (solid), F2 (sensors) and
+/// F3 (terrain); nothing is drawn at first.
+#[derive(Resource, Clone, Copy, Debug, Default)]
+pub struct DebugDraw {
+    pub shown: DebugCategories,
+}
+
+pub struct DebugDrawPlugin;
+
+impl Plugin for DebugDrawPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_plugin(RapierDebugRenderPlugin {
+                mode: DebugRenderMode::COLLIDER_SHAPES,
+                ..default()
+            })
+            .init_resource::<DebugDraw>()
+            .add_system(toggle_categories)
+            .add_system(color_colliders.after(toggle_categories));
+    }
+}
+
+fn toggle_categories(keys: Res<Input<KeyCode>>, mut debug_draw: ResMut<DebugDraw>) {
+    for (key, category) in [
+        (KeyCode::F1, DebugCategories::SOLID),
+        (KeyCode::F2, DebugCategories::SENSOR),
+        (KeyCode::F3, DebugCategories::TERRAIN),
+    ] {
+        if keys.just_pressed(key) {
+            debug_draw.shown.toggle(category);
+        }
+    }
+}
+
+/*
+ * Rapier draws every collider, so hidden categories are drawn fully transparent. Colliders are recolored when the
+ * toggles change and whenever one is added or replaced, which covers terrain regenerated on hot-reload; the shapes
+ * themselves always come straight from the physics world.
+ */
+fn color_colliders(
+    mut commands: Commands,
+    debug_draw: Res<DebugDraw>,
+    mut render_context: ResMut<DebugRenderContext>,
+    colliders: Query<(Entity, ChangeTrackers<Collider>, Option<&Sensor>, Option<&TerrainCollider>)>,
+) {
+    render_context.enabled = !debug_draw.shown.is_empty();
+
+    for (entity, collider, sensor, terrain) in colliders.iter() {
+        if !debug_draw.is_changed() && !collider.is_changed() {
+            continue;
+        }
+        let category = match (sensor, terrain) {
+            (Some(_), _) => DebugCategories::SENSOR,
+            (None, Some(_)) => DebugCategories::TERRAIN,
+            (None, None) => DebugCategories::SOLID,
+        };
+        let mut color = match category {
+            DebugCategories::SENSOR => SENSOR_COLOR,
+            DebugCategories::TERRAIN => TERRAIN_COLOR,
+            _ => {
+                let [r, g, b, a] = debug_color_for(entity.to_bits()).map(|channel| channel as f32);
+                Color::rgba(r, g, b, a)
+            }
+        };
+        if !debug_draw.shown.contains(category) {
+            color.set_a(0.0);
+        }
+        commands.entity(entity).insert(ColliderDebugColor(color));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toggling_flips_only_its_category() {
+        let mut shown = DebugCategories::NONE;
+        assert!(shown.is_empty());
+
+        shown.toggle(DebugCategories::SENSOR);
+        assert!(shown.contains(DebugCategories::SENSOR));
+        assert!(!shown.contains(DebugCategories::SOLID));
+        assert!(!shown.contains(DebugCategories::TERRAIN));
+
+        shown.toggle(DebugCategories::SOLID);
+        shown.toggle(DebugCategories::TERRAIN);
+        assert_eq!(shown, DebugCategories::ALL);
+
+        shown.toggle(DebugCategories::SENSOR);
+        assert!(!shown.contains(DebugCategories::SENSOR));
+        assert!(shown.contains(DebugCategories::SOLID) && shown.contains(DebugCategories::TERRAIN));
+
+        shown.toggle(DebugCategories::SOLID);
+        shown.toggle(DebugCategories::TERRAIN);
+        assert!(shown.is_empty());
+    }
+}
@@ -0,0 +1,736 @@
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_rapier2d::prelude::*;
+use gamelibs::aabb::{sweep_aabb, Aabb2};
+use gamelibs::state_machine::StateMachine;
+use nalgebra_glm::DVec2;
+
+use crate::breakable::spawn_debris;
+use crate::entity_registry::RegisterLevelEntity;
+use crate::fields::LdtkFields;
+use crate::health::{apply_damage, Damage, Invulnerable};
+use crate::level::PlayerRespawned;
+use crate::physics::{GameplayDelta, PhysicsUnits, StaticColliders};
+use crate::player::{move_player, Player};
+use crate::projectile::spawn_projectile;
+use crate::state::GameState;
+
+/// The LDtk entity identifier of an enemy, a solid body the size of the entity.
+pub const ENEMY_ENTITY: &str = "Enemy";
+/// String field: "Projectile" or "Lunge". Enemies without one don't attack, they only hurt on contact.
+const ATTACK_FIELD: &str = "Attack";
+/// Float field: how close (in pixels) the player has to come to set off an attack.
+const RANGE_FIELD: &str = "Range";
+/// Float field: how fast (in pixels per second) the projectile flies or the enemy lunges.
+const SPEED_FIELD: &str = "Speed";
+/// Float fields: seconds of telegraph before attacking, and of recovery after.
+const WIND_UP_FIELD: &str = "WindUp";
+const RECOVER_FIELD: &str = "Recover";
+/// Float field: how much a touch hurts the player.
+const DAMAGE_FIELD: &str = "Damage";
+/// Bool field: can be stomped on. Defaults to true.
+const STOMPABLE_FIELD: &str = "Stompable";
+/// Bool field: stays defeated until the player leaves the level, rather than coming back at checkpoints.
+const PERSISTS_FIELD: &str = "Persists";
+/// Float field: how far (in pixels) the enemy sees. Without it, it attacks whatever comes within range.
+const VISION_FIELD: &str = "Vision";
+/// Float field: how far either side of straight ahead the enemy sees, in degrees.
+const VISION_ANGLE_FIELD: &str = "VisionAngle";
+
+const ENEMY_COLOR: Color = Color::rgb(0.8, 0.2, 0.2);
+const TELEGRAPH_COLOR: Color = Color::rgb(1.0, 0.9, 0.9);
+const DEFAULT_RANGE: f32 = 160.0;
+const DEFAULT_PROJECTILE_SPEED: f32 = 300.0;
+const DEFAULT_LUNGE_SPEED: f32 = 400.0;
+const PROJECTILE_RADIUS: f32 = 4.0;
+const PROJECTILE_LIFETIME: f32 = 2.0;
+const DEFAULT_VISION_ANGLE: f32 = 45.0;
+const DEFAULT_TIMING: AttackTiming = AttackTiming { wind_up: 0.5, attack: 0.2, recover: 0.6 };
+
+/// How many times a second a telegraphing enemy blinks.
+const TELEGRAPH_BLINK_RATE: f64 = 10.0;
+/// A contact normal at least this steep, pointing up from the enemy to the player, is the player landing on top.
+const STOMP_NORMAL: f32 = 0.7;
+/// How fast (in m/s) the player can be rising away from an enemy and still stomp it, for contacts the solver has
+/// already pushed apart.
+const STOMP_RISE_TOLERANCE: f32 = 0.1;
+/// Seconds the player can't be hurt again after running into an enemy, so touching one isn't a hit every frame.
+const CONTACT_INVULNERABILITY: f32 = 1.0;
+/// The color of the burst a stomped enemy leaves when it has no sprite.
+const DEFEAT_DEBRIS_COLOR: Color = ENEMY_COLOR;
+/// Seconds a chasing enemy keeps after the player once it can no longer see them, before it goes back to patrolling.
+const LOSE_SIGHT_TIMEOUT: f32 = 2.0;
+
+/// Marker for hostile entities.
+#[derive(Component)]
+pub struct Enemy;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AttackPhase {
+    Idle,
+    /// The telegraph: the enemy visibly winds up so the player has time to react.
+    WindUp,
+    Attack,
+    Recover,
+}
+
+/// What happens at the end of the wind-up. Speeds are in pixels per second.
+#[derive(Clone, Copy, Debug)]
+pub enum AttackKind {
+    Projectile { speed: f32, radius: f32, lifetime: f32 },
+    Lunge { speed: f32 },
+}
+
+/// Per-phase durations of an attack, in seconds.
+#[derive(Clone, Copy, Debug)]
+pub struct AttackTiming {
+    pub wind_up: f64,
+    pub attack: f64,
+    pub recover: f64,
+}
+
+/// A telegraphed attack: when the player comes within `range` pixels the enemy winds up, then attacks, then recovers.
+#[derive(Component, Clone, Debug)]
+pub struct EnemyAttack {
+    pub kind: AttackKind,
+    pub timing: AttackTiming,
+    pub range: f32,
+    /// Blink to this colour while winding up, if set.
+    pub flash: Option<Color>,
+}
+
+/// The attack state machine of an enemy, plus the sprite colour to restore after a telegraph flash.
+#[derive(Component)]
+pub struct AttackState {
+    pub machine: StateMachine<AttackPhase>,
+    original_color: Option<Color>,
+}
+
+impl Default for AttackState {
+    fn default() -> Self {
+        AttackState {
+            machine: StateMachine::new(AttackPhase::Idle),
+            original_color: None,
+        }
+    }
+}
+
+/// What an enemy can see: the player within `range` pixels and `half_angle` radians either side of the way it's
+/// facing, with nothing solid in between. It faces right, or left while its sprite is flipped.
+///
+/// An enemy with one only attacks once it has seen the player; one without attacks whatever comes within range.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct Vision {
+    pub range: f32,
+    pub half_angle: f32,
+}
+
+/// What an enemy with `Vision` is doing about the player.
+///
+/// * `Awareness::Patrol` is going about its business, not having seen them.
+///
+/// * `Awareness::Chase { unseen }` is after them, `unseen` being the seconds since it last saw them.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq)]
+pub enum Awareness {
+    #[default]
+    Patrol,
+    Chase { unseen: f32 },
+}
+
+impl Awareness {
+    /// Runs `dt` seconds on from this, given whether the player is `seen`: spotting them starts a chase, and a
+    /// chase gives up once they've been out of sight for `timeout` seconds.
+    pub fn next(self, seen: bool, dt: f32, timeout: f32) -> Awareness {
+        match self {
+            _ if seen => Awareness::Chase { unseen: 0.0 },
+            Awareness::Patrol => Awareness::Patrol,
+            Awareness::Chase { unseen } if unseen + dt >= timeout => Awareness::Patrol,
+            Awareness::Chase { unseen } => Awareness::Chase { unseen: unseen + dt },
+        }
+    }
+
+    pub fn is_chasing(self) -> bool {
+        matches!(self, Awareness::Chase { .. })
+    }
+}
+
+/// Whether a defeated enemy comes back when the player respawns at a checkpoint.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RespawnPolicy {
+    /// Restored on every checkpoint respawn.
+    #[default]
+    AlwaysRespawn,
+    /// Stays defeated until the player leaves the level, even if the level is reloaded.
+    PersistUntilLevelExit,
+}
+
+/// What happens when the player runs into an enemy: landing on its top stomps it, if it's `stompable`, bouncing
+/// the player up at `bounce` m/s; any other contact hurts the player by `damage`.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct EnemyContact {
+    pub stompable: bool,
+    pub bounce: f32,
+    pub damage: f32,
+}
+
+impl Default for EnemyContact {
+    fn default() -> Self {
+        EnemyContact {
+            stompable: true,
+            bounce: 5.0,
+            damage: 1.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContactOutcome {
+    Stomp,
+    Hurt,
+}
+
+/// Send this to take an enemy out of play.
+pub struct EnemyDefeated(pub Entity);
+
+/// Marker for an enemy that has been defeated but may still be restored, so it is hidden and inert rather than despawned.
+#[derive(Component)]
+pub struct Defeated;
+
+/// LDtk IIDs of defeated `PersistUntilLevelExit` enemies in the current level.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct DefeatedEnemies(pub HashSet<String>);
+
+pub struct EnemyPlugin;
+
+impl Plugin for EnemyPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<DefeatedEnemies>()
+            .add_event::<EnemyDefeated>()
+            .register_level_entity(ENEMY_ENTITY, spawn_enemy)
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_system(watch_for_player)
+                    .with_system(enemy_attacks.after(watch_for_player))
+                    .with_system(
+                        // Before damage is applied, so the hit lands before the invulnerability that comes with it.
+                        touch_enemies.after(move_player).before(defeat_enemies).before(apply_damage),
+                    ),
+            )
+            .add_system(defeat_enemies)
+            .add_system(restore_enemies.after(defeat_enemies))
+            .add_system(skip_persisted_defeats)
+            .add_system(clear_defeats_on_level_change);
+    }
+}
+
+/*
+ * Enemies are dynamic bodies so a lunge carries them along the ground and off ledges, but they never tip over. Their
+ * attack and vision come from the instance's fields, so the same entity covers turrets, chargers and sentries.
+ */
+fn spawn_enemy(enemy: &mut EntityCommands, instance: &EntityInstance, _: Vec2) {
+    let fields = instance.fields();
+    let size = Vec2::new(instance.width as f32, instance.height as f32);
+    let speed = fields.get_float(SPEED_FIELD);
+    let kind = match fields.get_string(ATTACK_FIELD) {
+        Some("Projectile") => Some(AttackKind::Projectile {
+            speed: speed.unwrap_or(DEFAULT_PROJECTILE_SPEED),
+            radius: PROJECTILE_RADIUS,
+            lifetime: PROJECTILE_LIFETIME,
+        }),
+        Some("Lunge") => Some(AttackKind::Lunge { speed: speed.unwrap_or(DEFAULT_LUNGE_SPEED) }),
+        Some(other) => {
+            warn!("Unknown enemy attack \"{other}\"; it won't attack");
+            None
+        }
+        None => None,
+    };
+    let policy = if fields.get_bool(PERSISTS_FIELD).unwrap_or(false) {
+        RespawnPolicy::PersistUntilLevelExit
+    } else {
+        RespawnPolicy::AlwaysRespawn
+    };
+
+    enemy.insert((
+        Enemy,
+        EnemyContact {
+            stompable: fields.get_bool(STOMPABLE_FIELD).unwrap_or(true),
+            damage: fields.get_float(DAMAGE_FIELD).unwrap_or(EnemyContact::default().damage),
+            ..default()
+        },
+        policy,
+        RigidBody::Dynamic,
+        LockedAxes::ROTATION_LOCKED,
+        Velocity::default(),
+        Collider::cuboid(size.x / 2.0, size.y / 2.0),
+        Sprite {
+            color: ENEMY_COLOR,
+            custom_size: Some(size),
+            ..default()
+        },
+        Handle::<Image>::default(),
+    ));
+    if let Some(kind) = kind {
+        enemy.insert((
+            EnemyAttack {
+                kind,
+                timing: AttackTiming {
+                    wind_up: fields.get_float(WIND_UP_FIELD).map_or(DEFAULT_TIMING.wind_up, f64::from),
+                    recover: fields.get_float(RECOVER_FIELD).map_or(DEFAULT_TIMING.recover, f64::from),
+                    ..DEFAULT_TIMING
+                },
+                range: fields.get_float(RANGE_FIELD).unwrap_or(DEFAULT_RANGE),
+                flash: Some(TELEGRAPH_COLOR),
+            },
+            AttackState::default(),
+        ));
+    }
+    if let Some(range) = fields.get_float(VISION_FIELD) {
+        let half_angle = fields.get_float(VISION_ANGLE_FIELD).unwrap_or(DEFAULT_VISION_ANGLE);
+        enemy.insert(Vision { range, half_angle: half_angle.to_radians() });
+    }
+}
+
+/// Advances an attack by `dt` seconds, starting a wind-up when `triggered` and idle.
+///
+/// Returns `true` on exactly the step the wind-up completes, which is when the attack should fire.
+pub fn step_attack(machine: &mut StateMachine<AttackPhase>, timing: &AttackTiming, dt: f64, triggered: bool) -> bool {
+    machine.tick(dt);
+    let elapsed = machine.time_in_state();
+
+    match machine.current() {
+        AttackPhase::Idle => {
+            if triggered {
+                machine.transition_to(AttackPhase::WindUp);
+            }
+        }
+        AttackPhase::WindUp => {
+            if elapsed >= timing.wind_up {
+                machine.transition_to(AttackPhase::Attack);
+                return true;
+            }
+        }
+        AttackPhase::Attack => {
+            if elapsed >= timing.attack {
+                machine.transition_to(AttackPhase::Recover);
+            }
+        }
+        AttackPhase::Recover => {
+            if elapsed >= timing.recover {
+                machine.transition_to(AttackPhase::Idle);
+            }
+        }
+    }
+    false
+}
+
+/// What a contact between the player and an enemy does, given its normal pointing from the enemy to the player and
+/// the player's velocity relative to the enemy in m/s. Only a player coming down onto the top stomps.
+pub fn contact_outcome(normal: Vec2, relative_velocity: Vec2, stompable: bool) -> ContactOutcome {
+    if stompable && normal.y >= STOMP_NORMAL && relative_velocity.y <= STOMP_RISE_TOLERANCE {
+        ContactOutcome::Stomp
+    } else {
+        ContactOutcome::Hurt
+    }
+}
+
+/// Whether `target` is within `range` of an `eye` looking along `facing`, and no more than `half_angle` radians
+/// off it.
+pub fn in_vision_cone(eye: Vec2, facing: Vec2, target: Vec2, range: f32, half_angle: f32) -> bool {
+    let offset = target - eye;
+    let distance = offset.length();
+    if distance > range {
+        return false;
+    }
+    if distance == 0.0 {
+        return true;
+    }
+    facing.normalize_or_zero().dot(offset / distance) >= half_angle.min(std::f32::consts::PI).cos()
+}
+
+/// Whether the straight line from `eye` to `target` is clear of every box in `obstacles`. Boxes the eye is inside
+/// don't block it, so an enemy's own collider doesn't blind it.
+pub fn line_of_sight(eye: Vec2, target: Vec2, obstacles: &[Aabb2]) -> bool {
+    let eye = DVec2::new(eye.x as f64, eye.y as f64);
+    let ray = DVec2::new(target.x as f64, target.y as f64) - eye;
+    let (toi, _) = sweep_aabb(Aabb2::new(eye, eye), ray, obstacles);
+    toi >= 1.0
+}
+
+/*
+ * Static colliders are only gathered once something has the player in its cone, as most frames nothing does. The
+ * cone and the line of sight are both measured from the enemy's centre to the player's.
+ */
+fn watch_for_player(
+    mut commands: Commands,
+    delta: Res<GameplayDelta>,
+    statics: StaticColliders,
+    players: Query<&GlobalTransform, With<Player>>,
+    mut enemies: Query<
+        (Entity, &GlobalTransform, &Vision, Option<&Sprite>, Option<&mut Awareness>),
+        (With<Enemy>, Without<Defeated>),
+    >,
+) {
+    let target = players.get_single().ok().map(|player| player.translation().truncate());
+    let mut obstacles = None;
+
+    for (entity, transform, vision, sprite, awareness) in enemies.iter_mut() {
+        let eye = transform.translation().truncate();
+        let facing = if sprite.is_some_and(|sprite| sprite.flip_x) { Vec2::NEG_X } else { Vec2::X };
+        let seen = target.is_some_and(|target| {
+            in_vision_cone(eye, facing, target, vision.range, vision.half_angle)
+                && line_of_sight(eye, target, obstacles.get_or_insert_with(|| statics.aabbs()))
+        });
+
+        let current = awareness.as_deref().copied().unwrap_or_default();
+        let next = current.next(seen, delta.0, LOSE_SIGHT_TIMEOUT);
+        match awareness {
+            Some(mut awareness) if *awareness != next => *awareness = next,
+            Some(_) => {}
+            None => {
+                commands.entity(entity).insert(next);
+            }
+        }
+    }
+}
+
+fn enemy_attacks(
+    mut commands: Commands,
+    delta: Res<GameplayDelta>,
+    players: Query<&GlobalTransform, With<Player>>,
+    mut enemies: Query<
+        (
+            Entity,
+            &GlobalTransform,
+            &EnemyAttack,
+            &mut AttackState,
+            Option<&mut Sprite>,
+            Option<&mut Velocity>,
+            Option<&Awareness>,
+        ),
+        With<Enemy>,
+    >,
+) {
+    let Ok(player) = players.get_single() else { return };
+    let target = player.translation().truncate();
+
+    for (entity, transform, attack, mut state, sprite, velocity, awareness) in enemies.iter_mut() {
+        let position = transform.translation().truncate();
+        let aware = awareness.is_none_or(|awareness| awareness.is_chasing());
+        let triggered = aware && position.distance(target) <= attack.range;
+        let fired = step_attack(&mut state.machine, &attack.timing, delta.0 as f64, triggered);
+
+        let direction = (target - position).normalize_or_zero();
+        if fired {
+            match attack.kind {
+                AttackKind::Projectile { speed, radius, lifetime } => {
+                    spawn_projectile(&mut commands, entity, position, direction * speed, radius, lifetime, false);
+                }
+                AttackKind::Lunge { speed } => {
+                    if let Some(mut velocity) = velocity {
+                        velocity.linvel = direction * speed;
+                    }
+                }
+            }
+        }
+
+        let Some(mut sprite) = sprite else { continue };
+        let Some(flash) = attack.flash else { continue };
+        if state.machine.on_enter() == Some(AttackPhase::WindUp) {
+            state.original_color = Some(sprite.color);
+        }
+        if state.machine.on_exit() == Some(AttackPhase::WindUp) {
+            if let Some(original) = state.original_color.take() {
+                sprite.color = original;
+            }
+        }
+        if state.machine.is(AttackPhase::WindUp) {
+            let blink_on = (state.machine.time_in_state() * TELEGRAPH_BLINK_RATE).fract() < 0.5;
+            sprite.color = if blink_on { flash } else { state.original_color.unwrap_or(sprite.color) };
+        }
+    }
+}
+
+/*
+ * Contacts come from the last physics step, so the solver has usually stopped a landing player already; the
+ * normal is what tells a stomp from running into the enemy's side. A stomp bounces the player before the next step
+ * and defeats the enemy in a burst of debris.
+ */
+#[allow(clippy::too_many_arguments)]
+fn touch_enemies(
+    mut commands: Commands,
+    units: Res<PhysicsUnits>,
+    rapier_context: Res<RapierContext>,
+    enemies: Query<(Entity, &EnemyContact, &GlobalTransform, Option<&Velocity>, Option<&Sprite>), Without<Defeated>>,
+    mut players: Query<(&mut Velocity, Option<&Invulnerable>), (With<Player>, Without<EnemyContact>)>,
+    mut defeated: EventWriter<EnemyDefeated>,
+    mut damages: EventWriter<Damage>,
+) {
+    for (enemy, contact, transform, enemy_velocity, sprite) in enemies.iter() {
+        for pair in rapier_context.contacts_with(enemy) {
+            if !pair.has_any_active_contacts() {
+                continue;
+            }
+            let Some(manifold) = pair.manifolds().find(|manifold| manifold.num_points() > 0) else { continue };
+            let (other, normal) = if pair.collider1() == enemy {
+                (pair.collider2(), manifold.normal())
+            } else {
+                (pair.collider1(), -manifold.normal())
+            };
+            let player = rapier_context.collider_parent(other).unwrap_or(other);
+            let Ok((mut velocity, invulnerable)) = players.get_mut(player) else { continue };
+
+            let relative = velocity.linvel - enemy_velocity.map_or(Vec2::ZERO, |velocity| velocity.linvel);
+            let relative = Vec2::new(units.px_to_m(relative.x), units.px_to_m(relative.y));
+            match contact_outcome(normal, relative, contact.stompable) {
+                ContactOutcome::Stomp => {
+                    velocity.linvel.y = units.m_to_px(contact.bounce);
+                    let color = sprite.map_or(DEFEAT_DEBRIS_COLOR, |sprite| sprite.color);
+                    spawn_debris(&mut commands, transform.translation().truncate(), color, &units);
+                    defeated.send(EnemyDefeated(enemy));
+                }
+                ContactOutcome::Hurt => {
+                    if invulnerable.is_none() {
+                        damages.send(Damage { target: player, amount: contact.damage });
+                        commands.entity(player).insert(Invulnerable { remaining: CONTACT_INVULNERABILITY });
+                    }
+                }
+            }
+            break;
+        }
+    }
+}
+
+fn set_defeated(commands: &mut Commands, entity: Entity, defeated: bool) {
+    let mut enemy = commands.entity(entity);
+    if defeated {
+        enemy.insert((Defeated, ColliderDisabled, RigidBodyDisabled, Visibility::INVISIBLE));
+    } else {
+        enemy
+            .remove::<(Defeated, ColliderDisabled, RigidBodyDisabled, Awareness)>()
+            .insert((Visibility::VISIBLE, AttackState::default()));
+    }
+}
+
+fn defeat_enemies(
+    mut commands: Commands,
+    mut events: EventReader<EnemyDefeated>,
+    mut defeated: ResMut<DefeatedEnemies>,
+    enemies: Query<(Option<&RespawnPolicy>, Option<&EntityInstance>), (With<Enemy>, Without<Defeated>)>,
+) {
+    for EnemyDefeated(entity) in events.iter() {
+        let Ok((policy, instance)) = enemies.get(*entity) else { continue };
+
+        if policy.copied().unwrap_or_default() == RespawnPolicy::PersistUntilLevelExit {
+            if let Some(instance) = instance {
+                defeated.0.insert(instance.iid.clone());
+            }
+        }
+        set_defeated(&mut commands, *entity, true);
+    }
+}
+
+/// Brings back every defeated enemy that isn't persisted when the player respawns at a checkpoint.
+fn restore_enemies(
+    mut commands: Commands,
+    mut respawns: EventReader<PlayerRespawned>,
+    defeated: Res<DefeatedEnemies>,
+    enemies: Query<(Entity, Option<&EntityInstance>), (With<Enemy>, With<Defeated>)>,
+) {
+    if respawns.iter().count() == 0 {
+        return;
+    }
+
+    for (entity, instance) in enemies.iter() {
+        let persisted = instance.is_some_and(|instance| defeated.0.contains(&instance.iid));
+        if !persisted {
+            set_defeated(&mut commands, entity, false);
+        }
+    }
+}
+
+/// Keeps persisted defeats dead when their level is respawned and its entities come back.
+fn skip_persisted_defeats(
+    mut commands: Commands,
+    defeated: Res<DefeatedEnemies>,
+    enemies: Query<(Entity, &EntityInstance), (With<Enemy>, Added<EntityInstance>)>,
+) {
+    for (entity, instance) in enemies.iter() {
+        if defeated.0.contains(&instance.iid) {
+            set_defeated(&mut commands, entity, true);
+        }
+    }
+}
+
+fn clear_defeats_on_level_change(selection: Option<Res<LevelSelection>>, mut defeated: ResMut<DefeatedEnemies>) {
+    if selection.is_some_and(|selection| selection.is_changed()) {
+        defeated.0.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::CommandQueue;
+    use bevy_ecs_ldtk::ldtk::FieldInstance;
+
+    use super::*;
+
+    const TIMING: AttackTiming = AttackTiming { wind_up: 0.5, attack: 0.2, recover: 0.3 };
+
+    /// Steps `machine` every 0.1 s for `seconds`, returning how many times it fired.
+    fn run_attack(machine: &mut StateMachine<AttackPhase>, seconds: f64, triggered: bool) -> usize {
+        let steps = (seconds / 0.1).round() as usize;
+        (0..steps).filter(|_| step_attack(machine, &TIMING, 0.1, triggered)).count()
+    }
+
+    #[test]
+    fn attack_runs_a_full_cycle() {
+        let mut machine = StateMachine::new(AttackPhase::Idle);
+        assert_eq!(run_attack(&mut machine, 1.0, false), 0);
+        assert!(machine.is(AttackPhase::Idle));
+
+        assert_eq!(run_attack(&mut machine, 0.1, true), 0);
+        assert!(machine.is(AttackPhase::WindUp));
+        assert_eq!(run_attack(&mut machine, 0.4, false), 0);
+        assert!(machine.is(AttackPhase::WindUp));
+        // The wind-up ends on its 0.5 s mark, firing on that step alone.
+        assert_eq!(run_attack(&mut machine, 0.1, false), 1);
+        assert!(machine.is(AttackPhase::Attack));
+        assert_eq!(run_attack(&mut machine, 0.2, true), 0);
+        assert!(machine.is(AttackPhase::Recover));
+        // Still being in range doesn't cut the recovery short.
+        assert_eq!(run_attack(&mut machine, 0.3, true), 0);
+        assert!(machine.is(AttackPhase::Idle));
+
+        assert_eq!(run_attack(&mut machine, 1.1, true), 1);
+    }
+
+    fn field(identifier: &str, field_instance_type: &str, value: FieldValue) -> FieldInstance {
+        FieldInstance {
+            identifier: identifier.to_string(),
+            tile: None,
+            field_instance_type: field_instance_type.to_string(),
+            value,
+            def_uid: 0,
+            real_editor_values: Vec::new(),
+        }
+    }
+
+    /// Runs `spawn_enemy` on a fresh entity of a world.
+    fn spawn(fields: Vec<FieldInstance>) -> (World, Entity) {
+        let instance = EntityInstance {
+            identifier: ENEMY_ENTITY.to_string(),
+            width: 16,
+            height: 24,
+            field_instances: fields,
+            ..default()
+        };
+        let mut world = World::new();
+        let entity = world.spawn_empty().id();
+        let mut queue = CommandQueue::default();
+        spawn_enemy(&mut Commands::new(&mut queue, &world).entity(entity), &instance, Vec2::ZERO);
+        queue.apply(&mut world);
+        (world, entity)
+    }
+
+    #[test]
+    fn spawner_reads_the_attack_from_fields() {
+        let (world, entity) = spawn(vec![
+            field(ATTACK_FIELD, "String", FieldValue::String(Some("Lunge".to_string()))),
+            field(SPEED_FIELD, "Float", FieldValue::Float(Some(250.0))),
+            field(PERSISTS_FIELD, "Bool", FieldValue::Bool(true)),
+            field(VISION_FIELD, "Float", FieldValue::Float(Some(200.0))),
+        ]);
+        let enemy = world.entity(entity);
+        assert!(enemy.contains::<Enemy>() && enemy.contains::<AttackState>() && enemy.contains::<Collider>());
+        let attack = enemy.get::<EnemyAttack>().unwrap();
+        assert!(matches!(attack.kind, AttackKind::Lunge { speed } if speed == 250.0));
+        assert_eq!(attack.range, DEFAULT_RANGE);
+        assert_eq!(enemy.get::<RespawnPolicy>(), Some(&RespawnPolicy::PersistUntilLevelExit));
+        let vision = enemy.get::<Vision>().unwrap();
+        assert_eq!(vision.range, 200.0);
+        assert!((vision.half_angle - DEFAULT_VISION_ANGLE.to_radians()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn spawner_without_an_attack_only_hurts_on_contact() {
+        let (world, entity) = spawn(Vec::new());
+        let enemy = world.entity(entity);
+        assert!(enemy.contains::<Enemy>() && enemy.contains::<EnemyContact>());
+        assert!(!enemy.contains::<EnemyAttack>() && !enemy.contains::<Vision>());
+        assert_eq!(enemy.get::<RespawnPolicy>(), Some(&RespawnPolicy::AlwaysRespawn));
+    }
+
+    fn enemy(policy: RespawnPolicy, iid: &str) -> impl Bundle {
+        (Enemy, policy, EntityInstance { iid: iid.to_string(), ..default() })
+    }
+
+    #[test]
+    fn checkpoint_respawn_restores_only_unpersisted_enemies() {
+        let mut app = App::new();
+        app
+            .init_resource::<DefeatedEnemies>()
+            .add_event::<EnemyDefeated>()
+            .add_event::<PlayerRespawned>()
+            .add_system(defeat_enemies)
+            .add_system(restore_enemies.after(defeat_enemies));
+        let persistent = app.world.spawn(enemy(RespawnPolicy::PersistUntilLevelExit, "persistent")).id();
+        let returning = app.world.spawn(enemy(RespawnPolicy::AlwaysRespawn, "returning")).id();
+
+        app.world.send_event(EnemyDefeated(persistent));
+        app.world.send_event(EnemyDefeated(returning));
+        app.update();
+        assert!(app.world.entity(persistent).contains::<Defeated>());
+        assert!(app.world.entity(returning).contains::<Defeated>());
+        assert!(app.world.resource::<DefeatedEnemies>().0.contains("persistent"));
+
+        app.world.send_event(PlayerRespawned);
+        app.update();
+        assert!(app.world.entity(persistent).contains::<Defeated>());
+        assert!(!app.world.entity(returning).contains::<Defeated>());
+        assert!(app.world.entity(returning).get::<Visibility>().is_some_and(|visibility| visibility.is_visible));
+    }
+
+    #[test]
+    fn stomps_only_from_above_and_coming_down() {
+        let falling = Vec2::new(1.0, -4.0);
+        assert_eq!(contact_outcome(Vec2::Y, falling, true), ContactOutcome::Stomp);
+        assert_eq!(contact_outcome(Vec2::new(0.6, 0.8), falling, true), ContactOutcome::Stomp, "near the top corner");
+        // Landing as the enemy hops up still counts, within the tolerance.
+        assert_eq!(contact_outcome(Vec2::Y, Vec2::new(0.0, 0.05), true), ContactOutcome::Stomp);
+
+        // Jumping up past its top, running into its side or from below, or onto one that can't be stomped.
+        assert_eq!(contact_outcome(Vec2::Y, Vec2::new(0.0, 3.0), true), ContactOutcome::Hurt);
+        assert_eq!(contact_outcome(Vec2::X, falling, true), ContactOutcome::Hurt);
+        assert_eq!(contact_outcome(Vec2::new(0.8, 0.6), falling, true), ContactOutcome::Hurt);
+        assert_eq!(contact_outcome(Vec2::NEG_Y, falling, true), ContactOutcome::Hurt);
+        assert_eq!(contact_outcome(Vec2::Y, falling, false), ContactOutcome::Hurt);
+    }
+
+    #[test]
+    fn sees_within_the_cone_only() {
+        let quarter = std::f32::consts::FRAC_PI_4;
+        let eye = Vec2::ZERO;
+        assert!(in_vision_cone(eye, Vec2::X, Vec2::new(100.0, 50.0), 200.0, quarter));
+        assert!(in_vision_cone(eye, Vec2::NEG_X, Vec2::new(-100.0, 0.0), 200.0, quarter));
+        assert!(in_vision_cone(eye, Vec2::X, eye, 200.0, quarter));
+        // Behind it, too far off to the side, and out of range.
+        assert!(!in_vision_cone(eye, Vec2::X, Vec2::new(-100.0, 0.0), 200.0, quarter));
+        assert!(!in_vision_cone(eye, Vec2::X, Vec2::new(50.0, 100.0), 200.0, quarter));
+        assert!(!in_vision_cone(eye, Vec2::X, Vec2::new(250.0, 0.0), 200.0, quarter));
+    }
+
+    #[test]
+    fn walls_block_the_line_of_sight() {
+        let wall = Aabb2::new(DVec2::new(40.0, -20.0), DVec2::new(60.0, 20.0));
+        let own_collider = Aabb2::new(DVec2::new(-8.0, -8.0), DVec2::new(8.0, 8.0));
+        let obstacles = [wall, own_collider];
+        assert!(!line_of_sight(Vec2::ZERO, Vec2::new(100.0, 0.0), &obstacles));
+        // Over the top of the wall, and short of it, are both clear.
+        assert!(line_of_sight(Vec2::ZERO, Vec2::new(100.0, 60.0), &obstacles));
+        assert!(line_of_sight(Vec2::ZERO, Vec2::new(30.0, 0.0), &obstacles));
+        assert!(line_of_sight(Vec2::ZERO, Vec2::new(100.0, 0.0), &[]));
+    }
+}
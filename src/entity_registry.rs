@@ -0,0 +1,103 @@
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::level::seed_from_level;
+
+/// Sets up an LDtk entity once bevy_ecs_ldtk has spawned it, given the instance (for its fields and size) and its
+/// position in the world, in pixels.
+pub type EntitySpawner = fn(&mut EntityCommands, &EntityInstance, Vec2);
+
+/// The spawner for each LDtk entity identifier. Register them with `RegisterLevelEntity::register_level_entity`.
+#[derive(Resource, Default)]
+pub struct EntityRegistry(HashMap<String, EntitySpawner>);
+
+impl EntityRegistry {
+    /// Makes `spawner` set up every LDtk entity named `identifier`, replacing any spawner it had.
+    pub fn register(&mut self, identifier: &str, spawner: EntitySpawner) {
+        if self.0.insert(identifier.to_string(), spawner).is_some() {
+            warn!("Replacing the spawner for LDtk entity {identifier}");
+        }
+    }
+
+    pub fn get(&self, identifier: &str) -> Option<EntitySpawner> {
+        self.0.get(identifier).copied()
+    }
+}
+
+/// Registers LDtk entity spawners from a plugin, in any order relative to `EntityRegistryPlugin`.
+pub trait RegisterLevelEntity {
+    fn register_level_entity(&mut self, identifier: &str, spawner: EntitySpawner) -> &mut Self;
+}
+
+impl RegisterLevelEntity for App {
+    fn register_level_entity(&mut self, identifier: &str, spawner: EntitySpawner) -> &mut Self {
+        self.init_resource::<EntityRegistry>();
+        self.world.resource_mut::<EntityRegistry>().register(identifier, spawner);
+        self
+    }
+}
+
+pub struct EntityRegistryPlugin;
+
+impl Plugin for EntityRegistryPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<EntityRegistry>()
+            .add_system(spawn_registered_entities.after(seed_from_level));
+    }
+}
+
+/// Composes `entity`'s transform with its ancestors', since their `GlobalTransform`s haven't been propagated the
+/// frame they're spawned.
+pub fn world_position(entity: Entity, transforms: &Query<&Transform>, parents: &Query<&Parent>) -> Vec2 {
+    let local = transforms.get(entity).copied().unwrap_or_default();
+    let world = parents.iter_ancestors(entity).fold(local, |transform, ancestor| {
+        transforms.get(ancestor).map_or(transform, |parent| parent.mul_transform(transform))
+    });
+    world.translation.truncate()
+}
+
+/// Hands each newly loaded LDtk entity to the spawner registered for its identifier. Entities without one are left
+/// as bevy_ecs_ldtk spawned them. The level's `LevelSeed` is already set for spawners that place things at random.
+fn spawn_registered_entities(
+    mut commands: Commands,
+    registry: Res<EntityRegistry>,
+    instances: Query<(Entity, &EntityInstance), Added<EntityInstance>>,
+    transforms: Query<&Transform>,
+    parents: Query<&Parent>,
+) {
+    for (entity, instance) in instances.iter() {
+        let Some(spawner) = registry.get(&instance.identifier) else { continue };
+        let position = world_position(entity, &transforms, &parents);
+        spawner(&mut commands.entity(entity), instance, position);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Component)]
+    struct Spawned(Vec2);
+
+    fn spawn_dummy(entity: &mut EntityCommands, _instance: &EntityInstance, position: Vec2) {
+        entity.insert(Spawned(position));
+    }
+
+    #[test]
+    fn registered_spawner_runs_for_its_identifier() {
+        let mut app = App::new();
+        app.add_plugin(EntityRegistryPlugin).register_level_entity("Dummy", spawn_dummy);
+        let level = app.world.spawn(Transform::from_xyz(100.0, 50.0, 0.0)).id();
+        let instance = |identifier: &str| EntityInstance { identifier: identifier.to_string(), ..default() };
+        let dummy = app.world.spawn((instance("Dummy"), Transform::from_xyz(8.0, 4.0, 0.0))).id();
+        let other = app.world.spawn((instance("Other"), Transform::default())).id();
+        app.world.entity_mut(level).push_children(&[dummy, other]);
+        app.update();
+
+        assert_eq!(app.world.get::<Spawned>(dummy).map(|spawned| spawned.0), Some(Vec2::new(108.0, 54.0)));
+        assert!(app.world.get::<Spawned>(other).is_none());
+    }
+}
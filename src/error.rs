@@ -0,0 +1,53 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// Why loading or saving something the game depends on failed.
+///
+/// * `GameError::Io` is the file system refusing a read or write.
+///
+/// * `GameError::Parse` is a file that was read but isn't valid JSON for what it should hold.
+///
+/// * `GameError::AssetMissing` is an asset the game refers to, by its path, that isn't in the asset folder.
+///
+/// * `GameError::InvalidLevel` is a level asked for, e.g. with `--level`, that the project doesn't have.
+#[derive(Debug)]
+pub enum GameError {
+    Io(io::Error),
+    Parse(serde_json::Error),
+    AssetMissing(String),
+    InvalidLevel(String),
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameError::Io(error) => write!(f, "{error}"),
+            GameError::Parse(error) => write!(f, "invalid data: {error}"),
+            GameError::AssetMissing(path) => write!(f, "missing asset {path}"),
+            GameError::InvalidLevel(level) => write!(f, "no level {level}"),
+        }
+    }
+}
+
+impl Error for GameError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            GameError::Io(error) => Some(error),
+            GameError::Parse(error) => Some(error),
+            GameError::AssetMissing(_) | GameError::InvalidLevel(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for GameError {
+    fn from(error: io::Error) -> Self {
+        GameError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for GameError {
+    fn from(error: serde_json::Error) -> Self {
+        GameError::Parse(error)
+    }
+}
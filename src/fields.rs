@@ -0,0 +1,136 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::ldtk::FieldInstance;
+use bevy_ecs_ldtk::prelude::*;
+
+/// Typed lookups into the custom fields of an LDtk level or entity.
+///
+/// Every getter returns `None` when the field is missing or left empty in the editor, and also logs a warning
+/// when the field exists but holds a different type, which is almost always a typo in the project.
+#[derive(Clone, Copy)]
+pub struct Fields<'a>(pub &'a [FieldInstance]);
+
+impl<'a> Fields<'a> {
+    pub fn get_int(&self, identifier: &str) -> Option<i32> {
+        match self.find(identifier)? {
+            FieldValue::Int(value) => *value,
+            other => mismatch(identifier, "an Int", other),
+        }
+    }
+
+    pub fn get_float(&self, identifier: &str) -> Option<f32> {
+        match self.find(identifier)? {
+            FieldValue::Float(value) => *value,
+            other => mismatch(identifier, "a Float", other),
+        }
+    }
+
+    pub fn get_bool(&self, identifier: &str) -> Option<bool> {
+        match self.find(identifier)? {
+            FieldValue::Bool(value) => Some(*value),
+            other => mismatch(identifier, "a Bool", other),
+        }
+    }
+
+    /// String and multiline-text fields.
+    pub fn get_string(&self, identifier: &str) -> Option<&'a str> {
+        match self.find(identifier)? {
+            FieldValue::String(value) => value.as_deref(),
+            other => mismatch(identifier, "a String", other),
+        }
+    }
+
+    /// Point fields, in LDtk grid coordinates.
+    pub fn get_point(&self, identifier: &str) -> Option<IVec2> {
+        match self.find(identifier)? {
+            FieldValue::Point(value) => *value,
+            other => mismatch(identifier, "a Point", other),
+        }
+    }
+
+    pub fn get_color(&self, identifier: &str) -> Option<Color> {
+        match self.find(identifier)? {
+            FieldValue::Color(value) => Some(*value),
+            other => mismatch(identifier, "a Color", other),
+        }
+    }
+
+    fn find(&self, identifier: &str) -> Option<&'a FieldValue> {
+        self.0
+            .iter()
+            .find(|field| field.identifier == identifier)
+            .map(|field| &field.value)
+    }
+}
+
+fn mismatch<T>(identifier: &str, expected: &str, found: &FieldValue) -> Option<T> {
+    warn!("LDtk field \"{identifier}\" should be {expected}, but holds {found:?}");
+    None
+}
+
+/// Anything with LDtk custom fields.
+pub trait LdtkFields {
+    fn fields(&self) -> Fields<'_>;
+}
+
+impl LdtkFields for LdtkLevel {
+    fn fields(&self) -> Fields<'_> {
+        Fields(&self.level.field_instances)
+    }
+}
+
+impl LdtkFields for EntityInstance {
+    fn fields(&self) -> Fields<'_> {
+        Fields(&self.field_instances)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(identifier: &str, field_instance_type: &str, value: FieldValue) -> FieldInstance {
+        FieldInstance {
+            identifier: identifier.to_string(),
+            tile: None,
+            field_instance_type: field_instance_type.to_string(),
+            value,
+            def_uid: 0,
+            real_editor_values: Vec::new(),
+        }
+    }
+
+    fn sample() -> Vec<FieldInstance> {
+        vec![
+            field("damage", "Int", FieldValue::Int(Some(3))),
+            field("speed", "Float", FieldValue::Float(Some(2.5))),
+            field("persists", "Bool", FieldValue::Bool(true)),
+            field("target_level", "String", FieldValue::String(Some("Level_2".to_string()))),
+            field("exit", "Point", FieldValue::Point(Some(IVec2::new(4, 7)))),
+            field("tint", "Color", FieldValue::Color(Color::RED)),
+            field("unset", "Int", FieldValue::Int(None)),
+        ]
+    }
+
+    #[test]
+    fn typed_getters_read_matching_fields() {
+        let instances = sample();
+        let fields = Fields(&instances);
+        assert_eq!(fields.get_int("damage"), Some(3));
+        assert_eq!(fields.get_float("speed"), Some(2.5));
+        assert_eq!(fields.get_bool("persists"), Some(true));
+        assert_eq!(fields.get_string("target_level"), Some("Level_2"));
+        assert_eq!(fields.get_point("exit"), Some(IVec2::new(4, 7)));
+        assert_eq!(fields.get_color("tint"), Some(Color::RED));
+    }
+
+    #[test]
+    fn missing_empty_and_mistyped_fields_are_none() {
+        let instances = sample();
+        let fields = Fields(&instances);
+        assert_eq!(fields.get_int("missing"), None);
+        assert_eq!(fields.get_int("unset"), None);
+        assert_eq!(fields.get_float("damage"), None);
+        assert_eq!(fields.get_string("persists"), None);
+        assert_eq!(fields.get_point("target_level"), None);
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::BTreeSet;
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::level::{LevelCompleted, PlayerKilled};
+use crate::player::PlayerEvent;
+
+/// Persistent achievements and story flags, keyed by name. They are kept in the save file.
+#[derive(Resource, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Flags(BTreeSet<String>);
+
+impl Flags {
+    /// Sets `flag`, returning whether it was newly set.
+    pub fn set_flag(&mut self, flag: impl Into<String>) -> bool {
+        self.0.insert(flag.into())
+    }
+
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.0.contains(flag)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(String::as_str)
+    }
+}
+
+/// What happened to the player since the current level was entered, for flags derived at its exit.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq)]
+pub struct LevelRun {
+    pub damaged: bool,
+    pub deaths: u32,
+    /// Beans picked up.
+    pub beans: u32,
+    /// Seconds of play, not counting time paused.
+    pub time: f32,
+    /// Whether the exit has been reached.
+    pub completed: bool,
+}
+
+/// Set once the level with the given IID has been completed.
+pub fn completed_flag(iid: &str) -> String {
+    format!("completed:{iid}")
+}
+
+/// Set once the level with the given IID has been completed without taking any damage or dying.
+pub fn no_damage_flag(iid: &str) -> String {
+    format!("no_damage:{iid}")
+}
+
+/// The flags earned by completing the level `iid` after `run`.
+pub fn level_exit_flags(iid: &str, run: &LevelRun) -> Vec<String> {
+    let mut flags = vec![completed_flag(iid)];
+    if !run.damaged && run.deaths == 0 {
+        flags.push(no_damage_flag(iid));
+    }
+    flags
+}
+
+pub struct FlagsPlugin;
+
+impl Plugin for FlagsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<Flags>()
+            .init_resource::<LevelRun>()
+            .add_system(start_level_run)
+            .add_system(track_level_run.after(start_level_run))
+            .add_system(flag_level_exit.after(track_level_run));
+    }
+}
+
+fn start_level_run(mut level_events: EventReader<LevelEvent>, mut run: ResMut<LevelRun>) {
+    for event in level_events.iter() {
+        if let LevelEvent::Spawned(_) = event {
+            *run = LevelRun::default();
+        }
+    }
+}
+
+fn track_level_run(
+    mut player_events: EventReader<PlayerEvent>,
+    mut killed: EventReader<PlayerKilled>,
+    mut run: ResMut<LevelRun>,
+) {
+    for event in player_events.iter() {
+        if let PlayerEvent::Damaged { .. } = event {
+            run.damaged = true;
+        }
+    }
+    run.deaths += killed.iter().count() as u32;
+}
+
+fn flag_level_exit(mut completed: EventReader<LevelCompleted>, run: Res<LevelRun>, mut flags: ResMut<Flags>) {
+    for level in completed.iter() {
+        for flag in level_exit_flags(&level.iid, &run) {
+            if flags.set_flag(flag.clone()) {
+                info!("Flag set: {flag}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_damage_needs_a_clean_run() {
+        let clean = LevelRun { completed: true, ..default() };
+        assert_eq!(level_exit_flags("a", &clean), vec![completed_flag("a"), no_damage_flag("a")]);
+
+        let hurt = LevelRun { damaged: true, ..clean };
+        assert_eq!(level_exit_flags("a", &hurt), vec![completed_flag("a")]);
+
+        let died = LevelRun { deaths: 1, ..clean };
+        assert_eq!(level_exit_flags("a", &died), vec![completed_flag("a")]);
+    }
+}
@@ -0,0 +1,238 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use crate::settings::Settings;
+
+/// The shape of a flash's pulses.
+///
+/// * `Wave::Square` switches hard between the sprite's own color and the flash color, starting on the flash.
+///
+/// * `Wave::Sine` fades smoothly in and out, starting from the sprite's own color.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Wave {
+    #[default]
+    Square,
+    Sine,
+}
+
+/// Pulses a sprite towards `color` `frequency` times a second for `duration` seconds, then puts its color back.
+///
+/// The sprite's color from before the flash is always restored exactly, whether the flash runs out, is removed, or
+/// is replaced by another flash part way through.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct Flash {
+    pub color: Color,
+    pub frequency: f32,
+    pub duration: f32,
+    pub wave: Wave,
+    /// Only take `color`'s alpha, leaving the sprite's own color, for blinking.
+    pub alpha_only: bool,
+    elapsed: f32,
+}
+
+impl Flash {
+    /// Pulses the sprite's color towards `color`, e.g. red when hurt or white on a pickup.
+    pub fn tint(color: Color, frequency: f32, duration: f32) -> Self {
+        Flash {
+            color,
+            frequency,
+            duration,
+            wave: Wave::Square,
+            alpha_only: false,
+            elapsed: 0.0,
+        }
+    }
+
+    /// Blinks the sprite down to `alpha`, e.g. while invulnerable.
+    pub fn blink(alpha: f32, frequency: f32, duration: f32) -> Self {
+        Flash {
+            alpha_only: true,
+            ..Flash::tint(Color::rgba(1.0, 1.0, 1.0, alpha), frequency, duration)
+        }
+    }
+
+    pub fn with_wave(self, wave: Wave) -> Self {
+        Flash { wave, ..self }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    /// The sprite's color `elapsed` seconds into this flash, given its own color.
+    pub fn color_at(&self, original: Color, elapsed: f32) -> Color {
+        let amount = flash_amount(elapsed, self.frequency, self.wave);
+        let [r, g, b, a] = original.as_rgba_f32();
+        let [to_r, to_g, to_b, to_a] = self.color.as_rgba_f32();
+        let mix = |from: f32, to: f32| from + (to - from) * amount;
+        if self.alpha_only {
+            Color::rgba(r, g, b, mix(a, to_a))
+        } else {
+            Color::rgba(mix(r, to_r), mix(g, to_g), mix(b, to_b), mix(a, to_a))
+        }
+    }
+}
+
+/// The color a flashing sprite had before it started flashing, kept until it's restored.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct FlashOriginal(pub Color);
+
+/// How far towards the flash color a sprite is `elapsed` seconds into a flash, from 0 (its own color) to 1.
+pub fn flash_amount(elapsed: f32, frequency: f32, wave: Wave) -> f32 {
+    let cycles = elapsed.max(0.0) * frequency;
+    match wave {
+        Wave::Square => {
+            if cycles.fract() < 0.5 { 1.0 } else { 0.0 }
+        }
+        Wave::Sine => 0.5 - 0.5 * (cycles * TAU).cos(),
+    }
+}
+
+pub struct FlashPlugin;
+
+impl Plugin for FlashPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_system_to_stage(CoreStage::PostUpdate, run_flashes)
+            .add_system_to_stage(CoreStage::PostUpdate, restore_interrupted.after(run_flashes));
+    }
+}
+
+/// The color of whichever kind of sprite an entity has.
+fn sprite_color(sprite: &Option<Mut<Sprite>>, atlas_sprite: &Option<Mut<TextureAtlasSprite>>) -> Option<Color> {
+    sprite.as_ref().map(|sprite| sprite.color).or(atlas_sprite.as_ref().map(|sprite| sprite.color))
+}
+
+/// Sets the color of whichever kind of sprite an entity has, if it isn't that already.
+fn set_sprite_color(sprite: &mut Option<Mut<Sprite>>, atlas_sprite: &mut Option<Mut<TextureAtlasSprite>>, color: Color) {
+    match (sprite, atlas_sprite) {
+        (Some(sprite), _) if sprite.color != color => sprite.color = color,
+        (None, Some(sprite)) if sprite.color != color => sprite.color = color,
+        _ => {}
+    }
+}
+
+/*
+ * Runs last so a flash is drawn over whatever else set the color this frame. Flashes run on real time, so they
+ * finish even while gameplay is paused. With flashing turned off in the `JuiceSettings` they still run their
+ * course, but the sprite keeps its own color. Plain and atlas sprites flash alike.
+ */
+fn run_flashes(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<Settings>,
+    mut flashes: Query<(
+        Entity,
+        &mut Flash,
+        Option<&mut Sprite>,
+        Option<&mut TextureAtlasSprite>,
+        Option<&FlashOriginal>,
+    )>,
+) {
+    for (entity, mut flash, mut sprite, mut atlas_sprite, original) in flashes.iter_mut() {
+        let Some(color) = sprite_color(&sprite, &atlas_sprite) else { continue };
+        let original = match original {
+            Some(original) => original.0,
+            None => {
+                commands.entity(entity).insert(FlashOriginal(color));
+                color
+            }
+        };
+
+        flash.elapsed += time.delta_seconds();
+        if flash.is_finished() {
+            set_sprite_color(&mut sprite, &mut atlas_sprite, original);
+            commands.entity(entity).remove::<(Flash, FlashOriginal)>();
+        } else if settings.juice.flash {
+            set_sprite_color(&mut sprite, &mut atlas_sprite, flash.color_at(original, flash.elapsed));
+        } else {
+            set_sprite_color(&mut sprite, &mut atlas_sprite, original);
+        }
+    }
+}
+
+fn restore_interrupted(
+    mut commands: Commands,
+    removed: RemovedComponents<Flash>,
+    mut sprites: Query<(Option<&mut Sprite>, Option<&mut TextureAtlasSprite>, &FlashOriginal), Without<Flash>>,
+) {
+    for entity in removed.iter() {
+        let Ok((mut sprite, mut atlas_sprite, original)) = sprites.get_mut(entity) else { continue };
+        set_sprite_color(&mut sprite, &mut atlas_sprite, original.0);
+        commands.entity(entity).remove::<FlashOriginal>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    #[test]
+    fn waves_pulse_at_the_frequency() {
+        let square: Vec<f32> = [0.0, 0.2, 0.3, 0.5, 0.8].map(|t| flash_amount(t, 2.0, Wave::Square)).into();
+        assert_eq!(square, [1.0, 1.0, 0.0, 1.0, 0.0]);
+        for (elapsed, expected) in [(0.0, 0.0), (0.125, 0.5), (0.25, 1.0), (0.5, 0.0)] {
+            assert!((flash_amount(elapsed, 2.0, Wave::Sine) - expected).abs() < 1e-5);
+        }
+
+        let blink = Flash::blink(0.25, 2.0, 1.0);
+        assert_eq!(blink.color_at(Color::BLUE, 0.0), Color::rgba(0.0, 0.0, 1.0, 0.25), "only the alpha changes");
+        assert_eq!(blink.color_at(Color::BLUE, 0.3), Color::BLUE);
+    }
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.init_resource::<Time>().init_resource::<Settings>().add_plugin(FlashPlugin);
+        app
+    }
+
+    /// Runs a frame `seconds` after the last.
+    fn step(app: &mut App, now: &mut Instant, seconds: f32) {
+        *now += Duration::from_secs_f32(seconds);
+        app.world.resource_mut::<Time>().update_with_instant(*now);
+        app.update();
+    }
+
+    fn color(app: &App, entity: Entity) -> Color {
+        app.world.get::<Sprite>(entity).unwrap().color
+    }
+
+    #[test]
+    fn flashes_then_restores_the_color() {
+        let mut app = app();
+        let mut now = Instant::now();
+        app.world.resource_mut::<Time>().update_with_instant(now);
+        let sprite = Sprite { color: Color::BLUE, ..default() };
+        let entity = app.world.spawn((sprite, Flash::tint(Color::RED, 2.0, 1.0))).id();
+
+        step(&mut app, &mut now, 0.1);
+        assert_eq!(color(&app, entity), Color::RED);
+        step(&mut app, &mut now, 0.25);
+        assert_eq!(color(&app, entity), Color::BLUE);
+        step(&mut app, &mut now, 0.25);
+        assert_eq!(color(&app, entity), Color::RED);
+
+        step(&mut app, &mut now, 1.0);
+        assert_eq!(color(&app, entity), Color::BLUE);
+        assert!(!app.world.entity(entity).contains::<Flash>() && !app.world.entity(entity).contains::<FlashOriginal>());
+    }
+
+    #[test]
+    fn removing_a_flash_restores_the_color() {
+        let mut app = app();
+        let mut now = Instant::now();
+        app.world.resource_mut::<Time>().update_with_instant(now);
+        let sprite = Sprite { color: Color::BLUE, ..default() };
+        let entity = app.world.spawn((sprite, Flash::tint(Color::RED, 2.0, 1.0))).id();
+
+        step(&mut app, &mut now, 0.1);
+        assert_eq!(color(&app, entity), Color::RED);
+        app.world.entity_mut(entity).remove::<Flash>();
+        step(&mut app, &mut now, 0.1);
+        assert_eq!(color(&app, entity), Color::BLUE);
+        assert!(!app.world.entity(entity).contains::<FlashOriginal>());
+    }
+}
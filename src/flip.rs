@@ -0,0 +1,178 @@
+use std::f64::consts::TAU;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use gamelibs::prelude::*;
+
+use crate::physics::{GameplayDelta, PhysicsUnits, GRAVITY};
+use crate::player::{jump_velocity, MoveConfig, Player, PlayerEvent, PlayerSprite};
+
+/// How close (in radians) the spin has to get to a full turn before it counts as finished.
+const FLIP_SETTLE_ANGLE: f64 = 0.01;
+
+/// Tuning for the full turn the player's sprite does on a double jump.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct FlipConfig {
+    pub enabled: bool,
+    /// Damping and initial response of the spin's easing, as in `CurveStyle::Custom`. Its frequency is always
+    /// picked so the turn completes over the airtime of a jump.
+    pub damping: f64,
+    pub response: f64,
+}
+
+impl Default for FlipConfig {
+    fn default() -> Self {
+        FlipConfig {
+            enabled: true,
+            damping: 1.0,
+            response: 0.0,
+        }
+    }
+}
+
+/// A sprite spinning through a flip.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Flip {
+    spin: CurveFollower,
+    /// A full turn, clockwise when negative.
+    target: f64,
+}
+
+impl Flip {
+    pub fn new(config: &FlipConfig, airtime: f32, direction: f32) -> Self {
+        let style = CurveStyle::Custom {
+            f: 1.0 / airtime.max(f32::EPSILON) as f64,
+            z: config.damping,
+            r: config.response,
+        };
+        Flip {
+            spin: CurveFollower::new(style, 0.0),
+            target: TAU * direction.signum() as f64,
+        }
+    }
+
+    /// The current angle of the spin, counter-clockwise in radians.
+    pub fn angle(&self) -> f32 {
+        self.spin.value as f32
+    }
+
+    /// Turns the spin `dt` seconds further, returning whether it has come all the way round.
+    pub fn step(&mut self, dt: f32) -> bool {
+        self.spin.step(self.target, dt as f64);
+        (self.target - self.spin.value).abs() < FLIP_SETTLE_ANGLE
+    }
+}
+
+/// Seconds from a jump of `height` meters until the player falls back to the height they left from.
+pub fn jump_airtime(height: f32, gravity: f32) -> f32 {
+    2.0 * jump_velocity(height, gravity) / gravity
+}
+
+/// Which way a flip turns for a horizontal velocity: forwards, so clockwise when moving right.
+pub fn flip_direction(horizontal_velocity: f32) -> f32 {
+    if horizontal_velocity < 0.0 { 1.0 } else { -1.0 }
+}
+
+pub struct FlipPlugin;
+
+impl Plugin for FlipPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<FlipConfig>()
+            // Spinning first means a landing in the same frame always has the last word on the rotation.
+            .add_system(spin_flips)
+            .add_system(start_flips.after(spin_flips));
+    }
+}
+
+/// Starts a flip on an air jump and straightens the sprite up the moment the player lands, finished or not.
+fn start_flips(
+    mut commands: Commands,
+    mut events: EventReader<PlayerEvent>,
+    config: Res<FlipConfig>,
+    move_config: Res<MoveConfig>,
+    units: Res<PhysicsUnits>,
+    players: Query<(Entity, &Velocity), With<Player>>,
+    children: Query<&Children>,
+    mut sprites: Query<&mut Transform, With<PlayerSprite>>,
+) {
+    for event in events.iter() {
+        for (player, velocity) in players.iter() {
+            for child in children.iter_descendants(player) {
+                let Ok(mut transform) = sprites.get_mut(child) else { continue };
+                match event {
+                    PlayerEvent::AirJumped if config.enabled => {
+                        let airtime = jump_airtime(move_config.jump_height, GRAVITY);
+                        let direction = flip_direction(units.px_to_m(velocity.linvel.x));
+                        commands.entity(child).insert(Flip::new(&config, airtime, direction));
+                    }
+                    PlayerEvent::Landed => {
+                        transform.rotation = Quat::IDENTITY;
+                        commands.entity(child).remove::<Flip>();
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+fn spin_flips(
+    mut commands: Commands,
+    delta: Res<GameplayDelta>,
+    mut flips: Query<(Entity, &mut Transform, &mut Flip)>,
+) {
+    for (entity, mut transform, mut flip) in flips.iter_mut() {
+        if flip.step(delta.0) {
+            transform.rotation = Quat::IDENTITY;
+            commands.entity(entity).remove::<Flip>();
+        } else {
+            transform.rotation = Quat::from_rotation_z(flip.angle());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flip_app() -> (App, Entity) {
+        let mut app = App::new();
+        app
+            .add_event::<PlayerEvent>()
+            .init_resource::<MoveConfig>()
+            .init_resource::<PhysicsUnits>()
+            .insert_resource(GameplayDelta(1.0 / 60.0))
+            .add_plugin(FlipPlugin);
+        let sprite = app.world.spawn((PlayerSprite, Transform::default())).id();
+        app.world
+            .spawn((Player, Velocity::linear(Vec2::new(100.0, 0.0))))
+            .push_children(&[sprite]);
+        (app, sprite)
+    }
+
+    #[test]
+    fn landing_mid_flip_straightens_the_sprite() {
+        let (mut app, sprite) = flip_app();
+        app.world.send_event(PlayerEvent::AirJumped);
+        for _ in 0..10 {
+            app.update();
+        }
+        let rotation = app.world.get::<Transform>(sprite).unwrap().rotation;
+        // Moving right, so the spin is clockwise.
+        assert!(rotation.to_euler(EulerRot::ZYX).0 < 0.0);
+
+        app.world.send_event(PlayerEvent::Landed);
+        app.update();
+        assert_eq!(app.world.get::<Transform>(sprite).unwrap().rotation, Quat::IDENTITY);
+        assert!(app.world.get::<Flip>(sprite).is_none());
+    }
+
+    #[test]
+    fn a_finished_flip_ends_upright() {
+        let mut flip = Flip::new(&FlipConfig::default(), 0.5, 1.0);
+        let finished = (0..600).any(|_| flip.step(1.0 / 60.0));
+        assert!(finished);
+        assert!((flip.angle() - std::f32::consts::TAU).abs() < FLIP_SETTLE_ANGLE as f32);
+    }
+}
@@ -0,0 +1,153 @@
+use bevy::asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::HashMap;
+use serde::Deserialize;
+
+use crate::animation::{animate_sprites, clip_position, AnimationLibrary, SpriteAnimation};
+use crate::audio::Sfx;
+use crate::paths::SURFACE_SOUNDS;
+use crate::physics::GameplayDelta;
+use crate::player::{update_player_state, GroundSurface, Player, PlayerEvent};
+use crate::surface::SurfaceMaterial;
+
+/// The least time between footsteps, in seconds, so a fast stride or a landing straight into a run doesn't clatter.
+const MIN_FOOTSTEP_INTERVAL: f32 = 0.15;
+
+/// The sound effects a surface makes underfoot, by name in the `SfxLibrary`.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct SurfaceSound {
+    pub footstep: String,
+    pub landing: String,
+}
+
+/// One `.sounds.json` file: the sounds of each surface material. Materials left out are silent.
+#[derive(Deserialize, TypeUuid, Clone, Debug, Default)]
+#[uuid = "c41d7e92-6a3f-4b85-9e0d-2f7a8b5c1e64"]
+#[serde(transparent)]
+pub struct SurfaceSounds(pub HashMap<SurfaceMaterial, SurfaceSound>);
+
+#[derive(Resource)]
+struct SurfaceSoundsFile(Handle<SurfaceSounds>);
+
+/// Paces the footsteps of whoever's walking: the position in their animation last frame, and the time since their
+/// feet last made a sound.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct Footsteps {
+    position: Option<usize>,
+    since_last: f32,
+}
+
+impl Footsteps {
+    /// Runs `dt` seconds and returns whether a foot that's `coming_down` this frame should be heard: only once
+    /// `min_interval` has passed since the last.
+    pub fn step(&mut self, dt: f32, coming_down: bool, min_interval: f32) -> bool {
+        self.since_last += dt;
+        if !coming_down || self.since_last < min_interval {
+            return false;
+        }
+        self.since_last = 0.0;
+        true
+    }
+}
+
+pub struct FootstepsPlugin;
+
+impl Plugin for FootstepsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_asset::<SurfaceSounds>()
+            .add_asset_loader(SurfaceSoundsLoader)
+            .add_startup_system(load_surface_sounds)
+            .add_system(play_footsteps.after(animate_sprites).after(update_player_state));
+    }
+}
+
+struct SurfaceSoundsLoader;
+
+impl AssetLoader for SurfaceSoundsLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let sounds: SurfaceSounds = serde_json::from_slice(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(sounds));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["sounds.json"]
+    }
+}
+
+fn load_surface_sounds(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(SurfaceSoundsFile(asset_server.load(SURFACE_SOUNDS)));
+}
+
+/*
+ * A footstep sounds as the running animation reaches one of its clip's `strides`, so the feet are heard as they're
+ * seen to land, in the sound of the surface underfoot. Landing plays the surface's landing sound instead, and
+ * counts as a step so the first stride straight after it doesn't double up.
+ */
+fn play_footsteps(
+    delta: Res<GameplayDelta>,
+    library: Res<AnimationLibrary>,
+    file: Res<SurfaceSoundsFile>,
+    sounds: Res<Assets<SurfaceSounds>>,
+    mut events: EventReader<PlayerEvent>,
+    mut players: Query<(&GlobalTransform, &GroundSurface, &SpriteAnimation, &mut Footsteps), With<Player>>,
+    mut sfx: Sfx,
+) {
+    let landed = events.iter().any(|event| *event == PlayerEvent::Landed);
+    let Ok((transform, ground, animation, mut footsteps)) = players.get_single_mut() else { return };
+    let position = transform.translation().truncate();
+    let sound = ground.0.and_then(|material| sounds.get(&file.0)?.0.get(&material));
+
+    let clip = library.get(&animation.clip);
+    let stride = clip.and_then(|clip| clip_position(clip, animation.elapsed));
+    let coming_down = stride != footsteps.position
+        && stride.is_some_and(|stride| clip.is_some_and(|clip| clip.strides.contains(&stride)));
+    footsteps.position = stride;
+
+    if landed {
+        footsteps.since_last = 0.0;
+        if let Some(sound) = sound {
+            sfx.play_sfx_at(&sound.landing, position);
+        }
+    } else if footsteps.step(delta.0, coming_down && ground.0.is_some(), MIN_FOOTSTEP_INTERVAL) {
+        if let Some(sound) = sound {
+            sfx.play_sfx_at(&sound.footstep, position);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn footsteps_are_throttled() {
+        let mut footsteps = Footsteps::default();
+        // Frames of 0.05 s, with a foot coming down on the listed ones.
+        let coming_down = [false, false, false, true, true, true, false, true, false, true];
+        let heard: Vec<usize> = coming_down
+            .iter()
+            .enumerate()
+            .filter(|&(_, &down)| footsteps.step(0.05, down, 0.15))
+            .map(|(frame, _)| frame)
+            .collect();
+        // The feet right after the first and after the fourth land too soon to be heard.
+        assert_eq!(heard, [3, 7]);
+    }
+
+    #[test]
+    fn time_passes_between_steps() {
+        let mut footsteps = Footsteps::default();
+        assert!(!footsteps.step(0.1, false, 0.15));
+        assert!(footsteps.step(0.1, true, 0.15), "time without a step counts towards the interval");
+        assert!(!footsteps.step(0.1, true, 0.15));
+    }
+}
@@ -0,0 +1,138 @@
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::entity_registry::RegisterLevelEntity;
+use crate::fields::LdtkFields;
+use crate::physics::PhysicsUnits;
+
+/// The LDtk entity identifier of a force field. Its fields:
+///
+/// * `Strength`: acceleration in m/s². Negative radial fields pull towards the centre.
+/// * `Angle`: the direction a directional field pushes, in degrees anticlockwise from the right; 90 is an updraft.
+/// * `Radial`: push away from the centre instead of along `Angle`.
+pub const FORCE_FIELD_ENTITY: &str = "ForceField";
+const STRENGTH_FIELD: &str = "Strength";
+const ANGLE_FIELD: &str = "Angle";
+const RADIAL_FIELD: &str = "Radial";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FieldKind {
+    /// Pushes everything the same way, along a unit vector.
+    Directional(Vec2),
+    /// Pushes away from the field's centre, or pulls towards it with a negative strength.
+    Radial,
+}
+
+/// A zone accelerating the dynamic bodies that overlap its sensor collider, such as wind or an updraft.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct ForceField {
+    pub kind: FieldKind,
+    /// Acceleration in m/s².
+    pub strength: f32,
+}
+
+pub struct ForceFieldPlugin;
+
+impl Plugin for ForceFieldPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .register_level_entity(FORCE_FIELD_ENTITY, spawn_force_field)
+            .add_system(track_field_bodies)
+            .add_system(apply_force_fields.after(track_field_bodies));
+    }
+}
+
+/// The acceleration `kind` gives a body at `position` in a field centred on `center`. A body exactly on the centre
+/// of a radial field has no direction to go, so it's left alone.
+pub fn field_acceleration(kind: FieldKind, strength: f32, center: Vec2, position: Vec2) -> Vec2 {
+    let direction = match kind {
+        FieldKind::Directional(direction) => direction,
+        FieldKind::Radial => (position - center).normalize_or_zero(),
+    };
+    direction * strength
+}
+
+fn spawn_force_field(field: &mut EntityCommands, instance: &EntityInstance, _: Vec2) {
+    let fields = instance.fields();
+    let kind = if fields.get_bool(RADIAL_FIELD).unwrap_or(false) {
+        FieldKind::Radial
+    } else {
+        FieldKind::Directional(Vec2::from_angle(fields.get_float(ANGLE_FIELD).unwrap_or(0.0).to_radians()))
+    };
+    let strength = fields.get_float(STRENGTH_FIELD).unwrap_or(0.0);
+    let half_extents = Vec2::new(instance.width as f32, instance.height as f32) / 2.0;
+
+    field.insert((
+        ForceField { kind, strength },
+        Collider::cuboid(half_extents.x, half_extents.y),
+        Sensor,
+    ));
+}
+
+/// Dynamic bodies need their mass read back to turn a field's acceleration into a force.
+fn track_field_bodies(
+    mut commands: Commands,
+    bodies: Query<(Entity, &RigidBody), (With<Collider>, Without<ExternalForce>)>,
+) {
+    for (entity, body) in bodies.iter() {
+        if *body == RigidBody::Dynamic {
+            commands.entity(entity).insert((ExternalForce::default(), ReadMassProperties::default()));
+        }
+    }
+}
+
+/*
+ * Rapier applies an `ExternalForce` on every physics step until it is changed, so setting it once a frame keeps the
+ * push steady however many fixed steps that frame runs. Every body's force is rebuilt from scratch, so one that
+ * leaves all fields stops being pushed straight away. Overlapping fields add up.
+ */
+fn apply_force_fields(
+    rapier_context: Res<RapierContext>,
+    units: Res<PhysicsUnits>,
+    fields: Query<(Entity, &ForceField, &GlobalTransform)>,
+    mut bodies: Query<(&mut ExternalForce, &ReadMassProperties, &GlobalTransform)>,
+) {
+    for (mut force, _, _) in bodies.iter_mut() {
+        if force.force != Vec2::ZERO {
+            force.force = Vec2::ZERO;
+        }
+    }
+
+    for (field_entity, field, field_transform) in fields.iter() {
+        let center = field_transform.translation().truncate();
+        for (collider_a, collider_b, intersecting) in rapier_context.intersections_with(field_entity) {
+            if !intersecting {
+                continue;
+            }
+            let other = if collider_a == field_entity { collider_b } else { collider_a };
+            let Ok((mut force, mass, transform)) = bodies.get_mut(other) else { continue };
+
+            let acceleration = field_acceleration(field.kind, field.strength, center, transform.translation().truncate());
+            force.force += units.m_to_px(1.0) * acceleration * mass.0.mass;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn directional_fields_push_the_same_way_everywhere() {
+        let updraft = FieldKind::Directional(Vec2::Y);
+        for position in [Vec2::ZERO, Vec2::new(30.0, -12.0)] {
+            assert_eq!(field_acceleration(updraft, 4.0, Vec2::new(5.0, 5.0), position), Vec2::new(0.0, 4.0));
+        }
+    }
+
+    #[test]
+    fn radial_fields_push_away_from_the_centre() {
+        let center = Vec2::new(10.0, 10.0);
+        assert_eq!(field_acceleration(FieldKind::Radial, 3.0, center, Vec2::new(10.0, 50.0)), Vec2::new(0.0, 3.0));
+        let pull = field_acceleration(FieldKind::Radial, -2.0, center, Vec2::new(13.0, 14.0));
+        assert!(pull.abs_diff_eq(Vec2::new(-1.2, -1.6), 1e-6));
+        assert_eq!(field_acceleration(FieldKind::Radial, 3.0, center, center), Vec2::ZERO);
+    }
+}
@@ -0,0 +1,93 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::physics::PhysicsUnits;
+use crate::player::{move_player, update_player_state, Player, PlayerState, PlayerStateMachine};
+use crate::state::GameState;
+
+/// Tuning for the floaty moment at the top of a jump.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct HangTimeConfig {
+    /// An airborne player rising or falling slower than this, in m/s, is at the apex.
+    pub apex_speed: f32,
+    /// Scales gravity at the apex; 1 turns hang time off.
+    pub gravity_scale: f32,
+    /// Scales `MoveConfig::air_control` at the apex, for steering the landing.
+    pub control_scale: f32,
+}
+
+impl Default for HangTimeConfig {
+    fn default() -> Self {
+        HangTimeConfig {
+            apex_speed: 1.0,
+            gravity_scale: 0.5,
+            control_scale: 1.5,
+        }
+    }
+}
+
+/// Whether the player is hanging at the top of a jump this frame.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HangTime(pub bool);
+
+/// Returns whether a player moving at `vertical_velocity` m/s is at the apex of a jump: airborne and barely moving
+/// up or down.
+pub fn is_at_apex(airborne: bool, vertical_velocity: f32, apex_speed: f32) -> bool {
+    airborne && vertical_velocity.abs() < apex_speed
+}
+
+/// The player's gravity scale, reduced while they hang at the apex.
+pub fn apex_gravity_scale(at_apex: bool, config: &HangTimeConfig) -> f32 {
+    if at_apex { config.gravity_scale } else { 1.0 }
+}
+
+pub struct HangTimePlugin;
+
+impl Plugin for HangTimePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<HangTimeConfig>()
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_system(hang_at_apex.after(update_player_state).before(move_player)),
+            );
+    }
+}
+
+fn hang_at_apex(
+    config: Res<HangTimeConfig>,
+    units: Res<PhysicsUnits>,
+    mut players: Query<(&Velocity, &PlayerStateMachine, &mut HangTime, &mut GravityScale), With<Player>>,
+) {
+    for (velocity, state, mut hang_time, mut gravity_scale) in players.iter_mut() {
+        let at_apex = is_at_apex(state.is(PlayerState::Airborne), units.px_to_m(velocity.linvel.y), config.apex_speed);
+        if hang_time.0 != at_apex {
+            hang_time.0 = at_apex;
+        }
+        let scale = apex_gravity_scale(at_apex, &config);
+        if gravity_scale.0 != scale {
+            gravity_scale.0 = scale;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apex_is_airborne_and_nearly_still() {
+        assert!(is_at_apex(true, 0.5, 1.0));
+        assert!(is_at_apex(true, -0.5, 1.0));
+        assert!(!is_at_apex(true, 1.0, 1.0));
+        assert!(!is_at_apex(true, -3.0, 1.0));
+        assert!(!is_at_apex(false, 0.0, 1.0), "standing still on the ground isn't an apex");
+    }
+
+    #[test]
+    fn gravity_is_reduced_only_at_the_apex() {
+        let config = HangTimeConfig::default();
+        assert_eq!(apex_gravity_scale(true, &config), config.gravity_scale);
+        assert_eq!(apex_gravity_scale(false, &config), 1.0);
+    }
+}
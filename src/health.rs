@@ -0,0 +1,149 @@
+use bevy::prelude::*;
+
+use crate::damage_number::DamageNumbers;
+use crate::flash::Flash;
+use crate::level::PlayerKilled;
+use crate::physics::{GameplayDelta, Hitstop};
+use crate::player::{Player, PlayerEvent};
+use crate::settings::Settings;
+
+const PLAYER_DAMAGE_COLOR: Color = Color::rgb(1.0, 0.3, 0.25);
+/// How long, and how many times a second, a hit target's sprites flash white.
+const HIT_FLASH_TIME: f32 = 0.3;
+const HIT_FLASH_FREQUENCY: f32 = 10.0;
+/// Seconds gameplay freezes for when a hit lands, if the player has hitstop on.
+const HITSTOP_TIME: f32 = 0.08;
+
+/// Hit points of the player or an enemy.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
+}
+
+impl Health {
+    pub fn new(max: f32) -> Self {
+        Health { current: max, max }
+    }
+
+    /// Remaining health as a fraction of `max`, in `[0, 1]`.
+    pub fn fraction(&self) -> f32 {
+        if self.max <= 0.0 {
+            return 0.0;
+        }
+        (self.current / self.max).clamp(0.0, 1.0)
+    }
+}
+
+/// Ignores all damage for `remaining` seconds of gameplay time, then removes itself.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct Invulnerable {
+    pub remaining: f32,
+}
+
+/// Send this to hurt `target`. Damage to the player is reported as `PlayerEvent::Damaged`, and kills them once
+/// their health runs out. Every hit shows a damage number, flashes the target's sprites and starts a `Hitstop`.
+/// `Invulnerable` targets ignore it.
+pub struct Damage {
+    pub target: Entity,
+    pub amount: f32,
+}
+
+pub struct HealthPlugin;
+
+impl Plugin for HealthPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<Damage>()
+            .add_system(apply_damage)
+            .add_system(wear_off_invulnerability.after(apply_damage));
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn apply_damage(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    mut hitstop: ResMut<Hitstop>,
+    mut damages: EventReader<Damage>,
+    mut targets: Query<(&mut Health, &GlobalTransform, Option<&Player>), Without<Invulnerable>>,
+    mut player_events: EventWriter<PlayerEvent>,
+    mut killed: EventWriter<PlayerKilled>,
+    mut numbers: DamageNumbers,
+    children: Query<&Children>,
+    sprites: Query<(), With<Sprite>>,
+) {
+    for damage in damages.iter() {
+        let Ok((mut health, transform, player)) = targets.get_mut(damage.target) else { continue };
+        if health.current <= 0.0 {
+            continue;
+        }
+
+        health.current = (health.current - damage.amount).max(0.0);
+        let color = if player.is_some() { PLAYER_DAMAGE_COLOR } else { Color::WHITE };
+        numbers.spawn_damage_number(transform.translation().truncate(), damage.amount, color);
+        if settings.juice.hitstop {
+            hitstop.hit(HITSTOP_TIME);
+        }
+        for entity in std::iter::once(damage.target).chain(children.iter_descendants(damage.target)) {
+            if sprites.contains(entity) {
+                commands.entity(entity).insert(Flash::tint(Color::WHITE, HIT_FLASH_FREQUENCY, HIT_FLASH_TIME));
+            }
+        }
+        if player.is_some() {
+            player_events.send(PlayerEvent::Damaged { amount: damage.amount });
+            if health.current <= 0.0 {
+                killed.send(PlayerKilled);
+            }
+        }
+    }
+}
+
+fn wear_off_invulnerability(
+    mut commands: Commands,
+    delta: Res<GameplayDelta>,
+    mut targets: Query<(Entity, &mut Invulnerable)>,
+) {
+    for (entity, mut invulnerable) in targets.iter_mut() {
+        invulnerable.remaining -= delta.0;
+        if invulnerable.remaining <= 0.0 {
+            commands.entity(entity).remove::<Invulnerable>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::damage_number::DamageNumber;
+    use crate::pool::{EntityPool, SpawnBudget};
+
+    /// The hitstop a 1-point hit starts with hitstop set to `enabled`.
+    fn hitstop_after_a_hit(enabled: bool) -> Hitstop {
+        let mut settings = Settings::default();
+        settings.juice.hitstop = enabled;
+        let mut app = App::new();
+        app
+            .insert_resource(settings)
+            .init_resource::<Time>()
+            .init_resource::<Hitstop>()
+            .init_resource::<EntityPool<DamageNumber>>()
+            .init_resource::<SpawnBudget>()
+            .add_event::<Damage>()
+            .add_event::<PlayerEvent>()
+            .add_event::<PlayerKilled>()
+            .add_system(apply_damage);
+        let target = app.world.spawn((Health::new(3.0), GlobalTransform::default())).id();
+        app.world.send_event(Damage { target, amount: 1.0 });
+        app.update();
+
+        assert_eq!(app.world.get::<Health>(target).unwrap().current, 2.0);
+        *app.world.resource::<Hitstop>()
+    }
+
+    #[test]
+    fn hits_only_stop_time_with_hitstop_on() {
+        assert_eq!(hitstop_after_a_hit(true).remaining, HITSTOP_TIME);
+        assert!(!hitstop_after_a_hit(false).is_active());
+    }
+}
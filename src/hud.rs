@@ -0,0 +1,77 @@
+use bevy::prelude::*;
+use gamelibs::color::ColorRamp;
+
+use crate::health::Health;
+use crate::player::Player;
+use crate::state::{GameState, GameplayEntity};
+
+/// Health bar size and distance from the top-left corner of the screen, in logical pixels.
+const HEALTH_BAR_SIZE: Vec2 = Vec2::new(200.0, 16.0);
+const HEALTH_BAR_MARGIN: f32 = 16.0;
+
+/// The inner part of the health bar that shrinks and changes colour with the player's health.
+#[derive(Component)]
+pub struct HealthBarFill;
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_system_set(SystemSet::on_enter(GameState::InGame).with_system(spawn_health_bar))
+            .add_system(update_health_bar);
+    }
+}
+
+/// Red when nearly dead, through yellow, to green at full health.
+pub fn health_ramp() -> ColorRamp {
+    ColorRamp::new(vec![
+        (0.0, [0.85, 0.1, 0.1, 1.0]),
+        (0.5, [0.95, 0.8, 0.1, 1.0]),
+        (1.0, [0.2, 0.8, 0.2, 1.0]),
+    ])
+}
+
+fn spawn_health_bar(mut commands: Commands) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Px(HEALTH_BAR_SIZE.x), Val::Px(HEALTH_BAR_SIZE.y)),
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Px(HEALTH_BAR_MARGIN),
+                    top: Val::Px(HEALTH_BAR_MARGIN),
+                    ..default()
+                },
+                ..default()
+            },
+            background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+            ..default()
+        })
+        .insert(GameplayEntity)
+        .with_children(|bar| {
+            bar.spawn((
+                NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                        ..default()
+                    },
+                    ..default()
+                },
+                HealthBarFill,
+            ));
+        });
+}
+
+fn update_health_bar(
+    players: Query<&Health, (With<Player>, Changed<Health>)>,
+    mut fills: Query<(&mut Style, &mut BackgroundColor), With<HealthBarFill>>,
+) {
+    let Ok(health) = players.get_single() else { return };
+    let fraction = health.fraction();
+    let [r, g, b, a] = health_ramp().sample(fraction as f64).map(|channel| channel as f32);
+    for (mut style, mut color) in fills.iter_mut() {
+        style.size.width = Val::Percent(fraction * 100.0);
+        color.0 = Color::rgba(r, g, b, a);
+    }
+}
@@ -0,0 +1,435 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use gamelibs::math::{ease, Ease};
+
+use crate::camera::{cursor_to_world, GameCamera};
+use crate::physics::GameplayDelta;
+use crate::player::Player;
+use crate::settings::{Binding, Settings};
+
+/// Aim offsets shorter than this (in pixels) are too close to the player to give a direction.
+const MIN_AIM_DISTANCE: f32 = 1.0;
+/// Right-stick deflection below this is ignored so a resting stick doesn't override the mouse.
+const AIM_STICK_DEADZONE: f32 = 0.25;
+
+/// Everything gameplay reads from the player's controls this frame.
+///
+/// This is the single place input enters the game; keyboard and gamepad are folded into it in
+/// `PreUpdate`, and tests can script it directly instead.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq)]
+pub struct InputState {
+    /// Desired movement, each axis in `[-1, 1]`.
+    pub move_axis: Vec2,
+    pub jump: bool,
+    /// Held to charge a super jump, which is released by letting go.
+    pub charge: bool,
+    /// Use the door, lever or character being prompted.
+    pub interact: bool,
+    /// Shoot towards the `AimDirection`.
+    pub fire: bool,
+}
+
+/// Menu navigation pressed this frame. Unlike `InputState` these are press edges, not held buttons.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MenuInput {
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+    pub confirm: bool,
+    pub back: bool,
+    /// Open the pause menu during play.
+    pub pause: bool,
+}
+
+/// Press and release edges of one button, worked out from its held state fed in once a frame. A button that stays
+/// held only counts as pressed on the frame it went down.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EdgeDetector {
+    previous: bool,
+    current: bool,
+}
+
+impl EdgeDetector {
+    /// Moves on to the next frame, in which the button is `held` or not.
+    pub fn update(&mut self, held: bool) {
+        self.previous = self.current;
+        self.current = held;
+    }
+
+    pub fn just_pressed(&self) -> bool {
+        self.current && !self.previous
+    }
+
+    pub fn just_released(&self) -> bool {
+        !self.current && self.previous
+    }
+
+    pub fn held(&self) -> bool {
+        self.current
+    }
+}
+
+/// Ignores the player's controls for a while, e.g. to let them get their bearings after respawning. `InputState`
+/// stays at rest until it runs out; menus still work.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq)]
+pub struct ControlLock {
+    /// Seconds of gameplay time until the controls work again.
+    pub remaining: f32,
+}
+
+impl ControlLock {
+    /// Locks the controls for at least `seconds` from now.
+    pub fn lock(&mut self, seconds: f32) {
+        self.remaining = self.remaining.max(seconds);
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.remaining > 0.0
+    }
+}
+
+/// Discrete actions that can be buffered for a few frames.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    Jump,
+}
+
+/// Remembers recent presses so an action pressed slightly too early still happens.
+///
+/// Presses are timestamped with the elapsed game time rather than counted in frames, so the
+/// window means the same thing at any frame rate.
+#[derive(Resource, Clone, Debug)]
+pub struct InputBuffer<A: Copy + Eq + Send + Sync + 'static> {
+    pub window: Duration,
+    presses: Vec<(A, Duration)>,
+}
+
+impl<A: Copy + Eq + Send + Sync + 'static> InputBuffer<A> {
+    pub fn new(window: Duration) -> Self {
+        InputBuffer { window, presses: Vec::new() }
+    }
+
+    pub fn press(&mut self, action: A, now: Duration) {
+        self.expire(now);
+        self.presses.push((action, now));
+    }
+
+    /// Returns whether `action` was pressed within the window, removing that press if so.
+    pub fn consume(&mut self, action: A, now: Duration) -> bool {
+        self.expire(now);
+        match self.presses.iter().position(|(pressed, _)| *pressed == action) {
+            Some(index) => {
+                self.presses.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.presses.clear();
+    }
+
+    fn expire(&mut self, now: Duration) {
+        let window = self.window;
+        self.presses.retain(|(_, pressed_at)| now.saturating_sub(*pressed_at) <= window);
+    }
+}
+
+/// The normalized direction the player is aiming projectiles and the grapple in.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct AimDirection(pub Vec2);
+
+impl Default for AimDirection {
+    fn default() -> Self {
+        AimDirection(Vec2::X)
+    }
+}
+
+pub struct InputPlugin;
+
+impl Plugin for InputPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<InputState>()
+            .init_resource::<AimDirection>()
+            .init_resource::<MenuInput>()
+            .init_resource::<ControlLock>()
+            .add_system_to_stage(CoreStage::PreUpdate, read_input)
+            .add_system_to_stage(CoreStage::PreUpdate, read_menu_input)
+            .add_system(update_aim)
+            .add_system(release_controls);
+    }
+}
+
+/// Returns the normalized direction from `origin` to `target`, keeping `last` when the two
+/// are too close together to define one (e.g. the cursor is over the player).
+pub fn aim_direction(origin: Vec2, target: Vec2, last: Vec2) -> Vec2 {
+    let offset = target - origin;
+    if offset.length_squared() < MIN_AIM_DISTANCE * MIN_AIM_DISTANCE {
+        return last;
+    }
+    offset.normalize()
+}
+
+/// Shapes a raw analog stick reading into movement input.
+///
+/// The deadzone is radial, so a stick resting slightly off-centre is ignored whichever way it leans. Past it, the
+/// deflection is rescaled to start from 0 and run through `curve`, keeping the stick's direction. The result's
+/// length is at most 1.
+pub fn process_stick(raw: Vec2, deadzone: f32, curve: Ease) -> Vec2 {
+    let length = raw.length();
+    if length == 0.0 || length.min(1.0) <= deadzone {
+        return Vec2::ZERO;
+    }
+    let deflection = (length.min(1.0) - deadzone) / (1.0 - deadzone);
+    raw / length * ease(curve, deflection as f64) as f32
+}
+
+pub fn read_input(
+    settings: Res<Settings>,
+    keys: Res<Input<KeyCode>>,
+    mouse: Res<Input<MouseButton>>,
+    gamepads: Res<Gamepads>,
+    axes: Res<Axis<GamepadAxis>>,
+    buttons: Res<Input<GamepadButton>>,
+    lock: Res<ControlLock>,
+    mut input: ResMut<InputState>,
+) {
+    if lock.is_locked() {
+        if *input != InputState::default() {
+            *input = InputState::default();
+        }
+        return;
+    }
+
+    let bind = |binding: Binding| settings.key_bindings.key(binding);
+    let key_axis = |negative: [KeyCode; 2], positive: [KeyCode; 2]| {
+        let mut axis = 0.0;
+        if keys.any_pressed(negative) {
+            axis -= 1.0;
+        }
+        if keys.any_pressed(positive) {
+            axis += 1.0;
+        }
+        axis
+    };
+    let mut move_axis = Vec2::new(
+        key_axis([bind(Binding::Left), KeyCode::Left], [bind(Binding::Right), KeyCode::Right]),
+        key_axis([bind(Binding::Down), KeyCode::Down], [bind(Binding::Up), KeyCode::Up]),
+    );
+    let mut jump = keys.pressed(bind(Binding::Jump));
+    let mut charge = keys.pressed(bind(Binding::Charge));
+    let mut interact = keys.pressed(bind(Binding::Interact));
+    let mut fire = keys.pressed(bind(Binding::Fire)) || mouse.pressed(MouseButton::Left);
+
+    for gamepad in gamepads.iter() {
+        let x = axes.get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX)).unwrap_or(0.0);
+        let y = axes.get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickY)).unwrap_or(0.0);
+        let stick = process_stick(Vec2::new(x, y), settings.stick_deadzone, settings.stick_curve);
+        if stick != Vec2::ZERO && stick.length() > move_axis.length() {
+            move_axis = stick;
+        }
+        jump |= buttons.pressed(GamepadButton::new(gamepad, GamepadButtonType::South));
+        charge |= buttons.pressed(GamepadButton::new(gamepad, GamepadButtonType::West));
+        interact |= buttons.pressed(GamepadButton::new(gamepad, GamepadButtonType::North));
+        fire |= buttons.pressed(GamepadButton::new(gamepad, GamepadButtonType::RightTrigger2));
+    }
+
+    *input = InputState {
+        move_axis: move_axis.clamp(Vec2::NEG_ONE, Vec2::ONE),
+        jump,
+        charge,
+        interact,
+        fire,
+    };
+}
+
+fn release_controls(delta: Res<GameplayDelta>, mut lock: ResMut<ControlLock>) {
+    if lock.is_locked() {
+        lock.remaining = (lock.remaining - delta.0).max(0.0);
+    }
+}
+
+pub fn read_menu_input(
+    keys: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    buttons: Res<Input<GamepadButton>>,
+    mut input: ResMut<MenuInput>,
+) {
+    let pad = |button: GamepadButtonType| {
+        gamepads
+            .iter()
+            .any(|gamepad| buttons.just_pressed(GamepadButton::new(gamepad, button)))
+    };
+
+    *input = MenuInput {
+        up: keys.any_just_pressed([KeyCode::Up, KeyCode::W]) || pad(GamepadButtonType::DPadUp),
+        down: keys.any_just_pressed([KeyCode::Down, KeyCode::S]) || pad(GamepadButtonType::DPadDown),
+        left: keys.any_just_pressed([KeyCode::Left, KeyCode::A]) || pad(GamepadButtonType::DPadLeft),
+        right: keys.any_just_pressed([KeyCode::Right, KeyCode::D]) || pad(GamepadButtonType::DPadRight),
+        confirm: keys.any_just_pressed([KeyCode::Return, KeyCode::Space]) || pad(GamepadButtonType::South),
+        back: keys.just_pressed(KeyCode::Escape) || pad(GamepadButtonType::East) || pad(GamepadButtonType::Start),
+        pause: keys.just_pressed(KeyCode::Escape) || pad(GamepadButtonType::Start),
+    };
+}
+
+/// Records the press edges of buffered actions from the `InputState`.
+pub fn buffer_actions(
+    time: Res<Time>,
+    input: Res<InputState>,
+    mut jump: Local<EdgeDetector>,
+    mut buffer: ResMut<InputBuffer<Action>>,
+) {
+    jump.update(input.jump);
+    if jump.just_pressed() {
+        buffer.press(Action::Jump, time.elapsed());
+    }
+}
+
+/*
+ * A deflected right stick takes priority; otherwise the player aims at the mouse cursor.
+ */
+fn update_aim(
+    windows: Res<Windows>,
+    cameras: Query<(&Camera, &GlobalTransform), With<GameCamera>>,
+    players: Query<&GlobalTransform, With<Player>>,
+    gamepads: Res<Gamepads>,
+    axes: Res<Axis<GamepadAxis>>,
+    mut aim: ResMut<AimDirection>,
+) {
+    for gamepad in gamepads.iter() {
+        let x = axes.get(GamepadAxis::new(gamepad, GamepadAxisType::RightStickX)).unwrap_or(0.0);
+        let y = axes.get(GamepadAxis::new(gamepad, GamepadAxisType::RightStickY)).unwrap_or(0.0);
+        let stick = Vec2::new(x, y);
+        if stick.length() > AIM_STICK_DEADZONE {
+            aim.0 = stick.normalize();
+            return;
+        }
+    }
+
+    let Ok(player) = players.get_single() else { return };
+    let Ok((camera, camera_transform)) = cameras.get_single() else { return };
+    let Some(window) = windows.get_primary() else { return };
+    let Some(cursor) = cursor_to_world(window, camera, camera_transform) else { return };
+
+    aim.0 = aim_direction(player.translation().truncate(), cursor, aim.0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aim_points_from_player_to_target() {
+        let origin = Vec2::new(100.0, 50.0);
+        assert_eq!(aim_direction(origin, Vec2::new(300.0, 50.0), Vec2::X), Vec2::X);
+        assert_eq!(aim_direction(origin, Vec2::new(100.0, -10.0), Vec2::X), Vec2::NEG_Y);
+        let diagonal = aim_direction(origin, Vec2::new(130.0, 90.0), Vec2::X);
+        assert!(diagonal.abs_diff_eq(Vec2::new(0.6, 0.8), 1e-6));
+        assert!((diagonal.length() - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn cursor_on_player_keeps_last_aim() {
+        let origin = Vec2::new(100.0, 50.0);
+        assert_eq!(aim_direction(origin, origin, Vec2::NEG_X), Vec2::NEG_X);
+        assert_eq!(aim_direction(origin, origin + Vec2::splat(0.5), Vec2::Y), Vec2::Y);
+    }
+
+    #[test]
+    fn consume_inside_the_window() {
+        let mut buffer = InputBuffer::new(Duration::from_millis(120));
+        buffer.press(Action::Jump, Duration::from_millis(1000));
+        assert!(buffer.consume(Action::Jump, Duration::from_millis(1100)));
+    }
+
+    #[test]
+    fn consume_outside_the_window() {
+        let mut buffer = InputBuffer::new(Duration::from_millis(120));
+        buffer.press(Action::Jump, Duration::from_millis(1000));
+        assert!(!buffer.consume(Action::Jump, Duration::from_millis(1121)));
+    }
+
+    #[test]
+    fn consuming_removes_the_press() {
+        let mut buffer = InputBuffer::new(Duration::from_millis(120));
+        buffer.press(Action::Jump, Duration::from_millis(1000));
+        buffer.press(Action::Jump, Duration::from_millis(1010));
+        assert!(buffer.consume(Action::Jump, Duration::from_millis(1020)));
+        assert!(buffer.consume(Action::Jump, Duration::from_millis(1020)));
+        assert!(!buffer.consume(Action::Jump, Duration::from_millis(1020)));
+    }
+
+    #[test]
+    fn stick_inside_the_deadzone_is_ignored() {
+        assert_eq!(process_stick(Vec2::ZERO, 0.2, Ease::Linear), Vec2::ZERO);
+        assert_eq!(process_stick(Vec2::new(0.1, -0.15), 0.2, Ease::Linear), Vec2::ZERO);
+        assert_eq!(process_stick(Vec2::new(0.2, 0.0), 0.2, Ease::Linear), Vec2::ZERO);
+    }
+
+    #[test]
+    fn stick_past_the_deadzone_is_rescaled_along_the_curve() {
+        // Halfway between the deadzone and the rim, keeping the stick's direction.
+        let half = process_stick(Vec2::new(0.0, -0.6), 0.2, Ease::Linear);
+        assert!(half.abs_diff_eq(Vec2::new(0.0, -0.5), 1e-6));
+        let curved = process_stick(Vec2::new(0.0, -0.6), 0.2, Ease::QuadIn);
+        assert!(curved.abs_diff_eq(Vec2::new(0.0, -0.25), 1e-6));
+
+        // Full deflection, or past it on a square gate, is length 1.
+        assert!(process_stick(Vec2::X, 0.2, Ease::QuadIn).abs_diff_eq(Vec2::X, 1e-6));
+        assert!((process_stick(Vec2::ONE, 0.2, Ease::Linear).length() - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn edges_through_press_hold_release() {
+        let mut jump = EdgeDetector::default();
+        let frames = [false, true, true, true, false, false, true];
+        let edges: Vec<_> = frames
+            .iter()
+            .map(|&held| {
+                jump.update(held);
+                (jump.just_pressed(), jump.held(), jump.just_released())
+            })
+            .collect();
+        let expected = [
+            (false, false, false),
+            (true, true, false),
+            (false, true, false),
+            (false, true, false),
+            (false, false, true),
+            (false, false, false),
+            (true, true, false),
+        ];
+        assert_eq!(edges, expected);
+    }
+
+    #[test]
+    fn input_is_ignored_while_locked() {
+        let mut app = App::new();
+        app
+            .init_resource::<Settings>()
+            .init_resource::<Input<KeyCode>>()
+            .init_resource::<Input<MouseButton>>()
+            .init_resource::<Gamepads>()
+            .init_resource::<Axis<GamepadAxis>>()
+            .init_resource::<Input<GamepadButton>>()
+            .init_resource::<InputState>()
+            .init_resource::<ControlLock>()
+            .insert_resource(GameplayDelta(0.125))
+            .add_system_to_stage(CoreStage::PreUpdate, read_input)
+            .add_system(release_controls);
+        app.world.resource_mut::<Input<KeyCode>>().press(KeyCode::Right);
+        app.world.resource_mut::<ControlLock>().lock(0.25);
+
+        let mut moves = Vec::new();
+        for _ in 0..4 {
+            app.update();
+            moves.push(app.world.resource::<InputState>().move_axis.x);
+        }
+        assert_eq!(moves, [0.0, 0.0, 1.0, 1.0]);
+        assert!(!app.world.resource::<ControlLock>().is_locked());
+    }
+}
@@ -0,0 +1,183 @@
+use bevy::prelude::*;
+
+use crate::input::{EdgeDetector, InputState};
+use crate::menu::UiAssets;
+use crate::player::Player;
+use crate::state::{GameState, GameplayEntity};
+
+/// How far above an interactable its prompt floats, in pixels.
+const PROMPT_OFFSET: Vec2 = Vec2::new(0.0, 32.0);
+const PROMPT_FONT_SIZE: f32 = 16.0;
+/// Prompts draw over the level and its sprites.
+const PROMPT_Z: f32 = 50.0;
+
+/// Something the player can use (a door, a lever, a chest, someone to talk to) while within `range` pixels of it.
+/// `prompt` is shown above it, e.g. "Press E to open".
+#[derive(Component, Clone, Debug)]
+pub struct Interactable {
+    pub prompt: String,
+    pub range: f32,
+}
+
+/// Sent when the player interacts with `target`, the `Interactable` they were being prompted for.
+#[derive(Clone, Copy, Debug)]
+pub struct InteractEvent {
+    pub target: Entity,
+}
+
+/// The interactable currently prompted, if any.
+#[derive(Resource, Clone, Copy, Debug, Default)]
+pub struct InteractFocus(pub Option<Entity>);
+
+/// Marker for the text showing the focused interactable's prompt.
+#[derive(Component)]
+struct InteractPrompt;
+
+pub struct InteractPlugin;
+
+impl Plugin for InteractPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<InteractEvent>()
+            .init_resource::<InteractFocus>()
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_system(focus_nearest)
+                    .with_system(show_prompt.after(focus_nearest))
+                    .with_system(interact.after(focus_nearest)),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::InGame).with_system(clear_focus));
+    }
+}
+
+/// The nearest of `candidates` (entity, position, range) that has `player` within its range, if any.
+pub fn nearest_interactable(player: Vec2, candidates: impl IntoIterator<Item = (Entity, Vec2, f32)>) -> Option<Entity> {
+    candidates
+        .into_iter()
+        .map(|(entity, position, range)| (entity, position.distance(player), range))
+        .filter(|(_, distance, range)| distance <= range)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(entity, _, _)| entity)
+}
+
+/// Only the single nearest interactable is focused, so prompts never overlap and a press can't use two at once.
+fn focus_nearest(
+    players: Query<&GlobalTransform, With<Player>>,
+    interactables: Query<(Entity, &GlobalTransform, &Interactable)>,
+    mut focus: ResMut<InteractFocus>,
+) {
+    let nearest = players.get_single().ok().and_then(|player| {
+        let candidates = interactables
+            .iter()
+            .map(|(entity, transform, interactable)| (entity, transform.translation().truncate(), interactable.range));
+        nearest_interactable(player.translation().truncate(), candidates)
+    });
+    if focus.0 != nearest {
+        focus.0 = nearest;
+    }
+}
+
+fn show_prompt(
+    mut commands: Commands,
+    focus: Res<InteractFocus>,
+    ui: Option<Res<UiAssets>>,
+    interactables: Query<(&GlobalTransform, &Interactable)>,
+    mut prompts: Query<(&mut Text, &mut Transform, &mut Visibility), With<InteractPrompt>>,
+) {
+    let Some(ui) = ui else { return };
+
+    let Ok((mut text, mut transform, mut visibility)) = prompts.get_single_mut() else {
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    "",
+                    TextStyle {
+                        font: ui.font.clone(),
+                        font_size: PROMPT_FONT_SIZE,
+                        color: Color::WHITE,
+                    },
+                )
+                .with_alignment(TextAlignment::CENTER),
+                visibility: Visibility::INVISIBLE,
+                ..default()
+            },
+            InteractPrompt,
+            GameplayEntity,
+        ));
+        return;
+    };
+
+    let Some((target, interactable)) = focus.0.and_then(|entity| interactables.get(entity).ok()) else {
+        visibility.is_visible = false;
+        return;
+    };
+    visibility.is_visible = true;
+    transform.translation = (target.translation().truncate() + PROMPT_OFFSET).extend(PROMPT_Z);
+    if text.sections[0].value != interactable.prompt {
+        text.sections[0].value = interactable.prompt.clone();
+    }
+}
+
+/// Nothing is prompted once the session ends, so the next one can't start by using something from this one.
+fn clear_focus(mut focus: ResMut<InteractFocus>) {
+    focus.0 = None;
+}
+
+fn interact(
+    input: Res<InputState>,
+    focus: Res<InteractFocus>,
+    mut button: Local<EdgeDetector>,
+    mut events: EventWriter<InteractEvent>,
+) {
+    button.update(input.interact);
+    if let Some(target) = focus.0.filter(|_| button.just_pressed()) {
+        info!("Interacted with {target:?}");
+        events.send(InteractEvent { target });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_the_nearest_in_range() {
+        let [door, lever, sign] = [0, 1, 2].map(Entity::from_raw);
+        let candidates = [
+            (door, Vec2::new(30.0, 0.0), 40.0),
+            (lever, Vec2::new(-20.0, 0.0), 25.0),
+            // Closest of all, but its range doesn't reach the player.
+            (sign, Vec2::new(0.0, 10.0), 5.0),
+        ];
+        assert_eq!(nearest_interactable(Vec2::ZERO, candidates), Some(lever));
+        assert_eq!(nearest_interactable(Vec2::new(15.0, 0.0), candidates), Some(door));
+        assert_eq!(nearest_interactable(Vec2::new(0.0, 200.0), candidates), None);
+        assert_eq!(nearest_interactable(Vec2::ZERO, []), None);
+    }
+
+    #[test]
+    fn interacts_only_in_game() {
+        let mut app = App::new();
+        app.init_resource::<InputState>().add_state(GameState::InGame).add_plugin(InteractPlugin);
+        app.world.spawn((Player, GlobalTransform::default()));
+        let door = Interactable { prompt: "Open".into(), range: 40.0 };
+        let door = app.world.spawn((door, GlobalTransform::default())).id();
+        let pressed = |app: &mut App| {
+            app.world.resource_mut::<InputState>().interact = true;
+            app.update();
+            app.world.resource_mut::<InputState>().interact = false;
+            app.update();
+            let events = app.world.resource::<Events<InteractEvent>>();
+            events.get_reader().iter(events).map(|event| event.target).collect::<Vec<_>>()
+        };
+
+        assert_eq!(pressed(&mut app), [door]);
+        app.world.resource_mut::<State<GameState>>().push(GameState::Paused).unwrap();
+        assert_eq!(pressed(&mut app), []);
+
+        app.world.resource_mut::<State<GameState>>().replace(GameState::MainMenu).unwrap();
+        app.update();
+        assert_eq!(app.world.resource::<InteractFocus>().0, None);
+        assert_eq!(pressed(&mut app), []);
+    }
+}
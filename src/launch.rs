@@ -0,0 +1,163 @@
+use bevy::log::Level;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::LevelSelection;
+
+use crate::state::GameState;
+
+/// Options given on the command line, e.g. `beans_quest --level 2 --skip-menu --log-level debug`.
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct LaunchOptions {
+    /// The level to start in, from `--level <index or iid>`.
+    pub level: LevelSelection,
+    /// Whether `--skip-menu` was given, to go straight into play.
+    pub skip_menu: bool,
+    /// The most verbose log level shown, from `--log-level <level>`. `RUST_LOG`, when set, takes precedence.
+    pub log_level: Level,
+    /// Whether `--test-scene` was given, to open the physics test scene. Only builds with the `test-scene` feature
+    /// have it.
+    pub test_scene: bool,
+    /// What was wrong with the arguments, kept until there's a logger to report it to.
+    pub warnings: Vec<String>,
+}
+
+impl Default for LaunchOptions {
+    fn default() -> Self {
+        LaunchOptions {
+            level: LevelSelection::Index(0),
+            skip_menu: false,
+            log_level: Level::INFO,
+            test_scene: false,
+            warnings: Vec::new(),
+        }
+    }
+}
+
+impl LaunchOptions {
+    /// Parses the program's arguments, not including the program name.
+    ///
+    /// Unknown arguments and a `--level` without a value are ignored apart from a warning in `warnings`, so a typo
+    /// never stops the game from starting. A flag straight after `--level` or `--log-level` is taken as a flag, not
+    /// as their value.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Self {
+        let mut options = LaunchOptions::default();
+        let mut args = args.into_iter().peekable();
+
+        while let Some(arg) = args.next() {
+            let mut value = || args.next_if(|value| !value.starts_with("--"));
+            match arg.as_str() {
+                "--level" => match value().as_deref().and_then(parse_level) {
+                    Some(level) => options.level = level,
+                    None => options.warn("--level needs a level index or IID, starting at the first level"),
+                },
+                "--skip-menu" => options.skip_menu = true,
+                "--log-level" => match value().as_deref().and_then(parse_log_level) {
+                    Some(level) => options.log_level = level,
+                    None => options.warn("--log-level needs error, warn, info, debug or trace, logging at info"),
+                },
+                "--test-scene" if cfg!(feature = "test-scene") => options.test_scene = true,
+                "--test-scene" => options.warn("--test-scene needs a build with the test-scene feature, ignoring it"),
+                _ => options.warn(format!("Ignoring unknown argument {arg:?}")),
+            }
+        }
+        options
+    }
+
+    fn warn(&mut self, warning: impl Into<String>) {
+        self.warnings.push(warning.into());
+    }
+
+    /// Where to go once assets have loaded.
+    pub fn first_state(&self) -> GameState {
+        #[cfg(feature = "test-scene")]
+        if self.test_scene {
+            return GameState::TestScene;
+        }
+        if self.skip_menu { GameState::InGame } else { GameState::MainMenu }
+    }
+}
+
+/// Logs the `LaunchOptions` warnings, which can't be logged while parsing as that comes before `LogPlugin`.
+pub fn log_launch_warnings(options: Res<LaunchOptions>) {
+    for warning in &options.warnings {
+        warn!("{warning}");
+    }
+}
+
+/// A level index if `value` is a number, otherwise a level IID.
+fn parse_level(value: &str) -> Option<LevelSelection> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+    Some(match value.parse() {
+        Ok(index) => LevelSelection::Index(index),
+        Err(_) => LevelSelection::Iid(value.to_string()),
+    })
+}
+
+/// A log level by name, ignoring case: `error`, `warn` (or `warning`), `info`, `debug` or `trace`.
+pub fn parse_log_level(value: &str) -> Option<Level> {
+    match value.trim().to_lowercase().as_str() {
+        "error" => Some(Level::ERROR),
+        "warn" | "warning" => Some(Level::WARN),
+        "info" => Some(Level::INFO),
+        "debug" => Some(Level::DEBUG),
+        "trace" => Some(Level::TRACE),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> LaunchOptions {
+        LaunchOptions::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parses_valid_arguments() {
+        assert_eq!(parse(&[]), LaunchOptions::default());
+
+        let options = parse(&["--level", "2", "--skip-menu", "--log-level", "DEBUG"]);
+        assert_eq!(options.level, LevelSelection::Index(2));
+        assert!(options.skip_menu);
+        assert_eq!(options.log_level, Level::DEBUG);
+        assert_eq!(options.first_state(), GameState::InGame);
+
+        let options = parse(&["--level", "a2c1e9f0-5b7d-11ed-9b6a-0242ac120002"]);
+        assert_eq!(options.level, LevelSelection::Iid("a2c1e9f0-5b7d-11ed-9b6a-0242ac120002".to_string()));
+        assert_eq!(options.first_state(), GameState::MainMenu);
+        assert!(options.warnings.is_empty());
+    }
+
+    #[test]
+    fn invalid_arguments_fall_back_to_the_defaults() {
+        for args in [&["--level"][..], &["--level", " "], &["--log-level", "loud"]] {
+            let options = parse(args);
+            assert_eq!(options.warnings.len(), 1, "{args:?}");
+            assert_eq!(options, LaunchOptions { warnings: options.warnings.clone(), ..LaunchOptions::default() });
+        }
+
+        let options = parse(&["--level", "--skip-menu"]);
+        assert!(options.skip_menu);
+        assert_eq!(options.level, LevelSelection::Index(0));
+        assert_eq!(options.warnings.len(), 1);
+
+        let options = parse(&["--fly", "--skip-menu"]);
+        assert!(options.skip_menu);
+        assert_eq!(options.warnings, ["Ignoring unknown argument \"--fly\""]);
+    }
+
+    #[test]
+    fn test_scene_flag_needs_the_feature() {
+        let options = parse(&["--test-scene"]);
+        if cfg!(feature = "test-scene") {
+            assert!(options.test_scene && options.warnings.is_empty());
+        } else {
+            assert!(!options.test_scene);
+            assert_eq!(options.warnings.len(), 1);
+            assert_eq!(options.first_state(), GameState::MainMenu);
+        }
+    }
+}
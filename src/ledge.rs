@@ -0,0 +1,214 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use gamelibs::state_machine::StateMachine;
+
+use crate::input::{buffer_actions, Action, EdgeDetector, InputBuffer, InputState};
+use crate::physics::{GameplayDelta, PhysicsUnits};
+use crate::player::{jump, move_player, Player, PlayerState, PlayerStateMachine};
+use crate::state::GameState;
+
+/// How far the stick or keys have to be pushed up or down to climb or let go.
+const CLIMB_INPUT_THRESHOLD: f32 = 0.5;
+
+/// Tuning for grabbing and climbing ledges, in meters and seconds.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct LedgeConfig {
+    /// How far past the player's side a wall can be and still be grabbed.
+    pub reach: f32,
+    /// How far below the top of the player's head a ledge can be caught. Should be more than a fall covers in one
+    /// frame, or fast falls slip past ledges.
+    pub grab_window: f32,
+    /// How long pulling up onto a ledge takes.
+    pub climb_time: f32,
+    /// How long after letting go before the player can grab again, so they can drop past the ledge.
+    pub regrab_delay: f32,
+}
+
+impl Default for LedgeConfig {
+    fn default() -> Self {
+        LedgeConfig {
+            reach: 0.05,
+            grab_window: 0.15,
+            climb_time: 0.2,
+            regrab_delay: 0.3,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LedgePhase {
+    #[default]
+    Free,
+    /// Holding on to a ledge, with gravity off.
+    Hanging,
+    /// Pulling up onto the top of the ledge.
+    Climbing,
+}
+
+/// The player's hold on a ledge: its corner (in world pixels) and which side of the player it's on.
+#[derive(Component, Clone, Debug)]
+pub struct LedgeGrab {
+    pub machine: StateMachine<LedgePhase>,
+    pub corner: Vec2,
+    /// 1 when the ledge is to the player's right, -1 to their left.
+    pub side: f32,
+    /// Where a climb starts from and ends up.
+    climb: (Vec2, Vec2),
+}
+
+impl Default for LedgeGrab {
+    fn default() -> Self {
+        LedgeGrab {
+            machine: StateMachine::new(LedgePhase::Free),
+            corner: Vec2::ZERO,
+            side: 1.0,
+            climb: (Vec2::ZERO, Vec2::ZERO),
+        }
+    }
+}
+
+/// Returns whether the corner probe found a ledge to grab: the hand ray, just below the top of the player, hit a
+/// wall (`hand_hit` is its distance), and the head ray, level with the top, passed over it. A head ray hitting a wall
+/// set back at least `min_depth` further still counts, as the ledge is deep enough to stand on.
+pub fn is_ledge(hand_hit: Option<f32>, head_hit: Option<f32>, min_depth: f32) -> bool {
+    match hand_hit {
+        Some(hand) => head_hit.is_none_or(|head| head >= hand + min_depth),
+        None => false,
+    }
+}
+
+pub struct LedgePlugin;
+
+impl Plugin for LedgePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<LedgeConfig>()
+            // After the controller, to hold the player still over whatever it set, and before jumping so a jump
+            // press climbs instead of spending an air jump.
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_system(grab_ledges.after(move_player).after(buffer_actions).before(jump)),
+            );
+    }
+}
+
+/*
+ * Two rays from the player's centre, towards the way they're pushing, make the corner probe: one at hand height
+ * `grab_window` below the top of their head, one level with it. A third straight down just past the wall finds the
+ * ledge's top, and the player is snapped so their head is level with it and their side against the wall.
+ * Climbing moves them onto the top over `climb_time`, but only when their collider fits there.
+ */
+#[allow(clippy::too_many_arguments)]
+fn grab_ledges(
+    time: Res<Time>,
+    delta: Res<GameplayDelta>,
+    config: Res<LedgeConfig>,
+    units: Res<PhysicsUnits>,
+    input: Res<InputState>,
+    rapier_context: Res<RapierContext>,
+    mut buffer: ResMut<InputBuffer<Action>>,
+    mut up: Local<EdgeDetector>,
+    mut players: Query<
+        (Entity, &mut Transform, &mut Velocity, &mut GravityScale, &mut LedgeGrab, &Collider, &PlayerStateMachine),
+        With<Player>,
+    >,
+) {
+    up.update(input.move_axis.y >= CLIMB_INPUT_THRESHOLD);
+
+    for (entity, mut transform, mut velocity, mut gravity, mut ledge, collider, state) in players.iter_mut() {
+        ledge.machine.tick(delta.0 as f64);
+        let half_extents = collider.raw.compute_local_aabb().half_extents();
+        let half_extents = Vec2::new(half_extents.x, half_extents.y);
+        let center = transform.translation.truncate();
+        let filter = QueryFilter::default().exclude_rigid_body(entity).exclude_sensors();
+
+        match ledge.machine.current() {
+            LedgePhase::Free => {
+                let regrabbing = ledge.machine.previous() == Some(LedgePhase::Hanging)
+                    && ledge.machine.time_in_state() < config.regrab_delay as f64;
+                let falling = state.is(PlayerState::Airborne) && velocity.linvel.y <= 0.0;
+                if regrabbing || !falling || input.move_axis.x == 0.0 {
+                    continue;
+                }
+
+                let side = input.move_axis.x.signum();
+                let direction = Vec2::new(side, 0.0);
+                let reach = half_extents.x + units.m_to_px(config.reach);
+                let head_y = center.y + half_extents.y;
+                let hand_y = head_y - units.m_to_px(config.grab_window);
+                let cast = |y: f32| rapier_context.cast_ray(Vec2::new(center.x, y), direction, reach, true, filter);
+                let hand = cast(hand_y).map(|(_, distance)| distance);
+                let head = cast(head_y).map(|(_, distance)| distance);
+                if !is_ledge(hand, head, half_extents.x * 2.0) {
+                    continue;
+                }
+
+                let wall_x = center.x + side * hand.unwrap_or(reach);
+                let top = rapier_context.cast_ray(
+                    Vec2::new(wall_x + side, head_y),
+                    Vec2::NEG_Y,
+                    head_y - hand_y,
+                    true,
+                    filter,
+                );
+                let Some((_, depth)) = top else { continue };
+
+                ledge.corner = Vec2::new(wall_x, head_y - depth);
+                ledge.side = side;
+                ledge.machine.transition_to(LedgePhase::Hanging);
+                transform.translation.x = wall_x - side * half_extents.x;
+                transform.translation.y = ledge.corner.y - half_extents.y;
+            }
+            LedgePhase::Hanging => {
+                if input.move_axis.y <= -CLIMB_INPUT_THRESHOLD {
+                    ledge.machine.transition_to(LedgePhase::Free);
+                } else if up.just_pressed() || buffer.consume(Action::Jump, time.elapsed()) {
+                    let on_top = ledge.corner + Vec2::new(ledge.side * (half_extents.x + 1.0), half_extents.y + 0.5);
+                    if rapier_context.intersection_with_shape(on_top, 0.0, collider, filter).is_none() {
+                        ledge.climb = (center, on_top);
+                        ledge.machine.transition_to(LedgePhase::Climbing);
+                    }
+                }
+            }
+            LedgePhase::Climbing => {
+                let (from, to) = ledge.climb;
+                let t = (ledge.machine.time_in_state() as f32 / config.climb_time.max(f32::EPSILON)).min(1.0);
+                // Up first, then over the edge, so the player doesn't clip the corner.
+                let position = Vec2::new(
+                    from.x + (to.x - from.x) * (t * 2.0 - 1.0).max(0.0),
+                    from.y + (to.y - from.y) * (t * 2.0).min(1.0),
+                );
+                transform.translation.x = position.x;
+                transform.translation.y = position.y;
+                if t >= 1.0 {
+                    ledge.machine.transition_to(LedgePhase::Free);
+                }
+            }
+        }
+
+        // `hang_at_apex` sets the gravity scale again every frame, so it only has to be overridden while holding on.
+        if !ledge.machine.is(LedgePhase::Free) {
+            velocity.linvel = Vec2::ZERO;
+            if gravity.0 != 0.0 {
+                gravity.0 = 0.0;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ledge_needs_a_wall_at_hand_and_room_above() {
+        let min_depth = 8.0;
+        assert!(is_ledge(Some(4.0), None, min_depth));
+        assert!(is_ledge(Some(4.0), Some(12.0), min_depth), "a wall set back far enough leaves room to stand");
+        // A sheer wall, a step too shallow to stand on, or nothing to hold.
+        assert!(!is_ledge(Some(4.0), Some(4.0), min_depth));
+        assert!(!is_ledge(Some(4.0), Some(10.0), min_depth));
+        assert!(!is_ledge(None, None, min_depth));
+        assert!(!is_ledge(None, Some(4.0), min_depth));
+    }
+}
@@ -0,0 +1,421 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_rapier2d::prelude::*;
+use gamelibs::rng::Rng;
+
+use crate::error::GameError;
+use crate::health::Health;
+use crate::physics::StaticColliders;
+use crate::player::{Player, PlayerSpawn};
+use crate::toast::Toast;
+
+/// How far inside the level's bottom edge (in pixels) its floor is probed, and how far past a collider's top the
+/// next one stacked on it is.
+const FLOOR_PROBE_INSET: f32 = 0.5;
+/// The most colliders stacked on top of each other the fall guard climbs through looking for the top of the floor.
+const MAX_FLOOR_STACK: usize = 16;
+
+/// World-space extents of the currently loaded level.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct LevelBounds {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+/// The seed for everything procedural in the current level, such as enemy variants or decorations, so a level comes
+/// out the same every time it loads. It is `level_seed` of the level's IID, set as the level starts spawning.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LevelSeed(pub u64);
+
+impl LevelSeed {
+    /// A generator for one `purpose`, e.g. "decorations". Each purpose gets its own sequence, so adding random
+    /// draws for one doesn't change what another generates.
+    pub fn rng(&self, purpose: &str) -> Rng {
+        Rng::new(self.0 ^ level_seed(purpose))
+    }
+}
+
+/// Replaces every level's `LevelSeed` while set, e.g. to reproduce a bug report or pin a test.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LevelSeedOverride(pub Option<u64>);
+
+/// How far below `LevelBounds::min.y` a body may fall before it is considered lost.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct KillPlane {
+    pub margin: f32,
+}
+
+impl Default for KillPlane {
+    fn default() -> Self {
+        KillPlane { margin: 64.0 }
+    }
+}
+
+/// When a player who's got out of the level, say through a seam between colliders, is caught and put back on the
+/// ground instead of falling forever. In pixels and pixels per second.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct FallGuard {
+    /// Falling faster than this means they've been falling far longer than any drop in a level.
+    pub max_speed: f32,
+    /// How far below `LevelBounds::min.y` they're caught. Should be less than `KillPlane::margin`, or the kill
+    /// plane gets them first.
+    pub distance: f32,
+}
+
+impl Default for FallGuard {
+    fn default() -> Self {
+        FallGuard {
+            max_speed: 6000.0,
+            distance: 32.0,
+        }
+    }
+}
+
+/// What to do with a player who's fallen out of the level.
+///
+/// * `FallRecovery::Teleport(ground)` puts them on the ground at `ground`, which they fell through.
+///
+/// * `FallRecovery::Respawn` kills them, as there's no ground to put them on: they fell down a pit.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FallRecovery {
+    Teleport(Vec2),
+    Respawn,
+}
+
+impl FallRecovery {
+    /// The recovery for a player at `position` falling at `speed`, given the ground below them if any, or `None`
+    /// if they haven't fallen out of the level.
+    pub fn decide(
+        position: Vec2,
+        speed: f32,
+        bounds: &LevelBounds,
+        guard: &FallGuard,
+        ground: Option<Vec2>,
+    ) -> Option<FallRecovery> {
+        let lost = speed > guard.max_speed || is_below_bounds(position.y, bounds, guard.distance);
+        lost.then(|| ground.map_or(FallRecovery::Respawn, FallRecovery::Teleport))
+    }
+}
+
+/// Fired for every non-player body removed by the kill plane, so spawners can recycle it.
+pub struct DespawnedOutOfBounds {
+    pub entity: Entity,
+    pub position: Vec2,
+}
+
+/// Send this to kill the player; they are put back at their spawn point.
+pub struct PlayerKilled;
+
+/// Fired once a killed player has been put back at their spawn point, at full health.
+pub struct PlayerRespawned;
+
+/// Send this when the player reaches the exit of the level with the given IID.
+pub struct LevelCompleted {
+    pub iid: String,
+}
+
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<KillPlane>()
+            .init_resource::<FallGuard>()
+            .init_resource::<LevelSeed>()
+            .init_resource::<LevelSeedOverride>()
+            .add_event::<DespawnedOutOfBounds>()
+            .add_event::<PlayerKilled>()
+            .add_event::<PlayerRespawned>()
+            .add_event::<LevelCompleted>()
+            .add_system(check_level_selection)
+            .add_system(seed_from_level)
+            .add_system(bounds_from_level)
+            .add_system(guard_falls.before(kill_plane))
+            .add_system(kill_plane)
+            .add_system(respawn_player.after(kill_plane).after(guard_falls));
+    }
+}
+
+/// Whether `project` has the level `selection` asks for.
+pub fn validate_level_selection(selection: &LevelSelection, project: &LdtkAsset) -> Result<(), GameError> {
+    if project.iter_levels().enumerate().any(|(index, level)| selection.is_match(&index, level)) {
+        Ok(())
+    } else {
+        Err(GameError::InvalidLevel(format!("{selection:?}")))
+    }
+}
+
+/// Falls back to the first level when the one asked for, e.g. with `--level`, isn't in the project once it loads,
+/// rather than leaving the player in an empty world.
+fn check_level_selection(
+    mut events: EventReader<AssetEvent<LdtkAsset>>,
+    projects: Res<Assets<LdtkAsset>>,
+    selection: Option<ResMut<LevelSelection>>,
+    mut toasts: EventWriter<Toast>,
+) {
+    let Some(mut selection) = selection else { return };
+    for event in events.iter() {
+        let (AssetEvent::Created { handle } | AssetEvent::Modified { handle }) = event else { continue };
+        let Some(project) = projects.get(handle) else { continue };
+        if let Err(error) = validate_level_selection(&selection, project) {
+            error!("Couldn't start the level: {error}, starting at the first level");
+            toasts.send(Toast(format!("Couldn't start the level: {error}")));
+            *selection = LevelSelection::Index(0);
+        }
+    }
+}
+
+/// Looks up the loaded LDtk level with the given IID among the spawned level entities.
+pub fn find_level<'a>(
+    iid: &str,
+    levels: impl IntoIterator<Item = &'a Handle<LdtkLevel>>,
+    level_assets: &'a Assets<LdtkLevel>,
+) -> Option<&'a LdtkLevel> {
+    levels
+        .into_iter()
+        .filter_map(|handle| level_assets.get(handle))
+        .find(|ldtk_level| ldtk_level.level.iid == iid)
+}
+
+/// A seed for the level with IID `iid`: its 64-bit FNV-1a hash, which unlike `std`'s hasher never changes between
+/// builds or platforms.
+pub fn level_seed(iid: &str) -> u64 {
+    iid.bytes()
+        .fold(0xcbf29ce484222325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+/// Returns whether a body at height `y` has dropped past the kill plane.
+pub fn is_below_bounds(y: f32, bounds: &LevelBounds, margin: f32) -> bool {
+    y < bounds.min.y - margin
+}
+
+/// Seeds the level before anything in it spawns.
+pub fn seed_from_level(
+    mut level_events: EventReader<LevelEvent>,
+    seed_override: Res<LevelSeedOverride>,
+    mut seed: ResMut<LevelSeed>,
+) {
+    for event in level_events.iter() {
+        let LevelEvent::SpawnTriggered(iid) = event else { continue };
+        *seed = LevelSeed(seed_override.0.unwrap_or_else(|| level_seed(iid)));
+    }
+}
+
+/*
+ * Levels are anchored at their bottom-left corner, so the bounds span from the level's
+ * translation to its pixel size. Waits for `Transformed` so the global transform is current.
+ */
+fn bounds_from_level(
+    mut commands: Commands,
+    mut level_events: EventReader<LevelEvent>,
+    levels: Query<(&Handle<LdtkLevel>, &GlobalTransform)>,
+    level_assets: Res<Assets<LdtkLevel>>,
+) {
+    for event in level_events.iter() {
+        let LevelEvent::Transformed(iid) = event else { continue };
+
+        for (handle, transform) in levels.iter() {
+            let Some(ldtk_level) = level_assets.get(handle) else { continue };
+            if &ldtk_level.level.iid != iid {
+                continue;
+            }
+
+            let min = transform.translation().truncate();
+            let size = Vec2::new(ldtk_level.level.px_wid as f32, ldtk_level.level.px_hei as f32);
+            commands.insert_resource(LevelBounds { min, max: min + size });
+            info!("Loaded level {} ({iid})", ldtk_level.level.identifier);
+        }
+    }
+}
+
+/*
+ * A player who's only falling too fast is put on whatever is straight below them. Otherwise their column is checked
+ * at the bottom of the level: if it's solid there, they slipped through the floor and are lifted out through the
+ * top of it, through however many colliders are stacked up; if it's open, the column is a pit, so they die as they
+ * would have at the kill plane. Starting from the bottom rather than the top keeps a pit under a platform from
+ * putting them on the platform.
+ */
+fn guard_falls(
+    bounds: Option<Res<LevelBounds>>,
+    guard: Res<FallGuard>,
+    rapier_context: Res<RapierContext>,
+    statics: StaticColliders,
+    mut players: Query<(Entity, &mut Transform, &mut Velocity, &Collider), With<Player>>,
+    mut killed: EventWriter<PlayerKilled>,
+) {
+    let Some(bounds) = bounds else { return };
+
+    for (entity, mut transform, mut velocity, collider) in players.iter_mut() {
+        let position = transform.translation.truncate();
+        let speed = velocity.linvel.length();
+        if FallRecovery::decide(position, speed, &bounds, &guard, None).is_none() {
+            continue;
+        }
+
+        let filter = QueryFilter::default().exclude_rigid_body(entity).exclude_sensors();
+        let below = (position.y > bounds.min.y)
+            .then(|| rapier_context.cast_ray(position, Vec2::NEG_Y, position.y - bounds.min.y, true, filter))
+            .flatten()
+            .map(|(_, toi)| position - Vec2::Y * toi);
+        let ground = below.or_else(|| {
+            let x = position.x.clamp(bounds.min.x, bounds.max.x);
+            floor_top(&rapier_context, Vec2::new(x, bounds.min.y + FLOOR_PROBE_INSET), bounds.max.y, filter)
+        });
+
+        match FallRecovery::decide(position, speed, &bounds, &guard, ground) {
+            Some(FallRecovery::Teleport(ground)) => {
+                let half_extents = collider.raw.compute_local_aabb().half_extents();
+                let half_extents = Vec2::new(half_extents.x, half_extents.y);
+                let recovered = statics.resolve(ground + Vec2::Y * half_extents.y, half_extents);
+                transform.translation.x = recovered.x;
+                transform.translation.y = recovered.y;
+                *velocity = Velocity::zero();
+                warn!("Player fell out of the level at {position}, put back on the ground at {recovered}");
+            }
+            Some(FallRecovery::Respawn) => {
+                warn!("Player fell out of the level at {position} with no ground below, respawning");
+                killed.send(PlayerKilled);
+            }
+            None => {}
+        }
+    }
+}
+
+/// The top of the solid ground `point` is inside, climbing through colliders stacked on top of each other up to
+/// `ceiling`, or `None` if `point` isn't inside anything.
+fn floor_top(rapier_context: &RapierContext, point: Vec2, ceiling: f32, filter: QueryFilter) -> Option<Vec2> {
+    let mut point = point;
+    let mut inside = false;
+    for _ in 0..MAX_FLOOR_STACK {
+        let mut solid = false;
+        rapier_context.intersections_with_point(point, filter, |_| {
+            solid = true;
+            false
+        });
+        if point.y > ceiling || !solid {
+            break;
+        }
+        inside = true;
+        // Not solid, so the ray starting inside finds where it leaves rather than stopping at once.
+        let (_, toi) = rapier_context.cast_ray(point, Vec2::Y, ceiling - point.y, false, filter)?;
+        point.y += toi + FLOOR_PROBE_INSET;
+    }
+    inside.then(|| point - Vec2::Y * FLOOR_PROBE_INSET)
+}
+
+/// Despawns loose bodies that fall out of the level and kills the player if they do.
+fn kill_plane(
+    mut commands: Commands,
+    bounds: Option<Res<LevelBounds>>,
+    kill_plane: Res<KillPlane>,
+    bodies: Query<(Entity, &Transform, Option<&Player>), With<RigidBody>>,
+    mut despawned: EventWriter<DespawnedOutOfBounds>,
+    mut killed: EventWriter<PlayerKilled>,
+) {
+    let Some(bounds) = bounds else { return };
+
+    for (entity, transform, player) in bodies.iter() {
+        if !is_below_bounds(transform.translation.y, &bounds, kill_plane.margin) {
+            continue;
+        }
+
+        if player.is_some() {
+            killed.send(PlayerKilled);
+        } else {
+            commands.entity(entity).despawn_recursive();
+            despawned.send(DespawnedOutOfBounds {
+                entity,
+                position: transform.translation.truncate(),
+            });
+        }
+    }
+}
+
+pub fn respawn_player(
+    mut killed: EventReader<PlayerKilled>,
+    spawn: Res<PlayerSpawn>,
+    statics: StaticColliders,
+    mut players: Query<(&mut Transform, &Collider, Option<&mut Velocity>, Option<&mut Health>), With<Player>>,
+    mut respawned: EventWriter<PlayerRespawned>,
+) {
+    if killed.iter().count() == 0 {
+        return;
+    }
+
+    for (mut transform, collider, velocity, health) in players.iter_mut() {
+        let half_extents = collider.raw.compute_local_aabb().half_extents();
+        let position = statics.resolve(spawn.0, Vec2::new(half_extents.x, half_extents.y));
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+        if let Some(mut velocity) = velocity {
+            *velocity = Velocity::zero();
+        }
+        if let Some(mut health) = health {
+            health.current = health.max;
+        }
+        info!("Player died, respawning at {position}");
+        respawned.send(PlayerRespawned);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOUNDS: LevelBounds = LevelBounds { min: Vec2::new(0.0, 0.0), max: Vec2::new(512.0, 256.0) };
+
+    #[test]
+    fn below_bounds_only_past_the_margin() {
+        assert!(!is_below_bounds(10.0, &BOUNDS, 32.0));
+        assert!(!is_below_bounds(-20.0, &BOUNDS, 32.0));
+        assert!(!is_below_bounds(-32.0, &BOUNDS, 32.0));
+        assert!(is_below_bounds(-32.5, &BOUNDS, 32.0));
+        assert!(is_below_bounds(-1.0, &BOUNDS, 0.0));
+    }
+
+    /// The seed after `seed_from_level` sees each of `iids` spawn in turn.
+    fn seed_after(iids: &[&str], seed_override: Option<u64>) -> u64 {
+        let mut app = App::new();
+        app
+            .init_resource::<LevelSeed>()
+            .insert_resource(LevelSeedOverride(seed_override))
+            .add_event::<LevelEvent>()
+            .add_system(seed_from_level);
+        for iid in iids {
+            app.world.send_event(LevelEvent::SpawnTriggered(iid.to_string()));
+            app.update();
+        }
+        app.world.resource::<LevelSeed>().0
+    }
+
+    #[test]
+    fn same_level_gets_the_same_seed() {
+        let first = seed_after(&["level-a"], None);
+        assert_eq!(seed_after(&["level-b", "level-a"], None), first, "revisiting a level reseeds it alike");
+        assert_ne!(seed_after(&["level-b"], None), first);
+        assert_eq!(seed_after(&["level-a"], Some(7)), 7);
+
+        let seed = LevelSeed(first);
+        assert_eq!(seed.rng("decorations").next_u64(), seed.rng("decorations").next_u64());
+        assert_ne!(seed.rng("decorations").next_u64(), seed.rng("loot").next_u64());
+    }
+
+    #[test]
+    fn lost_players_are_put_back_or_respawned() {
+        let guard = FallGuard { max_speed: 6000.0, distance: 32.0 };
+        let floor = Vec2::new(100.0, 16.0);
+        let ground = Some(floor);
+        let decide = |position: Vec2, speed: f32, ground: Option<Vec2>| {
+            FallRecovery::decide(position, speed, &BOUNDS, &guard, ground)
+        };
+
+        // Falling normally inside the level, or only just below its edge.
+        assert_eq!(decide(Vec2::new(100.0, 100.0), 800.0, ground), None);
+        assert_eq!(decide(Vec2::new(100.0, -20.0), 800.0, ground), None);
+        // Slipped through the floor, or falling too fast to be anywhere sensible.
+        assert_eq!(decide(Vec2::new(100.0, -40.0), 800.0, ground), Some(FallRecovery::Teleport(floor)));
+        assert_eq!(decide(Vec2::new(100.0, 100.0), 7000.0, ground), Some(FallRecovery::Teleport(floor)));
+        // Down a pit, with nothing to put them on.
+        assert_eq!(decide(Vec2::new(100.0, -40.0), 800.0, None), Some(FallRecovery::Respawn));
+    }
+}
@@ -0,0 +1,75 @@
+// Bevy queries and systems routinely trip these lints; splitting them into aliases or bundles only hides the signature.
+#![allow(clippy::type_complexity, clippy::too_many_arguments)]
+
+pub mod ambient;
+pub mod animation;
+pub mod attach;
+pub mod audio;
+pub mod auto_scroll;
+pub mod bean;
+pub mod bounce;
+pub mod breakable;
+pub mod breathing;
+pub mod camera;
+pub mod charge_jump;
+pub mod checkpoint;
+pub mod confiner;
+pub mod conveyor;
+pub mod countdown;
+pub mod crouch;
+pub mod cutscene;
+pub mod damage_number;
+pub mod debug_draw;
+pub mod enemy;
+pub mod entity_registry;
+pub mod error;
+pub mod fields;
+pub mod flags;
+pub mod flash;
+pub mod flip;
+pub mod footsteps;
+pub mod force_field;
+pub mod hang_time;
+pub mod health;
+pub mod hud;
+pub mod input;
+pub mod interact;
+pub mod launch;
+pub mod ledge;
+pub mod level;
+pub mod lockstep;
+pub mod magnet;
+pub mod menu;
+pub mod minimap;
+#[cfg(feature = "dev")]
+pub mod nan_guard;
+pub mod nine_slice;
+pub mod parallax;
+pub mod paths;
+pub mod physics;
+pub mod platform;
+pub mod player;
+pub mod pool;
+pub mod projectile;
+pub mod render_layers;
+pub mod respawn;
+pub mod results;
+#[cfg(feature = "dev")]
+pub mod rewind;
+pub mod save;
+pub mod settings;
+pub mod settings_menu;
+pub mod sky;
+pub mod smoothing;
+pub mod state;
+pub mod status_effect;
+#[cfg(feature = "dev")]
+pub mod step_mode;
+pub mod surface;
+pub mod terrain;
+#[cfg(feature = "test-scene")]
+pub mod test_scene;
+pub mod tile_animation;
+pub mod toast;
+pub mod trail;
+pub mod y_sort;
@@ -0,0 +1,97 @@
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::crouch::CrouchPlugin;
+use crate::hang_time::HangTimePlugin;
+use crate::input::InputState;
+use crate::physics::{GameplayDeltaPlugin, PhysicsUnits, FIXED_TIMESTEP};
+use crate::player::{spawn_player, Player, PlayerPlugin, PlayerSpawn};
+use crate::state::GameState;
+
+/*
+ * A headless, deterministic app for regression-testing gameplay systems.
+ *
+ * There is no window, renderer or real clock: `Time` is advanced by exactly `FIXED_TIMESTEP` before
+ * every update and Rapier takes one fixed step per update, so a given input script always produces
+ * the same result. The player starts resting on a wide, flat floor at the origin.
+ */
+pub fn lockstep_app() -> App {
+    let units = PhysicsUnits::default();
+
+    let mut app = App::new();
+    app
+        .add_plugin(CorePlugin::default())
+        .add_plugin(TransformPlugin)
+        .add_plugin(HierarchyPlugin)
+        .init_resource::<Time>()
+        .insert_resource(units)
+        .insert_resource(RapierConfiguration {
+            gravity: units.gravity(),
+            timestep_mode: TimestepMode::Fixed { dt: FIXED_TIMESTEP, substeps: 1 },
+            ..default()
+        })
+        .add_plugin(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(units.pixels_per_meter))
+        .add_state(GameState::InGame)
+        .add_plugin(GameplayDeltaPlugin)
+        .add_plugin(PlayerPlugin)
+        .add_plugin(CrouchPlugin)
+        .add_plugin(HangTimePlugin)
+        .insert_resource(PlayerSpawn(Vec2::new(0.0, units.m_to_px(0.25))))
+        .add_startup_system(spawn_floor)
+        .add_startup_system(spawn_player);
+    app
+}
+
+fn spawn_floor(mut commands: Commands, units: Res<PhysicsUnits>) {
+    let half_thickness = units.m_to_px(0.5);
+    commands
+        .spawn(Collider::cuboid(units.m_to_px(1000.0), half_thickness))
+        .insert(TransformBundle::from(Transform::from_xyz(0.0, -half_thickness, 0.0)));
+}
+
+/// Runs `steps` fixed steps of a fresh `lockstep_app`, feeding `inputs[i]` on step `i` and holding
+/// the last input once the script runs out, and returns the player's final transform.
+pub fn run_scripted(inputs: &[InputState], steps: usize) -> Transform {
+    let mut app = lockstep_app();
+    let step = Duration::from_secs_f32(FIXED_TIMESTEP);
+    let mut now = Instant::now();
+    app.world.resource_mut::<Time>().update_with_instant(now);
+
+    for i in 0..steps {
+        let input = inputs.get(i).or(inputs.last()).copied().unwrap_or_default();
+        app.world.insert_resource(input);
+
+        now += step;
+        app.world.resource_mut::<Time>().update_with_instant(now);
+        app.update();
+    }
+
+    let mut players = app.world.query_filtered::<&Transform, With<Player>>();
+    *players.single(&app.world)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::MoveConfig;
+
+    #[test]
+    fn holding_right_for_a_second() {
+        let units = PhysicsUnits::default();
+        let config = MoveConfig::default();
+        let steps = (1.0 / FIXED_TIMESTEP).round() as usize;
+        let right = InputState { move_axis: Vec2::X, ..default() };
+
+        let start = run_scripted(&[InputState::default()], steps).translation;
+        let end = run_scripted(&[right], steps).translation;
+
+        // Speeding up to `max_speed` covers half the distance it would at full speed, then the rest is at full speed.
+        let ramp = config.max_speed / config.acceleration;
+        let expected = config.max_speed * (1.0 - ramp / 2.0);
+        let travelled = units.px_to_m(end.x - start.x);
+        assert!((travelled - expected).abs() < 0.1, "travelled {travelled} m, expected about {expected} m");
+        assert!((end.y - start.y).abs() < 0.5, "left the floor: {start} to {end}");
+    }
+}
@@ -0,0 +1,225 @@
+use std::f32::consts::TAU;
+
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use gamelibs::prelude::*;
+use nalgebra_glm::DVec2;
+
+use crate::audio::Sfx;
+use crate::bean::{collect_beans, Bean};
+use crate::entity_registry::RegisterLevelEntity;
+use crate::fields::LdtkFields;
+use crate::physics::GameplayDelta;
+use crate::player::Player;
+use crate::status_effect::{ApplyStatus, Modifier, StatusEffect, StatusEffects, StatusKind};
+
+/// The LDtk entity identifier of a bean magnet power-up.
+pub const MAGNET_ENTITY: &str = "Magnet";
+/// Float fields overriding the `Magnet` defaults.
+const RADIUS_FIELD: &str = "Radius";
+const STRENGTH_FIELD: &str = "Strength";
+const DURATION_FIELD: &str = "Duration";
+/// Played when the player picks up a magnet.
+const MAGNET_SFX: &str = "magnet";
+
+/// The power-up is picked up when the player's centre comes within this many pixels.
+const MAGNET_PICKUP_RADIUS: f32 = 12.0;
+const MAGNET_SIZE: Vec2 = Vec2::new(10.0, 10.0);
+const MAGNET_COLOR: Color = Color::rgb(0.85, 0.2, 0.25);
+/// Width of the cells beans are bucketed into for the radius query, in pixels: about a magnet's reach.
+const BEAN_GRID_CELL: f64 = 64.0;
+
+/// A bean magnet power-up. While it lasts, uncollected beans within `radius` pixels are pulled in to the player
+/// with `strength`, see `magnet_curve`.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct Magnet {
+    pub radius: f32,
+    pub strength: f32,
+    /// Seconds of gameplay time it lasts.
+    pub duration: f32,
+}
+
+impl Default for Magnet {
+    fn default() -> Self {
+        Magnet {
+            radius: 64.0,
+            strength: 60.0,
+            duration: 8.0,
+        }
+    }
+}
+
+impl Magnet {
+    /// The status effect the magnet puts on the player.
+    pub fn effect(&self) -> StatusEffect {
+        let modifier = Modifier::Magnet { radius: self.radius, strength: self.strength };
+        StatusEffect::new(StatusKind::Magnet, modifier, self.duration, 0.0)
+    }
+}
+
+/// A bean being pulled in by a magnet, and the followers moving it along each axis.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Attracted([CurveFollower; 2]);
+
+/// Every bean, bucketed by position each frame a magnet is active.
+#[derive(Resource)]
+struct BeanGrid(SpatialHash<Entity>);
+
+pub struct MagnetPlugin;
+
+impl Plugin for MagnetPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .insert_resource(BeanGrid(SpatialHash::new(BEAN_GRID_CELL)))
+            .register_level_entity(MAGNET_ENTITY, spawn_magnet)
+            .add_system(pick_up_magnets)
+            .add_system(attract_beans.before(collect_beans));
+    }
+}
+
+/// The curve a magnet pulls beans in along: a critically damped spring, accelerating a bean towards the player at
+/// `strength` pixels/s² for every pixel between them, less what it takes to keep it from overshooting.
+pub fn magnet_curve(strength: f32) -> CurveStyle {
+    CurveStyle::Custom {
+        f: (strength.max(0.0).sqrt() / TAU) as f64,
+        z: 1.0,
+        r: 0.0,
+    }
+}
+
+fn to_dvec2(v: Vec2) -> DVec2 {
+    DVec2::new(v.x as f64, v.y as f64)
+}
+
+/// Gives an LDtk magnet its sprite, with any of its settings overridden by the entity's fields.
+fn spawn_magnet(magnet: &mut EntityCommands, instance: &EntityInstance, _: Vec2) {
+    let fields = instance.fields();
+    let defaults = Magnet::default();
+    magnet.insert((
+        Magnet {
+            radius: fields.get_float(RADIUS_FIELD).unwrap_or(defaults.radius),
+            strength: fields.get_float(STRENGTH_FIELD).unwrap_or(defaults.strength),
+            duration: fields.get_float(DURATION_FIELD).unwrap_or(defaults.duration),
+        },
+        Sprite {
+            color: MAGNET_COLOR,
+            custom_size: Some(MAGNET_SIZE),
+            ..default()
+        },
+        Handle::<Image>::default(),
+    ));
+}
+
+fn pick_up_magnets(
+    mut commands: Commands,
+    players: Query<(Entity, &GlobalTransform), With<Player>>,
+    magnets: Query<(Entity, &GlobalTransform, &Magnet)>,
+    mut statuses: EventWriter<ApplyStatus>,
+    mut sfx: Sfx,
+) {
+    let Ok((player, player_transform)) = players.get_single() else { return };
+    let player_position = player_transform.translation().truncate();
+
+    for (entity, transform, magnet) in magnets.iter() {
+        let position = transform.translation().truncate();
+        if position.distance(player_position) > MAGNET_PICKUP_RADIUS {
+            continue;
+        }
+        commands.entity(entity).despawn_recursive();
+        statuses.send(ApplyStatus { target: player, effect: magnet.effect() });
+        sfx.play_sfx_at(MAGNET_SFX, position);
+    }
+}
+
+/*
+ * Beans within the magnet's radius are found through a spatial hash rather than by measuring every bean in the
+ * level. Each one caught follows the player through the curve integrator, so it starts slowly and speeds up as it's
+ * drawn in, and `collect_beans` picks it up once it's close. A bean that gets out of reach, or is still on its way
+ * when the magnet wears off, stops where it is.
+ */
+fn attract_beans(
+    mut commands: Commands,
+    delta: Res<GameplayDelta>,
+    mut grid: ResMut<BeanGrid>,
+    players: Query<(&GlobalTransform, Option<&StatusEffects>), With<Player>>,
+    mut beans: Query<(Entity, &GlobalTransform, &mut Transform, Option<&mut Attracted>), With<Bean>>,
+) {
+    let Ok((player, effects)) = players.get_single() else { return };
+    let player = player.translation().truncate();
+
+    grid.0.clear();
+    let mut nearby = Vec::new();
+    if let Some((radius, strength)) = effects.and_then(StatusEffects::magnet) {
+        for (entity, transform, ..) in beans.iter() {
+            let position = to_dvec2(transform.translation().truncate());
+            grid.0.insert(entity, Aabb2::new(position, position));
+        }
+        let reach = Aabb2::from_center(to_dvec2(player), DVec2::repeat(radius as f64));
+        let curve = CurveType::from_style(magnet_curve(strength));
+
+        // The query is by box; the magnet's reach is round.
+        nearby = grid.0.query_region(reach);
+        nearby.retain(|&entity| {
+            let position = beans.get(entity).map(|(_, transform, ..)| transform.translation().truncate());
+            position.is_ok_and(|position| position.distance(player) <= radius)
+        });
+        for &entity in nearby.iter() {
+            let Ok((_, global, mut transform, attracted)) = beans.get_mut(entity) else { continue };
+            let position = global.translation().truncate();
+            let mut followers = attracted.as_deref().map_or_else(
+                || [position.x, position.y].map(|value| CurveFollower::new(magnet_curve(strength), value as f64)),
+                |attracted| attracted.0,
+            );
+            let mut next = Vec2::ZERO;
+            for (axis, follower) in followers.iter_mut().enumerate() {
+                follower.curve = curve;
+                next[axis] = follower.step(player[axis] as f64, delta.0 as f64) as f32;
+            }
+            // Beans sit in their level, so move them by how far they go in the world.
+            transform.translation += (next - position).extend(0.0);
+            match attracted {
+                Some(mut attracted) => attracted.0 = followers,
+                None => {
+                    commands.entity(entity).insert(Attracted(followers));
+                }
+            }
+        }
+    }
+
+    for (entity, _, _, attracted) in beans.iter() {
+        if attracted.is_some() && !nearby.contains(&entity) {
+            commands.entity(entity).remove::<Attracted>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A bean's acceleration the moment a magnet of `strength` catches it at rest `distance` pixels away.
+    fn pull(strength: f32, distance: f64) -> f64 {
+        let dt = 1e-4;
+        let mut bean = CurveFollower::new(magnet_curve(strength), 0.0);
+        bean.step(distance, dt);
+        bean.velocity / dt
+    }
+
+    #[test]
+    fn pull_grows_with_distance_and_strength() {
+        for (strength, distance) in [(60.0, 10.0), (60.0, 40.0), (240.0, 10.0)] {
+            let expected = strength as f64 * distance;
+            assert!((pull(strength, distance) - expected).abs() < expected * 1e-6, "{strength} at {distance} px");
+        }
+        assert_eq!(pull(60.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn beans_arrive_without_overshooting() {
+        let mut bean = CurveFollower::new(magnet_curve(Magnet::default().strength), 0.0);
+        let path: Vec<f64> = (0..300).map(|_| bean.step(50.0, 1.0 / 60.0)).collect();
+        assert!(path.windows(2).all(|pair| pair[1] >= pair[0] && pair[1] <= 50.0 + 1e-9));
+        assert!((path.last().unwrap() - 50.0).abs() < 0.1);
+    }
+}
@@ -1,12 +1,77 @@
+use bevy::log::LogPlugin;
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 use bevy_asset_loader::prelude::*;
-use bevy_ecs_ldtk::{LdtkWorldBundle, LevelSelection, LdtkPlugin};
+use bevy_ecs_ldtk::{LdtkWorldBundle, LdtkPlugin};
 #[allow(unused_imports)]
 use iyes_loopless::prelude::*;
 
+use beans_quest::ambient::AmbientPlugin;
+use beans_quest::animation::AnimationPlugin;
+use beans_quest::attach::AttachPlugin;
+use beans_quest::audio::GameAudioPlugin;
+use beans_quest::auto_scroll::AutoScrollPlugin;
+use beans_quest::bean::BeanPlugin;
+use beans_quest::bounce::BouncePlugin;
+use beans_quest::breakable::BreakablePlugin;
+use beans_quest::breathing::BreathingPlugin;
+use beans_quest::camera::{CameraFollow, CameraPlugin, CameraShake, GameCamera};
+use beans_quest::charge_jump::ChargeJumpPlugin;
+use beans_quest::checkpoint::CheckpointPlugin;
+use beans_quest::confiner::ConfinerPlugin;
+use beans_quest::conveyor::ConveyorPlugin;
+use beans_quest::countdown::CountdownPlugin;
+use beans_quest::crouch::CrouchPlugin;
+use beans_quest::cutscene::CutscenePlugin;
+use beans_quest::damage_number::DamageNumberPlugin;
+use beans_quest::debug_draw::DebugDrawPlugin;
+use beans_quest::enemy::EnemyPlugin;
+use beans_quest::entity_registry::EntityRegistryPlugin;
+use beans_quest::flags::FlagsPlugin;
+use beans_quest::flash::FlashPlugin;
+use beans_quest::flip::FlipPlugin;
+use beans_quest::footsteps::FootstepsPlugin;
+use beans_quest::force_field::ForceFieldPlugin;
+use beans_quest::hang_time::HangTimePlugin;
+use beans_quest::health::HealthPlugin;
+use beans_quest::hud::HudPlugin;
+use beans_quest::input::InputPlugin;
+use beans_quest::interact::InteractPlugin;
+use beans_quest::ledge::LedgePlugin;
+use beans_quest::launch::{log_launch_warnings, LaunchOptions};
+use beans_quest::level::LevelPlugin;
+use beans_quest::magnet::MagnetPlugin;
+use beans_quest::menu::{MenuPlugin, UiAssets};
+use beans_quest::minimap::MinimapPlugin;
+use beans_quest::nine_slice::NineSlicePlugin;
+use beans_quest::parallax::ParallaxPlugin;
+use beans_quest::paths::LEVELS_FILE;
+use beans_quest::physics::{GameplayDeltaPlugin, PhysicsUnits};
+use beans_quest::platform::PlatformPlugin;
+use beans_quest::player::{load_player_atlas, spawn_player, PlayerPlugin};
+use beans_quest::projectile::ProjectilePlugin;
+use beans_quest::render_layers::{world_camera, RenderLayersPlugin};
+use beans_quest::respawn::RespawnPlugin;
+use beans_quest::results::ResultsPlugin;
+use beans_quest::save::SavePlugin;
+use beans_quest::settings::SettingsPlugin;
+use beans_quest::settings_menu::SettingsMenuPlugin;
+use beans_quest::sky::SkyPlugin;
+use beans_quest::smoothing::SmoothingPlugin;
+use beans_quest::state::{GameState, GameStatePlugin, GameplayEntity};
+use beans_quest::status_effect::StatusEffectPlugin;
+use beans_quest::terrain::TerrainPlugin;
+use beans_quest::tile_animation::TileAnimationPlugin;
+use beans_quest::toast::ToastPlugin;
+use beans_quest::trail::TrailPlugin;
+use beans_quest::y_sort::YSortPlugin;
+
 fn main() {
-    App::new()
+    let units = PhysicsUnits::default();
+    let options = LaunchOptions::parse(std::env::args().skip(1));
+
+    let mut app = App::new();
+    app
     .insert_resource(ClearColor(Color::BLACK))
     .add_plugins(DefaultPlugins.set(WindowPlugin {
         window: WindowDescriptor {
@@ -18,69 +83,123 @@ fn main() {
             ..Default::default()
         },
         ..default()
+    }).set(LogPlugin {
+        level: options.log_level,
+        ..default()
     }))
         .add_loading_state(
             LoadingState::new(GameState::AssetLoading)
-                .continue_to_state(GameState::Next)
+                .continue_to_state(options.first_state())
+                .with_collection::<UiAssets>()
         )
         .add_state(GameState::AssetLoading)
         .add_plugin(LdtkPlugin)
-        .add_plugin(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(100.0))
-        .add_plugin(RapierDebugRenderPlugin::default())
+        .insert_resource(units)
+        .insert_resource(RapierConfiguration {
+            gravity: units.gravity(),
+            ..default()
+        })
+        .add_plugin(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(units.pixels_per_meter))
+        .add_plugin(AmbientPlugin)
+        .add_plugin(AnimationPlugin)
+        .add_plugin(AttachPlugin)
+        .add_plugin(GameAudioPlugin)
+        .add_plugin(AutoScrollPlugin)
+        .add_plugin(BeanPlugin)
+        .add_plugin(BouncePlugin)
+        .add_plugin(BreakablePlugin)
+        .add_plugin(BreathingPlugin)
+        .add_plugin(CameraPlugin)
+        .add_plugin(ChargeJumpPlugin)
+        .add_plugin(CheckpointPlugin)
+        .add_plugin(ConfinerPlugin)
+        .add_plugin(ConveyorPlugin)
+        .add_plugin(CountdownPlugin)
+        .add_plugin(CrouchPlugin)
+        .add_plugin(CutscenePlugin)
+        .add_plugin(DamageNumberPlugin)
+        .add_plugin(DebugDrawPlugin)
+        .add_plugin(EnemyPlugin)
+        .add_plugin(EntityRegistryPlugin)
+        .add_plugin(FlagsPlugin)
+        .add_plugin(FlashPlugin)
+        .add_plugin(FlipPlugin)
+        .add_plugin(FootstepsPlugin)
+        .add_plugin(ForceFieldPlugin)
+        .add_plugin(GameplayDeltaPlugin)
+        .add_plugin(GameStatePlugin)
+        .add_plugin(HangTimePlugin)
+        .add_plugin(HealthPlugin)
+        .add_plugin(HudPlugin)
+        .add_plugin(InputPlugin)
+        .add_plugin(InteractPlugin)
+        .add_plugin(LedgePlugin)
+        .add_plugin(LevelPlugin)
+        .add_plugin(MagnetPlugin)
+        .add_plugin(MenuPlugin)
+        .add_plugin(MinimapPlugin)
+        .add_plugin(NineSlicePlugin)
+        .add_plugin(ParallaxPlugin)
+        .add_plugin(PlatformPlugin)
+        .add_plugin(PlayerPlugin)
+        .add_plugin(ProjectilePlugin)
+        .add_plugin(RenderLayersPlugin)
+        .add_plugin(RespawnPlugin)
+        .add_plugin(ResultsPlugin)
+        .add_plugin(SavePlugin)
+        .add_plugin(SettingsPlugin)
+        .add_plugin(SettingsMenuPlugin)
+        .add_plugin(SkyPlugin)
+        .add_plugin(SmoothingPlugin)
+        .add_plugin(StatusEffectPlugin)
+        .add_plugin(TerrainPlugin)
+        .add_plugin(TileAnimationPlugin)
+        .add_plugin(ToastPlugin)
+        .add_plugin(TrailPlugin)
+        .add_plugin(YSortPlugin)
         .add_startup_system(setup)
-        .add_startup_system(setup_physics)
-        .insert_resource(LevelSelection::Index(0))
-        .add_system_set(SystemSet::on_enter(GameState::Next).with_system(use_my_assets))
-        .add_system(print_ball_altitude)
-        .run();
-}
+        .add_startup_system(load_player_atlas)
+        .add_system_set(
+            SystemSet::on_enter(GameState::InGame)
+                .with_system(spawn_level)
+                .with_system(spawn_player)
+        )
+        .insert_resource(options.level.clone())
+        .add_startup_system(log_launch_warnings)
+        .insert_resource(options)
+        .add_system_set(SystemSet::on_enter(GameState::MainMenu).with_system(use_my_assets));
 
-#[derive(Component)]
-struct GameCamera;
+    #[cfg(feature = "dev")]
+    app
+        .add_startup_system(beans_quest::paths::check_asset_files)
+        .add_plugin(beans_quest::nan_guard::NanGuardPlugin)
+        .add_plugin(beans_quest::rewind::RewindPlugin)
+        .add_plugin(beans_quest::step_mode::StepModePlugin);
+    #[cfg(feature = "test-scene")]
+    app.add_plugin(beans_quest::test_scene::TestScenePlugin);
 
-fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    app.run();
+}
+
+fn setup(mut commands: Commands) {
     commands.spawn((
-        Camera2dBundle::default(), 
-        GameCamera
+        world_camera(),
+        GameCamera,
+        CameraFollow::default(),
+        CameraShake::default(),
     ));
+}
 
-    commands.spawn(
+fn spawn_level(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
         LdtkWorldBundle {
-            ldtk_handle: asset_server.load("maps/test_level.ldtk"),
+            ldtk_handle: asset_server.load(LEVELS_FILE),
             ..Default::default()
-        }
-    );
+        },
+        GameplayEntity,
+    ));
 }
 
 fn use_my_assets() {
     //TODO something
 }
-
-#[derive(Clone, Eq, PartialEq, Debug, Hash)]
-enum GameState {
-    AssetLoading,
-    Next,
-}
-
-/*
- * A test of the Rapier physics system
- */
-fn setup_physics(mut commands: Commands) {
-    /* Create the ground. */
-    commands
-        .spawn(Collider::cuboid(500.0, 50.0))
-        .insert(TransformBundle::from(Transform::from_xyz(0.0, -100.0, 0.0)));
-
-    /* Create the bouncing ball. */
-    commands
-        .spawn(RigidBody::Dynamic)
-        .insert(Collider::ball(50.0))
-        .insert(Restitution::coefficient(0.7))
-        .insert(TransformBundle::from(Transform::from_xyz(0.0, 400.0, 0.0)));
-}
-
-fn print_ball_altitude(positions: Query<&Transform, With<RigidBody>>) {
-    for transform in positions.iter() {
-        println!("Ball altitude: {}", transform.translation.y);
-    }
-}
\ No newline at end of file
@@ -0,0 +1,304 @@
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy_asset_loader::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::input::MenuInput;
+use crate::paths::{settings_path, UI_FONT};
+use crate::results::{format_time, LevelResults};
+use crate::settings::{save_settings, Settings, SettingsSnapshot};
+use crate::settings_menu::{settings_label, Rebinding, SettingsItem};
+use crate::state::GameState;
+use crate::toast::Toast;
+
+const TITLE_SIZE: f32 = 64.0;
+const ITEM_SIZE: f32 = 36.0;
+const LINE_SIZE: f32 = 28.0;
+const ITEM_COLOR: Color = Color::rgb(0.6, 0.6, 0.6);
+const SELECTED_COLOR: Color = Color::rgb(1.0, 0.85, 0.3);
+
+/// Assets every menu needs, loaded before the main menu opens.
+#[derive(Resource)]
+pub struct UiAssets {
+    pub font: Handle<Font>,
+}
+
+// Written out rather than derived, as the derive only takes paths as literals.
+impl AssetCollection for UiAssets {
+    fn create(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+        UiAssets { font: asset_server.get_handle(UI_FONT) }
+    }
+
+    fn load(world: &mut World) -> Vec<HandleUntyped> {
+        vec![world.resource::<AssetServer>().load_untyped(UI_FONT)]
+    }
+}
+
+/// What choosing a menu option does.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MenuAction {
+    Play,
+    Resume,
+    OpenSettings,
+    /// Adjust a setting; see `SettingsItem`.
+    Setting(SettingsItem),
+    /// Keep the settings as they are now and write them to the settings file.
+    SaveSettings,
+    /// Put the settings back as they were when the panel opened.
+    CancelSettings,
+    NextLevel,
+    QuitToMenu,
+    Exit,
+}
+
+/// A vertical list of options navigated with `MenuInput`.
+#[derive(Component)]
+pub struct Menu {
+    pub items: Vec<MenuAction>,
+    pub selected: usize,
+    /// What `MenuInput::back` does here, if anything.
+    pub back: Option<MenuAction>,
+}
+
+/// The option at this index of its parent `Menu`.
+#[derive(Component)]
+pub struct MenuItem(pub usize);
+
+#[derive(Component)]
+struct MainMenuRoot;
+
+/// Marker for the pause menu and its settings sub-panel, whichever is showing.
+#[derive(Component)]
+struct PauseMenuRoot;
+
+#[derive(Component)]
+struct ResultsMenuRoot;
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<MenuAction>()
+            .add_system_set(SystemSet::on_enter(GameState::MainMenu).with_system(spawn_main_menu))
+            .add_system_set(SystemSet::on_exit(GameState::MainMenu).with_system(despawn_all::<MainMenuRoot>))
+            .add_system_set(SystemSet::on_enter(GameState::Paused).with_system(spawn_pause_menu))
+            .add_system_set(SystemSet::on_exit(GameState::Paused).with_system(despawn_all::<PauseMenuRoot>))
+            .add_system_set(SystemSet::on_enter(GameState::LevelComplete).with_system(spawn_results_menu))
+            .add_system_set(
+                SystemSet::on_exit(GameState::LevelComplete).with_system(despawn_all::<ResultsMenuRoot>),
+            )
+            .add_system(navigate_menus)
+            .add_system(highlight_selection.after(navigate_menus))
+            .add_system(apply_menu_actions.after(navigate_menus));
+    }
+}
+
+/// Spawns a full-screen menu with a title, any `lines` of text under it, and one line per option, the first of
+/// them selected.
+fn spawn_menu(
+    commands: &mut Commands,
+    font: &Handle<Font>,
+    title: &str,
+    lines: &[String],
+    items: &[(&str, MenuAction)],
+    back: Option<MenuAction>,
+    marker: impl Component,
+) -> Entity {
+    let text = |value: &str, size: f32, color: Color| {
+        TextBundle::from_section(value, TextStyle { font: font.clone(), font_size: size, color })
+            .with_style(Style { margin: UiRect::all(Val::Px(8.0)), ..default() })
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    position_type: PositionType::Absolute,
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.7).into(),
+                ..default()
+            },
+            Menu {
+                items: items.iter().map(|(_, action)| *action).collect(),
+                selected: 0,
+                back,
+            },
+            marker,
+        ))
+        .with_children(|menu| {
+            menu.spawn(text(title, TITLE_SIZE, Color::WHITE));
+            for line in lines {
+                menu.spawn(text(line, LINE_SIZE, Color::WHITE));
+            }
+            for (index, (label, _)) in items.iter().enumerate() {
+                menu.spawn((text(label, ITEM_SIZE, ITEM_COLOR), MenuItem(index)));
+            }
+        })
+        .id()
+}
+
+fn spawn_main_menu(mut commands: Commands, ui: Res<UiAssets>) {
+    let items = [("Play", MenuAction::Play), ("Quit", MenuAction::Exit)];
+    spawn_menu(&mut commands, &ui.font, "Beans Quest", &[], &items, None, MainMenuRoot);
+}
+
+fn spawn_pause_menu(mut commands: Commands, ui: Res<UiAssets>) {
+    spawn_pause_panel(&mut commands, &ui.font);
+}
+
+fn spawn_pause_panel(commands: &mut Commands, font: &Handle<Font>) {
+    let items = [
+        ("Resume", MenuAction::Resume),
+        ("Settings", MenuAction::OpenSettings),
+        ("Quit to Main Menu", MenuAction::QuitToMenu),
+    ];
+    spawn_menu(commands, font, "Paused", &[], &items, Some(MenuAction::Resume), PauseMenuRoot);
+}
+
+fn spawn_settings_panel(commands: &mut Commands, font: &Handle<Font>, settings: &Settings) {
+    let rows: Vec<(String, MenuAction)> = SettingsItem::all()
+        .into_iter()
+        .map(|item| (settings_label(item, settings, Rebinding::default()), MenuAction::Setting(item)))
+        .collect();
+    let mut items: Vec<(&str, MenuAction)> = rows.iter().map(|(label, action)| (label.as_str(), *action)).collect();
+    items.push(("Save", MenuAction::SaveSettings));
+    items.push(("Cancel", MenuAction::CancelSettings));
+    spawn_menu(commands, font, "Settings", &[], &items, Some(MenuAction::CancelSettings), PauseMenuRoot);
+}
+
+/// After the last level there's nothing to go on to, so the results double as the ending.
+fn spawn_results_menu(mut commands: Commands, ui: Res<UiAssets>, results: Res<LevelResults>) {
+    let best = if results.is_new_best() {
+        "New best time!".to_string()
+    } else {
+        format!("Best: {}", format_time(results.previous_best.unwrap_or_default()))
+    };
+    let mut lines = vec![
+        format!("Time: {}", format_time(results.time)),
+        best,
+        format!("Beans: {} / {}", results.beans, results.beans_total),
+        format!("No damage: {}", if results.no_damage { "Yes" } else { "No" }),
+    ];
+
+    let (title, items) = match results.next_level {
+        Some(_) => ("Level Complete", vec![("Next Level", MenuAction::NextLevel), ("Menu", MenuAction::QuitToMenu)]),
+        None => {
+            lines.push("Thanks for playing!".to_string());
+            ("The End", vec![("Menu", MenuAction::QuitToMenu)])
+        }
+    };
+    spawn_menu(&mut commands, &ui.font, title, &lines, &items, None, ResultsMenuRoot);
+}
+
+fn despawn_all<T: Component>(mut commands: Commands, roots: Query<Entity, With<T>>) {
+    for root in roots.iter() {
+        commands.entity(root).despawn_recursive();
+    }
+}
+
+fn navigate_menus(input: Res<MenuInput>, mut menus: Query<&mut Menu>, mut actions: EventWriter<MenuAction>) {
+    for mut menu in menus.iter_mut() {
+        let count = menu.items.len();
+        if count == 0 {
+            continue;
+        }
+        if input.up {
+            menu.selected = (menu.selected + count - 1) % count;
+        }
+        if input.down {
+            menu.selected = (menu.selected + 1) % count;
+        }
+        if input.confirm {
+            actions.send(menu.items[menu.selected]);
+        } else if input.back {
+            if let Some(back) = menu.back {
+                actions.send(back);
+            }
+        }
+    }
+}
+
+fn highlight_selection(menus: Query<&Menu>, mut items: Query<(&MenuItem, &Parent, &mut Text)>) {
+    for (item, parent, mut text) in items.iter_mut() {
+        let Ok(menu) = menus.get(parent.get()) else { continue };
+        let color = if menu.selected == item.0 { SELECTED_COLOR } else { ITEM_COLOR };
+        for section in text.sections.iter_mut() {
+            section.style.color = color;
+        }
+    }
+}
+
+fn apply_menu_actions(
+    mut commands: Commands,
+    mut actions: EventReader<MenuAction>,
+    mut state: ResMut<State<GameState>>,
+    ui: Option<Res<UiAssets>>,
+    pause_panels: Query<Entity, With<PauseMenuRoot>>,
+    settings: Option<ResMut<Settings>>,
+    snapshot: Option<Res<SettingsSnapshot>>,
+    results: Option<Res<LevelResults>>,
+    selection: Option<ResMut<LevelSelection>>,
+    mut exit: EventWriter<AppExit>,
+    mut toasts: EventWriter<Toast>,
+) {
+    // Only the first choice of a frame counts; later ones would race the state change it queues.
+    let Some(action) = actions.iter().next().copied() else { return };
+    actions.clear();
+    // Menus only exist once loading has finished.
+    let Some(ui) = ui else { return };
+
+    match action {
+        MenuAction::Play => {
+            let _ = state.set(GameState::InGame);
+        }
+        MenuAction::Resume => {
+            let _ = state.pop();
+        }
+        MenuAction::OpenSettings | MenuAction::SaveSettings | MenuAction::CancelSettings => {
+            let Some(settings) = settings else { return };
+            for panel in pause_panels.iter() {
+                commands.entity(panel).despawn_recursive();
+            }
+            match action {
+                MenuAction::OpenSettings => {
+                    commands.insert_resource(SettingsSnapshot::take(&settings));
+                    spawn_settings_panel(&mut commands, &ui.font, &settings);
+                    return;
+                }
+                MenuAction::SaveSettings => {
+                    let path = settings_path();
+                    if let Err(error) = save_settings(&path, &settings) {
+                        error!("Couldn't write {}: {error}", path.display());
+                        toasts.send(Toast(format!("Couldn't save your settings: {error}")));
+                    }
+                }
+                _ => {
+                    if let Some(snapshot) = snapshot {
+                        snapshot.revert(settings);
+                    }
+                }
+            }
+            commands.remove_resource::<SettingsSnapshot>();
+            spawn_pause_panel(&mut commands, &ui.font);
+        }
+        MenuAction::Setting(_) => {}
+        MenuAction::NextLevel => {
+            let next = results.and_then(|results| results.next_level);
+            if let (Some(next), Some(mut selection)) = (next, selection) {
+                *selection = LevelSelection::Index(next);
+            }
+            let _ = state.pop();
+        }
+        MenuAction::QuitToMenu => {
+            let _ = state.replace(GameState::MainMenu);
+        }
+        MenuAction::Exit => exit.send(AppExit),
+    }
+}
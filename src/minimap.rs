@@ -0,0 +1,246 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::bean::Bean;
+use crate::entity_registry::world_position;
+use crate::level::LevelBounds;
+use crate::player::Player;
+use crate::state::{GameState, GameplayEntity};
+use crate::terrain::TerrainCollider;
+
+/// Shows and hides the minimap.
+const TOGGLE_KEY: KeyCode = KeyCode::M;
+/// The largest the minimap gets, in logical pixels; the level is scaled down to fit, keeping its proportions.
+const MINIMAP_MAX_SIZE: Vec2 = Vec2::new(200.0, 150.0);
+/// Distance from the top-right corner of the screen, in logical pixels.
+const MINIMAP_MARGIN: f32 = 16.0;
+const PLAYER_MARKER_SIZE: f32 = 5.0;
+const BEAN_MARKER_SIZE: f32 = 3.0;
+const BACKGROUND_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.6);
+const TERRAIN_COLOR: Color = Color::rgba(0.8, 0.8, 0.8, 0.8);
+const PLAYER_COLOR: Color = Color::rgb(0.2, 0.8, 0.2);
+const BEAN_COLOR: Color = Color::rgb(0.95, 0.6, 0.2);
+const COLLECTED_BEAN_COLOR: Color = Color::rgba(0.5, 0.5, 0.5, 0.6);
+
+/// Maps world positions onto the minimap: the level's bounds scaled down to fit `MINIMAP_MAX_SIZE`, with the origin
+/// in the top-left corner like UI positions.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MinimapTransform {
+    /// The world position of the level's bottom-left corner.
+    pub origin: Vec2,
+    /// Minimap pixels per world pixel.
+    pub scale: f32,
+    /// The size of the minimap, in logical pixels.
+    pub size: Vec2,
+}
+
+impl MinimapTransform {
+    /// The largest uniform scale that fits the whole level into `max_size`.
+    pub fn from_bounds(bounds: &LevelBounds, max_size: Vec2) -> Self {
+        let extents = (bounds.max - bounds.min).max(Vec2::ONE);
+        let scale = (max_size / extents).min_element();
+        MinimapTransform {
+            origin: bounds.min,
+            scale,
+            size: extents * scale,
+        }
+    }
+
+    /// Where `world` appears on the minimap, measured from its top-left corner.
+    pub fn to_minimap(&self, world: Vec2) -> Vec2 {
+        let scaled = (world - self.origin) * self.scale;
+        Vec2::new(scaled.x, self.size.y - scaled.y)
+    }
+
+    /// The top-left corner and size on the minimap of the world rectangle from `min` to `max`.
+    pub fn rect_to_minimap(&self, min: Vec2, max: Vec2) -> (Vec2, Vec2) {
+        (self.to_minimap(Vec2::new(min.x, max.y)), (max - min) * self.scale)
+    }
+}
+
+/// Whether the minimap is shown.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MinimapVisible(pub bool);
+
+impl Default for MinimapVisible {
+    fn default() -> Self {
+        MinimapVisible(true)
+    }
+}
+
+/// The minimap's panel. Everything drawn on it is a child.
+#[derive(Component)]
+pub struct Minimap;
+
+#[derive(Component)]
+pub struct MinimapPlayer;
+
+/// The marker for a bean, greyed out once the bean is collected.
+#[derive(Component)]
+pub struct MinimapBean(pub Entity);
+
+pub struct MinimapPlugin;
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<MinimapVisible>()
+            .add_system_set(SystemSet::on_enter(GameState::InGame).with_system(spawn_minimap))
+            .add_system(toggle_minimap)
+            .add_system(draw_minimap)
+            .add_system(move_player_marker.after(draw_minimap))
+            .add_system(mark_collected_beans.after(draw_minimap));
+    }
+}
+
+fn spawn_minimap(mut commands: Commands, visible: Res<MinimapVisible>) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    right: Val::Px(MINIMAP_MARGIN),
+                    top: Val::Px(MINIMAP_MARGIN),
+                    ..default()
+                },
+                display: if visible.0 { Display::Flex } else { Display::None },
+                ..default()
+            },
+            background_color: BACKGROUND_COLOR.into(),
+            ..default()
+        },
+        Minimap,
+        GameplayEntity,
+    ));
+}
+
+fn toggle_minimap(
+    keys: Option<Res<Input<KeyCode>>>,
+    mut visible: ResMut<MinimapVisible>,
+    mut minimaps: Query<&mut Style, With<Minimap>>,
+) {
+    let Some(keys) = keys else { return };
+    if !keys.just_pressed(TOGGLE_KEY) {
+        return;
+    }
+    visible.0 = !visible.0;
+    for mut style in minimaps.iter_mut() {
+        style.display = if visible.0 { Display::Flex } else { Display::None };
+    }
+}
+
+fn marker(position: Vec2, size: Vec2, color: Color) -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                left: Val::Px(position.x),
+                top: Val::Px(position.y),
+                ..default()
+            },
+            size: Size::new(Val::Px(size.x), Val::Px(size.y)),
+            ..default()
+        },
+        background_color: color.into(),
+        ..default()
+    }
+}
+
+/*
+ * Redraws the whole map whenever the level or what's in it changes: new bounds, terrain or beans. Positions are
+ * composed from local transforms, since a freshly spawned level's global transforms may not be propagated yet.
+ */
+#[allow(clippy::too_many_arguments)]
+fn draw_minimap(
+    mut commands: Commands,
+    bounds: Option<Res<LevelBounds>>,
+    mut minimaps: Query<(Entity, &mut Style), With<Minimap>>,
+    new_minimaps: Query<(), Added<Minimap>>,
+    new_content: Query<(), Or<(Added<TerrainCollider>, Added<Bean>)>>,
+    terrain: Query<(Entity, &Collider), With<TerrainCollider>>,
+    beans: Query<Entity, With<Bean>>,
+    transforms: Query<&Transform>,
+    parents: Query<&Parent>,
+) {
+    let Some(bounds) = bounds else { return };
+    if !bounds.is_changed() && new_minimaps.is_empty() && new_content.is_empty() {
+        return;
+    }
+    let map = MinimapTransform::from_bounds(&bounds, MINIMAP_MAX_SIZE);
+
+    for (minimap, mut style) in minimaps.iter_mut() {
+        style.size = Size::new(Val::Px(map.size.x), Val::Px(map.size.y));
+        let mut minimap = commands.entity(minimap);
+        minimap.despawn_descendants();
+        minimap.with_children(|minimap| {
+            for (entity, collider) in terrain.iter() {
+                // Terrain is always built from cuboids, see `terrain::spawn_terrain_colliders`.
+                let Some(cuboid) = collider.as_cuboid() else { continue };
+                let center = world_position(entity, &transforms, &parents);
+                let half_extents = cuboid.half_extents();
+                let (corner, size) = map.rect_to_minimap(center - half_extents, center + half_extents);
+                minimap.spawn(marker(corner, size, TERRAIN_COLOR));
+            }
+            for bean in beans.iter() {
+                let position = map.to_minimap(world_position(bean, &transforms, &parents));
+                minimap.spawn((
+                    marker(position - BEAN_MARKER_SIZE / 2.0, Vec2::splat(BEAN_MARKER_SIZE), BEAN_COLOR),
+                    MinimapBean(bean),
+                ));
+            }
+            minimap.spawn((marker(Vec2::ZERO, Vec2::splat(PLAYER_MARKER_SIZE), PLAYER_COLOR), MinimapPlayer));
+        });
+    }
+}
+
+fn move_player_marker(
+    bounds: Option<Res<LevelBounds>>,
+    players: Query<&Transform, With<Player>>,
+    mut markers: Query<&mut Style, With<MinimapPlayer>>,
+) {
+    let Some(bounds) = bounds else { return };
+    let Ok(player) = players.get_single() else { return };
+    let map = MinimapTransform::from_bounds(&bounds, MINIMAP_MAX_SIZE);
+    // Keep the marker on the map while the player is out of bounds, e.g. falling to the kill plane.
+    let position = map.to_minimap(player.translation.truncate()).clamp(Vec2::ZERO, map.size);
+    let position = position - PLAYER_MARKER_SIZE / 2.0;
+
+    for mut style in markers.iter_mut() {
+        let left = Val::Px(position.x);
+        let top = Val::Px(position.y);
+        if style.position.left != left || style.position.top != top {
+            style.position.left = left;
+            style.position.top = top;
+        }
+    }
+}
+
+/// Collected beans are despawned, which leaves their markers pointing at nothing.
+fn mark_collected_beans(beans: Query<(), With<Bean>>, mut markers: Query<(&MinimapBean, &mut BackgroundColor)>) {
+    for (marker, mut color) in markers.iter_mut() {
+        if beans.get(marker.0).is_err() && color.0 != COLLECTED_BEAN_COLOR {
+            color.0 = COLLECTED_BEAN_COLOR;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_the_level_into_the_minimap_corner_down() {
+        // A level twice as wide as it is tall, offset from the origin, fit into a square.
+        let bounds = LevelBounds { min: Vec2::new(100.0, 50.0), max: Vec2::new(900.0, 450.0) };
+        let minimap = MinimapTransform::from_bounds(&bounds, Vec2::new(200.0, 200.0));
+        assert_eq!(minimap.scale, 0.25);
+        assert_eq!(minimap.size, Vec2::new(200.0, 100.0));
+
+        assert_eq!(minimap.to_minimap(bounds.min), Vec2::new(0.0, 100.0));
+        assert_eq!(minimap.to_minimap(bounds.max), Vec2::new(200.0, 0.0));
+        assert_eq!(minimap.to_minimap(Vec2::new(500.0, 250.0)), Vec2::new(100.0, 50.0));
+
+        let (corner, size) = minimap.rect_to_minimap(Vec2::new(100.0, 50.0), Vec2::new(300.0, 130.0));
+        assert_eq!((corner, size), (Vec2::new(0.0, 80.0), Vec2::new(50.0, 20.0)));
+    }
+}
@@ -0,0 +1,150 @@
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+use bevy::utils::{get_short_name, HashSet};
+use bevy_rapier2d::prelude::*;
+
+use crate::player::{Player, PlayerSpawn};
+
+/// Watches for transforms that have gone NaN or infinite, which otherwise just make things vanish from the screen.
+#[derive(Resource, Clone, Debug)]
+pub struct NanGuard {
+    /// Put broken transforms back somewhere safe so play can go on: the player at their spawn point, anything else
+    /// at its parent's origin. Their velocity is cleared too if that's broken as well.
+    pub reset: bool,
+    /// Entities already reported, so one that stays broken isn't logged every frame.
+    reported: HashSet<Entity>,
+}
+
+impl Default for NanGuard {
+    fn default() -> Self {
+        NanGuard {
+            reset: true,
+            reported: HashSet::new(),
+        }
+    }
+}
+
+/// Sent the first frame an entity's transform is found non-finite, with the broken transform.
+#[derive(Clone, Copy, Debug)]
+pub struct NonFiniteTransform {
+    pub entity: Entity,
+    pub transform: Transform,
+}
+
+pub fn is_transform_finite(transform: &Transform) -> bool {
+    transform.translation.is_finite() && transform.rotation.is_finite() && transform.scale.is_finite()
+}
+
+/// `transform` with every non-finite part replaced: the translation by `safe_translation`, the rotation by none and
+/// the scale by one.
+pub fn sanitize_transform(transform: Transform, safe_translation: Vec3) -> Transform {
+    Transform {
+        translation: if transform.translation.is_finite() { transform.translation } else { safe_translation },
+        rotation: if transform.rotation.is_finite() { transform.rotation } else { Quat::IDENTITY },
+        scale: if transform.scale.is_finite() { transform.scale } else { Vec3::ONE },
+    }
+}
+
+pub struct NanGuardPlugin;
+
+impl Plugin for NanGuardPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<NanGuard>()
+            .add_event::<NonFiniteTransform>()
+            // Before propagation, so a reset transform never reaches `GlobalTransform`.
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                check_transforms.before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+/// Exclusive, to list every component of a broken entity in the report.
+fn check_transforms(world: &mut World) {
+    let broken: Vec<(Entity, Transform)> = world
+        .query::<(Entity, &Transform)>()
+        .iter(world)
+        .filter(|(_, transform)| !is_transform_finite(transform))
+        .map(|(entity, transform)| (entity, *transform))
+        .collect();
+
+    let Some(mut guard) = world.remove_resource::<NanGuard>() else { return };
+    guard.reported.retain(|entity| broken.iter().any(|(broken, _)| broken == entity));
+
+    for &(entity, transform) in &broken {
+        if guard.reported.insert(entity) {
+            let components: Vec<String> = world
+                .inspect_entity(entity)
+                .iter()
+                .map(|info| get_short_name(info.name()))
+                .collect();
+            error!("{entity:?} has a non-finite transform {transform:?}. Components: {}", components.join(", "));
+            world.send_event(NonFiniteTransform { entity, transform });
+        }
+        if guard.reset {
+            reset_entity(world, entity, transform);
+        }
+    }
+
+    world.insert_resource(guard);
+}
+
+fn reset_entity(world: &mut World, entity: Entity, transform: Transform) {
+    let safe_position = match world.get_resource::<PlayerSpawn>() {
+        Some(spawn) if world.get::<Player>(entity).is_some() => spawn.0,
+        _ => Vec2::ZERO,
+    };
+    let depth = if transform.translation.z.is_finite() { transform.translation.z } else { 0.0 };
+
+    let mut entity = world.entity_mut(entity);
+    if let Some(mut transform) = entity.get_mut::<Transform>() {
+        *transform = sanitize_transform(*transform, safe_position.extend(depth));
+    }
+    if let Some(mut velocity) = entity.get_mut::<Velocity>() {
+        if !velocity.linvel.is_finite() || !velocity.angvel.is_finite() {
+            *velocity = Velocity::zero();
+        }
+    }
+    warn!("Reset {:?}'s transform", entity.id());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reports(app: &App) -> Vec<Entity> {
+        let events = app.world.resource::<Events<NonFiniteTransform>>();
+        events.get_reader().iter(events).map(|report| report.entity).collect()
+    }
+
+    #[test]
+    fn nan_transform_is_reported_once() {
+        let mut app = App::new();
+        app.add_plugin(NanGuardPlugin);
+        app.world.resource_mut::<NanGuard>().reset = false;
+        let fine = app.world.spawn(Transform::from_xyz(1.0, 2.0, 0.0)).id();
+        let broken = app.world.spawn(Transform::from_xyz(f32::NAN, 2.0, 0.0)).id();
+
+        app.update();
+        assert_eq!(reports(&app), [broken]);
+        assert!(is_transform_finite(app.world.get::<Transform>(fine).unwrap()));
+        // Still broken, but already reported.
+        app.update();
+        app.update();
+        assert!(reports(&app).is_empty());
+    }
+
+    #[test]
+    fn nan_player_is_reset_to_the_spawn() {
+        let mut app = App::new();
+        app.insert_resource(PlayerSpawn(Vec2::new(64.0, 32.0))).add_plugin(NanGuardPlugin);
+        let velocity = Velocity::linear(Vec2::new(f32::INFINITY, 0.0));
+        let player = app.world.spawn((Player, Transform::from_xyz(f32::NAN, 0.0, 5.0), velocity)).id();
+
+        app.update();
+        assert_eq!(reports(&app), [player]);
+        assert_eq!(app.world.get::<Transform>(player).unwrap().translation, Vec3::new(64.0, 32.0, 5.0));
+        assert_eq!(*app.world.get::<Velocity>(player).unwrap(), Velocity::zero());
+    }
+}
@@ -0,0 +1,143 @@
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::sprite::Mesh2dHandle;
+
+/// A textured panel drawn at any `size` without distorting its borders.
+///
+/// `border` is the stretchable centre region of `texture` in texture pixels (y down, as in the image).
+/// Everything outside it is border: corners keep their size, edges stretch along one axis and the centre
+/// along both. Spawn it alongside a `SpatialBundle`; the mesh and material are filled in once the texture loads.
+#[derive(Component, Clone, Debug)]
+pub struct NineSlice {
+    pub texture: Handle<Image>,
+    pub border: Rect,
+    pub size: Vec2,
+}
+
+/// Grid lines of a 9-slice: the four vertex columns/rows in local space and their texture coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SliceGrid {
+    pub xs: [f32; 4],
+    pub ys: [f32; 4],
+    pub us: [f32; 4],
+    pub vs: [f32; 4],
+}
+
+pub struct NineSlicePlugin;
+
+impl Plugin for NineSlicePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(build_nine_slices);
+    }
+}
+
+/*
+ * Borders are clamped so that a panel smaller than its combined borders shrinks them
+ * proportionally rather than letting the corners overlap.
+ */
+pub fn slice_grid(size: Vec2, texture_size: Vec2, border: Rect) -> SliceGrid {
+    let mut left = border.min.x;
+    let mut right = texture_size.x - border.max.x;
+    let mut top = border.min.y;
+    let mut bottom = texture_size.y - border.max.y;
+
+    if left + right > size.x {
+        let shrink = size.x / (left + right);
+        left *= shrink;
+        right *= shrink;
+    }
+    if top + bottom > size.y {
+        let shrink = size.y / (top + bottom);
+        top *= shrink;
+        bottom *= shrink;
+    }
+
+    let half = size / 2.0;
+    SliceGrid {
+        xs: [-half.x, -half.x + left, half.x - right, half.x],
+        ys: [-half.y, -half.y + bottom, half.y - top, half.y],
+        us: [0.0, border.min.x / texture_size.x, border.max.x / texture_size.x, 1.0],
+        vs: [1.0, border.max.y / texture_size.y, border.min.y / texture_size.y, 0.0],
+    }
+}
+
+/// Builds a 4x4-vertex mesh covering the nine cells of `grid`.
+pub fn nine_slice_mesh(grid: &SliceGrid) -> Mesh {
+    let mut positions = Vec::with_capacity(16);
+    let mut normals = Vec::with_capacity(16);
+    let mut uvs = Vec::with_capacity(16);
+    for row in 0..4 {
+        for column in 0..4 {
+            positions.push([grid.xs[column], grid.ys[row], 0.0]);
+            normals.push([0.0, 0.0, 1.0]);
+            uvs.push([grid.us[column], grid.vs[row]]);
+        }
+    }
+
+    let mut indices = Vec::with_capacity(54);
+    for row in 0..3 {
+        for column in 0..3 {
+            let bottom_left = row * 4 + column;
+            let bottom_right = bottom_left + 1;
+            let top_left = bottom_left + 4;
+            let top_right = top_left + 1;
+            indices.extend([bottom_left, bottom_right, top_right, bottom_left, top_right, top_left]);
+        }
+    }
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh
+}
+
+/// (Re)builds the mesh of any panel that changed or is still waiting on its texture.
+fn build_nine_slices(
+    mut commands: Commands,
+    panels: Query<(Entity, &NineSlice, Option<&Handle<ColorMaterial>>), Or<(Changed<NineSlice>, Without<Mesh2dHandle>)>>,
+    images: Res<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (entity, panel, material) in panels.iter() {
+        let Some(image) = images.get(&panel.texture) else { continue };
+
+        let grid = slice_grid(panel.size, image.size(), panel.border);
+        let mut entity_commands = commands.entity(entity);
+        entity_commands.insert(Mesh2dHandle(meshes.add(nine_slice_mesh(&grid))));
+        if material.is_none() {
+            entity_commands.insert(materials.add(ColorMaterial::from(panel.texture.clone())));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXTURE: Vec2 = Vec2::new(32.0, 32.0);
+
+    fn border() -> Rect {
+        Rect::new(8.0, 8.0, 24.0, 24.0)
+    }
+
+    #[test]
+    fn corners_keep_their_size() {
+        let grid = slice_grid(Vec2::new(100.0, 60.0), TEXTURE, border());
+        assert_eq!(grid.xs, [-50.0, -42.0, 42.0, 50.0]);
+        assert_eq!(grid.ys, [-30.0, -22.0, 22.0, 30.0]);
+        assert_eq!(grid.us, [0.0, 0.25, 0.75, 1.0]);
+        assert_eq!(grid.vs, [1.0, 0.75, 0.25, 0.0]);
+    }
+
+    #[test]
+    fn small_panels_shrink_their_borders() {
+        let grid = slice_grid(Vec2::new(8.0, 100.0), TEXTURE, border());
+        assert_eq!(grid.xs, [-4.0, 0.0, 0.0, 4.0]);
+        assert_eq!(grid.ys, [-50.0, -42.0, 42.0, 50.0]);
+        // Only the positions shrink; the texture is still cut at the same place.
+        assert_eq!(grid.us, [0.0, 0.25, 0.75, 1.0]);
+    }
+}
@@ -0,0 +1,107 @@
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::camera::GameCamera;
+
+/// Parallax factor of the deepest background (the level's background image, if it has one) and of the background
+/// layer nearest the gameplay layers. Layers in between are spread evenly by depth.
+const FAR_PARALLAX: f32 = 0.25;
+const NEAR_PARALLAX: f32 = 0.75;
+
+/// Moves an entity by only `factor` of the camera's motion, so it appears further away the smaller the factor is.
+/// A factor of 1 moves with the world and 0 stays fixed to the screen.
+///
+/// `anchor` is the entity's local translation when the camera sits on its parent's origin.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Parallax {
+    pub factor: Vec2,
+    pub anchor: Vec3,
+}
+
+pub struct ParallaxPlugin;
+
+impl Plugin for ParallaxPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_system(parallax_from_level)
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                apply_parallax.before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+/// Whether an LDtk layer is background scenery by its identifier, e.g. "Bg", "BgFar" or "Background".
+pub fn is_background_layer(identifier: &str) -> bool {
+    let identifier = identifier.to_lowercase();
+    identifier.starts_with("bg") || identifier.contains("background")
+}
+
+/// The parallax factor of the background at `depth` out of `count`, where depth 0 is the furthest away.
+pub fn depth_factor(depth: usize, count: usize) -> f32 {
+    if count <= 1 {
+        return FAR_PARALLAX;
+    }
+    FAR_PARALLAX + (NEAR_PARALLAX - FAR_PARALLAX) * depth as f32 / (count - 1) as f32
+}
+
+/*
+ * bevy_ecs_ldtk has already spawned the level's background image (placed per its cover/contain/offset settings)
+ * and its tile layers as children of the level, stacked by z. Once the level is transformed, the background
+ * ones are picked out and given factors by that stacking order. A level without any is left alone.
+ */
+fn parallax_from_level(
+    mut commands: Commands,
+    mut level_events: EventReader<LevelEvent>,
+    levels: Query<(&Handle<LdtkLevel>, &Children)>,
+    level_assets: Res<Assets<LdtkLevel>>,
+    children: Query<
+        (&Transform, Option<&LayerMetadata>, Option<&TextureAtlasSprite>),
+        (Without<EntityInstance>, Without<Parallax>),
+    >,
+) {
+    for event in level_events.iter() {
+        let LevelEvent::Transformed(iid) = event else { continue };
+
+        let level = levels
+            .iter()
+            .find(|(handle, _)| level_assets.get(handle).is_some_and(|level| &level.level.iid == iid));
+        let Some((_, level_children)) = level else { continue };
+
+        let mut backgrounds: Vec<(Entity, Vec3)> = level_children
+            .iter()
+            .filter_map(|&child| {
+                let (transform, layer, sprite) = children.get(child).ok()?;
+                let is_background = match layer {
+                    Some(layer) => is_background_layer(&layer.identifier),
+                    // The only sprite sheet bevy_ecs_ldtk puts directly under a level is its background image.
+                    None => sprite.is_some(),
+                };
+                is_background.then_some((child, transform.translation))
+            })
+            .collect();
+        backgrounds.sort_by(|a, b| a.1.z.total_cmp(&b.1.z));
+
+        let count = backgrounds.len();
+        for (depth, (entity, anchor)) in backgrounds.into_iter().enumerate() {
+            let factor = Vec2::splat(depth_factor(depth, count));
+            commands.entity(entity).insert(Parallax { factor, anchor });
+        }
+    }
+}
+
+fn apply_parallax(
+    cameras: Query<&Transform, With<GameCamera>>,
+    parents: Query<&GlobalTransform>,
+    mut layers: Query<(&mut Transform, &Parallax, &Parent), Without<GameCamera>>,
+) {
+    let Ok(camera) = cameras.get_single() else { return };
+
+    for (mut transform, parallax, parent) in layers.iter_mut() {
+        let Ok(parent) = parents.get(parent.get()) else { continue };
+        let camera_offset = camera.translation.truncate() - parent.translation().truncate();
+        let offset = camera_offset * (Vec2::ONE - parallax.factor);
+        transform.translation = parallax.anchor + offset.extend(0.0);
+    }
+}
@@ -0,0 +1,155 @@
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use bevy::asset::FileAssetIo;
+use bevy::prelude::*;
+
+use crate::error::GameError;
+use crate::toast::Toast;
+
+/// The folder the game's files are kept in, inside the platform's config and data directories.
+pub const APP_DIR_NAME: &str = "beans_quest";
+pub const SETTINGS_FILE: &str = "settings.json";
+
+/// The game's assets, relative to the asset folder as the `AssetServer` takes them.
+pub const LEVELS_FILE: &str = "maps/test_level.ldtk";
+pub const UI_FONT: &str = "fonts/FiraSans-Bold.ttf";
+pub const PLAYER_ANIMATIONS: &str = "animations/player.anim.json";
+pub const PLAYER_SHEET: &str = "sprites/player.png";
+pub const SURFACE_SOUNDS: &str = "audio/surfaces.sounds.json";
+pub const AMBIENT_TINT_SHADER: &str = "shaders/ambient_tint.wgsl";
+/// Every asset above, checked for at startup in dev builds.
+pub const ASSET_FILES: &[&str] =
+    &[LEVELS_FILE, UI_FONT, PLAYER_ANIMATIONS, PLAYER_SHEET, SURFACE_SOUNDS, AMBIENT_TINT_SHADER];
+
+/// The kinds of file the game keeps, which platforms store in different places.
+///
+/// * `FileKind::Config` is preferences, such as the settings.
+///
+/// * `FileKind::Data` is progress, such as save slots.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileKind {
+    Config,
+    Data,
+}
+
+/// The platform's directory for `kind` of file, looked up from the environment through `var`: the XDG directories
+/// (or their defaults under `HOME`) on Linux and other Unixes, `Library/Application Support` on macOS and
+/// `APPDATA` on Windows. `None` if the variables it needs aren't set.
+pub fn platform_dir(kind: FileKind, var: impl Fn(&str) -> Option<OsString>) -> Option<PathBuf> {
+    let var = |name: &str| var(name).filter(|value| !value.is_empty()).map(PathBuf::from);
+    if cfg!(windows) {
+        var("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        var("HOME").map(|home| home.join("Library/Application Support"))
+    } else {
+        match kind {
+            FileKind::Config => var("XDG_CONFIG_HOME").or_else(|| var("HOME").map(|home| home.join(".config"))),
+            FileKind::Data => var("XDG_DATA_HOME").or_else(|| var("HOME").map(|home| home.join(".local/share"))),
+        }
+    }
+}
+
+/// `APP_DIR_NAME` inside `base`, created if it doesn't exist yet.
+pub fn ensure_app_dir(base: &Path) -> io::Result<PathBuf> {
+    let dir = base.join(APP_DIR_NAME);
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// The directory `kind` of file is kept in. Falls back to the working directory when the platform's directory
+/// can't be found or created, so the game can still save.
+pub fn app_dir(kind: FileKind) -> PathBuf {
+    let Some(base) = platform_dir(kind, |name| env::var_os(name)) else {
+        warn!("Couldn't find the {kind:?} directory, using the working directory");
+        return PathBuf::new();
+    };
+    ensure_app_dir(&base).unwrap_or_else(|error| {
+        warn!("Couldn't create {}, using the working directory: {error}", base.join(APP_DIR_NAME).display());
+        PathBuf::new()
+    })
+}
+
+/// Where the settings are saved.
+pub fn settings_path() -> PathBuf {
+    app_dir(FileKind::Config).join(SETTINGS_FILE)
+}
+
+/// The file name of save slot `slot`.
+pub fn save_slot_file(slot: u32) -> String {
+    format!("save{slot}.json")
+}
+
+/// Where save slot `slot` is saved.
+pub fn save_slot_path(slot: u32) -> PathBuf {
+    app_dir(FileKind::Data).join(save_slot_file(slot))
+}
+
+/// A `GameError::AssetMissing` for each entry of `ASSET_FILES` that isn't under the asset folder `root`.
+pub fn missing_assets(root: &Path) -> Vec<GameError> {
+    ASSET_FILES
+        .iter()
+        .filter(|path| !root.join(path).is_file())
+        .map(|path| GameError::AssetMissing(path.to_string()))
+        .collect()
+}
+
+/// Reports any asset the game refers to that isn't in the asset folder, so a renamed or misspelt path shows up
+/// straight away rather than when something first tries to load it.
+pub fn check_asset_files(mut toasts: EventWriter<Toast>) {
+    let root = FileAssetIo::get_base_path().join("assets");
+    for error in missing_assets(&root) {
+        error!("{error} under {}", root.display());
+        toasts.send(Toast(error.to_string()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An environment with just `vars` set.
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<OsString> {
+        let vars: Vec<(String, OsString)> = vars.iter().map(|&(name, value)| (name.into(), value.into())).collect();
+        move |name| vars.iter().find(|(var, _)| var == name).map(|(_, value)| value.clone())
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn platform_dirs_follow_xdg_then_home() {
+        let home = env(&[("HOME", "/home/bean")]);
+        assert_eq!(platform_dir(FileKind::Config, &home), Some(PathBuf::from("/home/bean/.config")));
+        assert_eq!(platform_dir(FileKind::Data, &home), Some(PathBuf::from("/home/bean/.local/share")));
+
+        let xdg = env(&[("HOME", "/home/bean"), ("XDG_CONFIG_HOME", "/cfg"), ("XDG_DATA_HOME", "")]);
+        assert_eq!(platform_dir(FileKind::Config, &xdg), Some(PathBuf::from("/cfg")));
+        // An empty variable counts as unset.
+        assert_eq!(platform_dir(FileKind::Data, &xdg), Some(PathBuf::from("/home/bean/.local/share")));
+
+        assert_eq!(platform_dir(FileKind::Config, env(&[])), None);
+    }
+
+    #[test]
+    fn app_dir_is_created_when_missing() {
+        let base = env::temp_dir().join(format!("beans_quest_{}_app_dir", std::process::id()));
+        let _ = fs::remove_dir_all(&base);
+
+        let dir = ensure_app_dir(&base).unwrap();
+        assert_eq!(dir, base.join(APP_DIR_NAME));
+        assert!(dir.is_dir());
+        // Already there is fine too.
+        assert_eq!(ensure_app_dir(&base).unwrap(), dir);
+        fs::remove_dir_all(&base).unwrap();
+
+        assert_eq!(save_slot_file(2), "save2.json");
+    }
+
+    #[test]
+    fn shipped_assets_are_all_present() {
+        assert!(missing_assets(Path::new("assets")).is_empty());
+        assert_eq!(missing_assets(&env::temp_dir().join("beans_quest_no_assets")).len(), ASSET_FILES.len());
+    }
+}
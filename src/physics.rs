@@ -0,0 +1,333 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use bevy_rapier2d::rapier::math::{Isometry, Vector};
+use gamelibs::aabb::{resolve_overlap, Aabb2};
+use nalgebra_glm::DVec2;
+
+/// Length in seconds of one physics step when stepping deterministically.
+pub const FIXED_TIMESTEP: f32 = 1.0 / 60.0;
+/// Downward acceleration in m/s².
+pub const GRAVITY: f32 = 9.81;
+/// How many frames after gameplay starts have their delta clamped by `DeltaGuard`.
+const DELTA_GUARD_FRAMES: u32 = 3;
+
+/// The scale between world pixels and physics meters.
+///
+/// Rapier is configured from this and gameplay values (sizes, speeds, gravity) are authored
+/// in meters and converted on the way in, so changing `pixels_per_meter` rescales everything.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct PhysicsUnits {
+    pub pixels_per_meter: f32,
+}
+
+impl Default for PhysicsUnits {
+    fn default() -> Self {
+        PhysicsUnits { pixels_per_meter: 100.0 }
+    }
+}
+
+impl PhysicsUnits {
+    pub fn px_to_m(&self, pixels: f32) -> f32 {
+        pixels / self.pixels_per_meter
+    }
+
+    pub fn m_to_px(&self, meters: f32) -> f32 {
+        meters * self.pixels_per_meter
+    }
+
+    /// `GRAVITY` as a world-space vector in px/s², for `RapierConfiguration::gravity`.
+    pub fn gravity(&self) -> Vec2 {
+        Vec2::NEG_Y * self.m_to_px(GRAVITY)
+    }
+}
+
+/// How many seconds Rapier advances the simulation in a frame that took `delta` seconds.
+pub fn physics_step(mode: TimestepMode, delta: f32) -> f32 {
+    match mode {
+        TimestepMode::Fixed { dt, .. } | TimestepMode::Interpolated { dt, .. } => dt,
+        TimestepMode::Variable { max_dt, time_scale, .. } => (delta * time_scale).min(max_dt),
+    }
+}
+
+/// `mode` running `scale` times as fast as real time. Fixed steps ignore it, as they never look at real time.
+pub fn with_time_scale(mode: TimestepMode, scale: f32) -> TimestepMode {
+    match mode {
+        TimestepMode::Fixed { .. } => mode,
+        TimestepMode::Variable { max_dt, substeps, .. } => TimestepMode::Variable { max_dt, time_scale: scale, substeps },
+        TimestepMode::Interpolated { dt, substeps, .. } => TimestepMode::Interpolated { dt, time_scale: scale, substeps },
+    }
+}
+
+/// How fast gameplay runs compared to real time, e.g. 0.5 for slow motion. Rapier is kept at the same scale.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct TimeScale(pub f32);
+
+impl Default for TimeScale {
+    fn default() -> Self {
+        TimeScale(1.0)
+    }
+}
+
+/// Freezes gameplay, physics included, for a moment when a hit lands so it feels heavy. Counted down in real time.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq)]
+pub struct Hitstop {
+    /// Seconds of freeze left.
+    pub remaining: f32,
+}
+
+impl Hitstop {
+    /// Freezes for `duration` seconds, or keeps a longer freeze already running.
+    pub fn hit(&mut self, duration: f32) {
+        self.remaining = self.remaining.max(duration);
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.remaining > 0.0
+    }
+
+    /// `scale` as it applies this frame: nothing moves during a hitstop.
+    pub fn time_scale(&self, scale: f32) -> f32 {
+        if self.is_active() { 0.0 } else { scale }
+    }
+}
+
+/// The frame delta in seconds that gameplay systems integrate with, so cooldowns and animations slow down with
+/// `TimeScale` and stop while paused or in a `Hitstop`: `Time::delta` scaled, clamped by `DeltaGuard`, and zero while
+/// the physics pipeline is off. UI and debug tools that should keep going through pauses use `Time`.
+///
+/// Rapier caps its own step through `TimestepMode::Variable::max_dt`, so this only matters to our systems.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq)]
+pub struct GameplayDelta(pub f32);
+
+/// Seconds of gameplay time until something can happen again, e.g. another shot or dash. Counted down on
+/// `GameplayDelta`, so it waits out pauses and slows with `TimeScale`.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq)]
+pub struct Cooldown {
+    /// The seconds a `start` waits.
+    pub duration: f32,
+    /// Seconds left before it's ready, 0 once it is.
+    pub remaining: f32,
+}
+
+impl Cooldown {
+    /// A cooldown of `duration` seconds, ready straight away.
+    pub fn new(duration: f32) -> Self {
+        Cooldown { duration, remaining: 0.0 }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.remaining <= 0.0
+    }
+
+    /// Waits the full `duration` again, e.g. once the thing it guards has been used.
+    pub fn start(&mut self) {
+        self.remaining = self.duration;
+    }
+
+    pub fn tick(&mut self, delta: f32) {
+        self.remaining = (self.remaining - delta).max(0.0);
+    }
+}
+
+/// Clamps `GameplayDelta` to `FIXED_TIMESTEP` for a few frames, so the long frame spent loading before play starts
+/// doesn't launch anything.
+#[derive(Resource, Clone, Copy, Debug, Default)]
+pub struct DeltaGuard {
+    pub frames_left: u32,
+}
+
+impl DeltaGuard {
+    /// Starts guarding the next few frames.
+    pub fn arm(&mut self) {
+        self.frames_left = DELTA_GUARD_FRAMES;
+    }
+}
+
+pub struct GameplayDeltaPlugin;
+
+impl Plugin for GameplayDeltaPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<GameplayDelta>()
+            .init_resource::<DeltaGuard>()
+            .init_resource::<TimeScale>()
+            .init_resource::<Hitstop>()
+            .add_system_to_stage(CoreStage::First, run_hitstop.after(bevy::time::TimeSystem))
+            .add_system_to_stage(CoreStage::First, update_gameplay_delta.after(run_hitstop))
+            .add_system_to_stage(CoreStage::First, scale_physics_time.after(run_hitstop))
+            .add_system(tick_cooldowns);
+    }
+}
+
+/// The delta gameplay should use this frame given the real one.
+pub fn guarded_delta(delta: f32, guarded: bool) -> f32 {
+    if guarded {
+        delta.min(FIXED_TIMESTEP)
+    } else {
+        delta
+    }
+}
+
+/// The delta gameplay should use this frame given the real one, the `TimeScale` and whether play is paused.
+pub fn scaled_delta(delta: f32, scale: f32, paused: bool, guarded: bool) -> f32 {
+    if paused {
+        0.0
+    } else {
+        guarded_delta(delta, guarded) * scale
+    }
+}
+
+/// Counts the hitstop down, so the frame it runs out on plays on as normal.
+fn run_hitstop(time: Res<Time>, mut hitstop: ResMut<Hitstop>) {
+    if hitstop.is_active() {
+        hitstop.remaining = (hitstop.remaining - time.delta_seconds()).max(0.0);
+    }
+}
+
+pub fn update_gameplay_delta(
+    time: Res<Time>,
+    scale: Res<TimeScale>,
+    hitstop: Res<Hitstop>,
+    rapier_config: Res<RapierConfiguration>,
+    mut guard: ResMut<DeltaGuard>,
+    mut delta: ResMut<GameplayDelta>,
+) {
+    let paused = !rapier_config.physics_pipeline_active;
+    delta.0 = scaled_delta(time.delta_seconds(), hitstop.time_scale(scale.0), paused, guard.frames_left > 0);
+    guard.frames_left = guard.frames_left.saturating_sub(1);
+}
+
+fn tick_cooldowns(delta: Res<GameplayDelta>, mut cooldowns: Query<&mut Cooldown>) {
+    for mut cooldown in cooldowns.iter_mut() {
+        if !cooldown.is_ready() {
+            cooldown.tick(delta.0);
+        }
+    }
+}
+
+/// Checked every frame rather than on change, as switching `SmoothingMode` replaces the timestep mode at full speed.
+fn scale_physics_time(scale: Res<TimeScale>, hitstop: Res<Hitstop>, mut rapier_config: ResMut<RapierConfiguration>) {
+    let mode = with_time_scale(rapier_config.timestep_mode, hitstop.time_scale(scale.0));
+    if rapier_config.timestep_mode != mode {
+        rapier_config.timestep_mode = mode;
+    }
+}
+
+/// The world-space bounding box of a collider, in pixels.
+pub fn collider_aabb(collider: &Collider, transform: &GlobalTransform) -> Aabb2 {
+    let (_, rotation, translation) = transform.to_scale_rotation_translation();
+    let angle = rotation.to_euler(EulerRot::ZYX).0;
+    let aabb = collider.raw.compute_aabb(&Isometry::new(Vector::new(translation.x, translation.y), angle));
+    Aabb2::new(
+        DVec2::new(aabb.mins.x as f64, aabb.mins.y as f64),
+        DVec2::new(aabb.maxs.x as f64, aabb.maxs.y as f64),
+    )
+}
+
+/// Solid colliders that never move: level geometry and fixed bodies.
+#[derive(SystemParam)]
+pub struct StaticColliders<'w, 's> {
+    colliders: Query<'w, 's, (&'static Collider, &'static GlobalTransform, Option<&'static RigidBody>), Without<Sensor>>,
+}
+
+impl<'w, 's> StaticColliders<'w, 's> {
+    pub fn aabbs(&self) -> Vec<Aabb2> {
+        self.colliders
+            .iter()
+            .filter(|(_, _, body)| matches!(body, None | Some(RigidBody::Fixed)))
+            .map(|(collider, transform, _)| collider_aabb(collider, transform))
+            .collect()
+    }
+
+    /// Where a box of `half_extents` teleported to `pos` should actually go so it isn't stuck inside anything.
+    pub fn resolve(&self, pos: Vec2, half_extents: Vec2) -> Vec2 {
+        let resolved = resolve_overlap(
+            DVec2::new(pos.x as f64, pos.y as f64),
+            DVec2::new(half_extents.x as f64, half_extents.y as f64),
+            &self.aabbs(),
+        );
+        Vec2::new(resolved.x as f32, resolved.y as f32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    #[test]
+    fn unit_conversion_round_trips() {
+        for pixels_per_meter in [16.0, 64.0, 100.0] {
+            let units = PhysicsUnits { pixels_per_meter };
+            assert_eq!(units.m_to_px(1.0), pixels_per_meter);
+            for meters in [0.0, 0.25, 3.5, -12.0] {
+                assert!((units.px_to_m(units.m_to_px(meters)) - meters).abs() < 1e-5);
+            }
+        }
+        assert!(PhysicsUnits::default().gravity().abs_diff_eq(Vec2::new(0.0, -981.0), 1e-3));
+    }
+
+    #[test]
+    fn large_first_delta_is_clamped() {
+        let mut app = App::new();
+        app
+            .init_resource::<Time>()
+            .insert_resource(RapierConfiguration::default())
+            .add_plugin(GameplayDeltaPlugin);
+
+        let mut now = Instant::now();
+        app.world.resource_mut::<Time>().update_with_instant(now);
+        app.world.resource_mut::<DeltaGuard>().arm();
+
+        // The frame after a long load, and the ones after it.
+        for _ in 0..DELTA_GUARD_FRAMES {
+            now += Duration::from_secs(3);
+            app.world.resource_mut::<Time>().update_with_instant(now);
+            app.update();
+            assert_eq!(app.world.resource::<GameplayDelta>().0, FIXED_TIMESTEP);
+        }
+
+        now += Duration::from_millis(50);
+        app.world.resource_mut::<Time>().update_with_instant(now);
+        app.update();
+        assert!((app.world.resource::<GameplayDelta>().0 - 0.05).abs() < 1e-6);
+    }
+
+    #[test]
+    fn cooldown_pauses_and_slows_with_gameplay_time() {
+        let mut app = App::new();
+        app
+            .init_resource::<Time>()
+            .insert_resource(RapierConfiguration::default())
+            .add_plugin(GameplayDeltaPlugin);
+        let mut now = Instant::now();
+        app.world.resource_mut::<Time>().update_with_instant(now);
+        let mut cooldown = Cooldown::new(1.0);
+        cooldown.start();
+        let cooldown = app.world.spawn(cooldown).id();
+
+        // Ten 50 ms frames at a time, returning the seconds left after them.
+        let mut run = |app: &mut App| {
+            for _ in 0..10 {
+                now += Duration::from_millis(50);
+                app.world.resource_mut::<Time>().update_with_instant(now);
+                app.update();
+            }
+            app.world.get::<Cooldown>(cooldown).unwrap().remaining
+        };
+
+        assert!((run(&mut app) - 0.5).abs() < 1e-4);
+
+        app.world.resource_mut::<RapierConfiguration>().physics_pipeline_active = false;
+        assert!((run(&mut app) - 0.5).abs() < 1e-4, "advanced while paused");
+
+        app.world.resource_mut::<RapierConfiguration>().physics_pipeline_active = true;
+        app.insert_resource(TimeScale(0.5));
+        assert!((run(&mut app) - 0.25).abs() < 1e-4, "half a second at half speed is a quarter");
+        assert!(!app.world.get::<Cooldown>(cooldown).unwrap().is_ready());
+        run(&mut app);
+        assert!(app.world.get::<Cooldown>(cooldown).unwrap().is_ready());
+    }
+}
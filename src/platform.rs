@@ -0,0 +1,179 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use bevy_rapier2d::plugin::systems;
+use bevy_rapier2d::prelude::*;
+
+use crate::physics::{physics_step, GameplayDelta};
+
+/// How far below a rider (in pixels) a platform still counts as carrying them.
+const RIDE_PROBE_DISTANCE: f32 = 2.0;
+
+/// A kinematic platform that travels back and forth between `origin` and `origin + travel` once every `period`
+/// seconds, easing at each end, while turning at `angular_velocity` radians per second.
+///
+/// The entity needs `RigidBody::KinematicPositionBased`; its transform is moved here every frame.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct MovingPlatform {
+    pub origin: Vec2,
+    pub travel: Vec2,
+    pub period: f32,
+    pub angular_velocity: f32,
+    pub elapsed: f32,
+}
+
+impl MovingPlatform {
+    pub fn new(origin: Vec2, travel: Vec2, period: f32) -> Self {
+        MovingPlatform {
+            origin,
+            travel,
+            period,
+            angular_velocity: 0.0,
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn with_spin(self, angular_velocity: f32) -> Self {
+        MovingPlatform { angular_velocity, ..self }
+    }
+
+    /// Where the platform is and how far it has turned at its current time.
+    pub fn pose(&self) -> (Vec2, f32) {
+        let progress = if self.period > 0.0 {
+            0.5 - 0.5 * (TAU * self.elapsed / self.period).cos()
+        } else {
+            0.0
+        };
+        (self.origin + self.travel * progress, self.angular_velocity * self.elapsed)
+    }
+}
+
+/// The platform's transform before its latest move, so riders can follow the same step.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct PlatformStep {
+    pub previous: Transform,
+}
+
+/// Something that can be carried by the moving platform it stands on.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct Rider {
+    pub platform: Option<Entity>,
+    /// The velocity in pixels per second the platform is lending the rider for this step. It's handed over for
+    /// good when they leave.
+    pub carry_velocity: Vec2,
+}
+
+pub struct PlatformPlugin;
+
+impl Plugin for PlatformPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_system(move_platforms)
+            // After all of gameplay has had its say on velocities, but before Rapier reads them.
+            .add_system_to_stage(
+                PhysicsStages::SyncBackend,
+                carry_riders.before(systems::apply_rigid_body_user_changes),
+            )
+            .add_system_to_stage(CoreStage::PostUpdate, release_riders);
+    }
+}
+
+/// Where `point`, fixed to a body moving from `from` to `to`, ends up: rotated about the body by the change in its
+/// rotation and then moved with it.
+pub fn carry_point(point: Vec2, from: &Transform, to: &Transform) -> Vec2 {
+    let local = from.rotation.inverse() * (point.extend(0.0) - from.translation);
+    (to.translation + to.rotation * local).truncate()
+}
+
+fn move_platforms(
+    mut commands: Commands,
+    delta: Res<GameplayDelta>,
+    mut platforms: Query<(Entity, &mut MovingPlatform, &mut Transform, Option<&mut PlatformStep>)>,
+) {
+    for (entity, mut platform, mut transform, step) in platforms.iter_mut() {
+        match step {
+            Some(mut step) => step.previous = *transform,
+            None => {
+                commands.entity(entity).insert(PlatformStep { previous: *transform });
+            }
+        }
+
+        platform.elapsed += delta.0;
+        let (position, angle) = platform.pose();
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+        transform.rotation = Quat::from_rotation_z(angle);
+    }
+}
+
+/*
+ * Runs after the platforms have moved, just before Rapier picks up the frame's changes. A rider is given the velocity
+ * that takes them exactly as far as the platform moves the point they stand on (so rotating platforms swing them
+ * round too), which also leaves them at rest relative to the platform as far as the solver is concerned. The extra
+ * velocity is taken off again once the step is done. Whatever the rider stands on is found by sweeping their
+ * collider a little way down, which still finds a tilted platform under one corner.
+ */
+fn carry_riders(
+    time: Res<Time>,
+    rapier_config: Res<RapierConfiguration>,
+    rapier_context: Res<RapierContext>,
+    platforms: Query<(&Transform, Option<&PlatformStep>), (With<MovingPlatform>, Without<Rider>)>,
+    mut riders: Query<(Entity, &Transform, &mut Rider, &Collider, &mut Velocity)>,
+) {
+    let dt = physics_step(rapier_config.timestep_mode, time.delta_seconds());
+
+    for (entity, transform, mut rider, collider, mut velocity) in riders.iter_mut() {
+        let is_platform = |entity| platforms.contains(entity);
+        let probe = rapier_context.cast_shape(
+            transform.translation.truncate(),
+            0.0,
+            Vec2::NEG_Y,
+            collider,
+            RIDE_PROBE_DISTANCE,
+            QueryFilter::default().exclude_rigid_body(entity).predicate(&is_platform),
+        );
+        let platform = probe.map(|(platform, _)| platform);
+
+        // Stepping or jumping off keeps the platform's momentum instead of stopping dead in the air.
+        if rider.platform.is_some() && platform.is_none() {
+            velocity.linvel += rider.carry_velocity;
+        }
+        rider.platform = platform;
+        rider.carry_velocity = Vec2::ZERO;
+
+        let Some((platform, Some(step))) = platform.and_then(|platform| platforms.get(platform).ok()) else { continue };
+        if dt <= 0.0 {
+            continue;
+        }
+        let position = transform.translation.truncate();
+        rider.carry_velocity = (carry_point(position, &step.previous, platform) - position) / dt;
+        velocity.linvel += rider.carry_velocity;
+    }
+}
+
+fn release_riders(mut riders: Query<(&Rider, &mut Velocity)>) {
+    for (rider, mut velocity) in riders.iter_mut() {
+        velocity.linvel -= rider.carry_velocity;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+
+    #[test]
+    fn rotating_platform_carries_a_point_round() {
+        let from = Transform::from_xyz(10.0, 0.0, 0.0);
+        let to = Transform::from_xyz(10.0, 5.0, 0.0).with_rotation(Quat::from_rotation_z(FRAC_PI_2));
+
+        // A quarter turn swings a point on the right edge up to the top, on top of moving with the platform.
+        let carried = carry_point(Vec2::new(12.0, 0.0), &from, &to);
+        assert!(carried.abs_diff_eq(Vec2::new(10.0, 7.0), 1e-5));
+
+        // The point stays where it was relative to the platform, so carrying it back undoes the move.
+        assert!(carry_point(carried, &to, &from).abs_diff_eq(Vec2::new(12.0, 0.0), 1e-5));
+        assert_eq!(carry_point(Vec2::new(3.0, 4.0), &from, &from), Vec2::new(3.0, 4.0));
+    }
+}
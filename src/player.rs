@@ -0,0 +1,728 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use gamelibs::math::{clamp_timestep, weighted_step, CurveStyle, CurveType, DEFAULT_MAX_STEP};
+use gamelibs::state_machine::StateMachine;
+
+use crate::animation::SpriteAnimation;
+use crate::breathing::Breathing;
+use crate::charge_jump::ChargeJump;
+use crate::crouch::{Crouch, CrouchConfig, CrouchState};
+use crate::footsteps::Footsteps;
+use crate::hang_time::{HangTime, HangTimeConfig};
+use crate::health::Health;
+use crate::input::{buffer_actions, Action, InputBuffer, InputState};
+use crate::ledge::LedgeGrab;
+use crate::paths::PLAYER_SHEET;
+use crate::physics::{GameplayDelta, PhysicsUnits, GRAVITY};
+use crate::platform::Rider;
+use crate::state::{GameState, GameplayEntity};
+use crate::status_effect::StatusEffects;
+use crate::surface::SurfaceMaterial;
+use crate::trail::Trail;
+use crate::y_sort::YSort;
+
+/// Player collider size in meters.
+const PLAYER_SIZE: Vec2 = Vec2::new(0.3, 0.5);
+/// The size of one frame of `PLAYER_SHEET`, in texture pixels, and how the frames are laid out on it. The layout
+/// has to match the columns and rows `PLAYER_ANIMATIONS` was authored against.
+const PLAYER_FRAME_SIZE: Vec2 = Vec2::new(32.0, 48.0);
+const PLAYER_SHEET_COLUMNS: usize = 4;
+const PLAYER_SHEET_ROWS: usize = 2;
+/// How far below the player's feet (in pixels) ground still counts as underfoot.
+const GROUND_PROBE_DISTANCE: f32 = 2.0;
+/// How far below the player's feet (in pixels) a grounded player is pulled back down onto the ground, so small
+/// bumps and seams don't launch them into the air.
+const GROUND_SNAP_DISTANCE: f32 = 6.0;
+/// How long before landing a jump press is remembered.
+const JUMP_BUFFER: Duration = Duration::from_millis(120);
+/// Horizontal speed (in m/s) above which the run animation plays instead of idle.
+const RUN_ANIMATION_SPEED: f32 = 0.1;
+const PLAYER_MAX_HEALTH: f32 = 5.0;
+/// How far ahead of the player's side (in pixels) a step is looked for, on top of the distance covered while
+/// stepping up.
+const STEP_PROBE_REACH: f32 = 2.0;
+/// How long lifting onto a step takes, in seconds.
+const STEP_UP_TIME: f32 = 0.06;
+/// The player leaves afterimages while moving faster than this (in m/s), quicker than they can run.
+const PLAYER_TRAIL_MIN_SPEED: f32 = 6.0;
+const PLAYER_TRAIL_LIFETIME: f32 = 0.25;
+const PLAYER_TRAIL_INTERVAL: f32 = 0.05;
+/// Below this speed (in m/s) a slowing player is stopped outright, since exponential and curve-based stops would
+/// otherwise creep on forever.
+const STOP_SPEED: f32 = 0.05;
+
+/// Marker for the entity the player controls.
+#[derive(Component)]
+pub struct Player;
+
+/// Marker for the player's sprite, a descendant of the player so it can be turned without turning their collider.
+#[derive(Component)]
+pub struct PlayerSprite;
+
+/// The texture atlas the player's sprite is drawn from. Empty until `load_player_atlas` has run, which leaves
+/// the player undrawn but otherwise working, as in headless tests.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct PlayerAtlas(pub Handle<TextureAtlas>);
+
+/// Mid-air jumps the player has left before they have to land.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct AirJumps(pub u32);
+
+/// The height (in world pixels) the player's feet are being lifted to while stepping onto a ledge.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct StepUp(pub Option<f32>);
+
+/// Where the player is put back when they die or fall out of the level.
+#[derive(Resource, Clone, Copy, Debug, Default)]
+pub struct PlayerSpawn(pub Vec2);
+
+/// The rate at which speed changes along a curve-based stop, in m/s², carried between frames.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct Braking(pub f32);
+
+/// How the player's speed bleeds off with no input.
+///
+/// * `Deceleration::Linear` loses `MoveConfig::deceleration` m/s every second, for a short, snappy stop.
+///
+/// * `Deceleration::Exponential { half_life }` halves the speed every `half_life` seconds: a long slide that sheds
+///   speed quickly at first and slowly comes to rest.
+///
+/// * `Deceleration::Curve(style)` follows a `CurveStyle` response down to zero, e.g. `CurveStyle::SmoothDamped` to
+///   ease into the stop. Underdamped curves stop at zero rather than swinging the player back.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Deceleration {
+    #[default]
+    Linear,
+    Exponential { half_life: f32 },
+    Curve(CurveStyle),
+}
+
+/// Horizontal movement tuning, in meters and seconds.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct MoveConfig {
+    /// Top running speed in m/s.
+    pub max_speed: f32,
+    /// How quickly speed builds towards the input direction, in m/s².
+    pub acceleration: f32,
+    /// How quickly speed bleeds off with no input, in m/s², for `Deceleration::Linear`.
+    pub deceleration: f32,
+    /// How the player stops with no input. On the ground the surface's material decides, see
+    /// `SurfaceMaterial::deceleration`.
+    pub stopping: Deceleration,
+    /// Peak height of a jump in meters.
+    pub jump_height: f32,
+    /// How many more times the player can jump before landing again.
+    pub air_jumps: u32,
+    /// Scales acceleration and deceleration while airborne: 1 steers like on the ground, 0 keeps the horizontal
+    /// velocity the player left the ground with.
+    pub air_control: f32,
+    /// Ledges up to this high (in meters) are stepped onto instead of blocking the player.
+    pub step_height: f32,
+    /// How far past the player's sides (in meters) the ground probe reaches, so a player walking off an edge stays
+    /// grounded until their collider has slipped a little way past it, not just their centre.
+    pub edge_extension: f32,
+    /// How a grounded player running faster than `max_speed`, say after landing from a dash or a fast fall, comes
+    /// back down to it while they keep running that way. `None` cuts them back at `acceleration`, all but
+    /// instantly; otherwise the speed over the cap bleeds off like a stop, so the momentum carries on for a while.
+    pub momentum_decay: Option<Deceleration>,
+}
+
+impl MoveConfig {
+    /// This config with acceleration and deceleration scaled by a surface's traction.
+    pub fn with_traction(&self, traction: f32) -> Self {
+        MoveConfig {
+            acceleration: self.acceleration * traction,
+            deceleration: self.deceleration * traction,
+            ..*self
+        }
+    }
+}
+
+impl Default for MoveConfig {
+    fn default() -> Self {
+        MoveConfig {
+            max_speed: 4.0,
+            acceleration: 40.0,
+            deceleration: 50.0,
+            stopping: Deceleration::Linear,
+            jump_height: 1.5,
+            air_jumps: 1,
+            air_control: 1.0,
+            step_height: 0.2,
+            edge_extension: 0.05,
+            momentum_decay: None,
+        }
+    }
+}
+
+/// Things that happen to the player, for anything that reacts to them (flags, effects, sounds).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PlayerEvent {
+    Damaged { amount: f32 },
+    Jumped,
+    /// A jump in mid-air, such as a double jump.
+    AirJumped,
+    Landed,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlayerState {
+    Grounded,
+    Airborne,
+}
+
+#[derive(Component, Deref, DerefMut)]
+pub struct PlayerStateMachine(pub StateMachine<PlayerState>);
+
+/// The material of the ground under the player, if they're standing on any.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct GroundSurface(pub Option<SurfaceMaterial>);
+
+pub struct PlayerPlugin;
+
+impl Plugin for PlayerPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<PlayerSpawn>()
+            .init_resource::<PlayerAtlas>()
+            .init_resource::<MoveConfig>()
+            .init_resource::<InputState>()
+            .add_event::<PlayerEvent>()
+            .insert_resource(InputBuffer::<Action>::new(JUMP_BUFFER))
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_system(update_player_state)
+                    .with_system(buffer_actions)
+                    .with_system(move_player)
+                    .with_system(step_up.after(move_player).after(update_player_state))
+                    .with_system(jump.after(update_player_state).after(buffer_actions))
+                    .with_system(animate_player.after(update_player_state)),
+            );
+    }
+}
+
+/// Returns whether a downward probe from the player's centre found ground close enough to stand on.
+pub fn is_grounded(probe_hit: Option<f32>, half_height: f32, tolerance: f32) -> bool {
+    match probe_hit {
+        Some(distance) => distance <= half_height + tolerance,
+        None => false,
+    }
+}
+
+/// Returns whether a probe beside the player, from the height of their centre, found ground level with their feet.
+/// Ground well above their feet is the side of a wall rather than an edge they're standing on.
+pub fn is_on_edge(probe_hit: Option<f32>, half_height: f32, tolerance: f32) -> bool {
+    match probe_hit {
+        Some(distance) => (distance - half_height).abs() <= tolerance,
+        None => false,
+    }
+}
+
+/// Returns whether a player who was grounded last frame and is now `probe_hit` pixels above the ground should be
+/// snapped back onto it: only when the ground is within `snap_distance` of their feet and they aren't rising.
+pub fn should_snap_to_ground(probe_hit: Option<f32>, half_height: f32, snap_distance: f32, vertical_velocity: f32) -> bool {
+    match probe_hit {
+        Some(distance) => vertical_velocity <= 0.0 && distance <= half_height + snap_distance,
+        None => false,
+    }
+}
+
+/// Steps the horizontal velocity (in m/s) towards `input * max_speed`, accelerating while there is input and
+/// stopping as `config.stopping` says when there isn't. Off the ground both are slowed down by `air_control`. On
+/// the ground, running faster than `max_speed` the way the input points slows down by `config.momentum_decay`
+/// when it's set. `braking` carries a curve-based stop over from one frame to the next.
+pub fn compute_horizontal_velocity(
+    current: f32,
+    input: f32,
+    grounded: bool,
+    config: &MoveConfig,
+    dt: f32,
+    braking: &mut f32,
+) -> f32 {
+    let input = input.clamp(-1.0, 1.0);
+    let control = if grounded { 1.0 } else { config.air_control.max(0.0) };
+    if input == 0.0 {
+        return decelerate(current, config, dt * control, braking);
+    }
+
+    let target = input * config.max_speed;
+    if let Some(decay) = config.momentum_decay.filter(|_| grounded && current * target > target * target) {
+        let overspeed = MoveConfig { stopping: decay, ..*config };
+        return target + decelerate(current - target, &overspeed, dt, braking);
+    }
+
+    *braking = 0.0;
+    let max_change = config.acceleration * control * dt;
+    current + (target - current).clamp(-max_change, max_change)
+}
+
+/// Slows `current` (in m/s) towards zero over `dt` seconds by the config's stopping model.
+fn decelerate(current: f32, config: &MoveConfig, dt: f32, braking: &mut f32) -> f32 {
+    let next = match config.stopping {
+        Deceleration::Linear => {
+            *braking = 0.0;
+            let max_change = config.deceleration * dt;
+            return current - current.clamp(-max_change, max_change);
+        }
+        Deceleration::Exponential { half_life } => {
+            *braking = 0.0;
+            if half_life > 0.0 { current * 0.5f32.powf(dt / half_life) } else { 0.0 }
+        }
+        Deceleration::Curve(style) => {
+            let Some(dt) = clamp_timestep(dt as f64, DEFAULT_MAX_STEP) else { return current };
+            let curve = CurveType::from_style(style);
+            let (next, rate) = weighted_step(&curve, dt, 0.0, 0.0, current as f64, *braking as f64);
+            *braking = rate as f32;
+            next as f32
+        }
+    };
+
+    // Come to rest instead of creeping on forever or swinging back the other way.
+    if next.abs() < STOP_SPEED || next.signum() != current.signum() {
+        *braking = 0.0;
+        0.0
+    } else {
+        next
+    }
+}
+
+/// Returns whether the player should step onto an obstacle `obstacle_height` pixels above their feet, as measured by
+/// a probe down onto it (`None` when nothing was found), given whether a probe found room for them on top of it.
+pub fn can_step_up(obstacle_height: Option<f32>, step_height: f32, headroom_clear: bool) -> bool {
+    match obstacle_height {
+        Some(height) => height > 0.0 && height <= step_height && headroom_clear,
+        None => false,
+    }
+}
+
+/// The launch speed (in m/s) that peaks at `height` meters under `gravity` m/s².
+pub fn jump_velocity(height: f32, gravity: f32) -> f32 {
+    (2.0 * gravity * height).sqrt()
+}
+
+/// Cuts `PLAYER_SHEET` into the `PlayerAtlas`.
+pub fn load_player_atlas(
+    asset_server: Res<AssetServer>,
+    mut atlases: ResMut<Assets<TextureAtlas>>,
+    mut player_atlas: ResMut<PlayerAtlas>,
+) {
+    let sheet = asset_server.load(PLAYER_SHEET);
+    let columns = PLAYER_SHEET_COLUMNS;
+    let atlas = TextureAtlas::from_grid(sheet, PLAYER_FRAME_SIZE, columns, PLAYER_SHEET_ROWS, None, None);
+    player_atlas.0 = atlases.add(atlas);
+}
+
+/// Spawns the player at `PlayerSpawn`. The app decides when: at startup, or whenever a play session begins.
+pub fn spawn_player(
+    mut commands: Commands,
+    units: Res<PhysicsUnits>,
+    spawn: Res<PlayerSpawn>,
+    atlas: Res<PlayerAtlas>,
+) {
+    let size = PLAYER_SIZE * units.pixels_per_meter;
+
+    commands
+        .spawn((
+            SpatialBundle::from_transform(Transform::from_translation(spawn.0.extend(1.0))),
+            RigidBody::Dynamic,
+            Collider::cuboid(size.x / 2.0, size.y / 2.0),
+            LockedAxes::ROTATION_LOCKED,
+            // The controller owns horizontal speed, so ground friction mustn't fight it.
+            Friction {
+                coefficient: 0.0,
+                combine_rule: CoefficientCombineRule::Min,
+            },
+            Velocity::zero(),
+            Player,
+            PlayerStateMachine(StateMachine::new(PlayerState::Airborne)),
+            GroundSurface::default(),
+            AirJumps::default(),
+            ChargeJump::default(),
+            Rider::default(),
+            SpriteAnimation::new("idle"),
+            Health::new(PLAYER_MAX_HEALTH),
+            GameplayEntity,
+        ))
+        .insert((
+            Crouch::default(),
+            StepUp::default(),
+            StatusEffects::default(),
+            HangTime::default(),
+            Braking::default(),
+            LedgeGrab::default(),
+            GravityScale(1.0),
+            Footsteps::default(),
+            // Sorted by their feet, where they meet the ground.
+            YSort { offset: -size.y / 2.0 },
+            Trail::new(PLAYER_TRAIL_LIFETIME, PLAYER_TRAIL_INTERVAL, units.m_to_px(PLAYER_TRAIL_MIN_SPEED)),
+        ))
+        .with_children(|player| {
+            player.spawn((SpatialBundle::default(), Breathing::default())).with_children(|pivot| {
+                pivot.spawn((
+                    SpriteSheetBundle {
+                        sprite: TextureAtlasSprite {
+                            custom_size: Some(size),
+                            ..default()
+                        },
+                        texture_atlas: atlas.0.clone(),
+                        ..default()
+                    },
+                    PlayerSprite,
+                ));
+            });
+        });
+}
+
+/*
+ * The ground is found by a ray straight down from the player's centre. Once that misses, a player who was grounded
+ * stays so while a ray just past either side of their collider, `MoveConfig::edge_extension` out, still finds ground
+ * level with their feet: they're standing on the edge, or have only just stepped off it. That lasts until they've
+ * fallen past `GROUND_PROBE_DISTANCE`, a few frames, so it forgives a late jump without letting them stand on air.
+ */
+pub fn update_player_state(
+    delta: Res<GameplayDelta>,
+    config: Res<MoveConfig>,
+    units: Res<PhysicsUnits>,
+    rapier_context: Res<RapierContext>,
+    surfaces: Query<&SurfaceMaterial>,
+    mut events: EventWriter<PlayerEvent>,
+    mut players: Query<
+        (Entity, &mut Transform, &mut Velocity, &mut PlayerStateMachine, &mut GroundSurface, &Collider),
+        With<Player>,
+    >,
+) {
+    for (entity, mut transform, mut velocity, mut state, mut ground, collider) in players.iter_mut() {
+        state.tick(delta.0 as f64);
+        // How far the player's feet are below their centre, measured off the collider since crouching shrinks it.
+        let aabb = collider.raw.compute_local_aabb();
+        let half_height = -aabb.mins.y;
+
+        let cast = |offset: f32| {
+            rapier_context.cast_ray(
+                transform.translation.truncate() + Vec2::new(offset, 0.0),
+                Vec2::NEG_Y,
+                half_height + GROUND_SNAP_DISTANCE.max(GROUND_PROBE_DISTANCE),
+                true,
+                QueryFilter::default().exclude_rigid_body(entity),
+            )
+        };
+        let mut probe = cast(0.0);
+        let distance = probe.map(|(_, distance)| distance);
+
+        let mut grounded = is_grounded(distance, half_height, GROUND_PROBE_DISTANCE);
+        if !grounded && state.is(PlayerState::Grounded) {
+            let reach = aabb.half_extents().x + units.m_to_px(config.edge_extension);
+            let edge = [-reach, reach]
+                .into_iter()
+                .filter_map(cast)
+                .find(|&(_, distance)| is_on_edge(Some(distance), half_height, GROUND_PROBE_DISTANCE));
+            if edge.is_some() {
+                grounded = true;
+                probe = edge;
+            }
+        }
+        let snap = !grounded
+            && state.is(PlayerState::Grounded)
+            && should_snap_to_ground(distance, half_height, GROUND_SNAP_DISTANCE, velocity.linvel.y);
+        if let Some(distance) = distance.filter(|_| snap) {
+            transform.translation.y -= distance - half_height;
+            velocity.linvel.y = 0.0;
+        }
+
+        if grounded || snap {
+            if state.is(PlayerState::Airborne) {
+                events.send(PlayerEvent::Landed);
+            }
+            state.transition_to(PlayerState::Grounded);
+            // Colliders without a material are plain ground.
+            ground.0 = probe.map(|(collider, _)| surfaces.get(collider).copied().unwrap_or_default());
+        } else {
+            state.transition_to(PlayerState::Airborne);
+            ground.0 = None;
+        }
+    }
+}
+
+pub fn move_player(
+    delta: Res<GameplayDelta>,
+    input: Res<InputState>,
+    config: Res<MoveConfig>,
+    crouch_config: Res<CrouchConfig>,
+    hang_time_config: Res<HangTimeConfig>,
+    units: Res<PhysicsUnits>,
+    mut players: Query<
+        (
+            &mut Velocity,
+            &mut Braking,
+            &GroundSurface,
+            Option<&Crouch>,
+            Option<&StatusEffects>,
+            Option<&HangTime>,
+        ),
+        With<Player>,
+    >,
+) {
+    for (mut velocity, mut braking, ground, crouch, effects, hang_time) in players.iter_mut() {
+        let mut config = match crouch.map(|crouch| crouch.state) {
+            // A slide coasts on its own, see `crouch::slide`.
+            Some(CrouchState::Sliding) => continue,
+            Some(CrouchState::Crouching) => MoveConfig {
+                max_speed: config.max_speed * crouch_config.speed_scale,
+                ..*config
+            },
+            _ => *config,
+        };
+        config.max_speed *= effects.map_or(1.0, StatusEffects::speed_scale);
+        if hang_time.is_some_and(|hang_time| hang_time.0) {
+            config.air_control *= hang_time_config.control_scale;
+        }
+        let traction = ground.0.map_or(1.0, SurfaceMaterial::traction);
+        config.stopping = ground.0.map_or(config.stopping, SurfaceMaterial::deceleration);
+        let current = units.px_to_m(velocity.linvel.x);
+        let next = compute_horizontal_velocity(
+            current,
+            input.move_axis.x,
+            ground.0.is_some(),
+            &config.with_traction(traction),
+            delta.0,
+            &mut braking.0,
+        );
+        velocity.linvel.x = units.m_to_px(next);
+    }
+}
+
+/*
+ * A ray at foot level finds anything blocking the way, and a ray straight down just past its face measures how tall
+ * it is, starting a little above `step_height` so anything taller is hit from inside and rejected. The player's own
+ * shape, placed on top of the step, has to fit there too. Then they're sent up fast enough to clear it in about
+ * `STEP_UP_TIME`, and stop rising the moment their feet are level with the top so they don't hop.
+ */
+fn step_up(
+    config: Res<MoveConfig>,
+    units: Res<PhysicsUnits>,
+    rapier_context: Res<RapierContext>,
+    mut players: Query<
+        (Entity, &Transform, &mut Velocity, &mut StepUp, &Collider, &PlayerStateMachine),
+        With<Player>,
+    >,
+) {
+    let step_height = units.m_to_px(config.step_height);
+
+    for (entity, transform, mut velocity, mut step, collider, state) in players.iter_mut() {
+        let aabb = collider.raw.compute_local_aabb();
+        let center = transform.translation.truncate();
+        let feet = center.y + aabb.mins.y;
+
+        if let Some(target) = step.0 {
+            if feet >= target || velocity.linvel.x == 0.0 {
+                velocity.linvel.y = velocity.linvel.y.min(0.0);
+                step.0 = None;
+            }
+            continue;
+        }
+        if !state.is(PlayerState::Grounded) || velocity.linvel.x == 0.0 {
+            continue;
+        }
+
+        let direction = velocity.linvel.x.signum();
+        let side = if direction > 0.0 { aabb.maxs.x } else { aabb.mins.x };
+        let filter = QueryFilter::default().exclude_rigid_body(entity).exclude_sensors();
+
+        // Far enough ahead that the lift is over by the time the player reaches the step.
+        let reach = STEP_PROBE_REACH + velocity.linvel.x.abs() * STEP_UP_TIME;
+        let wall = rapier_context.cast_ray(
+            Vec2::new(center.x, feet + 1.0),
+            Vec2::new(direction, 0.0),
+            side.abs() + reach,
+            true,
+            filter,
+        );
+        let Some((_, wall_distance)) = wall else { continue };
+
+        let probe_x = center.x + direction * (wall_distance + 1.0);
+        let probe_top = feet + step_height + 1.0;
+        let top = rapier_context.cast_ray(Vec2::new(probe_x, probe_top), Vec2::NEG_Y, step_height + 1.0, true, filter);
+        let obstacle_height = top.map(|(_, distance)| probe_top - distance - feet);
+
+        let headroom_clear = obstacle_height.is_some_and(|height| {
+            let over_edge = wall_distance - side.abs() + 1.0;
+            let on_step = Vec2::new(center.x + direction * over_edge, center.y + height + 0.5);
+            rapier_context.intersection_with_shape(on_step, 0.0, collider, filter).is_none()
+        });
+
+        if let Some(height) = obstacle_height.filter(|_| can_step_up(obstacle_height, step_height, headroom_clear)) {
+            velocity.linvel.y = velocity.linvel.y.max(height / STEP_UP_TIME);
+            step.0 = Some(feet + height);
+        }
+    }
+}
+
+pub fn jump(
+    time: Res<Time>,
+    config: Res<MoveConfig>,
+    units: Res<PhysicsUnits>,
+    mut buffer: ResMut<InputBuffer<Action>>,
+    mut players: Query<(&mut Velocity, &PlayerStateMachine, &mut AirJumps, Option<&StatusEffects>), With<Player>>,
+    mut events: EventWriter<PlayerEvent>,
+) {
+    for (mut velocity, state, mut air_jumps, effects) in players.iter_mut() {
+        let grounded = state.is(PlayerState::Grounded);
+        if grounded {
+            air_jumps.0 = config.air_jumps + effects.map_or(0, StatusEffects::extra_air_jumps);
+        }
+        // Leave the press buffered for landing if there's no jump to spend it on yet.
+        if !grounded && air_jumps.0 == 0 {
+            continue;
+        }
+        if !buffer.consume(Action::Jump, time.elapsed()) {
+            continue;
+        }
+
+        velocity.linvel.y = units.m_to_px(jump_velocity(config.jump_height, GRAVITY));
+        if grounded {
+            events.send(PlayerEvent::Jumped);
+        } else {
+            air_jumps.0 -= 1;
+            events.send(PlayerEvent::AirJumped);
+        }
+    }
+}
+
+/// Picks the player's animation clip by name from their movement state.
+fn animate_player(
+    units: Res<PhysicsUnits>,
+    mut players: Query<(&Velocity, &PlayerStateMachine, &mut SpriteAnimation), With<Player>>,
+) {
+    for (velocity, state, mut animation) in players.iter_mut() {
+        let clip = match state.current() {
+            PlayerState::Airborne if velocity.linvel.y > 0.0 => "jump",
+            PlayerState::Airborne => "fall",
+            PlayerState::Grounded if units.px_to_m(velocity.linvel.x.abs()) > RUN_ANIMATION_SPEED => "run",
+            PlayerState::Grounded => "idle",
+        };
+        animation.play(clip);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snaps_only_when_close_and_not_rising() {
+        let (half_height, snap_distance) = (24.0, 6.0);
+        // Just over a seam, falling or level.
+        assert!(should_snap_to_ground(Some(27.0), half_height, snap_distance, -40.0));
+        assert!(should_snap_to_ground(Some(30.0), half_height, snap_distance, 0.0));
+        // Rising out of a jump.
+        assert!(!should_snap_to_ground(Some(27.0), half_height, snap_distance, 200.0));
+        // Too far above the ground, or nothing below at all.
+        assert!(!should_snap_to_ground(Some(31.0), half_height, snap_distance, -40.0));
+        assert!(!should_snap_to_ground(None, half_height, snap_distance, -40.0));
+    }
+
+    #[test]
+    fn steps_up_low_obstacles_with_headroom() {
+        let step_height = 20.0;
+        assert!(can_step_up(Some(12.0), step_height, true));
+        assert!(can_step_up(Some(20.0), step_height, true));
+        // Too tall, blocked overhead, level with the feet or nothing there.
+        assert!(!can_step_up(Some(25.0), step_height, true));
+        assert!(!can_step_up(Some(12.0), step_height, false));
+        assert!(!can_step_up(Some(0.0), step_height, true));
+        assert!(!can_step_up(None, step_height, true));
+    }
+
+    #[test]
+    fn edge_probes_keep_the_footprint_grounded() {
+        let (half_height, tolerance) = (24.0, 2.0);
+        // Walking off an edge: the centre probe has passed it, but a side probe still finds it under the feet.
+        assert!(!is_grounded(Some(40.0), half_height, tolerance));
+        assert!(is_on_edge(Some(24.0), half_height, tolerance));
+        assert!(is_on_edge(Some(25.5), half_height, tolerance));
+        // Sunk past the tolerance, or nothing beside them at all.
+        assert!(!is_on_edge(Some(27.0), half_height, tolerance));
+        assert!(!is_on_edge(None, half_height, tolerance));
+        // A wall beside them reaches well above their feet, so it isn't something to stand on.
+        assert!(!is_on_edge(Some(4.0), half_height, tolerance));
+        assert!(is_grounded(Some(4.0), half_height, tolerance), "unlike the centre probe, which takes any ground");
+    }
+
+    #[test]
+    fn air_control_scales_acceleration_and_stopping() {
+        let dt = 0.1;
+        let step = |grounded, air_control, current, input| {
+            let config = MoveConfig { air_control, ..default() };
+            compute_horizontal_velocity(current, input, grounded, &config, dt, &mut 0.0)
+        };
+        let ground = step(true, 0.5, 0.0, 1.0);
+        assert!((ground - 4.0).abs() < 1e-5);
+        assert!((step(false, 0.5, 0.0, 1.0) - ground / 2.0).abs() < 1e-5);
+        assert_eq!(step(false, 1.0, 0.0, 1.0), ground);
+
+        // No air control holds the velocity the jump started with, whatever the input.
+        assert_eq!(step(false, 0.0, 3.0, -1.0), 3.0);
+        assert_eq!(step(false, 0.0, 3.0, 0.0), 3.0);
+        assert!(step(true, 0.0, 3.0, 0.0) < 3.0);
+    }
+
+    /// How far (in meters) a grounded player running at 4 m/s slides after letting go with `stopping`, checking the
+    /// speed only ever drops on the way.
+    fn stopping_distance(stopping: Deceleration) -> f32 {
+        let config = MoveConfig { stopping, ..default() };
+        let dt = 1.0 / 60.0;
+        let (mut velocity, mut braking, mut distance) = (4.0, 0.0, 0.0);
+        for _ in 0..6000 {
+            let next = compute_horizontal_velocity(velocity, 0.0, true, &config, dt, &mut braking);
+            assert!((0.0..=velocity).contains(&next), "{stopping:?} went from {velocity} to {next} m/s");
+            velocity = next;
+            distance += velocity * dt;
+            if velocity == 0.0 {
+                return distance;
+            }
+        }
+        panic!("{stopping:?} never stopped");
+    }
+
+    #[test]
+    fn stopping_models_stop_over_different_distances() {
+        let linear = stopping_distance(Deceleration::Linear);
+        let exponential = stopping_distance(Deceleration::Exponential { half_life: 0.4 });
+        let curve = stopping_distance(Deceleration::Curve(CurveStyle::SmoothDamped));
+        // v² / 2a and v·t½ / ln 2, less the bit the discrete steps and the final snap to rest leave out.
+        assert!((linear - 0.16).abs() < 0.05, "linear slid {linear} m");
+        assert!((exponential - 2.25).abs() < 0.1, "exponential slid {exponential} m");
+        assert!(linear < curve && curve < exponential, "curve slid {curve} m");
+    }
+
+    /// The speed after each of `frames` 60 Hz frames of a player landing at 8 m/s and running on the same way.
+    fn landing_speeds(momentum_decay: Option<Deceleration>, frames: usize) -> Vec<f32> {
+        let config = MoveConfig { momentum_decay, ..default() };
+        let mut velocity = 8.0;
+        let mut braking = 0.0;
+        (0..frames)
+            .map(|_| {
+                velocity = compute_horizontal_velocity(velocity, 1.0, true, &config, 1.0 / 60.0, &mut braking);
+                velocity
+            })
+            .collect()
+    }
+
+    #[test]
+    fn landing_keeps_momentum_only_when_preserved() {
+        let cut = landing_speeds(None, 15);
+        let kept = landing_speeds(Some(Deceleration::Exponential { half_life: 0.25 }), 15);
+        // Cut back to the run speed within a few frames.
+        assert_eq!(cut[6..], [4.0; 9]);
+        // Half the extra speed is still there a quarter of a second later, and it never drops below the run speed.
+        assert!((kept[14] - 6.0).abs() < 0.01, "{kept:?}");
+        assert!(kept.windows(2).all(|pair| pair[1] < pair[0] && pair[1] > 4.0));
+        assert!(kept.iter().zip(&cut).all(|(kept, cut)| kept >= cut));
+        // Turning round isn't momentum, so it isn't kept either.
+        let config = MoveConfig { momentum_decay: Some(Deceleration::Exponential { half_life: 0.25 }), ..default() };
+        let turned = compute_horizontal_velocity(8.0, -1.0, true, &config, 1.0 / 60.0, &mut 0.0);
+        assert!((turned - (8.0 - 40.0 / 60.0)).abs() < 1e-5);
+    }
+}
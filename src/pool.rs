@@ -0,0 +1,152 @@
+use std::any::{type_name, TypeId};
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+/// How many entities of one kind may be in use at once unless `SpawnBudget` says otherwise.
+const DEFAULT_SPAWN_CAP: usize = 256;
+
+/// Caps how many entities each `EntityPool` hands out at once, so a runaway emitter can't pile up entities without
+/// end. A pool at its cap reuses its oldest entity instead of adding another.
+#[derive(Resource, Clone, Debug)]
+pub struct SpawnBudget {
+    /// The cap of every kind without its own.
+    pub default_cap: usize,
+    caps: HashMap<TypeId, usize>,
+}
+
+impl Default for SpawnBudget {
+    fn default() -> Self {
+        SpawnBudget {
+            default_cap: DEFAULT_SPAWN_CAP,
+            caps: HashMap::default(),
+        }
+    }
+}
+
+impl SpawnBudget {
+    /// Caps the entities of kind `T` in use at once, at least 1.
+    pub fn set_cap<T: 'static>(&mut self, cap: usize) {
+        self.caps.insert(TypeId::of::<T>(), cap);
+    }
+
+    pub fn cap<T: 'static>(&self) -> usize {
+        self.caps.get(&TypeId::of::<T>()).copied().unwrap_or(self.default_cap).max(1)
+    }
+}
+
+/// Spare entities for short-lived objects of kind `T`, kept hidden instead of despawned so they can be reused.
+///
+/// Pooled entities are long-lived: don't tag them `GameplayEntity`, or the pool ends up holding despawned ones.
+#[derive(Resource)]
+pub struct EntityPool<T> {
+    free: Vec<Entity>,
+    /// Entities handed out and not yet released, oldest first.
+    in_use: VecDeque<Entity>,
+    warned: bool,
+    _kind: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for EntityPool<T> {
+    fn default() -> Self {
+        EntityPool {
+            free: Vec::new(),
+            in_use: VecDeque::new(),
+            warned: false,
+            _kind: PhantomData,
+        }
+    }
+}
+
+impl<T: 'static> EntityPool<T> {
+    /// A spare entity, or a freshly spawned empty one if the pool has run dry.
+    ///
+    /// At its `SpawnBudget` cap the pool hands back its oldest entity in use instead, components and all, for the
+    /// caller to overwrite. A warning is logged the first time that happens.
+    pub fn take(&mut self, commands: &mut Commands, budget: &SpawnBudget) -> Entity {
+        if self.in_use.len() >= budget.cap::<T>() {
+            if let Some(oldest) = self.in_use.pop_front() {
+                if !self.warned {
+                    self.warned = true;
+                    warn!("Over the spawn budget for {}, reusing the oldest", type_name::<T>());
+                }
+                self.in_use.push_back(oldest);
+                return oldest;
+            }
+        }
+
+        let entity = self.free.pop().unwrap_or_else(|| commands.spawn_empty().id());
+        self.in_use.push_back(entity);
+        entity
+    }
+}
+
+impl<T> EntityPool<T> {
+    /// Hides `entity` and keeps it for the next `take`. Callers remove their own components first.
+    pub fn release(&mut self, commands: &mut Commands, entity: Entity) {
+        commands.entity(entity).insert(Visibility::INVISIBLE);
+        if let Some(index) = self.in_use.iter().position(|&used| used == entity) {
+            self.in_use.remove(index);
+        }
+        self.free.push(entity);
+    }
+
+    /// How many spare entities are waiting to be reused.
+    pub fn len(&self) -> usize {
+        self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.free.is_empty()
+    }
+
+    /// How many entities are handed out and not yet released.
+    pub fn in_use(&self) -> usize {
+        self.in_use.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::CommandQueue;
+
+    use super::*;
+
+    struct Spark;
+
+    #[test]
+    fn over_budget_recycles_the_oldest() {
+        let mut world = World::new();
+        let mut queue = CommandQueue::default();
+        let mut commands = Commands::new(&mut queue, &world);
+        let mut budget = SpawnBudget::default();
+        budget.set_cap::<Spark>(3);
+        let mut pool = EntityPool::<Spark>::default();
+
+        let first: Vec<Entity> = (0..3).map(|_| pool.take(&mut commands, &budget)).collect();
+        let reused: Vec<Entity> = (0..4).map(|_| pool.take(&mut commands, &budget)).collect();
+        queue.apply(&mut world);
+
+        // The oldest goes round again, in the order they were first handed out, and nothing new is spawned.
+        assert_eq!(reused, [first[0], first[1], first[2], first[0]]);
+        assert_eq!(pool.in_use(), 3);
+        assert_eq!(world.entities().len(), 3);
+    }
+
+    #[test]
+    fn released_entities_are_reused_first() {
+        let world = World::new();
+        let mut queue = CommandQueue::default();
+        let mut commands = Commands::new(&mut queue, &world);
+        let budget = SpawnBudget::default();
+        let mut pool = EntityPool::<Spark>::default();
+
+        let spark = pool.take(&mut commands, &budget);
+        pool.release(&mut commands, spark);
+        assert_eq!((pool.len(), pool.in_use()), (1, 0));
+        assert_eq!(pool.take(&mut commands, &budget), spark);
+        assert!(pool.is_empty());
+    }
+}
@@ -0,0 +1,238 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::input::{AimDirection, EdgeDetector, InputState};
+use crate::physics::{GameplayDelta, PhysicsUnits};
+use crate::player::Player;
+use crate::state::{GameState, GameplayEntity};
+
+/// Rapier substeps per physics step while any `FastObject` exists.
+const FAST_OBJECT_SUBSTEPS: usize = 4;
+/// The mass of a fast projectile, in kilograms.
+const FAST_PROJECTILE_MASS: f32 = 0.01;
+/// How fast the player's shots fly, in m/s.
+const PLAYER_SHOT_SPEED: f32 = 12.0;
+/// The radius of the player's shots, in meters.
+const PLAYER_SHOT_RADIUS: f32 = 0.05;
+/// Seconds before a player's shot that hits nothing vanishes.
+const PLAYER_SHOT_LIFETIME: f32 = 1.0;
+/// How far from the player's centre a shot starts, in meters, clear of the player's own collider.
+const PLAYER_SHOT_OFFSET: f32 = 0.35;
+
+/// A short-lived body fired by the player or an enemy.
+#[derive(Component)]
+pub struct Projectile {
+    /// The entity that fired it, so it doesn't hit its own shooter.
+    pub owner: Entity,
+    pub lifetime: Timer,
+}
+
+/// A body quick enough to pass through thin colliders between two physics steps.
+///
+/// It gets continuous collision detection, and while any exists every physics step is split into
+/// `FAST_OBJECT_SUBSTEPS` substeps. Both cost real time: CCD sweeps the body's whole path every step, and substeps
+/// are global, multiplying the cost of the entire simulation rather than just this body. Keep it for the few
+/// bodies that need it.
+#[derive(Component)]
+pub struct FastObject;
+
+pub struct ProjectilePlugin;
+
+impl Plugin for ProjectilePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_system(fire_player_shots)
+                    .with_system(expire_projectiles),
+            )
+            .add_system(stop_projectiles)
+            .add_system(substep_fast_objects);
+    }
+}
+
+/// Spawns a projectile at `origin` moving with `velocity` (both in pixels) that lives for `lifetime` seconds.
+///
+/// It vanishes on hitting solid geometry. An ordinary projectile is a kinematic sensor, which a fast one can skip
+/// clean over, so a `fast_object` projectile is instead a weightless dynamic body with CCD, as `FastObject`.
+pub fn spawn_projectile(
+    commands: &mut Commands,
+    owner: Entity,
+    origin: Vec2,
+    velocity: Vec2,
+    radius: f32,
+    lifetime: f32,
+    fast_object: bool,
+) -> Entity {
+    let mut projectile = commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgb(0.9, 0.8, 0.3),
+                custom_size: Some(Vec2::splat(radius * 2.0)),
+                ..default()
+            },
+            transform: Transform::from_translation(origin.extend(1.0)),
+            ..default()
+        },
+        Collider::ball(radius),
+        ActiveEvents::COLLISION_EVENTS,
+        Velocity::linear(velocity),
+        Projectile {
+            owner,
+            lifetime: Timer::from_seconds(lifetime, TimerMode::Once),
+        },
+        GameplayEntity,
+    ));
+
+    if fast_object {
+        projectile.insert((
+            RigidBody::Dynamic,
+            Ccd::enabled(),
+            GravityScale(0.0),
+            // CCD only stops bodies that make solver contacts, so it has to touch things; it's too light to shove them.
+            ColliderMassProperties::Mass(FAST_PROJECTILE_MASS),
+            FastObject,
+        ));
+    } else {
+        projectile.insert((
+            RigidBody::KinematicVelocityBased,
+            Sensor,
+            ActiveCollisionTypes::default() | ActiveCollisionTypes::KINEMATIC_STATIC,
+        ));
+    }
+    projectile.id()
+}
+
+/// `mode` with each physics step split into `substeps`.
+pub fn with_substeps(mode: TimestepMode, substeps: usize) -> TimestepMode {
+    match mode {
+        TimestepMode::Fixed { dt, .. } => TimestepMode::Fixed { dt, substeps },
+        TimestepMode::Variable { max_dt, time_scale, .. } => TimestepMode::Variable { max_dt, time_scale, substeps },
+        TimestepMode::Interpolated { dt, time_scale, .. } => TimestepMode::Interpolated { dt, time_scale, substeps },
+    }
+}
+
+/// The player shoots once each time fire is pressed, in the `AimDirection`. Shots are fast objects, as they're quick
+/// enough to skip through a thin platform between steps.
+fn fire_player_shots(
+    mut commands: Commands,
+    units: Res<PhysicsUnits>,
+    input: Res<InputState>,
+    aim: Res<AimDirection>,
+    mut fire: Local<EdgeDetector>,
+    players: Query<(Entity, &GlobalTransform), With<Player>>,
+) {
+    fire.update(input.fire);
+    if !fire.just_pressed() {
+        return;
+    }
+    let Ok((player, transform)) = players.get_single() else { return };
+
+    let origin = transform.translation().truncate() + aim.0 * units.m_to_px(PLAYER_SHOT_OFFSET);
+    spawn_projectile(
+        &mut commands,
+        player,
+        origin,
+        aim.0 * units.m_to_px(PLAYER_SHOT_SPEED),
+        units.m_to_px(PLAYER_SHOT_RADIUS),
+        PLAYER_SHOT_LIFETIME,
+        true,
+    );
+}
+
+fn expire_projectiles(
+    mut commands: Commands,
+    delta: Res<GameplayDelta>,
+    mut projectiles: Query<(Entity, &mut Projectile)>,
+) {
+    for (entity, mut projectile) in projectiles.iter_mut() {
+        if projectile.lifetime.tick(Duration::from_secs_f32(delta.0)).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+/// Projectiles stop at the first solid collider they touch that isn't their shooter. Sensors don't stop them.
+fn stop_projectiles(
+    mut commands: Commands,
+    mut collisions: EventReader<CollisionEvent>,
+    projectiles: Query<&Projectile>,
+    sensors: Query<(), With<Sensor>>,
+) {
+    for collision in collisions.iter() {
+        let CollisionEvent::Started(a, b, _) = *collision else { continue };
+        for (projectile_entity, other) in [(a, b), (b, a)] {
+            let Ok(projectile) = projectiles.get(projectile_entity) else { continue };
+            if other != projectile.owner && !sensors.contains(other) {
+                commands.entity(projectile_entity).despawn_recursive();
+            }
+        }
+    }
+}
+
+fn substep_fast_objects(fast_objects: Query<(), With<FastObject>>, mut rapier_config: ResMut<RapierConfiguration>) {
+    let substeps = if fast_objects.is_empty() { 1 } else { FAST_OBJECT_SUBSTEPS };
+    let mode = with_substeps(rapier_config.timestep_mode, substeps);
+    if rapier_config.timestep_mode != mode {
+        rapier_config.timestep_mode = mode;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+    use crate::lockstep::lockstep_app;
+    use crate::physics::FIXED_TIMESTEP;
+
+    const WALL_X: f32 = 400.0;
+
+    /// Fires a projectile at 60 m/s, a meter's travel a step, at a wall 2 px thick, returning the furthest it got
+    /// and whether it's still around after a second.
+    fn fire_at_thin_wall(fast_object: bool) -> (f32, bool) {
+        let mut app = lockstep_app();
+        app.init_resource::<AimDirection>().add_plugin(ProjectilePlugin);
+        let units = *app.world.resource::<PhysicsUnits>();
+        app.world
+            .spawn(Collider::cuboid(1.0, 200.0))
+            .insert(TransformBundle::from(Transform::from_xyz(WALL_X, 0.0, 0.0)));
+
+        let owner = app.world.spawn_empty().id();
+        let mut queue = bevy::ecs::system::CommandQueue::default();
+        let mut commands = Commands::new(&mut queue, &app.world);
+        let velocity = Vec2::new(units.m_to_px(60.0), 0.0);
+        let origin = Vec2::new(50.0, 100.0);
+        let projectile = spawn_projectile(&mut commands, owner, origin, velocity, 5.0, 1.0, fast_object);
+        queue.apply(&mut app.world);
+
+        let step = Duration::from_secs_f32(FIXED_TIMESTEP);
+        let mut now = Instant::now();
+        app.world.resource_mut::<Time>().update_with_instant(now);
+        let mut furthest = f32::MIN;
+        for _ in 0..(1.0 / FIXED_TIMESTEP) as usize - 1 {
+            now += step;
+            app.world.resource_mut::<Time>().update_with_instant(now);
+            app.update();
+            if let Some(transform) = app.world.get::<Transform>(projectile) {
+                furthest = furthest.max(transform.translation.x);
+            }
+        }
+        (furthest, app.world.get_entity(projectile).is_some())
+    }
+
+    #[test]
+    fn fast_projectile_stops_at_a_thin_wall() {
+        let (furthest, alive) = fire_at_thin_wall(true);
+        assert!(furthest < WALL_X, "got to {furthest}");
+        assert!(!alive);
+    }
+
+    #[test]
+    fn ordinary_projectile_skips_through_a_thin_wall() {
+        let (furthest, _) = fire_at_thin_wall(false);
+        assert!(furthest > WALL_X, "stopped at {furthest}");
+    }
+}
@@ -0,0 +1,138 @@
+use bevy::core_pipeline::clear_color::ClearColorConfig;
+use bevy::prelude::*;
+use bevy::render::view::{NoFrustumCulling, RenderLayers};
+use bevy::sprite::Mesh2dHandle;
+use bevy::transform::TransformSystem;
+
+use crate::camera::{shake_camera, GameCamera};
+
+/*
+ * The game draws with three cameras, in order of `Camera::priority`:
+ *
+ * - The world camera (`GameCamera`), priority 0, draws render layer 0: the level, sprites, and world-space text
+ *   such as interact prompts and damage numbers. It follows and zooms, and may be letterboxed.
+ * - The HUD camera, priority 1, draws only Bevy UI (health bar, minimap, menus) over the whole window, so world zoom
+ *   and letterboxing never move or scale it. It draws no render layers.
+ * - The debug camera, priority 2, draws `DEBUG_LAYER` (Rapier's collider outlines) over everything else, with the
+ *   world camera's view so the outlines line up with what they outline.
+ *
+ * Only the world camera clears the screen; the others draw over what's already there.
+ */
+pub const WORLD_CAMERA_PRIORITY: isize = 0;
+pub const HUD_CAMERA_PRIORITY: isize = 1;
+pub const DEBUG_CAMERA_PRIORITY: isize = 2;
+/// The render layer for debug overlays, seen only by the debug camera.
+pub const DEBUG_LAYER: u8 = 1;
+
+#[derive(Component)]
+pub struct HudCamera;
+
+#[derive(Component)]
+pub struct DebugCamera;
+
+pub struct RenderLayersPlugin;
+
+impl Plugin for RenderLayersPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_startup_system(spawn_overlay_cameras)
+            .add_system(layer_debug_lines)
+            // After the camera has moved for the frame, but before propagation so the copy isn't a frame behind.
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                follow_world_camera.after(shake_camera).before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+/// The bundle for the world camera: draws the level and everything in it, but not the UI.
+pub fn world_camera() -> impl Bundle {
+    (
+        Camera2dBundle {
+            camera: Camera {
+                priority: WORLD_CAMERA_PRIORITY,
+                ..default()
+            },
+            ..default()
+        },
+        UiCameraConfig { show_ui: false },
+    )
+}
+
+/// A 2D camera drawn over the ones before it, at `priority`.
+fn overlay_camera(priority: isize) -> Camera2dBundle {
+    Camera2dBundle {
+        camera: Camera { priority, ..default() },
+        camera_2d: Camera2d {
+            clear_color: ClearColorConfig::None,
+        },
+        ..default()
+    }
+}
+
+fn spawn_overlay_cameras(mut commands: Commands) {
+    commands.spawn((overlay_camera(HUD_CAMERA_PRIORITY), RenderLayers::none(), HudCamera));
+    commands.spawn((
+        overlay_camera(DEBUG_CAMERA_PRIORITY),
+        RenderLayers::layer(DEBUG_LAYER),
+        UiCameraConfig { show_ui: false },
+        DebugCamera,
+    ));
+}
+
+/// Rapier's debug lines are the only meshes spawned without frustum culling; their marker component is private.
+fn layer_debug_lines(mut commands: Commands, lines: Query<Entity, (Added<NoFrustumCulling>, With<Mesh2dHandle>)>) {
+    for entity in lines.iter() {
+        commands.entity(entity).insert(RenderLayers::layer(DEBUG_LAYER));
+    }
+}
+
+fn follow_world_camera(
+    world_cameras: Query<(&Camera, &Transform, &OrthographicProjection), (With<GameCamera>, Without<DebugCamera>)>,
+    mut debug_cameras: Query<(&mut Camera, &mut Transform, &mut OrthographicProjection), With<DebugCamera>>,
+) {
+    let Ok((world_camera, world_transform, world_projection)) = world_cameras.get_single() else { return };
+
+    // `Viewport` can't be compared directly.
+    let rect = |camera: &Camera| camera.viewport.as_ref().map(|v| (v.physical_position, v.physical_size));
+
+    for (mut camera, mut transform, mut projection) in debug_cameras.iter_mut() {
+        if rect(&camera) != rect(world_camera) {
+            camera.viewport = world_camera.viewport.clone();
+        }
+        if *transform != *world_transform {
+            *transform = *world_transform;
+        }
+        if projection.scale != world_projection.scale {
+            projection.scale = world_projection.scale;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cameras_draw_in_order() {
+        let mut app = App::new();
+        app.add_plugin(RenderLayersPlugin);
+        let world = app.world.spawn((world_camera(), GameCamera)).id();
+        app.update();
+
+        let hud = app.world.query_filtered::<Entity, With<HudCamera>>().single(&app.world);
+        let debug = app.world.query_filtered::<Entity, With<DebugCamera>>().single(&app.world);
+        let priority = |entity: Entity| app.world.get::<Camera>(entity).unwrap().priority;
+        assert_eq!([world, hud, debug].map(priority), [0, 1, 2]);
+
+        // Only the world camera clears, and only the HUD camera draws the UI.
+        let clears = |entity: Entity| {
+            !matches!(app.world.get::<Camera2d>(entity).unwrap().clear_color, ClearColorConfig::None)
+        };
+        assert_eq!([world, hud, debug].map(clears), [true, false, false]);
+        let shows_ui = |entity: Entity| app.world.get::<UiCameraConfig>(entity).is_none_or(|config| config.show_ui);
+        assert_eq!([world, hud, debug].map(shows_ui), [false, true, false]);
+        assert_eq!(app.world.get::<RenderLayers>(debug), Some(&RenderLayers::layer(DEBUG_LAYER)));
+        assert_eq!(app.world.get::<RenderLayers>(hud), Some(&RenderLayers::none()));
+    }
+}
@@ -0,0 +1,73 @@
+use bevy::prelude::*;
+
+use crate::flash::Flash;
+use crate::health::Invulnerable;
+use crate::input::{Action, ControlLock, InputBuffer};
+use crate::level::{respawn_player, PlayerRespawned};
+use crate::player::{Player, PlayerSprite};
+use crate::status_effect::StatusEffects;
+
+/// Blinks per second of the player's sprite while invulnerable.
+const INVULNERABLE_FLASH_FREQUENCY: f32 = 8.0;
+/// The sprite's alpha during the faded half of a blink.
+const INVULNERABLE_FLASH_ALPHA: f32 = 0.3;
+
+/// What the player gets after respawning, in seconds of gameplay time, so they don't die again straight away.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct RespawnConfig {
+    /// How long the controls are ignored, for the player to see where they are.
+    pub grace: f32,
+    /// How long they can't be hurt, blinking all the while. Should outlast `grace`, so they can get clear.
+    pub invulnerability: f32,
+}
+
+impl Default for RespawnConfig {
+    fn default() -> Self {
+        RespawnConfig {
+            grace: 0.4,
+            invulnerability: 1.5,
+        }
+    }
+}
+
+pub struct RespawnPlugin;
+
+impl Plugin for RespawnPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<RespawnConfig>()
+            .add_system(start_respawn_grace.after(respawn_player));
+    }
+}
+
+/// Status effects are cleared too, so burning or poison doesn't carry over into the new life.
+fn start_respawn_grace(
+    mut commands: Commands,
+    config: Res<RespawnConfig>,
+    mut respawned: EventReader<PlayerRespawned>,
+    mut lock: ResMut<ControlLock>,
+    mut buffer: ResMut<InputBuffer<Action>>,
+    mut players: Query<(Entity, &mut StatusEffects), With<Player>>,
+    children: Query<&Children>,
+    sprites: Query<(), With<PlayerSprite>>,
+) {
+    if respawned.iter().count() == 0 {
+        return;
+    }
+
+    lock.lock(config.grace);
+    buffer.clear();
+    for (player, mut effects) in players.iter_mut() {
+        if !effects.is_empty() {
+            effects.0.clear();
+        }
+        commands.entity(player).insert(Invulnerable { remaining: config.invulnerability });
+        for descendant in children.iter_descendants(player) {
+            if sprites.contains(descendant) {
+                let flash =
+                    Flash::blink(INVULNERABLE_FLASH_ALPHA, INVULNERABLE_FLASH_FREQUENCY, config.invulnerability);
+                commands.entity(descendant).insert(flash);
+            }
+        }
+    }
+}
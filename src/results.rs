@@ -0,0 +1,209 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::bean::Bean;
+use crate::flags::{level_exit_flags, no_damage_flag, LevelRun};
+use crate::level::LevelCompleted;
+use crate::physics::GameplayDelta;
+use crate::player::{Player, PlayerSpawn};
+use crate::state::GameState;
+
+/// The LDtk entity identifier of a level's exit. The level is complete once the player is anywhere inside it.
+pub const EXIT_ENTITY: &str = "Exit";
+
+/// The fastest completion of each level, in seconds, keyed by level IID. They are kept in the save file.
+#[derive(Resource, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct BestTimes(BTreeMap<String, f32>);
+
+impl BestTimes {
+    pub fn get(&self, iid: &str) -> Option<f32> {
+        self.0.get(iid).copied()
+    }
+
+    /// Records `time` for the level `iid` if it beats the best so far, returning whether it did.
+    pub fn record(&mut self, iid: &str, time: f32) -> bool {
+        if self.get(iid).is_some_and(|best| best <= time) {
+            return false;
+        }
+        self.0.insert(iid.to_string(), time);
+        true
+    }
+}
+
+/// The summary of a completed level, shown on the results screen.
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct LevelResults {
+    /// Seconds taken.
+    pub time: f32,
+    pub beans: u32,
+    pub beans_total: u32,
+    pub no_damage: bool,
+    /// The best time before this run, if the level had been completed before.
+    pub previous_best: Option<f32>,
+    /// The index of the level to go on to, or `None` after the last one.
+    pub next_level: Option<usize>,
+}
+
+impl LevelResults {
+    pub fn is_new_best(&self) -> bool {
+        self.previous_best.is_none_or(|best| self.time < best)
+    }
+}
+
+/// Sums up the run through level `iid`, the `level_index`th of `level_count`, with `beans_left` still uncollected.
+pub fn level_results(
+    iid: &str,
+    run: &LevelRun,
+    beans_left: u32,
+    best_times: &BestTimes,
+    level_index: usize,
+    level_count: usize,
+) -> LevelResults {
+    LevelResults {
+        time: run.time,
+        beans: run.beans,
+        beans_total: run.beans + beans_left,
+        no_damage: level_exit_flags(iid, run).contains(&no_damage_flag(iid)),
+        previous_best: best_times.get(iid),
+        next_level: (level_index + 1 < level_count).then_some(level_index + 1),
+    }
+}
+
+/// Formats `seconds` as minutes, seconds and hundredths, e.g. "1:05.30".
+pub fn format_time(seconds: f32) -> String {
+    let hundredths = (seconds.max(0.0) * 100.0).round() as u32;
+    format!("{}:{:02}.{:02}", hundredths / 6000, hundredths / 100 % 60, hundredths % 100)
+}
+
+pub struct ResultsPlugin;
+
+impl Plugin for ResultsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<BestTimes>()
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_system(time_level_run)
+                    .with_system(reach_exit)
+                    .with_system(show_results.after(reach_exit)),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::LevelComplete).with_system(return_to_spawn));
+    }
+}
+
+fn time_level_run(delta: Res<GameplayDelta>, mut run: ResMut<LevelRun>) {
+    run.time += delta.0;
+}
+
+/// The exit's level is found through its ancestors: bevy_ecs_ldtk puts entities under their layer, under the level.
+fn reach_exit(
+    mut run: ResMut<LevelRun>,
+    players: Query<&GlobalTransform, With<Player>>,
+    exits: Query<(Entity, &EntityInstance, &GlobalTransform)>,
+    parents: Query<&Parent>,
+    levels: Query<&Handle<LdtkLevel>>,
+    level_assets: Res<Assets<LdtkLevel>>,
+    mut completed: EventWriter<LevelCompleted>,
+) {
+    if run.completed {
+        return;
+    }
+    let Ok(player) = players.get_single() else { return };
+
+    for (entity, instance, transform) in exits.iter() {
+        if instance.identifier != EXIT_ENTITY {
+            continue;
+        }
+        let size = Vec2::new(instance.width as f32, instance.height as f32);
+        if !Rect::from_center_size(transform.translation().truncate(), size).contains(player.translation().truncate()) {
+            continue;
+        }
+
+        let level = parents
+            .iter_ancestors(entity)
+            .find_map(|ancestor| level_assets.get(levels.get(ancestor).ok()?));
+        let Some(level) = level else { continue };
+        run.completed = true;
+        info!("Completed level {} in {}", level.level.identifier, format_time(run.time));
+        completed.send(LevelCompleted { iid: level.level.iid.clone() });
+        return;
+    }
+}
+
+fn show_results(
+    mut commands: Commands,
+    mut completed: EventReader<LevelCompleted>,
+    run: Res<LevelRun>,
+    beans: Query<(), With<Bean>>,
+    mut best_times: ResMut<BestTimes>,
+    projects: Query<&Handle<LdtkAsset>>,
+    project_assets: Res<Assets<LdtkAsset>>,
+    mut state: ResMut<State<GameState>>,
+) {
+    let Some(level) = completed.iter().last() else { return };
+    let Some(project) = projects.iter().find_map(|handle| project_assets.get(handle)) else { return };
+    let levels = &project.project.levels;
+    let index = levels.iter().position(|level_def| level_def.iid == level.iid).unwrap_or(levels.len());
+
+    let results = level_results(&level.iid, &run, beans.iter().count() as u32, &best_times, index, levels.len());
+    best_times.record(&level.iid, results.time);
+    commands.insert_resource(results);
+    let _ = state.push(GameState::LevelComplete);
+}
+
+/// Whichever way the player leaves the results, a next level or the menu, they start again from the spawn point.
+fn return_to_spawn(spawn: Res<PlayerSpawn>, mut players: Query<(&mut Transform, &mut Velocity), With<Player>>) {
+    for (mut transform, mut velocity) in players.iter_mut() {
+        transform.translation.x = spawn.0.x;
+        transform.translation.y = spawn.0.y;
+        *velocity = Velocity::zero();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn results_sum_up_the_run() {
+        let run = LevelRun { beans: 7, time: 42.5, completed: true, ..default() };
+        let mut best_times = BestTimes::default();
+        best_times.record("a", 50.0);
+
+        let results = level_results("a", &run, 3, &best_times, 0, 2);
+        assert_eq!(
+            results,
+            LevelResults {
+                time: 42.5,
+                beans: 7,
+                beans_total: 10,
+                no_damage: true,
+                previous_best: Some(50.0),
+                next_level: Some(1),
+            }
+        );
+        assert!(results.is_new_best());
+
+        let hurt = LevelRun { damaged: true, time: 60.0, ..run };
+        let results = level_results("a", &hurt, 0, &best_times, 1, 2);
+        assert!(!results.no_damage && !results.is_new_best());
+        assert_eq!(results.next_level, None);
+        assert!(level_results("b", &hurt, 0, &best_times, 0, 2).is_new_best());
+    }
+
+    #[test]
+    fn best_times_only_improve() {
+        let mut best_times = BestTimes::default();
+        assert!(best_times.record("a", 50.0));
+        assert!(!best_times.record("a", 55.0));
+        assert!(!best_times.record("a", 50.0));
+        assert!(best_times.record("a", 45.0));
+        assert_eq!(best_times.get("a"), Some(45.0));
+        assert_eq!(format_time(65.3), "1:05.30");
+    }
+}
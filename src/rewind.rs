@@ -0,0 +1,187 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::physics::GameplayDelta;
+use crate::player::Player;
+
+/// Remembers where the player stands now.
+const MARK_KEY: KeyCode = KeyCode::F5;
+/// Puts the player back where they were marked.
+const RETURN_KEY: KeyCode = KeyCode::F6;
+/// Puts the player back where they were `REWIND_SECONDS` ago.
+const REWIND_KEY: KeyCode = KeyCode::F7;
+const REWIND_SECONDS: f32 = 3.0;
+
+/// Where the player was and how they were moving, in pixels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PlayerSnapshot {
+    pub position: Vec2,
+    pub velocity: Velocity,
+}
+
+/// Recent snapshots of the player, each stamped with the gameplay time it was taken at, covering the last `span`
+/// seconds. Older ones are dropped as new ones come in.
+#[derive(Resource, Clone, Debug)]
+pub struct PlayerHistory {
+    pub span: f32,
+    /// Seconds of gameplay recorded so far.
+    pub clock: f32,
+    samples: VecDeque<(f32, PlayerSnapshot)>,
+}
+
+impl Default for PlayerHistory {
+    fn default() -> Self {
+        PlayerHistory::new(REWIND_SECONDS)
+    }
+}
+
+impl PlayerHistory {
+    pub fn new(span: f32) -> Self {
+        PlayerHistory {
+            span,
+            clock: 0.0,
+            samples: VecDeque::new(),
+        }
+    }
+
+    /// Records `snapshot` after `dt` more seconds of gameplay.
+    pub fn record(&mut self, dt: f32, snapshot: PlayerSnapshot) {
+        self.clock += dt;
+        self.samples.push_back((self.clock, snapshot));
+        // Keep the newest sample at or past the span, so a full rewind still has somewhere to go.
+        while self.samples.get(1).is_some_and(|&(time, _)| self.clock - time >= self.span) {
+            self.samples.pop_front();
+        }
+    }
+
+    /// The snapshot from `seconds` ago: the latest taken no later than then, or the oldest kept if the history
+    /// doesn't reach back that far.
+    pub fn ago(&self, seconds: f32) -> Option<PlayerSnapshot> {
+        let time = self.clock - seconds;
+        self.samples
+            .iter()
+            .rev()
+            .find(|&&(taken, _)| taken <= time)
+            .or_else(|| self.samples.front())
+            .map(|&(_, snapshot)| snapshot)
+    }
+
+    /// Goes back `seconds`, forgetting everything since, so rewinding again goes back further still.
+    pub fn rewind(&mut self, seconds: f32) -> Option<PlayerSnapshot> {
+        let snapshot = self.ago(seconds)?;
+        let time = self.clock - seconds;
+        while self.samples.len() > 1 && self.samples.back().is_some_and(|&(taken, _)| taken > time) {
+            self.samples.pop_back();
+        }
+        self.clock = self.samples.back().map_or(self.clock, |&(taken, _)| taken);
+        Some(snapshot)
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+}
+
+/// The spot marked with `MARK_KEY`, if any.
+#[derive(Resource, Clone, Copy, Debug, Default)]
+pub struct MarkedPosition(pub Option<PlayerSnapshot>);
+
+/// Playtest helpers for retrying a jump: mark a spot and return to it, or rewind the last few seconds.
+pub struct RewindPlugin;
+
+impl Plugin for RewindPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<PlayerHistory>()
+            .init_resource::<MarkedPosition>()
+            .add_system(record_player)
+            .add_system(rewind_keys.after(record_player));
+    }
+}
+
+/// Frames with no gameplay time, such as while paused or in step mode, aren't recorded.
+fn record_player(
+    delta: Res<GameplayDelta>,
+    mut history: ResMut<PlayerHistory>,
+    players: Query<(&Transform, &Velocity), With<Player>>,
+) {
+    let Ok((transform, velocity)) = players.get_single() else { return };
+    if delta.0 <= 0.0 {
+        return;
+    }
+    history.record(delta.0, PlayerSnapshot { position: transform.translation.truncate(), velocity: *velocity });
+}
+
+fn rewind_keys(
+    keys: Option<Res<Input<KeyCode>>>,
+    mut history: ResMut<PlayerHistory>,
+    mut marked: ResMut<MarkedPosition>,
+    mut players: Query<(&mut Transform, &mut Velocity), With<Player>>,
+) {
+    let Some(keys) = keys else { return };
+    let Ok((mut transform, mut velocity)) = players.get_single_mut() else { return };
+
+    let restore = if keys.just_pressed(MARK_KEY) {
+        marked.0 = Some(PlayerSnapshot { position: transform.translation.truncate(), velocity: *velocity });
+        info!("Marked the player's position");
+        None
+    } else if keys.just_pressed(RETURN_KEY) {
+        marked.0
+    } else if keys.just_pressed(REWIND_KEY) {
+        history.rewind(REWIND_SECONDS)
+    } else {
+        None
+    };
+
+    if let Some(snapshot) = restore {
+        transform.translation.x = snapshot.position.x;
+        transform.translation.y = snapshot.position.y;
+        *velocity = snapshot.velocity;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(x: f32) -> PlayerSnapshot {
+        PlayerSnapshot { position: Vec2::new(x, 0.0), velocity: Velocity::zero() }
+    }
+
+    /// Ten half-second samples, each at its own timestamp along x.
+    fn history() -> PlayerHistory {
+        let mut history = PlayerHistory::new(2.0);
+        for step in 1..=10 {
+            history.record(0.5, at(step as f32 * 0.5));
+        }
+        history
+    }
+
+    #[test]
+    fn keeps_only_the_recorded_span() {
+        let history = history();
+        assert_eq!(history.clock, 5.0);
+        assert_eq!(history.len(), 5);
+        assert_eq!(history.ago(2.0), Some(at(3.0)));
+        assert_eq!(history.ago(10.0), Some(at(3.0)), "falls back to the oldest kept");
+    }
+
+    #[test]
+    fn looks_up_and_rewinds_by_time() {
+        let mut history = history();
+        assert_eq!(history.ago(0.0), Some(at(5.0)));
+        assert_eq!(history.ago(0.75), Some(at(4.0)), "the latest sample no later than the time asked for");
+
+        assert_eq!(history.rewind(1.0), Some(at(4.0)));
+        assert_eq!(history.clock, 4.0);
+        assert_eq!(history.rewind(1.0), Some(at(3.0)), "a second rewind goes back further");
+        assert_eq!(history.len(), 1);
+        assert!(PlayerHistory::new(2.0).rewind(1.0).is_none());
+    }
+}
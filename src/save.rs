@@ -0,0 +1,159 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::error::GameError;
+use crate::flags::Flags;
+use crate::paths::save_slot_path;
+use crate::results::BestTimes;
+use crate::toast::Toast;
+
+/// The save slot the game plays from. There's only the one for now.
+pub const SAVE_SLOT: u32 = 1;
+
+/// Where the game saves to.
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct SavePath(pub PathBuf);
+
+impl Default for SavePath {
+    fn default() -> Self {
+        SavePath(save_slot_path(SAVE_SLOT))
+    }
+}
+
+/// Everything that persists between sessions.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SaveData {
+    #[serde(default)]
+    pub flags: Flags,
+    #[serde(default)]
+    pub best_times: BestTimes,
+}
+
+/// Writes `data` to `path` as JSON.
+pub fn save_game(path: impl AsRef<Path>, data: &SaveData) -> Result<(), GameError> {
+    let json = serde_json::to_string_pretty(data)?;
+    Ok(fs::write(path, json)?)
+}
+
+/// Reads the save at `path`. A missing file is a fresh game rather than an error.
+pub fn load_game(path: impl AsRef<Path>) -> Result<SaveData, GameError> {
+    match fs::read(path) {
+        Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(SaveData::default()),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Loads the save at startup and writes it back whenever its contents change.
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<SavePath>()
+            .add_startup_system(load_save)
+            .add_system_to_stage(CoreStage::Last, write_save);
+    }
+}
+
+fn load_save(mut commands: Commands, path: Res<SavePath>, mut toasts: EventWriter<Toast>) {
+    let path = &path.0;
+    let data = load_game(path).unwrap_or_else(|error| {
+        error!("Couldn't load {}, starting a fresh game: {error}", path.display());
+        toasts.send(Toast(format!("Couldn't load your save, starting a fresh game: {error}")));
+        SaveData::default()
+    });
+    commands.insert_resource(data.flags);
+    commands.insert_resource(data.best_times);
+}
+
+fn write_save(
+    path: Res<SavePath>,
+    flags: Option<Res<Flags>>,
+    best_times: Option<Res<BestTimes>>,
+    mut loaded: Local<bool>,
+    mut toasts: EventWriter<Toast>,
+) {
+    let (Some(flags), Some(best_times)) = (flags, best_times) else { return };
+    // The first change is the load itself. It replaces the plugins' defaults, which only counts as changed, not
+    // added, so it's skipped by hand.
+    if !*loaded {
+        *loaded = true;
+        return;
+    }
+    if !(flags.is_changed() || best_times.is_changed()) {
+        return;
+    }
+
+    let data = SaveData { flags: flags.clone(), best_times: best_times.clone() };
+    let path = &path.0;
+    if let Err(error) = save_game(path, &data) {
+        error!("Couldn't write {}: {error}", path.display());
+        toasts.send(Toast(format!("Couldn't save your progress: {error}")));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_save(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("beans_quest_{}_{name}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn flags_round_trip() {
+        let path = temp_save("round_trip");
+        let mut data = SaveData::default();
+        data.flags.set_flag("completed:level_1");
+        data.flags.set_flag("no_damage:level_1");
+
+        save_game(&path, &data).unwrap();
+        assert_eq!(load_game(&path).unwrap(), data);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn writes_only_after_the_load() {
+        let path = temp_save("first_frame");
+        let mut app = App::new();
+        app
+            .add_event::<Toast>()
+            .init_resource::<Flags>()
+            .init_resource::<BestTimes>()
+            .insert_resource(SavePath(path.clone()))
+            .add_plugin(SavePlugin);
+
+        app.update();
+        app.update();
+        assert!(!path.exists());
+
+        app.world.resource_mut::<Flags>().set_flag("completed:level_1");
+        app.update();
+        assert!(load_game(&path).unwrap().flags.has_flag("completed:level_1"));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn a_missing_save_is_a_fresh_game() {
+        let path = temp_save("missing");
+        let GameError::Io(error) = GameError::from(fs::read(&path).unwrap_err()) else { panic!("not an Io error") };
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+        assert_eq!(load_game(&path).unwrap(), SaveData::default());
+        // Other failures to read it are still errors.
+        assert!(matches!(load_game(std::env::temp_dir()), Err(GameError::Io(_))));
+
+        let mut app = App::new();
+        app.add_event::<Toast>().insert_resource(SavePath(path.clone())).add_startup_system(load_save);
+        app.update();
+        assert_eq!(app.world.resource::<Flags>(), &Flags::default());
+        assert!(app.world.resource::<Events<Toast>>().is_empty());
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,510 @@
+use std::fs;
+use std::io;
+use std::ops::DerefMut;
+use std::path::Path;
+
+use bevy::prelude::*;
+use bevy::render::render_resource::{FilterMode, SamplerDescriptor};
+use bevy::render::texture::ImageSampler;
+use bevy::window::PresentMode;
+use gamelibs::math::Ease;
+use serde::{Deserialize, Serialize};
+
+use crate::error::GameError;
+use crate::paths::settings_path;
+use crate::smoothing::SmoothingMode;
+use crate::toast::Toast;
+
+/// The window sizes the settings menu offers, smallest first.
+pub const RESOLUTIONS: [UVec2; 4] = [
+    UVec2::new(1280, 720),
+    UVec2::new(1600, 900),
+    UVec2::new(1920, 1080),
+    UVec2::new(2560, 1440),
+];
+
+/// How textures are sampled when scaled on screen.
+///
+/// * `SamplerMode::Nearest` keeps pixel art crisp, every texel a hard-edged square.
+///
+/// * `SamplerMode::Linear` blends neighbouring texels, which suits painted art but blurs pixel art.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SamplerMode {
+    #[default]
+    Nearest,
+    Linear,
+}
+
+impl SamplerMode {
+    pub fn descriptor(self) -> SamplerDescriptor<'static> {
+        let filter = match self {
+            SamplerMode::Nearest => FilterMode::Nearest,
+            SamplerMode::Linear => FilterMode::Linear,
+        };
+        SamplerDescriptor {
+            mag_filter: filter,
+            min_filter: filter,
+            mipmap_filter: filter,
+            ..default()
+        }
+    }
+}
+
+/// How the game takes up the screen.
+///
+/// * `WindowMode::Windowed` is a window of the chosen resolution.
+///
+/// * `WindowMode::BorderlessFullscreen` covers the monitor at its own resolution, so the chosen one is ignored.
+///
+/// * `WindowMode::ExclusiveFullscreen` switches the monitor to the chosen resolution, or the nearest it supports.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WindowMode {
+    #[default]
+    Windowed,
+    BorderlessFullscreen,
+    ExclusiveFullscreen,
+}
+
+impl WindowMode {
+    pub const ALL: [WindowMode; 3] =
+        [WindowMode::Windowed, WindowMode::BorderlessFullscreen, WindowMode::ExclusiveFullscreen];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            WindowMode::Windowed => "Windowed",
+            WindowMode::BorderlessFullscreen => "Borderless",
+            WindowMode::ExclusiveFullscreen => "Fullscreen",
+        }
+    }
+
+    /// Whether the window is sized by the `resolution` setting rather than the monitor.
+    pub fn uses_resolution(self) -> bool {
+        self != WindowMode::BorderlessFullscreen
+    }
+
+    /// Bevy's mode for a window in this mode at `resolution`. Exclusive fullscreen only takes a resolution from
+    /// `RESOLUTIONS`, since the monitor is switched to it; any other falls back to borderless.
+    pub fn bevy_mode(self, resolution: UVec2) -> bevy::window::WindowMode {
+        match self {
+            WindowMode::Windowed => bevy::window::WindowMode::Windowed,
+            WindowMode::ExclusiveFullscreen if RESOLUTIONS.contains(&resolution) => {
+                bevy::window::WindowMode::SizedFullscreen
+            }
+            WindowMode::BorderlessFullscreen | WindowMode::ExclusiveFullscreen => {
+                bevy::window::WindowMode::BorderlessFullscreen
+            }
+        }
+    }
+}
+
+/// A control that can be rebound to another key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Binding {
+    Left,
+    Right,
+    Up,
+    Down,
+    Jump,
+    Charge,
+    Interact,
+    Fire,
+}
+
+impl Binding {
+    pub const ALL: [Binding; 8] = [
+        Binding::Left,
+        Binding::Right,
+        Binding::Up,
+        Binding::Down,
+        Binding::Jump,
+        Binding::Charge,
+        Binding::Interact,
+        Binding::Fire,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Binding::Left => "Left",
+            Binding::Right => "Right",
+            Binding::Up => "Up",
+            Binding::Down => "Down",
+            Binding::Jump => "Jump",
+            Binding::Charge => "Charge",
+            Binding::Interact => "Interact",
+            Binding::Fire => "Fire",
+        }
+    }
+}
+
+/// The key for each `Binding`. The arrow keys move too, whatever the movement keys are bound to.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    pub left: KeyCode,
+    pub right: KeyCode,
+    pub up: KeyCode,
+    pub down: KeyCode,
+    pub jump: KeyCode,
+    pub charge: KeyCode,
+    pub interact: KeyCode,
+    pub fire: KeyCode,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings {
+            left: KeyCode::A,
+            right: KeyCode::D,
+            up: KeyCode::W,
+            down: KeyCode::S,
+            jump: KeyCode::Space,
+            charge: KeyCode::LShift,
+            interact: KeyCode::E,
+            fire: KeyCode::F,
+        }
+    }
+}
+
+impl KeyBindings {
+    pub fn key(&self, binding: Binding) -> KeyCode {
+        match binding {
+            Binding::Left => self.left,
+            Binding::Right => self.right,
+            Binding::Up => self.up,
+            Binding::Down => self.down,
+            Binding::Jump => self.jump,
+            Binding::Charge => self.charge,
+            Binding::Interact => self.interact,
+            Binding::Fire => self.fire,
+        }
+    }
+
+    /// Binds `binding` to `key`. A control already on `key` takes over `binding`'s old key, so no two share one.
+    pub fn bind(&mut self, binding: Binding, key: KeyCode) {
+        let old = self.key(binding);
+        for other in Binding::ALL {
+            if other != binding && self.key(other) == key {
+                *self.slot(other) = old;
+            }
+        }
+        *self.slot(binding) = key;
+    }
+
+    fn slot(&mut self, binding: Binding) -> &mut KeyCode {
+        match binding {
+            Binding::Left => &mut self.left,
+            Binding::Right => &mut self.right,
+            Binding::Up => &mut self.up,
+            Binding::Down => &mut self.down,
+            Binding::Jump => &mut self.jump,
+            Binding::Charge => &mut self.charge,
+            Binding::Interact => &mut self.interact,
+            Binding::Fire => &mut self.fire,
+        }
+    }
+}
+
+/// How strongly the game's effects play, for players who find them too much. Each effect scales or switches off
+/// on its own.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JuiceSettings {
+    /// Multiplier on how far the camera shakes; 0 turns shaking off.
+    pub screen_shake: f32,
+    /// Whether gameplay freezes for a moment when a hit lands.
+    pub hitstop: bool,
+    /// Whether sprites flash, e.g. when hurt or invulnerable.
+    pub flash: bool,
+    /// Multiplier on how far sprites squash and stretch; 0 keeps them their own shape.
+    pub squash_stretch: f32,
+}
+
+impl Default for JuiceSettings {
+    fn default() -> Self {
+        JuiceSettings {
+            screen_shake: 1.0,
+            hitstop: true,
+            flash: true,
+            squash_stretch: 1.0,
+        }
+    }
+}
+
+/// Player-facing options, applied live whenever they change and kept in the settings file.
+///
+/// Options missing from the file take their defaults, so files from older versions still load.
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Multi-sample anti-aliasing samples per pixel; 1 turns it off.
+    pub msaa_samples: u32,
+    pub sampler: SamplerMode,
+    pub smoothing: SmoothingMode,
+    /// Left-stick deflection, out of 1, that is still treated as no movement.
+    pub stick_deadzone: f32,
+    /// How deflection past the deadzone maps onto movement; an ease-in gives finer control at low speeds.
+    pub stick_curve: Ease,
+    /// Loudness of everything, out of 1.
+    pub master_volume: f32,
+    /// Loudness of music, out of 1, before `master_volume`.
+    pub music_volume: f32,
+    /// Loudness of sound effects, out of 1, before `master_volume`.
+    pub sfx_volume: f32,
+    /// Loudness of spoken lines, out of 1, before `master_volume`.
+    pub dialog_volume: f32,
+    pub window_mode: WindowMode,
+    /// The window's size in logical pixels, when `window_mode` uses it.
+    pub resolution: UVec2,
+    pub vsync: bool,
+    pub juice: JuiceSettings,
+    pub key_bindings: KeyBindings,
+}
+
+impl Default for Settings {
+    /// Pixel art: no anti-aliasing and nearest-neighbour sampling.
+    fn default() -> Self {
+        Settings {
+            msaa_samples: 1,
+            sampler: SamplerMode::Nearest,
+            smoothing: SmoothingMode::Off,
+            stick_deadzone: 0.2,
+            stick_curve: Ease::QuadIn,
+            master_volume: 1.0,
+            music_volume: 1.0,
+            sfx_volume: 1.0,
+            dialog_volume: 1.0,
+            window_mode: WindowMode::Windowed,
+            resolution: UVec2::new(1920, 1080),
+            vsync: false,
+            juice: JuiceSettings::default(),
+            key_bindings: KeyBindings::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn present_mode(&self) -> PresentMode {
+        if self.vsync { PresentMode::AutoVsync } else { PresentMode::AutoNoVsync }
+    }
+}
+
+/// The settings as they were when the settings menu opened, to go back to if the changes are cancelled.
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct SettingsSnapshot(pub Settings);
+
+impl SettingsSnapshot {
+    pub fn take(settings: &Settings) -> Self {
+        SettingsSnapshot(settings.clone())
+    }
+
+    /// Puts `settings` back as they were. They're only written if something differs, so cancelling without
+    /// changes doesn't reapply everything.
+    pub fn revert(&self, mut settings: impl DerefMut<Target = Settings>) {
+        if *settings != self.0 {
+            *settings = self.0.clone();
+        }
+    }
+}
+
+/// Writes `settings` to `path` as JSON.
+pub fn save_settings(path: impl AsRef<Path>, settings: &Settings) -> Result<(), GameError> {
+    let json = serde_json::to_string_pretty(settings)?;
+    Ok(fs::write(path, json)?)
+}
+
+/// Reads the settings at `path`. A missing file means the defaults rather than an error.
+pub fn load_settings(path: impl AsRef<Path>) -> Result<Settings, GameError> {
+    match fs::read(path) {
+        Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Settings::default()),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// The MSAA sample count the renderer can actually use for a requested one. wgpu only supports 1 or 4.
+pub fn supported_msaa_samples(requested: u32) -> u32 {
+    if requested >= 4 { 4 } else { 1 }
+}
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<Settings>()
+            .add_startup_system(load_settings_file)
+            .add_system(apply_msaa)
+            .add_system(apply_sampler)
+            .add_system(apply_window);
+    }
+}
+
+fn load_settings_file(mut commands: Commands, mut toasts: EventWriter<Toast>) {
+    let path = settings_path();
+    let settings = load_settings(&path).unwrap_or_else(|error| {
+        error!("Couldn't load {}, using the default settings: {error}", path.display());
+        toasts.send(Toast(format!("Couldn't load your settings, using the defaults: {error}")));
+        Settings::default()
+    });
+    commands.insert_resource(settings);
+}
+
+fn apply_msaa(settings: Res<Settings>, mut commands: Commands) {
+    if settings.is_changed() {
+        commands.insert_resource(Msaa {
+            samples: supported_msaa_samples(settings.msaa_samples),
+        });
+    }
+}
+
+/*
+ * Every image gets the sampler from the settings as it finishes loading: sprites and tilesets, whether loaded by
+ * the asset collections or by LDtk. Changing the setting resamples everything already loaded, which marks every
+ * image modified and so re-uploads it; other settings changing leaves them be.
+ */
+fn apply_sampler(
+    settings: Res<Settings>,
+    mut image_events: EventReader<AssetEvent<Image>>,
+    mut images: ResMut<Assets<Image>>,
+    mut applied: Local<Option<SamplerMode>>,
+) {
+    let sampler = || ImageSampler::Descriptor(settings.sampler.descriptor());
+
+    if *applied != Some(settings.sampler) {
+        *applied = Some(settings.sampler);
+        image_events.clear();
+        for (_, image) in images.iter_mut() {
+            image.sampler_descriptor = sampler();
+        }
+        return;
+    }
+
+    for event in image_events.iter() {
+        let AssetEvent::Created { handle } = event else { continue };
+        if let Some(image) = images.get_mut(handle) {
+            image.sampler_descriptor = sampler();
+        }
+    }
+}
+
+/// The size is set before the mode, as exclusive fullscreen picks the monitor resolution nearest the window's.
+fn apply_window(settings: Res<Settings>, mut windows: ResMut<Windows>) {
+    if !settings.is_changed() {
+        return;
+    }
+    let Some(window) = windows.get_primary_mut() else { return };
+
+    if window.present_mode() != settings.present_mode() {
+        window.set_present_mode(settings.present_mode());
+    }
+    let size = settings.resolution.as_vec2();
+    let resized = settings.window_mode.uses_resolution()
+        && Vec2::new(window.requested_width(), window.requested_height()) != size;
+    if resized {
+        window.set_resolution(size.x, size.y);
+    }
+    let mode = settings.window_mode.bevy_mode(settings.resolution);
+    let exclusive = mode == bevy::window::WindowMode::SizedFullscreen;
+    if window.mode() != mode || (resized && exclusive) {
+        if settings.window_mode == WindowMode::ExclusiveFullscreen && !exclusive {
+            let UVec2 { x, y } = settings.resolution;
+            warn!("{x} x {y} isn't a fullscreen resolution, using borderless fullscreen");
+        }
+        window.set_mode(mode);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sampler_modes_map_to_filters() {
+        let nearest = SamplerMode::Nearest.descriptor();
+        assert_eq!(
+            (nearest.mag_filter, nearest.min_filter, nearest.mipmap_filter),
+            (FilterMode::Nearest, FilterMode::Nearest, FilterMode::Nearest),
+        );
+        let linear = SamplerMode::Linear.descriptor();
+        assert_eq!(
+            (linear.mag_filter, linear.min_filter, linear.mipmap_filter),
+            (FilterMode::Linear, FilterMode::Linear, FilterMode::Linear),
+        );
+        assert_eq!(SamplerMode::default(), SamplerMode::Nearest);
+    }
+
+    #[test]
+    fn msaa_samples_round_to_supported_counts() {
+        assert_eq!(supported_msaa_samples(0), 1);
+        assert_eq!(supported_msaa_samples(1), 1);
+        assert_eq!(supported_msaa_samples(2), 1);
+        assert_eq!(supported_msaa_samples(4), 4);
+        assert_eq!(supported_msaa_samples(8), 4);
+    }
+
+    #[test]
+    fn cancelling_reverts_to_the_snapshot() {
+        let mut world = World::new();
+        world.insert_resource(Settings::default());
+        let snapshot = SettingsSnapshot::take(world.resource::<Settings>());
+
+        world.resource_mut::<Settings>().msaa_samples = 1;
+        world.resource_mut::<Settings>().sampler = SamplerMode::Linear;
+        world.clear_trackers();
+        snapshot.revert(world.resource_mut::<Settings>());
+        assert_eq!(*world.resource::<Settings>(), Settings::default());
+        assert!(world.is_resource_changed::<Settings>());
+
+        // Nothing to undo, so nothing is reapplied.
+        world.clear_trackers();
+        snapshot.revert(world.resource_mut::<Settings>());
+        assert!(!world.is_resource_changed::<Settings>());
+    }
+
+    #[test]
+    fn window_modes_map_to_bevy_modes() {
+        use bevy::window::WindowMode as BevyMode;
+
+        let listed = UVec2::new(1600, 900);
+        let unlisted = UVec2::new(1000, 700);
+        assert_eq!(WindowMode::Windowed.bevy_mode(unlisted), BevyMode::Windowed);
+        assert_eq!(WindowMode::BorderlessFullscreen.bevy_mode(listed), BevyMode::BorderlessFullscreen);
+        assert_eq!(WindowMode::ExclusiveFullscreen.bevy_mode(listed), BevyMode::SizedFullscreen);
+        assert_eq!(WindowMode::ExclusiveFullscreen.bevy_mode(unlisted), BevyMode::BorderlessFullscreen);
+
+        let uses_resolution = WindowMode::ALL.map(WindowMode::uses_resolution);
+        assert_eq!(uses_resolution, [true, false, true]);
+    }
+
+    #[test]
+    fn only_sampler_changes_resample_loaded_images() {
+        let mut app = App::new();
+        app
+            .add_plugin(CorePlugin::default())
+            .add_plugin(bevy::asset::AssetPlugin::default())
+            .add_asset::<Image>()
+            .init_resource::<Settings>()
+            .add_system(apply_sampler);
+        let image = app.world.resource_mut::<Assets<Image>>().add(Image::default());
+        // The frame it's added on, and the one its creation is seen on.
+        app.update();
+        app.update();
+        let sampler = |app: &App| {
+            match &app.world.resource::<Assets<Image>>().get(&image).unwrap().sampler_descriptor {
+                ImageSampler::Descriptor(descriptor) => Some(descriptor.mag_filter),
+                ImageSampler::Default => None,
+            }
+        };
+        assert_eq!(sampler(&app), Some(SamplerMode::Nearest.descriptor().mag_filter));
+
+        // Left alone by anything but the sampler changing, which would otherwise re-upload it.
+        app.world.resource_mut::<Assets<Image>>().get_mut(&image).unwrap().sampler_descriptor = ImageSampler::Default;
+        app.world.resource_mut::<Settings>().master_volume = 0.5;
+        app.update();
+        assert_eq!(sampler(&app), None);
+
+        app.world.resource_mut::<Settings>().sampler = SamplerMode::Linear;
+        app.update();
+        assert_eq!(sampler(&app), Some(SamplerMode::Linear.descriptor().mag_filter));
+    }
+}
@@ -0,0 +1,193 @@
+use bevy::prelude::*;
+
+use crate::input::{read_menu_input, MenuInput};
+use crate::menu::{Menu, MenuAction, MenuItem};
+use crate::settings::{Binding, Settings, WindowMode, RESOLUTIONS};
+
+/// How far one press moves a volume slider.
+const VOLUME_STEP: f32 = 0.1;
+
+/// A row of the settings menu. Left and right adjust it; confirming steps it forward, or starts a rebind.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SettingsItem {
+    MasterVolume,
+    MusicVolume,
+    SfxVolume,
+    DialogVolume,
+    WindowMode,
+    Resolution,
+    Vsync,
+    ScreenShake,
+    Hitstop,
+    Flash,
+    SquashStretch,
+    Rebind(Binding),
+}
+
+impl SettingsItem {
+    /// Every row, in menu order.
+    pub fn all() -> Vec<SettingsItem> {
+        let mut items = vec![
+            SettingsItem::MasterVolume,
+            SettingsItem::MusicVolume,
+            SettingsItem::SfxVolume,
+            SettingsItem::DialogVolume,
+            SettingsItem::WindowMode,
+            SettingsItem::Resolution,
+            SettingsItem::Vsync,
+            SettingsItem::ScreenShake,
+            SettingsItem::Hitstop,
+            SettingsItem::Flash,
+            SettingsItem::SquashStretch,
+        ];
+        items.extend(Binding::ALL.map(SettingsItem::Rebind));
+        items
+    }
+}
+
+/// The control waiting for a new key, if the settings menu is listening for one.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Rebinding(pub Option<Binding>);
+
+pub struct SettingsMenuPlugin;
+
+impl Plugin for SettingsMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<Rebinding>()
+            .add_system_to_stage(CoreStage::PreUpdate, capture_rebind.after(read_menu_input))
+            .add_system(adjust_settings)
+            .add_system(label_settings.after(adjust_settings));
+    }
+}
+
+/// The row's text, e.g. "Master Volume: < 80% >".
+pub fn settings_label(item: SettingsItem, settings: &Settings, rebinding: Rebinding) -> String {
+    let slider = |volume: f32| format!("< {:.0}% >", volume * 100.0);
+    let toggle = |on: bool| if on { "On" } else { "Off" };
+    match item {
+        SettingsItem::MasterVolume => format!("Master Volume: {}", slider(settings.master_volume)),
+        SettingsItem::MusicVolume => format!("Music Volume: {}", slider(settings.music_volume)),
+        SettingsItem::SfxVolume => format!("Effects Volume: {}", slider(settings.sfx_volume)),
+        SettingsItem::DialogVolume => format!("Dialog Volume: {}", slider(settings.dialog_volume)),
+        SettingsItem::WindowMode => format!("Window: < {} >", settings.window_mode.label()),
+        SettingsItem::Resolution if !settings.window_mode.uses_resolution() => "Resolution: Desktop".to_string(),
+        SettingsItem::Resolution => {
+            format!("Resolution: < {} x {} >", settings.resolution.x, settings.resolution.y)
+        }
+        SettingsItem::Vsync => format!("Vsync: {}", toggle(settings.vsync)),
+        SettingsItem::ScreenShake => format!("Screen Shake: {}", slider(settings.juice.screen_shake)),
+        SettingsItem::Hitstop => format!("Hitstop: {}", toggle(settings.juice.hitstop)),
+        SettingsItem::Flash => format!("Flashing: {}", toggle(settings.juice.flash)),
+        SettingsItem::SquashStretch => format!("Squash & Stretch: {}", slider(settings.juice.squash_stretch)),
+        SettingsItem::Rebind(binding) if rebinding.0 == Some(binding) => {
+            format!("{}: press a key", binding.label())
+        }
+        SettingsItem::Rebind(binding) => format!("{}: {:?}", binding.label(), settings.key_bindings.key(binding)),
+    }
+}
+
+/// Moves `item` `step` notches: volumes and effect strengths go up or down a tenth, window modes and resolutions
+/// cycle, and on/off options flip.
+/// The resolution stays put in borderless fullscreen, which uses the monitor's. Key bindings aren't stepped; they
+/// wait for a key instead.
+pub fn adjust_setting(settings: &mut Settings, item: SettingsItem, step: i32) {
+    let notch = |volume: f32| ((volume / VOLUME_STEP).round() + step as f32) * VOLUME_STEP;
+    match item {
+        SettingsItem::MasterVolume => settings.master_volume = notch(settings.master_volume).clamp(0.0, 1.0),
+        SettingsItem::MusicVolume => settings.music_volume = notch(settings.music_volume).clamp(0.0, 1.0),
+        SettingsItem::SfxVolume => settings.sfx_volume = notch(settings.sfx_volume).clamp(0.0, 1.0),
+        SettingsItem::DialogVolume => settings.dialog_volume = notch(settings.dialog_volume).clamp(0.0, 1.0),
+        SettingsItem::WindowMode => {
+            let current = WindowMode::ALL.iter().position(|&mode| mode == settings.window_mode).unwrap_or(0);
+            let next = (current as i32 + step).rem_euclid(WindowMode::ALL.len() as i32);
+            settings.window_mode = WindowMode::ALL[next as usize];
+        }
+        SettingsItem::Resolution if !settings.window_mode.uses_resolution() => {}
+        SettingsItem::Resolution => {
+            // A size from outside the list, such as one edited into the file, steps from the start of it.
+            let current = RESOLUTIONS.iter().position(|&size| size == settings.resolution).unwrap_or(0);
+            let next = (current as i32 + step).rem_euclid(RESOLUTIONS.len() as i32);
+            settings.resolution = RESOLUTIONS[next as usize];
+        }
+        SettingsItem::Vsync => flip(&mut settings.vsync, step),
+        SettingsItem::ScreenShake => settings.juice.screen_shake = notch(settings.juice.screen_shake).clamp(0.0, 1.0),
+        SettingsItem::Hitstop => flip(&mut settings.juice.hitstop, step),
+        SettingsItem::Flash => flip(&mut settings.juice.flash, step),
+        SettingsItem::SquashStretch => {
+            settings.juice.squash_stretch = notch(settings.juice.squash_stretch).clamp(0.0, 1.0);
+        }
+        SettingsItem::Rebind(_) => {}
+    }
+}
+
+fn flip(option: &mut bool, step: i32) {
+    if step % 2 != 0 {
+        *option = !*option;
+    }
+}
+
+/*
+ * While a control waits for its key, the next key pressed is bound to it, or Escape gives up. Either way the press
+ * is swallowed so the menu doesn't also act on it.
+ */
+fn capture_rebind(
+    keys: Res<Input<KeyCode>>,
+    mut rebinding: ResMut<Rebinding>,
+    mut settings: ResMut<Settings>,
+    mut menu_input: ResMut<MenuInput>,
+) {
+    let Some(binding) = rebinding.0 else { return };
+    *menu_input = MenuInput::default();
+
+    let Some(&key) = keys.get_just_pressed().next() else { return };
+    if key != KeyCode::Escape {
+        settings.key_bindings.bind(binding, key);
+    }
+    rebinding.0 = None;
+}
+
+fn adjust_settings(
+    input: Res<MenuInput>,
+    mut actions: EventReader<MenuAction>,
+    menus: Query<&Menu>,
+    mut settings: ResMut<Settings>,
+    mut rebinding: ResMut<Rebinding>,
+) {
+    for action in actions.iter() {
+        match *action {
+            MenuAction::Setting(SettingsItem::Rebind(binding)) => rebinding.0 = Some(binding),
+            MenuAction::Setting(item) => adjust_setting(&mut settings, item, 1),
+            _ => {}
+        }
+    }
+
+    let step = input.right as i32 - input.left as i32;
+    if step == 0 {
+        return;
+    }
+    for menu in menus.iter() {
+        if let Some(&MenuAction::Setting(item)) = menu.items.get(menu.selected) {
+            adjust_setting(&mut settings, item, step);
+        }
+    }
+}
+
+fn label_settings(
+    settings: Res<Settings>,
+    rebinding: Res<Rebinding>,
+    menus: Query<&Menu>,
+    mut items: Query<(&MenuItem, &Parent, &mut Text)>,
+) {
+    if !settings.is_changed() && !rebinding.is_changed() {
+        return;
+    }
+    for (item, parent, mut text) in items.iter_mut() {
+        let Ok(menu) = menus.get(parent.get()) else { continue };
+        let Some(&MenuAction::Setting(setting)) = menu.items.get(item.0) else { continue };
+        let label = settings_label(setting, &settings, *rebinding);
+        if text.sections[0].value != label {
+            text.sections[0].value = label;
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use bevy::prelude::*;
+use bevy::sprite::{MaterialMesh2dBundle, Mesh2dHandle};
+use bevy::transform::TransformSystem;
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::camera::GameCamera;
+use crate::fields::LdtkFields;
+use crate::level::find_level;
+
+/// LDtk level field identifiers for the top and bottom colours of the sky.
+const SKY_TOP_FIELD: &str = "SkyTop";
+const SKY_BOTTOM_FIELD: &str = "SkyBottom";
+
+/// A vertical gradient drawn behind everything else in the world.
+///
+/// When this resource is absent the background quad is hidden and the `ClearColor` shows through.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct SkyGradient {
+    pub top: Color,
+    pub bottom: Color,
+}
+
+/// Marker for the full-screen quad the gradient is drawn on.
+#[derive(Component)]
+struct SkyQuad;
+
+pub struct SkyPlugin;
+
+impl Plugin for SkyPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_startup_system(spawn_sky_quad)
+            .add_system(sky_from_level)
+            .add_system(update_sky_colors.after(sky_from_level))
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                fit_sky_to_camera.before(TransformSystem::TransformPropagate)
+            );
+    }
+}
+
+fn spawn_sky_quad(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let mut mesh = Mesh::from(shape::Quad::new(Vec2::ONE));
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, vertex_colors(Color::NONE, Color::NONE));
+
+    commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: meshes.add(mesh).into(),
+            material: materials.add(ColorMaterial::from(Color::WHITE)),
+            visibility: Visibility { is_visible: false },
+            ..default()
+        },
+        SkyQuad,
+    ));
+}
+
+/// Vertex colours for a `shape::Quad`, whose vertices run bottom-left, top-left, top-right, bottom-right.
+fn vertex_colors(top: Color, bottom: Color) -> Vec<[f32; 4]> {
+    let top = top.as_linear_rgba_f32();
+    let bottom = bottom.as_linear_rgba_f32();
+    vec![bottom, top, top, bottom]
+}
+
+/// Reads the sky colours off a freshly spawned LDtk level, clearing the gradient if the level doesn't declare one.
+fn sky_from_level(
+    mut commands: Commands,
+    mut level_events: EventReader<LevelEvent>,
+    levels: Query<&Handle<LdtkLevel>>,
+    level_assets: Res<Assets<LdtkLevel>>,
+) {
+    for event in level_events.iter() {
+        let LevelEvent::Spawned(iid) = event else { continue };
+
+        let Some(level) = find_level(iid, &levels, &level_assets) else { continue };
+
+        let fields = level.fields();
+        match fields.get_color(SKY_TOP_FIELD).zip(fields.get_color(SKY_BOTTOM_FIELD)) {
+            Some((top, bottom)) => commands.insert_resource(SkyGradient { top, bottom }),
+            None => commands.remove_resource::<SkyGradient>(),
+        }
+    }
+}
+
+fn update_sky_colors(
+    gradient: Option<Res<SkyGradient>>,
+    mut quads: Query<(&Mesh2dHandle, &mut Visibility), With<SkyQuad>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let changed = match &gradient {
+        Some(gradient) => gradient.is_changed(),
+        None => true,
+    };
+    if !changed {
+        return;
+    }
+
+    for (mesh_handle, mut visibility) in quads.iter_mut() {
+        let Some(gradient) = &gradient else {
+            visibility.is_visible = false;
+            continue;
+        };
+
+        if let Some(mesh) = meshes.get_mut(&mesh_handle.0) {
+            mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, vertex_colors(gradient.top, gradient.bottom));
+        }
+        visibility.is_visible = true;
+    }
+}
+
+/*
+ * Keeps the quad centred on the camera, scaled to the visible area and pushed to the very back of the frustum.
+ * Runs in PostUpdate, after gameplay has moved or zoomed the camera, so the edges are never exposed.
+ */
+fn fit_sky_to_camera(
+    cameras: Query<(&Transform, &OrthographicProjection), (With<GameCamera>, Without<SkyQuad>)>,
+    mut quads: Query<&mut Transform, With<SkyQuad>>,
+) {
+    let Ok((camera_transform, projection)) = cameras.get_single() else { return };
+
+    let width = (projection.right - projection.left) * projection.scale;
+    let height = (projection.top - projection.bottom) * projection.scale;
+    let depth = camera_transform.translation.z - projection.far + 0.01;
+
+    for mut transform in quads.iter_mut() {
+        transform.translation = camera_transform.translation.truncate().extend(depth);
+        transform.scale = Vec3::new(width, height, 1.0);
+    }
+}
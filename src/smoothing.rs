@@ -0,0 +1,174 @@
+use bevy::prelude::*;
+use bevy_rapier2d::plugin::systems;
+use bevy_rapier2d::prelude::*;
+use bevy_rapier2d::rapier::math::{Isometry, Real, Vector};
+use serde::{Deserialize, Serialize};
+
+use crate::physics::FIXED_TIMESTEP;
+use crate::settings::Settings;
+
+/// How bodies are drawn between physics steps.
+///
+/// * `SmoothingMode::Off` steps physics once per frame by the frame's delta, so there is nothing to smooth, but the
+///   simulation depends on the frame rate.
+///
+/// * `SmoothingMode::Interpolate` steps physics at `FIXED_TIMESTEP` and draws bodies between their last two steps.
+///   Motion is exact but shown up to one step late, which adds that much input latency.
+///
+/// * `SmoothingMode::Extrapolate` steps the same way but draws bodies ahead of their last step along their velocity.
+///   Input shows up a step sooner, at the cost of overshooting by up to a step's travel whenever a body stops or
+///   turns suddenly, e.g. landing or hitting a wall, until the next step snaps it back.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SmoothingMode {
+    #[default]
+    Off,
+    Interpolate,
+    Extrapolate,
+}
+
+impl SmoothingMode {
+    pub fn timestep_mode(self) -> TimestepMode {
+        match self {
+            SmoothingMode::Off => RapierConfiguration::default().timestep_mode,
+            SmoothingMode::Interpolate | SmoothingMode::Extrapolate => TimestepMode::Interpolated {
+                dt: FIXED_TIMESTEP,
+                time_scale: 1.0,
+                substeps: 1,
+            },
+        }
+    }
+}
+
+/// A body's `TransformInterpolation` as the writeback left it, and where the body was at the time.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct SmoothingSnapshot {
+    interpolation: TransformInterpolation,
+    position: Option<Isometry<Real>>,
+}
+
+pub struct SmoothingPlugin;
+
+impl Plugin for SmoothingPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_system(apply_smoothing)
+            .add_system(smooth_bodies)
+            .add_system_to_stage(
+                PhysicsStages::SyncBackend,
+                restore_interpolation.after(systems::apply_rigid_body_user_changes),
+            )
+            .add_system_to_stage(
+                PhysicsStages::Writeback,
+                extrapolate_bodies.before(systems::writeback_rigid_bodies),
+            )
+            .add_system_to_stage(
+                PhysicsStages::Writeback,
+                snapshot_interpolation.after(systems::writeback_rigid_bodies),
+            );
+    }
+}
+
+/// Where a body at `position` moving at `linvel` and `angvel` will be `dt` seconds later, all in Rapier's units.
+pub fn extrapolate(position: &Isometry<Real>, linvel: Vector<Real>, angvel: Real, dt: Real) -> Isometry<Real> {
+    Isometry::new(position.translation.vector + linvel * dt, position.rotation.angle() + angvel * dt)
+}
+
+fn apply_smoothing(settings: Res<Settings>, mut rapier_config: ResMut<RapierConfiguration>) {
+    if settings.is_changed() {
+        rapier_config.timestep_mode = settings.smoothing.timestep_mode();
+    }
+}
+
+/// Rapier only smooths bodies that have a `TransformInterpolation`, and ignores it outside its interpolated mode.
+fn smooth_bodies(mut commands: Commands, bodies: Query<Entity, (With<RigidBody>, Without<TransformInterpolation>)>) {
+    for entity in bodies.iter() {
+        commands.entity(entity).insert((TransformInterpolation::default(), SmoothingSnapshot::default()));
+    }
+}
+
+/*
+ * Rapier throws a body's interpolation away whenever its transform changes, taking that for the game moving it.
+ * Its own writeback of the smoothed transform counts too, so every frame after a step would draw the body at the
+ * end of the step instead of blending towards it. If the body is still where the writeback left it, nothing else
+ * moved it and the interpolation is put back.
+ */
+fn restore_interpolation(
+    context: Res<RapierContext>,
+    mut bodies: Query<(&RapierRigidBodyHandle, &mut TransformInterpolation, &SmoothingSnapshot)>,
+) {
+    for (handle, mut interpolation, snapshot) in bodies.iter_mut() {
+        if interpolation.start.is_some() || interpolation.end.is_some() {
+            continue;
+        }
+        let Some(body) = context.bodies.get(handle.0) else { continue };
+        if snapshot.position == Some(*body.position()) {
+            *interpolation = snapshot.interpolation;
+        }
+    }
+}
+
+/*
+ * Rapier draws each body at `start.lerp(end, t)`, where t runs from 0 to 1 over the step it is catching up on.
+ * It fills `start` with the position before the frame's last step, and leaves `end` empty for the writeback to fill
+ * in with the position after it. Extrapolating instead starts from the position after the step and ends one step
+ * further along the body's velocity, so the same blend runs ahead of the simulation rather than behind it.
+ */
+fn extrapolate_bodies(
+    settings: Res<Settings>,
+    rapier_config: Res<RapierConfiguration>,
+    context: Res<RapierContext>,
+    mut bodies: Query<(&RapierRigidBodyHandle, &mut TransformInterpolation)>,
+) {
+    if settings.smoothing != SmoothingMode::Extrapolate {
+        return;
+    }
+    let TimestepMode::Interpolated { dt, .. } = rapier_config.timestep_mode else { return };
+
+    for (handle, mut interpolation) in bodies.iter_mut() {
+        // A body that didn't step this frame keeps blending towards the end it already has.
+        if interpolation.end.is_some() {
+            continue;
+        }
+        let Some(body) = context.bodies.get(handle.0) else { continue };
+        interpolation.start = Some(*body.position());
+        interpolation.end = Some(extrapolate(body.position(), *body.linvel(), body.angvel(), dt));
+    }
+}
+
+fn snapshot_interpolation(
+    context: Res<RapierContext>,
+    mut bodies: Query<(&RapierRigidBodyHandle, &TransformInterpolation, &mut SmoothingSnapshot)>,
+) {
+    for (handle, interpolation, mut snapshot) in bodies.iter_mut() {
+        snapshot.interpolation = *interpolation;
+        snapshot.position = context.bodies.get(handle.0).map(|body| *body.position());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extrapolation_runs_a_step_ahead_of_interpolation() {
+        // A body moving right at 3 m/s that has just finished a step.
+        let linvel = Vector::new(3.0, 0.0);
+        let before = Isometry::new(Vector::new(1.0, 2.0), 0.0);
+        let after = extrapolate(&before, linvel, 0.0, FIXED_TIMESTEP);
+
+        let interpolated = TransformInterpolation { start: Some(before), end: Some(after) };
+        let extrapolated = TransformInterpolation {
+            start: Some(after),
+            end: Some(extrapolate(&after, linvel, 0.0, FIXED_TIMESTEP)),
+        };
+
+        let step = linvel * FIXED_TIMESTEP;
+        for t in [0.0, 0.25, 0.5, 1.0] {
+            let behind = interpolated.lerp_slerp(t).unwrap().translation.vector;
+            let ahead = extrapolated.lerp_slerp(t).unwrap().translation.vector;
+            assert!((behind - (after.translation.vector - step * (1.0 - t))).norm() < 1e-6);
+            assert!((ahead - (after.translation.vector + step * t)).norm() < 1e-6);
+            assert!((ahead - behind - step).norm() < 1e-6);
+        }
+    }
+}
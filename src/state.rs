@@ -0,0 +1,127 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::auto_scroll::AutoScroll;
+use crate::camera::CameraFocus;
+use crate::confiner::CameraConfiner;
+use crate::cutscene::Cutscene;
+use crate::input::MenuInput;
+use crate::level::LevelBounds;
+use crate::physics::DeltaGuard;
+
+/// The top-level flow of the game.
+///
+/// `Countdown`, `Paused` and `LevelComplete` are pushed on top of `InGame` rather than replacing it, so the play session survives
+/// them and leaving `InGame` always means the session is over.
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Hash)]
+pub enum GameState {
+    AssetLoading,
+    MainMenu,
+    InGame,
+    /// The start countdown of a timed level, with the player held still until it finishes.
+    Countdown,
+    Paused,
+    /// The results screen after reaching a level's exit.
+    LevelComplete,
+    /// The physics test scene, in place of the game.
+    #[cfg(feature = "test-scene")]
+    TestScene,
+}
+
+/// Marker for the root entities of a play session: the level, the player, the HUD and anything spawned during play.
+/// All of them are despawned (with their children) when the session ends.
+#[derive(Component)]
+pub struct GameplayEntity;
+
+pub struct GameStatePlugin;
+
+impl Plugin for GameStatePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_system_set(SystemSet::on_enter(GameState::InGame).with_system(arm_delta_guard))
+            .add_system_set(SystemSet::on_update(GameState::InGame).with_system(pause_on_request))
+            .add_system_set(SystemSet::on_enter(GameState::Countdown).with_system(pause_physics))
+            .add_system_set(SystemSet::on_exit(GameState::Countdown).with_system(resume_physics))
+            .add_system_set(SystemSet::on_enter(GameState::Paused).with_system(pause_physics))
+            .add_system_set(SystemSet::on_exit(GameState::Paused).with_system(resume_physics))
+            .add_system_set(SystemSet::on_enter(GameState::LevelComplete).with_system(pause_physics))
+            .add_system_set(SystemSet::on_exit(GameState::LevelComplete).with_system(resume_physics))
+            .add_system_set(SystemSet::on_exit(GameState::InGame).with_system(despawn_gameplay));
+    }
+}
+
+fn arm_delta_guard(mut guard: ResMut<DeltaGuard>) {
+    guard.arm();
+}
+
+fn pause_on_request(input: Res<MenuInput>, mut state: ResMut<State<GameState>>) {
+    if input.pause {
+        // Ignore the error from a transition already queued this frame.
+        let _ = state.push(GameState::Paused);
+    }
+}
+
+fn pause_physics(mut rapier_config: ResMut<RapierConfiguration>) {
+    rapier_config.physics_pipeline_active = false;
+}
+
+fn resume_physics(mut rapier_config: ResMut<RapierConfiguration>) {
+    rapier_config.physics_pipeline_active = true;
+}
+
+/// Tears the play session down so a fresh one can start from the main menu without leftovers.
+fn despawn_gameplay(
+    mut commands: Commands,
+    entities: Query<Entity, With<GameplayEntity>>,
+    cameras: Query<Entity, Or<(With<AutoScroll>, With<CameraFocus>, With<CameraConfiner>)>>,
+) {
+    for entity in entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    for camera in cameras.iter() {
+        commands.entity(camera).remove::<(AutoScroll, CameraFocus, CameraConfiner)>();
+    }
+    commands.remove_resource::<LevelBounds>();
+    commands.remove_resource::<Cutscene>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quitting_to_the_menu_despawns_the_session() {
+        let mut app = App::new();
+        app
+            .init_resource::<DeltaGuard>()
+            .init_resource::<MenuInput>()
+            .insert_resource(RapierConfiguration::default())
+            .add_state(GameState::InGame)
+            .add_plugin(GameStatePlugin);
+        app.update();
+
+        let root = app.world.spawn(GameplayEntity).id();
+        let child = app.world.spawn_empty().id();
+        app.world.entity_mut(root).push_children(&[child]);
+        app.world.spawn(GameplayEntity);
+        let camera = app.world.spawn(CameraFocus { target_rect: Rect::default(), duration: 1.0 }).id();
+        let bystander = app.world.spawn_empty().id();
+        app.world.insert_resource(LevelBounds { min: Vec2::ZERO, max: Vec2::ONE });
+        app.world.insert_resource(Cutscene::new(Default::default()));
+
+        app.world.resource_mut::<State<GameState>>().push(GameState::Paused).unwrap();
+        app.update();
+        assert!(!app.world.resource::<RapierConfiguration>().physics_pipeline_active);
+
+        app.world.resource_mut::<State<GameState>>().replace(GameState::MainMenu).unwrap();
+        app.update();
+
+        let mut gameplay = app.world.query_filtered::<(), With<GameplayEntity>>();
+        assert_eq!(gameplay.iter(&app.world).count(), 0);
+        assert!(app.world.get_entity(child).is_none());
+        assert!(app.world.get::<CameraFocus>(camera).is_none());
+        assert!(app.world.get_entity(bystander).is_some());
+        assert!(!app.world.contains_resource::<LevelBounds>());
+        assert!(!app.world.contains_resource::<Cutscene>());
+    }
+}
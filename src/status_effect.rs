@@ -0,0 +1,329 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::flash::Flash;
+use crate::health::Damage;
+use crate::physics::GameplayDelta;
+use crate::player::{Player, PlayerSprite};
+use crate::state::GameState;
+
+/// Blinks per second of the player's sprite while affected.
+const FLASH_FREQUENCY: f32 = 5.0;
+/// The sprite's alpha during the faded half of a blink.
+const FLASH_ALPHA: f32 = 0.35;
+
+/// Which status an effect is, for stacking: effects of the same kind stack, different kinds always coexist.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StatusKind {
+    Burning,
+    Poisoned,
+    SpeedBoost,
+    ExtraJump,
+    Magnet,
+}
+
+/// What a status effect does while it lasts.
+///
+/// * `Modifier::Damage(amount)` hurts by `amount` on every tick.
+///
+/// * `Modifier::Speed(scale)` scales the top running speed.
+///
+/// * `Modifier::AirJumps(count)` adds mid-air jumps, refilled on landing like the usual ones.
+///
+/// * `Modifier::Magnet { radius, strength }` pulls beans within `radius` pixels in, see `magnet::magnet_curve`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Modifier {
+    Damage(f32),
+    Speed(f32),
+    AirJumps(u32),
+    Magnet { radius: f32, strength: f32 },
+}
+
+/// How a new effect combines with one of the same kind already running.
+///
+/// * `Stacking::Refresh` keeps one effect, running for the longer of the two durations with the new modifier. Its
+///   tick timing carries on, so a hazard reapplying it every frame still ticks.
+///
+/// * `Stacking::Extend` keeps one effect and adds the new duration to what's left.
+///
+/// * `Stacking::Stack(max)` runs up to `max` effects side by side; past that, the one closest to expiring is
+///   replaced.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stacking {
+    Refresh,
+    Extend,
+    Stack(usize),
+}
+
+/// The `Stacking` of each `StatusKind`. Kinds without one refresh.
+#[derive(Resource, Clone, Debug)]
+pub struct StatusRules(pub HashMap<StatusKind, Stacking>);
+
+impl Default for StatusRules {
+    fn default() -> Self {
+        StatusRules(HashMap::from_iter([
+            (StatusKind::Burning, Stacking::Refresh),
+            (StatusKind::Poisoned, Stacking::Stack(3)),
+            (StatusKind::SpeedBoost, Stacking::Refresh),
+            (StatusKind::ExtraJump, Stacking::Extend),
+            (StatusKind::Magnet, Stacking::Refresh),
+        ]))
+    }
+}
+
+impl StatusRules {
+    pub fn stacking(&self, kind: StatusKind) -> Stacking {
+        self.0.get(&kind).copied().unwrap_or(Stacking::Refresh)
+    }
+}
+
+/// A timed effect, such as damage over time from lava or a power-up's buff.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    pub modifier: Modifier,
+    /// Seconds until it wears off.
+    pub remaining: f32,
+    /// Seconds between ticks, or 0 for an effect that never ticks, like a buff.
+    pub tick_interval: f32,
+    /// Seconds until the next tick.
+    until_tick: f32,
+}
+
+impl StatusEffect {
+    /// An effect lasting `duration` seconds. The first tick comes one `tick_interval` after it starts.
+    pub fn new(kind: StatusKind, modifier: Modifier, duration: f32, tick_interval: f32) -> Self {
+        StatusEffect {
+            kind,
+            modifier,
+            remaining: duration,
+            tick_interval,
+            until_tick: tick_interval,
+        }
+    }
+
+    /// Runs the effect for `dt` seconds, returning how many ticks fell in that time. A tick landing exactly as the
+    /// effect expires still counts; none come after.
+    pub fn advance(&mut self, dt: f32) -> u32 {
+        let dt = dt.min(self.remaining.max(0.0));
+        self.remaining -= dt;
+        if self.tick_interval <= 0.0 {
+            return 0;
+        }
+
+        self.until_tick -= dt;
+        let mut ticks = 0;
+        while self.until_tick <= 0.0 {
+            ticks += 1;
+            self.until_tick += self.tick_interval;
+        }
+        ticks
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.remaining <= 0.0
+    }
+}
+
+/// The status effects on an entity. Send `ApplyStatus` to add one.
+#[derive(Component, Clone, Debug, Default, PartialEq)]
+pub struct StatusEffects(pub Vec<StatusEffect>);
+
+impl StatusEffects {
+    /// Adds `effect`, combining it with any of the same kind by `stacking`.
+    pub fn apply(&mut self, effect: StatusEffect, stacking: Stacking) {
+        let kind = effect.kind;
+        let mut same_kind = self.0.iter_mut().filter(|existing| existing.kind == kind);
+        match stacking {
+            Stacking::Refresh => {
+                if let Some(existing) = same_kind.next() {
+                    existing.remaining = existing.remaining.max(effect.remaining);
+                    existing.modifier = effect.modifier;
+                    return;
+                }
+            }
+            Stacking::Extend => {
+                if let Some(existing) = same_kind.next() {
+                    existing.remaining += effect.remaining;
+                    return;
+                }
+            }
+            Stacking::Stack(max) => {
+                if self.0.iter().filter(|existing| existing.kind == kind).count() >= max.max(1) {
+                    let soonest = self
+                        .0
+                        .iter_mut()
+                        .filter(|existing| existing.kind == kind)
+                        .min_by(|a, b| a.remaining.total_cmp(&b.remaining));
+                    if let Some(soonest) = soonest {
+                        *soonest = effect;
+                    }
+                    return;
+                }
+            }
+        }
+        self.0.push(effect);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The product of every `Modifier::Speed`.
+    pub fn speed_scale(&self) -> f32 {
+        self.0
+            .iter()
+            .map(|effect| match effect.modifier {
+                Modifier::Speed(scale) => scale,
+                _ => 1.0,
+            })
+            .product()
+    }
+
+    /// The sum of every `Modifier::AirJumps`.
+    pub fn extra_air_jumps(&self) -> u32 {
+        self.0
+            .iter()
+            .map(|effect| match effect.modifier {
+                Modifier::AirJumps(count) => count,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    /// The radius and strength of the widest-reaching `Modifier::Magnet`, if any.
+    pub fn magnet(&self) -> Option<(f32, f32)> {
+        self.0
+            .iter()
+            .filter_map(|effect| match effect.modifier {
+                Modifier::Magnet { radius, strength } => Some((radius, strength)),
+                _ => None,
+            })
+            .max_by(|a, b| a.0.total_cmp(&b.0))
+    }
+}
+
+/// Send this to put `effect` on `target`, stacking by the `StatusRules`.
+pub struct ApplyStatus {
+    pub target: Entity,
+    pub effect: StatusEffect,
+}
+
+pub struct StatusEffectPlugin;
+
+impl Plugin for StatusEffectPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<StatusRules>()
+            .add_event::<ApplyStatus>()
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_system(apply_statuses)
+                    .with_system(tick_statuses.after(apply_statuses))
+                    .with_system(flash_affected.after(tick_statuses)),
+            );
+    }
+}
+
+/// Targets without `StatusEffects` get them. Only the first effect sent to such a target in a frame lands.
+fn apply_statuses(
+    mut commands: Commands,
+    rules: Res<StatusRules>,
+    mut events: EventReader<ApplyStatus>,
+    mut targets: Query<&mut StatusEffects>,
+) {
+    for event in events.iter() {
+        match targets.get_mut(event.target) {
+            Ok(mut effects) => effects.apply(event.effect, rules.stacking(event.effect.kind)),
+            Err(_) => {
+                if let Some(mut target) = commands.get_entity(event.target) {
+                    target.insert(StatusEffects(vec![event.effect]));
+                }
+            }
+        }
+    }
+}
+
+fn tick_statuses(
+    delta: Res<GameplayDelta>,
+    mut targets: Query<(Entity, &mut StatusEffects)>,
+    mut damages: EventWriter<Damage>,
+) {
+    for (target, mut effects) in targets.iter_mut() {
+        if effects.is_empty() {
+            continue;
+        }
+        for effect in effects.0.iter_mut() {
+            let ticks = effect.advance(delta.0);
+            if let Modifier::Damage(amount) = effect.modifier {
+                for _ in 0..ticks {
+                    damages.send(Damage { target, amount });
+                }
+            }
+        }
+        effects.0.retain(|effect| !effect.is_expired());
+    }
+}
+
+/// Affected players blink until their longest effect runs out. A flash already running, such as from a hit, is let
+/// finish first.
+fn flash_affected(
+    mut commands: Commands,
+    players: Query<(Entity, &StatusEffects), With<Player>>,
+    children: Query<&Children>,
+    sprites: Query<(), (With<PlayerSprite>, Without<Flash>)>,
+) {
+    for (player, effects) in players.iter() {
+        let Some(longest) = effects.0.iter().map(|effect| effect.remaining).reduce(f32::max) else { continue };
+        for descendant in children.iter_descendants(player) {
+            if sprites.contains(descendant) {
+                commands.entity(descendant).insert(Flash::blink(FLASH_ALPHA, FLASH_FREQUENCY, longest));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn burn(duration: f32) -> StatusEffect {
+        StatusEffect::new(StatusKind::Burning, Modifier::Damage(1.0), duration, 0.5)
+    }
+
+    #[test]
+    fn ticks_on_schedule_until_expiry() {
+        let mut effect = burn(1.0);
+        assert_eq!(effect.advance(0.25), 0);
+        assert_eq!(effect.advance(0.25), 1);
+        // A long frame catches up on every tick it covered, including one landing as the effect runs out.
+        assert_eq!(effect.advance(2.0), 1);
+        assert!(effect.is_expired());
+        assert_eq!(effect.advance(1.0), 0);
+
+        let mut buff = StatusEffect::new(StatusKind::SpeedBoost, Modifier::Speed(1.5), 1.0, 0.0);
+        assert_eq!(buff.advance(0.5), 0);
+        assert!(!buff.is_expired());
+    }
+
+    #[test]
+    fn stacking_rules_combine_effects() {
+        let mut effects = StatusEffects::default();
+        effects.apply(burn(2.0), Stacking::Refresh);
+        effects.apply(burn(1.0), Stacking::Refresh);
+        assert_eq!(effects.0.len(), 1);
+        assert_eq!(effects.0[0].remaining, 2.0);
+
+        effects.apply(burn(1.0), Stacking::Extend);
+        assert_eq!(effects.0.len(), 1);
+        assert_eq!(effects.0[0].remaining, 3.0);
+
+        let mut effects = StatusEffects::default();
+        for duration in [3.0, 1.0, 2.0, 4.0] {
+            effects.apply(burn(duration), Stacking::Stack(3));
+        }
+        // The fourth replaced the one closest to running out.
+        let remaining: Vec<f32> = effects.0.iter().map(|effect| effect.remaining).collect();
+        assert_eq!(remaining, [3.0, 4.0, 2.0]);
+    }
+}
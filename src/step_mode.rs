@@ -0,0 +1,178 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::menu::UiAssets;
+use crate::physics::{update_gameplay_delta, GameplayDelta, FIXED_TIMESTEP};
+
+/// Turns step mode on and off.
+const TOGGLE_KEY: KeyCode = KeyCode::F9;
+/// Advances one step while step mode is on.
+const STEP_KEY: KeyCode = KeyCode::F10;
+
+/// Freezes the simulation and advances it one fixed step at a time, for inspecting physics frame by frame.
+///
+/// While enabled, frames without a requested step have no gameplay delta and don't step Rapier; a step frame gets
+/// exactly `FIXED_TIMESTEP` for both, just like `lockstep_app`, so stepping matches a continuous fixed-step run.
+#[derive(Resource, Clone, Copy, Debug, Default)]
+pub struct StepMode {
+    pub enabled: bool,
+    /// How many steps have been taken since step mode was turned on.
+    pub steps: u64,
+    step_requested: bool,
+    /// What to restore when step mode is turned off again.
+    resume: Option<(TimestepMode, bool)>,
+}
+
+impl StepMode {
+    /// Advances one step on the next frame, if step mode is on.
+    pub fn request_step(&mut self) {
+        self.step_requested = true;
+    }
+}
+
+/// Marker for the step counter shown while step mode is on.
+#[derive(Component)]
+struct StepCounter;
+
+pub struct StepModePlugin;
+
+impl Plugin for StepModePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<StepMode>()
+            .add_system_to_stage(CoreStage::First, read_step_keys)
+            .add_system_to_stage(
+                CoreStage::First,
+                apply_step_mode.after(read_step_keys).after(update_gameplay_delta),
+            )
+            .add_system(show_step_counter);
+    }
+}
+
+fn read_step_keys(keys: Option<Res<Input<KeyCode>>>, mut step_mode: ResMut<StepMode>) {
+    let Some(keys) = keys else { return };
+    if keys.just_pressed(TOGGLE_KEY) {
+        step_mode.enabled = !step_mode.enabled;
+        step_mode.steps = 0;
+    }
+    if keys.just_pressed(STEP_KEY) {
+        step_mode.request_step();
+    }
+}
+
+fn apply_step_mode(
+    mut step_mode: ResMut<StepMode>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+    mut delta: ResMut<GameplayDelta>,
+) {
+    let step = std::mem::take(&mut step_mode.step_requested);
+
+    if !step_mode.enabled {
+        if let Some((timestep_mode, active)) = step_mode.resume.take() {
+            rapier_config.timestep_mode = timestep_mode;
+            rapier_config.physics_pipeline_active = active;
+        }
+        return;
+    }
+
+    if step_mode.resume.is_none() {
+        step_mode.resume = Some((rapier_config.timestep_mode, rapier_config.physics_pipeline_active));
+        rapier_config.timestep_mode = TimestepMode::Fixed { dt: FIXED_TIMESTEP, substeps: 1 };
+    }
+    rapier_config.physics_pipeline_active = step;
+    if step {
+        delta.0 = FIXED_TIMESTEP;
+        step_mode.steps += 1;
+    } else {
+        delta.0 = 0.0;
+    }
+}
+
+fn show_step_counter(
+    mut commands: Commands,
+    step_mode: Res<StepMode>,
+    ui: Option<Res<UiAssets>>,
+    mut counters: Query<(Entity, &mut Text), With<StepCounter>>,
+) {
+    if !step_mode.is_changed() {
+        return;
+    }
+    let label = format!("STEP {}", step_mode.steps);
+
+    match (step_mode.enabled, counters.get_single_mut()) {
+        (true, Ok((_, mut text))) => text.sections[0].value = label,
+        (true, Err(_)) => {
+            let Some(ui) = ui else { return };
+            let style = TextStyle {
+                font: ui.font.clone(),
+                font_size: 24.0,
+                color: Color::YELLOW,
+            };
+            commands.spawn((
+                TextBundle::from_section(label, style).with_style(Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        right: Val::Px(16.0),
+                        top: Val::Px(16.0),
+                        ..default()
+                    },
+                    ..default()
+                }),
+                StepCounter,
+            ));
+        }
+        (false, Ok((entity, _))) => commands.entity(entity).despawn_recursive(),
+        (false, Err(_)) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+    use crate::input::InputState;
+    use crate::lockstep::{lockstep_app, run_scripted};
+    use crate::player::Player;
+
+    fn input(step: usize) -> InputState {
+        InputState { move_axis: Vec2::X, jump: (10..20).contains(&step), ..default() }
+    }
+
+    #[test]
+    fn single_steps_match_a_continuous_run() {
+        const STEPS: usize = 60;
+        let script: Vec<InputState> = (0..STEPS).map(input).collect();
+        let continuous = run_scripted(&script, STEPS);
+
+        let mut app = lockstep_app();
+        app.add_plugin(StepModePlugin);
+        app.world.resource_mut::<StepMode>().enabled = true;
+        let frame = Duration::from_secs_f32(FIXED_TIMESTEP);
+        let mut now = Instant::now();
+        app.world.resource_mut::<Time>().update_with_instant(now);
+
+        for step in 0..STEPS {
+            app.world.insert_resource(input(step));
+            // Each press of the step key is followed by a few frames with nothing to step.
+            for stepping in [true, false, false] {
+                if stepping {
+                    app.world.resource_mut::<StepMode>().request_step();
+                }
+                now += frame;
+                app.world.resource_mut::<Time>().update_with_instant(now);
+                app.update();
+            }
+        }
+
+        assert_eq!(app.world.resource::<StepMode>().steps, STEPS as u64);
+        let mut players = app.world.query_filtered::<&Transform, With<Player>>();
+        let stepped = players.single(&app.world);
+        assert!(
+            stepped.translation.abs_diff_eq(continuous.translation, 1e-3),
+            "stepped to {}, ran to {}",
+            stepped.translation,
+            continuous.translation,
+        );
+    }
+}
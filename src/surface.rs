@@ -0,0 +1,122 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::player::Deceleration;
+
+/// What a collider's surface is made of, which decides how bodies slide and bounce on it.
+///
+/// * `SurfaceMaterial::Normal` is plain solid ground.
+///
+/// * `SurfaceMaterial::Ice` has almost no friction, gives the player very little traction and lets them slide.
+///
+/// * `SurfaceMaterial::Bouncy` throws bodies back off with most of their speed.
+///
+/// * `SurfaceMaterial::Sticky` grips hard and lets the player change direction quickly.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SurfaceMaterial {
+    #[default]
+    Normal,
+    Ice,
+    Bouncy,
+    Sticky,
+}
+
+impl SurfaceMaterial {
+    /// Maps an LDtk IntGrid value identifier onto a material; `None` means the value isn't solid.
+    pub fn from_identifier(identifier: &str) -> Option<Self> {
+        match identifier.to_lowercase().as_str() {
+            "walls" | "ground" | "normal" => Some(SurfaceMaterial::Normal),
+            "ice" => Some(SurfaceMaterial::Ice),
+            "bouncy" => Some(SurfaceMaterial::Bouncy),
+            "sticky" => Some(SurfaceMaterial::Sticky),
+            _ => None,
+        }
+    }
+
+    pub fn friction(self) -> f32 {
+        match self {
+            SurfaceMaterial::Normal => 0.7,
+            SurfaceMaterial::Ice => 0.02,
+            SurfaceMaterial::Bouncy => 0.5,
+            SurfaceMaterial::Sticky => 1.5,
+        }
+    }
+
+    pub fn restitution(self) -> f32 {
+        match self {
+            SurfaceMaterial::Bouncy => 0.7,
+            _ => 0.0,
+        }
+    }
+
+    /// Multiplier on the player's acceleration and deceleration while standing on this surface.
+    pub fn traction(self) -> f32 {
+        match self {
+            SurfaceMaterial::Normal => 1.0,
+            SurfaceMaterial::Ice => 0.1,
+            SurfaceMaterial::Bouncy => 1.0,
+            SurfaceMaterial::Sticky => 1.5,
+        }
+    }
+
+    /// How the player comes to a stop on this surface: snappy on solid ground, a long slide on ice.
+    pub fn deceleration(self) -> Deceleration {
+        match self {
+            SurfaceMaterial::Ice => Deceleration::Exponential { half_life: 0.4 },
+            _ => Deceleration::Linear,
+        }
+    }
+
+    /// The Rapier components that give a collider this material.
+    pub fn components(self) -> (Friction, Restitution) {
+        (Friction::coefficient(self.friction()), Restitution::coefficient(self.restitution()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::{compute_horizontal_velocity, MoveConfig};
+
+    #[test]
+    fn presets_map_to_coefficients() {
+        let coefficients = |material: SurfaceMaterial| (material.friction(), material.restitution());
+        assert_eq!(coefficients(SurfaceMaterial::Normal), (0.7, 0.0));
+        assert_eq!(coefficients(SurfaceMaterial::Ice), (0.02, 0.0));
+        assert_eq!(coefficients(SurfaceMaterial::Bouncy), (0.5, 0.7));
+        assert_eq!(coefficients(SurfaceMaterial::Sticky), (1.5, 0.0));
+
+        let (friction, restitution) = SurfaceMaterial::Bouncy.components();
+        assert_eq!((friction.coefficient, restitution.coefficient), (0.5, 0.7));
+        assert_eq!(SurfaceMaterial::from_identifier("Ice"), Some(SurfaceMaterial::Ice));
+        assert_eq!(SurfaceMaterial::from_identifier("walls"), Some(SurfaceMaterial::Normal));
+        assert_eq!(SurfaceMaterial::from_identifier("water"), None);
+    }
+
+    /// How far (in meters) a grounded player running at full speed slides after letting go, the way `move_player`
+    /// sets up the config for `material`.
+    fn stopping_distance(material: SurfaceMaterial) -> f32 {
+        let config = MoveConfig { stopping: material.deceleration(), ..default() }.with_traction(material.traction());
+        let dt = 1.0 / 60.0;
+        let mut velocity = config.max_speed;
+        let mut braking = 0.0;
+        let mut distance = 0.0;
+        for _ in 0..6000 {
+            velocity = compute_horizontal_velocity(velocity, 0.0, true, &config, dt, &mut braking);
+            distance += velocity * dt;
+            if velocity == 0.0 {
+                return distance;
+            }
+        }
+        panic!("{material:?} never stopped");
+    }
+
+    #[test]
+    fn ice_slides_further_than_normal_ground() {
+        let normal = stopping_distance(SurfaceMaterial::Normal);
+        let ice = stopping_distance(SurfaceMaterial::Ice);
+        assert!(normal < 0.2, "normal ground slid {normal} m");
+        assert!(ice > normal * 5.0, "ice slid {ice} m, normal ground {normal} m");
+    }
+}
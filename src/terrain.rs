@@ -0,0 +1,115 @@
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+use bevy_ecs_ldtk::prelude::*;
+use bevy_rapier2d::prelude::*;
+use gamelibs::map::{cell_rects, greedy_rects, row_strips, CellRect};
+
+use crate::surface::SurfaceMaterial;
+
+/// Marker for colliders generated from a level's IntGrid layers.
+#[derive(Component)]
+pub struct TerrainCollider;
+
+/// How solid IntGrid cells are combined into colliders. Every strategy covers exactly the same cells, so they
+/// collide the same; they differ in how many shapes Rapier has to deal with.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColliderMergeStrategy {
+    /// A collider per cell. The simplest, but slow with many tiles, and bodies can catch on the seams.
+    PerTile,
+    /// The fewest colliders, merging both along and across rows.
+    #[default]
+    GreedyRects,
+    /// One collider per horizontal run of cells, which keeps floors seamless without merging rows.
+    RowStrips,
+}
+
+impl ColliderMergeStrategy {
+    /// The rectangles covering the set cells of a row-major `width` x `height` grid.
+    pub fn merge(self, cells: &[bool], width: usize, height: usize) -> Vec<CellRect> {
+        match self {
+            ColliderMergeStrategy::PerTile => cell_rects(cells, width, height),
+            ColliderMergeStrategy::GreedyRects => greedy_rects(cells, width, height),
+            ColliderMergeStrategy::RowStrips => row_strips(cells, width, height),
+        }
+    }
+}
+
+pub struct TerrainPlugin;
+
+impl Plugin for TerrainPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<ColliderMergeStrategy>()
+            .add_system(spawn_terrain_colliders);
+    }
+}
+
+/*
+ * Every IntGrid value whose identifier names a `SurfaceMaterial` is solid. Cells of the same material are
+ * merged into rectangles by the `ColliderMergeStrategy` and spawned as children of the level, so they go away when the level does.
+ */
+fn spawn_terrain_colliders(
+    mut commands: Commands,
+    mut level_events: EventReader<LevelEvent>,
+    levels: Query<(Entity, &Handle<LdtkLevel>)>,
+    level_assets: Res<Assets<LdtkLevel>>,
+    projects: Query<&Handle<LdtkAsset>>,
+    project_assets: Res<Assets<LdtkAsset>>,
+    strategy: Res<ColliderMergeStrategy>,
+) {
+    for event in level_events.iter() {
+        let LevelEvent::Spawned(iid) = event else { continue };
+
+        let Some(project) = projects.iter().find_map(|handle| project_assets.get(handle)) else { continue };
+        let level = levels
+            .iter()
+            .filter_map(|(entity, handle)| Some((entity, level_assets.get(handle)?)))
+            .find(|(_, ldtk_level)| &ldtk_level.level.iid == iid);
+        let Some((level_entity, ldtk_level)) = level else { continue };
+
+        for layer in ldtk_level.level.layer_instances.iter().flatten() {
+            if layer.int_grid_csv.is_empty() {
+                continue;
+            }
+            let Some(layer_def) = project.project.defs.layers.iter().find(|def| def.uid == layer.layer_def_uid) else { continue };
+
+            let materials: HashMap<i32, SurfaceMaterial> = layer_def.int_grid_values
+                .iter()
+                .filter_map(|value| {
+                    let material = SurfaceMaterial::from_identifier(value.identifier.as_deref()?)?;
+                    Some((value.value, material))
+                })
+                .collect();
+
+            let width = layer.c_wid as usize;
+            let height = layer.c_hei as usize;
+            let grid_size = layer.grid_size as f32;
+            let offset = Vec2::new(layer.px_total_offset_x as f32, -layer.px_total_offset_y as f32);
+
+            let mut level_commands = commands.entity(level_entity);
+            for material in materials.values().copied().collect::<HashSet<_>>() {
+                // LDtk rows run top-down; flip them so cell (0, 0) is the bottom-left corner like the world.
+                let cells: Vec<bool> = (0..height)
+                    .flat_map(|y| (0..width).map(move |x| (x, height - 1 - y)))
+                    .map(|(x, row)| materials.get(&layer.int_grid_csv[row * width + x]) == Some(&material))
+                    .collect();
+
+                level_commands.with_children(|level| {
+                    for rect in strategy.merge(&cells, width, height) {
+                        let size = Vec2::new(rect.width as f32, rect.height as f32) * grid_size;
+                        let corner = Vec2::new(rect.x as f32, rect.y as f32) * grid_size;
+                        let center = corner + size / 2.0 + offset;
+
+                        level.spawn((
+                            Collider::cuboid(size.x / 2.0, size.y / 2.0),
+                            material.components(),
+                            material,
+                            TerrainCollider,
+                            TransformBundle::from(Transform::from_translation(center.extend(0.0))),
+                        ));
+                    }
+                });
+            }
+        }
+    }
+}
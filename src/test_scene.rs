@@ -0,0 +1,88 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::bounce::BounceGuard;
+use crate::physics::PhysicsUnits;
+use crate::state::GameState;
+use crate::surface::SurfaceMaterial;
+
+/// Marker for everything in the test scene, despawned when it's left.
+#[derive(Component)]
+pub struct TestSceneEntity;
+
+/// Marker for the test scene's bouncing ball.
+#[derive(Component)]
+pub struct TestBall;
+
+/// A bare physics test: a bouncy ball dropped onto a strip of ground, with nothing else of the game running on it.
+/// Reached with `--test-scene` instead of the main menu.
+pub struct TestScenePlugin;
+
+impl Plugin for TestScenePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_system_set(SystemSet::on_enter(GameState::TestScene).with_system(spawn_test_scene))
+            .add_system_set(SystemSet::on_update(GameState::TestScene).with_system(print_ball_altitude))
+            .add_system_set(SystemSet::on_exit(GameState::TestScene).with_system(despawn_test_scene));
+    }
+}
+
+/// Sizes and positions are in meters and converted through `PhysicsUnits`.
+pub fn spawn_test_scene(mut commands: Commands, units: Res<PhysicsUnits>) {
+    /* Create the ground. */
+    commands
+        .spawn(Collider::cuboid(units.m_to_px(5.0), units.m_to_px(0.5)))
+        .insert(TransformBundle::from(Transform::from_xyz(0.0, units.m_to_px(-1.0), 0.0)))
+        .insert(TestSceneEntity);
+
+    /* Create the bouncing ball. */
+    commands
+        .spawn(RigidBody::Dynamic)
+        .insert(Collider::ball(units.m_to_px(0.5)))
+        .insert(SurfaceMaterial::Bouncy.components())
+        .insert((Velocity::default(), Ccd::enabled(), BounceGuard::default()))
+        .insert(TransformBundle::from(Transform::from_xyz(0.0, units.m_to_px(4.0), 0.0)))
+        .insert((TestSceneEntity, TestBall));
+}
+
+fn print_ball_altitude(positions: Query<&Transform, With<TestBall>>, units: Res<PhysicsUnits>) {
+    for transform in positions.iter() {
+        debug!("Ball altitude: {}m", units.px_to_m(transform.translation.y));
+    }
+}
+
+fn despawn_test_scene(mut commands: Commands, entities: Query<Entity, With<TestSceneEntity>>) {
+    for entity in entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::launch::LaunchOptions;
+
+    /// The test scene's entities once the game has started with `args`, and after it moves on to the main menu.
+    fn scene_entities(args: &[&str]) -> (usize, usize) {
+        let options = LaunchOptions::parse(args.iter().map(|arg| arg.to_string()));
+        let mut app = App::new();
+        app
+            .init_resource::<PhysicsUnits>()
+            .add_state(options.first_state())
+            .add_plugin(TestScenePlugin);
+        app.update();
+        let mut scene = app.world.query_filtered::<(), With<TestSceneEntity>>();
+        let started = scene.iter(&app.world).count();
+
+        let _ = app.world.resource_mut::<State<GameState>>().set(GameState::MainMenu);
+        app.update();
+        (started, scene.iter(&app.world).count())
+    }
+
+    #[test]
+    fn demo_spawns_only_with_the_flag() {
+        assert_eq!(scene_entities(&["--test-scene"]), (2, 0));
+        assert_eq!(scene_entities(&[]), (0, 0));
+        assert_eq!(scene_entities(&["--skip-menu"]), (0, 0));
+    }
+}
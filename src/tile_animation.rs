@@ -0,0 +1,71 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_ecs_tilemap::tiles::TileTextureIndex;
+
+use crate::animation::{clip_frame, AnimationClip};
+
+/// Cycles a tilemap tile through the tileset indices of `clip`.
+///
+/// Added to any LDtk tile whose tileset custom data is an `AnimationClip` as JSON, such as
+/// `{"frames": [12, 13, 14, 13], "fps": 6}`. Tile animations always loop.
+#[derive(Component, Clone, Debug, PartialEq)]
+pub struct TileAnimation(pub AnimationClip);
+
+pub struct TileAnimationPlugin;
+
+impl Plugin for TileAnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_system(animations_from_metadata)
+            .add_system(animate_tiles.after(animations_from_metadata));
+    }
+}
+
+/// Reads a tile animation from LDtk tile custom data; other custom data isn't an animation and is ignored.
+pub fn parse_tile_animation(data: &str) -> Option<TileAnimation> {
+    let mut clip: AnimationClip = serde_json::from_str(data).ok()?;
+    if clip.frames.is_empty() {
+        return None;
+    }
+    clip.looping = true;
+    Some(TileAnimation(clip))
+}
+
+fn animations_from_metadata(mut commands: Commands, tiles: Query<(Entity, &TileMetadata), Added<TileMetadata>>) {
+    for (entity, metadata) in tiles.iter() {
+        if let Some(animation) = parse_tile_animation(&metadata.data) {
+            commands.entity(entity).insert(animation);
+        }
+    }
+}
+
+/// Every tile runs off the same clock, so all copies of an animated tile stay in step.
+fn animate_tiles(time: Res<Time>, mut tiles: Query<(&TileAnimation, &mut TileTextureIndex)>) {
+    let elapsed = time.elapsed_seconds();
+
+    for (animation, mut texture) in tiles.iter_mut() {
+        let Some(frame) = clip_frame(&animation.0, elapsed) else { continue };
+        let frame = frame as u32;
+        if texture.0 != frame {
+            texture.0 = frame;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_follow_the_shared_clock_and_loop() {
+        let TileAnimation(clip) = parse_tile_animation(r#"{"frames": [12, 13, 14, 13], "fps": 4}"#).unwrap();
+        let frames: Vec<_> = [0.0, 0.2, 0.25, 0.5, 0.99, 1.0, 1.3].iter().map(|&t| clip_frame(&clip, t)).collect();
+        assert_eq!(frames, [12, 12, 13, 14, 13, 12, 13].map(Some));
+    }
+
+    #[test]
+    fn other_custom_data_is_not_an_animation() {
+        assert_eq!(parse_tile_animation(r#"{"frames": [], "fps": 4}"#), None);
+        assert_eq!(parse_tile_animation("solid"), None);
+    }
+}
@@ -0,0 +1,89 @@
+use bevy::prelude::*;
+
+use crate::menu::UiAssets;
+
+/// Seconds a toast stays on screen.
+const TOAST_TIME: f32 = 4.0;
+const TOAST_FONT_SIZE: f32 = 24.0;
+const TOAST_COLOR: Color = Color::rgb(1.0, 0.55, 0.45);
+const TOAST_MARGIN: f32 = 16.0;
+
+/// Send this to tell the player something went wrong, e.g. their settings couldn't be saved. It shows in the corner
+/// of the screen for a few seconds over whatever else is going on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Toast(pub String);
+
+/// The on-screen text of a toast, and the seconds it has been up.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct ToastText {
+    pub elapsed: f32,
+}
+
+/// The column toasts are stacked in.
+#[derive(Component)]
+struct ToastColumn;
+
+pub struct ToastPlugin;
+
+impl Plugin for ToastPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<Toast>()
+            .add_system(show_toasts)
+            .add_system(expire_toasts);
+    }
+}
+
+/*
+ * Toasts sent before the UI font has loaded, such as errors reading files at startup, wait until it has. They're
+ * kept out of `GameplayEntity` so changing state doesn't take them down early.
+ */
+fn show_toasts(
+    mut commands: Commands,
+    ui: Option<Res<UiAssets>>,
+    mut toasts: EventReader<Toast>,
+    mut pending: Local<Vec<String>>,
+    columns: Query<Entity, With<ToastColumn>>,
+) {
+    pending.extend(toasts.iter().map(|toast| toast.0.clone()));
+    let Some(ui) = ui else { return };
+    if pending.is_empty() {
+        return;
+    }
+
+    let column = columns.get_single().unwrap_or_else(|_| {
+        commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        position: UiRect { left: Val::Px(TOAST_MARGIN), bottom: Val::Px(TOAST_MARGIN), ..default() },
+                        flex_direction: FlexDirection::Column,
+                        ..default()
+                    },
+                    ..default()
+                },
+                ToastColumn,
+            ))
+            .id()
+    });
+    let style = TextStyle {
+        font: ui.font.clone(),
+        font_size: TOAST_FONT_SIZE,
+        color: TOAST_COLOR,
+    };
+    for message in pending.drain(..) {
+        let toast = commands.spawn((TextBundle::from_section(message, style.clone()), ToastText::default())).id();
+        commands.entity(column).add_child(toast);
+    }
+}
+
+/// Toasts run on real time, so they still go away while gameplay is paused.
+fn expire_toasts(mut commands: Commands, time: Res<Time>, mut toasts: Query<(Entity, &mut ToastText)>) {
+    for (entity, mut toast) in toasts.iter_mut() {
+        toast.elapsed += time.delta_seconds();
+        if toast.elapsed >= TOAST_TIME {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use gamelibs::prelude::*;
+
+use crate::physics::GameplayDelta;
+use crate::pool::{EntityPool, SpawnBudget};
+
+/// How opaque a fresh afterimage is, relative to the sprite it copies.
+const TRAIL_GHOST_ALPHA: f32 = 0.5;
+/// Afterimages sit this far behind the sprite they copy.
+const TRAIL_GHOST_DEPTH: f32 = 0.1;
+
+/// Leaves fading afterimages of this entity's sprites behind it every `spawn_interval` seconds, each lasting
+/// `lifetime` seconds. It only emits while moving faster than `min_speed` pixels per second, or while `forced`.
+///
+/// The sprites copied are the entity's own and all of its descendants', as they look at that moment.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Trail {
+    pub lifetime: f32,
+    pub spawn_interval: f32,
+    pub min_speed: f32,
+    /// Emits regardless of speed, e.g. for the length of a dash.
+    pub forced: bool,
+    since_spawn: f32,
+}
+
+impl Trail {
+    pub fn new(lifetime: f32, spawn_interval: f32, min_speed: f32) -> Self {
+        Trail {
+            lifetime,
+            spawn_interval,
+            min_speed,
+            forced: false,
+            since_spawn: 0.0,
+        }
+    }
+}
+
+/// One afterimage, fading out from its sprite's `color` over `lifetime` seconds.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct TrailGhost {
+    pub color: Color,
+    pub lifetime: f32,
+    pub elapsed: f32,
+}
+
+/// Advances a trail's time since its last afterimage by `dt`, returning whether another is due and the new time.
+///
+/// At most one afterimage is due per call, so a long frame doesn't drop a clump of them in one spot.
+pub fn step_trail_timer(since_spawn: f32, dt: f32, interval: f32) -> (bool, f32) {
+    let since_spawn = since_spawn + dt;
+    if since_spawn < interval {
+        return (false, since_spawn);
+    }
+    (true, (since_spawn - interval).min(interval))
+}
+
+/// How opaque an afterimage is `elapsed` seconds into its `lifetime`, relative to its sprite.
+pub fn trail_ghost_alpha(elapsed: f32, lifetime: f32) -> f32 {
+    let t = if lifetime > 0.0 { elapsed / lifetime } else { 1.0 };
+    TRAIL_GHOST_ALPHA * (1.0 - ease(Ease::QuadOut, t as f64) as f32)
+}
+
+pub struct TrailPlugin;
+
+impl Plugin for TrailPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<EntityPool<TrailGhost>>()
+            .init_resource::<SpawnBudget>()
+            .add_system(emit_trails)
+            .add_system(fade_trail_ghosts);
+    }
+}
+
+/*
+ * The timer only runs while the trail is emitting, so one that starts moving fast leaves its first afterimage a full
+ * interval later rather than straight away, and short bursts of speed don't leave a ghost at the start of every one.
+ */
+fn emit_trails(
+    mut commands: Commands,
+    delta: Res<GameplayDelta>,
+    mut pool: ResMut<EntityPool<TrailGhost>>,
+    budget: Res<SpawnBudget>,
+    mut trails: Query<(Entity, &mut Trail, Option<&Velocity>)>,
+    children: Query<&Children>,
+    sprites: Query<(&Sprite, &Handle<Image>, &GlobalTransform, &ComputedVisibility)>,
+    atlas_sprites: Query<(&TextureAtlasSprite, &Handle<TextureAtlas>, &GlobalTransform, &ComputedVisibility)>,
+) {
+    for (entity, mut trail, velocity) in trails.iter_mut() {
+        let speed = velocity.map_or(0.0, |velocity| velocity.linvel.length());
+        if !trail.forced && speed <= trail.min_speed {
+            trail.since_spawn = 0.0;
+            continue;
+        }
+
+        let (due, since_spawn) = step_trail_timer(trail.since_spawn, delta.0, trail.spawn_interval);
+        trail.since_spawn = since_spawn;
+        if !due {
+            continue;
+        }
+
+        let sources = std::iter::once(entity).chain(children.iter_descendants(entity));
+        for source in sources {
+            let ghost = if let Ok((sprite, image, transform, visibility)) = sprites.get(source) {
+                if !visibility.is_visible() {
+                    continue;
+                }
+                let entity = pool.take(&mut commands, &budget);
+                // An entity reused over the budget may still be the other kind of sprite.
+                commands.entity(entity).remove::<(TextureAtlasSprite, Handle<TextureAtlas>)>().insert(SpriteBundle {
+                    sprite: sprite.clone(),
+                    texture: image.clone(),
+                    transform: ghost_transform(transform),
+                    ..default()
+                });
+                (entity, sprite.color)
+            } else if let Ok((sprite, atlas, transform, visibility)) = atlas_sprites.get(source) {
+                if !visibility.is_visible() {
+                    continue;
+                }
+                let entity = pool.take(&mut commands, &budget);
+                commands.entity(entity).remove::<(Sprite, Handle<Image>)>().insert(SpriteSheetBundle {
+                    sprite: sprite.clone(),
+                    texture_atlas: atlas.clone(),
+                    transform: ghost_transform(transform),
+                    ..default()
+                });
+                (entity, sprite.color)
+            } else {
+                continue;
+            };
+
+            let (entity, color) = ghost;
+            commands.entity(entity).insert(TrailGhost {
+                color,
+                lifetime: trail.lifetime,
+                elapsed: 0.0,
+            });
+        }
+    }
+}
+
+fn ghost_transform(source: &GlobalTransform) -> Transform {
+    let mut transform = source.compute_transform();
+    transform.translation.z -= TRAIL_GHOST_DEPTH;
+    transform
+}
+
+fn fade_trail_ghosts(
+    mut commands: Commands,
+    delta: Res<GameplayDelta>,
+    mut pool: ResMut<EntityPool<TrailGhost>>,
+    mut ghosts: Query<(Entity, &mut TrailGhost, Option<&mut Sprite>, Option<&mut TextureAtlasSprite>)>,
+) {
+    for (entity, mut ghost, sprite, atlas_sprite) in ghosts.iter_mut() {
+        ghost.elapsed += delta.0;
+        if ghost.elapsed >= ghost.lifetime {
+            // A pooled entity may come back as the other kind of sprite, so neither may linger.
+            commands
+                .entity(entity)
+                .remove::<(TrailGhost, Sprite, Handle<Image>, TextureAtlasSprite, Handle<TextureAtlas>)>();
+            pool.release(&mut commands, entity);
+            continue;
+        }
+
+        let mut color = ghost.color;
+        color.set_a(ghost.color.a() * trail_ghost_alpha(ghost.elapsed, ghost.lifetime));
+        if let Some(mut sprite) = sprite {
+            sprite.color = color;
+        }
+        if let Some(mut sprite) = atlas_sprite {
+            sprite.color = color;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn afterimages_are_spaced_by_the_interval() {
+        let interval = 0.625;
+        let mut since_spawn = 0.0;
+        let due: Vec<bool> = (0..10)
+            .map(|_| {
+                let (due, next) = step_trail_timer(since_spawn, 0.25, interval);
+                since_spawn = next;
+                due
+            })
+            .collect();
+        // Frames of 2/5 of the interval: one every two or three frames, carrying the remainder over.
+        assert_eq!(due, [false, false, true, false, true, false, false, true, false, true]);
+
+        // A long frame only makes one due, and keeps no more than an interval in hand.
+        assert_eq!(step_trail_timer(0.0, 10.0, interval), (true, interval));
+    }
+
+    #[test]
+    fn afterimages_fade_out_over_their_lifetime() {
+        let lifetime = 0.4;
+        assert_eq!(trail_ghost_alpha(0.0, lifetime), TRAIL_GHOST_ALPHA);
+        let alphas: Vec<f32> = (1..=4).map(|i| trail_ghost_alpha(lifetime * i as f32 / 4.0, lifetime)).collect();
+        assert!(alphas.windows(2).all(|pair| pair[1] < pair[0]), "{alphas:?}");
+        assert!(alphas[3].abs() < 1e-6);
+        assert_eq!(trail_ghost_alpha(0.0, 0.0), 0.0);
+    }
+}
@@ -0,0 +1,84 @@
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+
+use crate::parallax::Parallax;
+
+/// The depth given to something at a height of 0, in the middle of where sprites are drawn.
+pub const Y_SORT_BASE: f32 = 1.0;
+/// How much nearer the camera each pixel lower down brings something. Small enough that a level's whole height
+/// stays between the background and the camera, yet far coarser than an `f32` depth can tell apart.
+pub const Y_SORT_DEPTH_PER_PIXEL: f32 = 1e-5;
+
+/// Draws this entity in front of those above it on screen and behind those below, by setting its depth from its
+/// height each frame. `offset` moves the point it's sorted by from its origin, e.g. down to a character's feet.
+///
+/// Only what has one is sorted: backgrounds, parallax layers and the UI keep the depth they were given.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq)]
+pub struct YSort {
+    pub offset: f32,
+}
+
+pub struct YSortPlugin;
+
+impl Plugin for YSortPlugin {
+    fn build(&self, app: &mut App) {
+        // After everything has moved for the frame, but before propagation so the depth is drawn this frame.
+        app.add_system_to_stage(CoreStage::PostUpdate, y_sort.before(TransformSystem::TransformPropagate));
+    }
+}
+
+/// The depth for something whose sort point is at world height `y` plus `offset`: lower is nearer.
+pub fn y_sort_z(y: f32, offset: f32) -> f32 {
+    Y_SORT_BASE - (y + offset) * Y_SORT_DEPTH_PER_PIXEL
+}
+
+/// Sorts by world height, taken from last frame's global transform for anything in a hierarchy, as the global
+/// transforms for this frame haven't been worked out yet. The depth set is the entity's own, so a child is sorted
+/// relative to its parent.
+fn y_sort(
+    mut sorted: Query<
+        (&mut Transform, &YSort, Option<&GlobalTransform>, Option<&Parent>),
+        (Without<Parallax>, Without<Node>),
+    >,
+) {
+    for (mut transform, y_sort, global, parent) in sorted.iter_mut() {
+        let y = match (parent, global) {
+            (Some(_), Some(global)) => global.translation().y,
+            _ => transform.translation.y,
+        };
+        let z = y_sort_z(y, y_sort.offset);
+        if transform.translation.z != z {
+            transform.translation.z = z;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lower_is_nearer() {
+        assert_eq!(y_sort_z(0.0, 0.0), Y_SORT_BASE);
+        assert!((y_sort_z(100.0, -24.0) - (Y_SORT_BASE - 76.0 * Y_SORT_DEPTH_PER_PIXEL)).abs() < 1e-7);
+        assert!(y_sort_z(-50.0, 0.0) > y_sort_z(50.0, 0.0));
+        // Anything in a tall level stays in front of the background and behind the camera.
+        assert!(y_sort_z(10_000.0, 0.0) > 0.0 && y_sort_z(-10_000.0, 0.0) < 2.0);
+    }
+
+    #[test]
+    fn entities_sort_by_height() {
+        let mut app = App::new();
+        app.add_plugin(YSortPlugin);
+        let high = app.world.spawn((Transform::from_xyz(0.0, 120.0, 5.0), YSort::default())).id();
+        let low = app.world.spawn((Transform::from_xyz(0.0, 80.0, 5.0), YSort::default())).id();
+        // Its feet are lower than the low one's, though its centre is higher.
+        let tall = app.world.spawn((Transform::from_xyz(0.0, 100.0, 5.0), YSort { offset: -30.0 })).id();
+        let unsorted = app.world.spawn(Transform::from_xyz(0.0, 0.0, 5.0)).id();
+        app.update();
+
+        let z = |entity: Entity| app.world.get::<Transform>(entity).unwrap().translation.z;
+        assert!(z(high) < z(low) && z(low) < z(tall));
+        assert_eq!(z(unsorted), 5.0);
+    }
+}